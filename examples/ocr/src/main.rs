@@ -16,7 +16,6 @@ extern crate simplelog;
 // internal crates
 extern crate darwin_rs;
 
-use std::sync::Arc;
 use rand::Rng;
 use std::fs::File;
 use std::io::Read;
@@ -25,32 +24,32 @@ use image::{ImageBuffer, Luma};
 use imageproc::stats::root_mean_squared_error;
 use simplelog::{SimpleLogger, LogLevelFilter, Config};
 use std::str;
+use std::fmt;
 
 // internal modules
 use darwin_rs::{Individual, SimulationBuilder, Population, PopulationBuilder, simulation_builder};
+use darwin_rs::rng::SimRng;
+use darwin_rs::select::MinimizeSelector;
 
-fn make_population<'a>(count: u32, config: &OCRConfig<'a>) -> Vec<OCRItem<'a>> {
+fn make_population(count: u32) -> Vec<OCRItem> {
     let mut result = Vec::new();
 
-    let shared = Arc::new(config.clone());
-
     for _ in 0..count {
         result.push( OCRItem {
             content: vec![
                 // Start with letter 'A' in each line
                 TextBox{ x: 10, y: 10, text: vec![65, 65, 65, 65, 65, 65, 65, 65, 65] },
-                TextBox{ x: 10, y: 40, text: vec![65, 65, 65, 65, 65, 65, 65, 65, 65] }],
-            config: shared.clone()
+                TextBox{ x: 10, y: 40, text: vec![65, 65, 65, 65, 65, 65, 65, 65, 65] }]
         });
     }
 
     result
 }
 
-fn make_all_populations<'a>(individuals: u32, config: &OCRConfig<'a>, populations: u32) -> Vec<Population<OCRItem<'a>>> {
+fn make_all_populations(individuals: u32, populations: u32) -> Vec<Population<OCRItem>> {
     let mut result = Vec::new();
 
-    let initial_population = make_population(individuals, &config);
+    let initial_population = make_population(individuals);
 
     for i in 1..(populations + 1) {
         let pop = PopulationBuilder::<OCRItem>::new()
@@ -65,7 +64,7 @@ fn make_all_populations<'a>(individuals: u32, config: &OCRConfig<'a>, population
     result
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 struct TextBox {
     x: u32,
     y: u32,
@@ -85,49 +84,58 @@ struct OCRConfig<'a> {
     font_config: FontConfig<'a>
 }
 
-#[derive(Clone)]
-struct OCRItem<'a> {
-    content: Vec<TextBox>,
-    config: Arc<OCRConfig<'a>>
+impl<'a> fmt::Debug for OCRConfig<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "OCRConfig {{ {} x {} }}", self.original_img.width(), self.original_img.height())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct OCRItem {
+    content: Vec<TextBox>
 }
 
-impl<'a> Individual for OCRItem<'a> {
-    fn mutate(&mut self) {
-        let mut rng = rand::thread_rng();
+impl Individual for OCRItem {
+    type Fitness = f64;
+    // The config is shared by all individuals. It is set once in main, the populations
+    // start without it (None).
+    type Context = Option<OCRConfig<'static>>;
 
-        let content_line = rng.gen_range(0, self.content.len());
+    fn mutate(&mut self, _context: &Option<OCRConfig<'static>>, rng: &mut SimRng) {
+        let content_line = rng.gen_range(0..self.content.len());
 
-        let operation = rng.gen_range(0, 2);
+        let operation = rng.gen_range(0..2);
 
-        let index1 = rng.gen_range(0, self.content[content_line].text.len());
+        let index1 = rng.gen_range(0..self.content[content_line].text.len());
 
         match operation {
             0 => {
                 // Change character
-                let new_char = rng.gen_range(32, 127); // All printable ASCII characters
+                let new_char = rng.gen_range(32..127); // All printable ASCII characters
                 self.content[content_line].text[index1] = new_char;
             },
             1 => {
                 // Swap characters
-                let index2 = rng.gen_range(0, self.content[content_line].text.len());
+                let index2 = rng.gen_range(0..self.content[content_line].text.len());
                 self.content[content_line].text.swap(index1, index2);
             },
             n => info!("mutate(): unknown operation: {}", n)
         }
     }
 
-    fn calculate_fitness(&mut self) -> f64 {
+    fn calculate_fitness(&mut self, context: &Option<OCRConfig<'static>>) -> f64 {
+        let config = context.as_ref().expect("the OCR config is set in main");
         let mut constructed_img: ImageBuffer<Luma<u8>, Vec<u8>> = ImageBuffer::new(120, 70);
 
-        draw_text_line(&mut constructed_img, &self.config.font_config,
+        draw_text_line(&mut constructed_img, &config.font_config,
             self.content[0].x as i32, self.content[0].y as i32,
             str::from_utf8(&self.content[0].text).unwrap());
 
-        draw_text_line(&mut constructed_img, &self.config.font_config,
+        draw_text_line(&mut constructed_img, &config.font_config,
             self.content[1].x as i32, self.content[1].y as i32,
             str::from_utf8(&self.content[1].text).unwrap());
 
-        root_mean_squared_error(&self.config.original_img, &constructed_img)
+        root_mean_squared_error(&config.original_img, &constructed_img)
     }
 
     fn reset(&mut self) {
@@ -206,7 +214,8 @@ fn main() {
     let ocr_builder = SimulationBuilder::<OCRItem>::new()
         .fitness(0.0)
         .threads(16)
-        .add_multiple_populations(make_all_populations(16, &ocr_config, num_populations as u32))
+        .context(Some(ocr_config))
+        .add_multiple_populations(make_all_populations(16, num_populations as u32))
         .share_fittest()
         .finalize();
 
@@ -214,7 +223,7 @@ fn main() {
         Err(simulation_builder::Error(simulation_builder::ErrorKind::EndIterationTooLow, _)) => println!("more than 10 iteratons needed"),
         Err(e) => println!("unexpected error: {}", e),
         Ok(mut ocr_simulation) => {
-            ocr_simulation.run();
+            ocr_simulation.run(&MinimizeSelector::new(4));
 
             println!("total run time: {} ms", ocr_simulation.total_time_in_ms);
             println!("improvement factor: {}", ocr_simulation.simulation_result.improvement_factor);
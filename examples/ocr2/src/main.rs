@@ -25,9 +25,12 @@ use image::{ImageBuffer, Luma};
 use imageproc::stats::root_mean_squared_error;
 use simplelog::{SimpleLogger, LogLevelFilter, Config};
 use std::str;
+use std::fmt;
 
 // internal modules
 use darwin_rs::{Individual, SimulationBuilder, Population, PopulationBuilder, simulation_builder};
+use darwin_rs::rng::SimRng;
+use darwin_rs::select::MinimizeSelector;
 
 const MIN_ASCII: u8 = 32;
 const MAX_ASCII: u8 = 126;
@@ -88,9 +91,9 @@ fn make_all_populations2<'a>(individuals: u32, config: &OCRConfig<'a>, populatio
             .set_id(i)
             .initial_population(&initial_population)
             .increasing_exp_mutation_rate(((200 + i) as f64) / 200.0)
-            .reset_limit_start(rng.gen_range(100, 501))
+            .reset_limit_start(rng.gen_range(100..501))
             .reset_limit_end(10000)
-            .reset_limit_increment(rng.gen_range(100, 501))
+            .reset_limit_increment(rng.gen_range(100..501))
             .finalize().unwrap();
 
         result.push(pop);
@@ -99,7 +102,7 @@ fn make_all_populations2<'a>(individuals: u32, config: &OCRConfig<'a>, populatio
     result
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 struct TextBox {
     x: u32,
     y: u32,
@@ -119,21 +122,28 @@ struct OCRConfig<'a> {
     font_config: FontConfig<'a>
 }
 
-#[derive(Clone)]
+impl<'a> fmt::Debug for OCRConfig<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "OCRConfig {{ {} x {} }}", self.original_img.width(), self.original_img.height())
+    }
+}
+
+#[derive(Clone, Debug)]
 struct OCRItem<'a> {
     content: Vec<TextBox>,
     config: Arc<OCRConfig<'a>>
 }
 
 impl<'a> Individual for OCRItem<'a> {
-    fn mutate(&mut self) {
-        let mut rng = rand::thread_rng();
+    type Fitness = f64;
+    type Context = ();
 
-        let content_line = rng.gen_range(0, self.content.len());
+    fn mutate(&mut self, _context: &(), rng: &mut SimRng) {
+        let content_line = rng.gen_range(0..self.content.len());
 
-        let operation = rng.gen_range(0, 11);
+        let operation = rng.gen_range(0..11);
 
-        let index1 = rng.gen_range(0, self.content[content_line].text.len());
+        let index1 = rng.gen_range(0..self.content[content_line].text.len());
 
         let max_move_step = 20;
 
@@ -141,18 +151,18 @@ impl<'a> Individual for OCRItem<'a> {
             0 => {
                 // Change character
                 // All printable ASCII characters
-                let new_char = rng.gen_range(MIN_ASCII, MAX_ASCII + 1);
+                let new_char = rng.gen_range(MIN_ASCII..=MAX_ASCII);
                 self.content[content_line].text[index1] = new_char;
             }
             1 => {
                 // Swap characters
-                let index2 = rng.gen_range(0, self.content[content_line].text.len());
+                let index2 = rng.gen_range(0..self.content[content_line].text.len());
                 self.content[content_line].text.swap(index1, index2);
             }
             2 => {
                 // Add character
                 // All printable ASCII characters
-                let new_char = rng.gen_range(MIN_ASCII, MAX_ASCII + 1);
+                let new_char = rng.gen_range(MIN_ASCII..=MAX_ASCII);
                 self.content[content_line].text.insert(index1, new_char);
             }
             3 => {
@@ -173,14 +183,14 @@ impl<'a> Individual for OCRItem<'a> {
             }
             5 => {
                 // New position
-                self.content[content_line].x = rng.gen_range(0, self.config.original_img.width());
-                self.content[content_line].y = rng.gen_range(0, self.config.original_img.height());
+                self.content[content_line].x = rng.gen_range(0..self.config.original_img.width());
+                self.content[content_line].y = rng.gen_range(0..self.config.original_img.height());
             }
             6 => {
                 // Move by a small amount
-                let direction = rng.gen_range(0, 4);
+                let direction = rng.gen_range(0..4);
 
-                let move_step = rng.gen_range(1, max_move_step);
+                let move_step = rng.gen_range(1..max_move_step);
 
                 match direction {
                     0 => {
@@ -212,7 +222,7 @@ impl<'a> Individual for OCRItem<'a> {
             }
             7 => {
                 // Rotate / shift
-                let index2 = rng.gen_range(0, self.content[content_line].text.len());
+                let index2 = rng.gen_range(0..self.content[content_line].text.len());
 
                 let tmp = self.content[content_line].text.remove(index1);
                 self.content[content_line].text.insert(index2, tmp);
@@ -238,18 +248,18 @@ impl<'a> Individual for OCRItem<'a> {
             }
             9 => {
                 // Add character at the beginning and move left
-                let move_step = rng.gen_range(1, max_move_step);
+                let move_step = rng.gen_range(1..max_move_step);
 
                 if self.content[content_line].x > move_step {
                     // All printable ASCII characters
-                    let new_char = rng.gen_range(MIN_ASCII, MAX_ASCII + 1);
+                    let new_char = rng.gen_range(MIN_ASCII..=MAX_ASCII);
                     self.content[content_line].text.insert(0, new_char);
                     self.content[content_line].x -= move_step;
                 }
             }
             10 => {
                 // Remove character at the beginning and move right
-                let move_step = rng.gen_range(1, max_move_step);
+                let move_step = rng.gen_range(1..max_move_step);
 
                 if self.content[content_line].x < self.config.original_img.width() - move_step {
                     if self.content[content_line].text.len() > 1 {
@@ -262,7 +272,7 @@ impl<'a> Individual for OCRItem<'a> {
         }
     }
 
-    fn calculate_fitness(&mut self) -> f64 {
+    fn calculate_fitness(&mut self, _context: &()) -> f64 {
         let mut constructed_img: ImageBuffer<Luma<u8>, Vec<u8>> = ImageBuffer::new(120, 70);
 
         if !draw_text_line(&mut constructed_img, &self.config.font_config,
@@ -372,7 +382,7 @@ fn main() {
         Err(simulation_builder::Error(simulation_builder::ErrorKind::EndIterationTooLow, _)) => println!("more than 10 iteratons needed"),
         Err(e) => println!("unexpected error: {}", e),
         Ok(mut ocr_simulation) => {
-            ocr_simulation.run();
+            ocr_simulation.run(&MinimizeSelector::new(4));
 
             println!("total run time: {} ms", ocr_simulation.total_time_in_ms);
             println!("improvement factor: {}", ocr_simulation.simulation_result.improvement_factor);
@@ -13,6 +13,8 @@ use simplelog::{SimpleLogger, LogLevelFilter, Config};
 
 // internal modules
 use darwin_rs::{Individual, SimulationBuilder, Population, PopulationBuilder, simulation_builder};
+use darwin_rs::rng::SimRng;
+use darwin_rs::select::MinimizeSelector;
 
 #[derive(Debug, Clone)]
 struct Queens {
@@ -120,21 +122,22 @@ fn make_all_populations(individuals: u32, populations: u32) -> Vec<Population<Qu
 
 // implement trait functions mutate and calculate_fitness:
 impl Individual for Queens {
-    fn mutate(&mut self) {
-        let mut rng = rand::thread_rng();
+    type Fitness = f64;
+    type Context = ();
 
-        let mut index1: usize = rng.gen_range(0, self.board.len());
-        let mut index2: usize = rng.gen_range(0, self.board.len());
+    fn mutate(&mut self, _context: &(), rng: &mut SimRng) {
+        let mut index1: usize = rng.gen_range(0..self.board.len());
+        let mut index2: usize = rng.gen_range(0..self.board.len());
 
         // Pick a position where a queen is placed.
         // Try random position until it finds a queen
         while self.board[index1] != 1 {
-            index1 = rng.gen_range(0, self.board.len());
+            index1 = rng.gen_range(0..self.board.len());
         }
 
         // Pick a position where no queen is placed and this index is different from the other
         while (index1 == index2) && (self.board[index2] != 0) {
-            index2 = rng.gen_range(0, self.board.len());
+            index2 = rng.gen_range(0..self.board.len());
         }
 
         // Move queen onto an empty position
@@ -142,7 +145,7 @@ impl Individual for Queens {
     }
 
     // fitness means here: how many queens are colliding
-    fn calculate_fitness(&mut self) -> f64 {
+    fn calculate_fitness(&mut self, _context: &()) -> f64 {
         let mut num_of_collisions = 0;
 
         for row in 0..8 {
@@ -186,7 +189,7 @@ fn main() {
         Err(simulation_builder::Error(simulation_builder::ErrorKind::EndIterationTooLow, _)) => println!("more than 10 iteratons needed"),
         Err(e) => println!("unexpected error: {}", e),
         Ok(mut queens_simulation) => {
-            queens_simulation.run();
+            queens_simulation.run(&MinimizeSelector::new(4));
 
             // A fitness of zero means a solution was found.
             queens_simulation.print_fitness();
@@ -0,0 +1,80 @@
+// This example compares real valued genomes stored in a Vec<f64> with fixed-size
+// genomes ([f64; N]) for small numbers of genes.
+// Each step does what a population does for every individual: clone the parents,
+// cross them, mutate the child and calculate its fitness (sphere function).
+//
+// Run it with: cargo run --release
+
+// internal crates
+extern crate darwin_rs;
+
+use std::time::Instant;
+
+// internal modules
+use darwin_rs::benchmark::{Objective, Sphere};
+use darwin_rs::ops::real::{polynomial_mutation, sbx, sbx_array};
+use darwin_rs::rng;
+
+const STEPS: u32 = 1_000_000;
+
+fn genes(n: usize, offset: f64) -> Vec<f64> {
+    (0..n).map(|i| ((i as f64 + offset) % 10.0) - 5.0).collect()
+}
+
+// Returns the time for one step in nanoseconds and the sum of the fitness
+// (so the work can't be optimized away).
+fn bench_vec(n: usize) -> (f64, f64) {
+    let bounds = vec![(-5.0, 5.0); n];
+    let first = genes(n, 0.0);
+    let second = genes(n, 3.0);
+    let mut rng = rng::from_seed(1);
+    let mut sum = 0.0;
+
+    let start = Instant::now();
+    for _ in 0..STEPS {
+        let (a, b) = (first.clone(), second.clone());
+        let mut child = sbx(&a, &b, &bounds, 20.0, &mut rng);
+        polynomial_mutation(&mut child, &bounds, 20.0, 1.0 / n as f64, &mut rng);
+        sum += Sphere.evaluate(&child);
+    }
+
+    (start.elapsed().as_secs_f64() * 1.0e9 / STEPS as f64, sum)
+}
+
+fn bench_array<const N: usize>() -> (f64, f64) {
+    let bounds = [(-5.0, 5.0); N];
+    let mut first = [0.0; N];
+    let mut second = [0.0; N];
+    first.copy_from_slice(&genes(N, 0.0));
+    second.copy_from_slice(&genes(N, 3.0));
+    let mut rng = rng::from_seed(1);
+    let mut sum = 0.0;
+
+    let start = Instant::now();
+    for _ in 0..STEPS {
+        let (a, b) = (first, second);
+        let mut child = sbx_array(&a, &b, &bounds, 20.0, &mut rng);
+        polynomial_mutation(&mut child, &bounds, 20.0, 1.0 / N as f64, &mut rng);
+        sum += Sphere.evaluate(&child);
+    }
+
+    (start.elapsed().as_secs_f64() * 1.0e9 / STEPS as f64, sum)
+}
+
+fn report(n: usize, vec: (f64, f64), array: (f64, f64)) {
+    // Both use the same random numbers, so they must calculate the same fitness.
+    assert_eq!(vec.1, array.1);
+    println!("{:>3} genes: Vec {:>7.1} ns, array {:>7.1} ns, speedup: {:.2}",
+             n, vec.0, array.0, vec.0 / array.0);
+}
+
+fn main() {
+    println!("Darwin test: fixed-size genomes vs Vec<f64>, {} steps each", STEPS);
+
+    report(2, bench_vec(2), bench_array::<2>());
+    report(4, bench_vec(4), bench_array::<4>());
+    report(6, bench_vec(6), bench_array::<6>());
+    report(8, bench_vec(8), bench_array::<8>());
+    report(16, bench_vec(16), bench_array::<16>());
+    report(32, bench_vec(32), bench_array::<32>());
+}
@@ -17,6 +17,8 @@ use simplelog::{SimpleLogger, LogLevelFilter, Config};
 
 // Internal modules
 use darwin_rs::{Individual, SimulationBuilder, Population, PopulationBuilder, simulation_builder};
+use darwin_rs::rng::SimRng;
+use darwin_rs::select::MinimizeSelector;
 
 fn city_distance(city: &[(f64, f64)], index1: usize, index2: usize) -> f64 {
     let (x1, y1) = city[index1];
@@ -75,15 +77,17 @@ struct CityItem {
 
 // Implement trait functions mutate and calculate_fitness:
 impl Individual for CityItem {
-    fn mutate(&mut self) {
-        let mut rng = rand::thread_rng();
+    type Fitness = f64;
+    type Context = ();
+
+    fn mutate(&mut self, _context: &(), rng: &mut SimRng) {
         // Keep stating position always the same: (random numbers from 1, not 0)
-        let index1: usize = rng.gen_range(1, self.cities.len());
-        let mut index2: usize = rng.gen_range(1, self.cities.len());
+        let index1: usize = rng.gen_range(1..self.cities.len());
+        let mut index2: usize = rng.gen_range(1..self.cities.len());
 
         // Small optimisation
         while index1 == index2 {
-            index2 = rng.gen_range(1, self.cities.len());
+            index2 = rng.gen_range(1..self.cities.len());
         }
 
         // Here we just swap the two indices. Compare this to example/tsp2 where we have
@@ -93,7 +97,7 @@ impl Individual for CityItem {
     }
 
     // fitness means here: the length of the route, the shorter the better
-    fn calculate_fitness(&mut self) -> f64 {
+    fn calculate_fitness(&mut self, _context: &()) -> f64 {
         let mut prev_index = &(self.cities.len() - 1);
         let mut length: f64 = 0.0;
 
@@ -154,7 +158,7 @@ fn main() {
         Err(simulation_builder::Error(simulation_builder::ErrorKind::EndIterationTooLow, _)) => println!("more than 10 iteratons needed"),
         Err(e) => println!("unexpected error: {}", e),
         Ok(mut tsp_simulation) => {
-            tsp_simulation.run();
+            tsp_simulation.run(&MinimizeSelector::new(4));
 
             tsp_simulation.print_fitness();
 
@@ -0,0 +1,465 @@
+//! This module defines the archive trait, that stores interesting individuals found during the
+//! simulation, and some common archives.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug};
+use std::mem;
+
+use rand::seq::SliceRandom;
+
+use fitness::Fitness;
+use individual::{Individual, IndividualWrapper};
+use rng::{self, SimRng};
+
+/// This trait can be implemented to store individuals outside of the populations, for example
+/// the best ones ever found or the most diverse ones.
+/// Register it with `PopulationBuilder::archive` (the archive is offered the survivors of
+/// that population after each iteration) or `SimulationBuilder::archive` (the archive is
+/// offered the survivors of all populations after each iteration).
+///
+/// An archive gets the same individuals again and again as long as they survive, so `insert`
+/// must ignore individuals that are already stored.
+pub trait Archive<T: Individual>: Debug + Send {
+    /// Offers a copy of the given individual to the archive. Returns true if it has been
+    /// stored.
+    fn insert(&mut self, candidate: &IndividualWrapper<T>) -> bool;
+    /// Returns up to `count` randomly chosen individuals of the archive.
+    fn sample(&self, count: usize, rng: &mut SimRng) -> Vec<&IndividualWrapper<T>>;
+    /// Returns the fittest individual of the archive.
+    fn best(&self) -> Option<&IndividualWrapper<T>>;
+    /// Returns the number of individuals in the archive.
+    fn len(&self) -> usize;
+    /// Returns true if the archive is empty.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Returns a boxed copy of the archive, needed to clone populations and simulations.
+    fn box_clone(&self) -> Box<dyn Archive<T>>;
+    /// Returns the estimated memory of the stored individuals in bytes, based on
+    /// `Individual::size_hint`. It is used by `Simulation::estimated_memory`.
+    fn size_hint(&self) -> usize {
+        let overhead = mem::size_of::<IndividualWrapper<T>>() - mem::size_of::<T>();
+        self.sample(self.len(), &mut rng::from_seed(0))
+            .iter()
+            .map(|member| member.individual.size_hint() + overhead)
+            .sum()
+    }
+}
+
+impl<T: Individual> Clone for Box<dyn Archive<T>> {
+    fn clone(&self) -> Box<dyn Archive<T>> {
+        self.box_clone()
+    }
+}
+
+/// Returns the fittest individual of the given ones.
+fn fittest<'a, T, I>(members: I) -> Option<&'a IndividualWrapper<T>>
+where
+    T: Individual + 'a,
+    I: Iterator<Item = &'a IndividualWrapper<T>>,
+{
+    members.fold(None, |best: Option<&IndividualWrapper<T>>, member| match best {
+        Some(best) if best.fitness <= member.fitness => Some(best),
+        _ => Some(member),
+    })
+}
+
+/// Keeps the `capacity` fittest individuals ever found, sorted by fitness (the fittest first).
+/// An individual with the same fitness as a member and a distance of 0.0 to it (see
+/// `Individual::distance`) is treated as a duplicate and not stored.
+#[derive(Debug, Clone)]
+pub struct HallOfFame<T: Individual + Debug> {
+    capacity: usize,
+    members: Vec<IndividualWrapper<T>>,
+}
+
+impl<T: Individual + Debug> HallOfFame<T> {
+    /// Creates an empty hall of fame for up to `capacity` individuals.
+    pub fn new(capacity: usize) -> HallOfFame<T> {
+        HallOfFame {
+            capacity,
+            members: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Returns all members, the fittest first.
+    pub fn members(&self) -> &[IndividualWrapper<T>] {
+        &self.members
+    }
+}
+
+impl<T: Individual + Debug + 'static> Archive<T> for HallOfFame<T> {
+    fn insert(&mut self, candidate: &IndividualWrapper<T>) -> bool {
+        if self.members.len() == self.capacity &&
+            self.members.last().map_or(true, |worst| candidate.fitness >= worst.fitness)
+        {
+            return false;
+        }
+
+        let duplicate = self.members.iter().any(|member| {
+            member.fitness == candidate.fitness &&
+                member.individual.distance(&candidate.individual) == 0.0
+        });
+        if duplicate {
+            return false;
+        }
+
+        let position = self.members
+            .iter()
+            .position(|member| candidate.fitness < member.fitness)
+            .unwrap_or(self.members.len());
+        self.members.insert(position, candidate.clone());
+        self.members.truncate(self.capacity);
+        true
+    }
+
+    fn sample(&self, count: usize, rng: &mut SimRng) -> Vec<&IndividualWrapper<T>> {
+        self.members.choose_multiple(rng, count).collect()
+    }
+
+    fn best(&self) -> Option<&IndividualWrapper<T>> {
+        self.members.first()
+    }
+
+    fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    fn box_clone(&self) -> Box<dyn Archive<T>> {
+        Box::new(self.clone())
+    }
+}
+
+/// Divides the fitness axis into boxes of the size `epsilon` and keeps the fittest individual
+/// of each box, for the `capacity` fittest boxes. In contrast to the `HallOfFame` the archive
+/// can not fill up with many nearly equal individuals, it keeps a spread of fitness levels.
+/// Individuals with a fitness that is not finite are never stored.
+#[derive(Debug, Clone)]
+pub struct EpsilonArchive<T: Individual + Debug> {
+    epsilon: f64,
+    capacity: usize,
+    /// At most one member per box, sorted by fitness.
+    members: Vec<IndividualWrapper<T>>,
+}
+
+impl<T: Individual + Debug> EpsilonArchive<T> {
+    /// Creates an empty archive with boxes of the size `epsilon` (must be larger than zero)
+    /// for up to `capacity` individuals.
+    pub fn new(epsilon: f64, capacity: usize) -> EpsilonArchive<T> {
+        EpsilonArchive {
+            epsilon,
+            capacity,
+            members: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Returns all members, the fittest first.
+    pub fn members(&self) -> &[IndividualWrapper<T>] {
+        &self.members
+    }
+
+    /// Returns the box of the given fitness (see `Fitness::to_f64`).
+    fn box_of(&self, fitness: &T::Fitness) -> i64 {
+        (fitness.to_f64() / self.epsilon).floor() as i64
+    }
+}
+
+impl<T: Individual + Debug + 'static> Archive<T> for EpsilonArchive<T> {
+    fn insert(&mut self, candidate: &IndividualWrapper<T>) -> bool {
+        if !candidate.fitness.to_f64().is_finite() || self.capacity == 0 {
+            return false;
+        }
+
+        let candidate_box = self.box_of(&candidate.fitness);
+        if let Some(index) = self.members
+            .iter()
+            .position(|member| self.box_of(&member.fitness) == candidate_box)
+        {
+            if candidate.fitness >= self.members[index].fitness {
+                return false;
+            }
+            self.members.remove(index);
+        }
+
+        let position = self.members
+            .iter()
+            .position(|member| candidate.fitness < member.fitness)
+            .unwrap_or(self.members.len());
+        if position >= self.capacity {
+            return false;
+        }
+        self.members.insert(position, candidate.clone());
+        self.members.truncate(self.capacity);
+        true
+    }
+
+    fn sample(&self, count: usize, rng: &mut SimRng) -> Vec<&IndividualWrapper<T>> {
+        self.members.choose_multiple(rng, count).collect()
+    }
+
+    fn best(&self) -> Option<&IndividualWrapper<T>> {
+        self.members.first()
+    }
+
+    fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    fn box_clone(&self) -> Box<dyn Archive<T>> {
+        Box::new(self.clone())
+    }
+}
+
+/// Keeps individuals that are different from the ones already stored, for novelty search.
+/// The novelty of an individual is its mean distance (see `Individual::distance`) to the `k`
+/// nearest members. Individuals with a novelty of at least `threshold` are stored, if the
+/// archive is full the oldest member is removed.
+#[derive(Debug, Clone)]
+pub struct NoveltyArchive<T: Individual + Debug> {
+    k: usize,
+    threshold: f64,
+    capacity: usize,
+    /// The members in the order they have been stored.
+    members: Vec<IndividualWrapper<T>>,
+}
+
+impl<T: Individual + Debug> NoveltyArchive<T> {
+    /// Creates an empty archive for up to `capacity` individuals.
+    pub fn new(k: usize, threshold: f64, capacity: usize) -> NoveltyArchive<T> {
+        NoveltyArchive {
+            k: k.max(1),
+            threshold,
+            capacity,
+            members: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Returns all members, the oldest first.
+    pub fn members(&self) -> &[IndividualWrapper<T>] {
+        &self.members
+    }
+
+    /// Returns the novelty of the given individual: the mean distance to the `k` nearest
+    /// members, infinity if the archive is empty.
+    pub fn novelty(&self, individual: &T) -> f64 {
+        if self.members.is_empty() {
+            return f64::INFINITY;
+        }
+
+        let mut distances: Vec<f64> = self.members
+            .iter()
+            .map(|member| individual.distance(&member.individual))
+            .collect();
+        distances.sort_by(|a, b| a.partial_cmp(b).unwrap_or(::std::cmp::Ordering::Greater));
+        let nearest = &distances[..self.k.min(distances.len())];
+
+        nearest.iter().sum::<f64>() / nearest.len() as f64
+    }
+}
+
+impl<T: Individual + Debug + 'static> Archive<T> for NoveltyArchive<T> {
+    fn insert(&mut self, candidate: &IndividualWrapper<T>) -> bool {
+        if self.capacity == 0 || self.novelty(&candidate.individual) < self.threshold {
+            return false;
+        }
+
+        if self.members.len() == self.capacity {
+            self.members.remove(0);
+        }
+        self.members.push(candidate.clone());
+        true
+    }
+
+    fn sample(&self, count: usize, rng: &mut SimRng) -> Vec<&IndividualWrapper<T>> {
+        self.members.choose_multiple(rng, count).collect()
+    }
+
+    fn best(&self) -> Option<&IndividualWrapper<T>> {
+        fittest(self.members.iter())
+    }
+
+    fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    fn box_clone(&self) -> Box<dyn Archive<T>> {
+        Box::new(self.clone())
+    }
+}
+
+/// The MAP-Elites archive: the user defined `descriptor` maps each individual to a cell of a
+/// grid (for example its size and its speed, each divided into ranges). The archive keeps the
+/// fittest individual of each cell, so it shows the best solution for each kind of behaviour.
+#[derive(Clone)]
+pub struct MapElites<T: Individual + Debug> {
+    descriptor: fn(&T) -> Vec<usize>,
+    cells: BTreeMap<Vec<usize>, IndividualWrapper<T>>,
+}
+
+impl<T: Individual + Debug> Debug for MapElites<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("MapElites").field("cells", &self.cells).finish()
+    }
+}
+
+impl<T: Individual + Debug> MapElites<T> {
+    /// Creates an empty archive, `descriptor` returns the coordinates of the cell of an
+    /// individual.
+    pub fn new(descriptor: fn(&T) -> Vec<usize>) -> MapElites<T> {
+        MapElites {
+            descriptor,
+            cells: BTreeMap::new(),
+        }
+    }
+
+    /// Returns all occupied cells with their fittest individual.
+    pub fn cells(&self) -> &BTreeMap<Vec<usize>, IndividualWrapper<T>> {
+        &self.cells
+    }
+}
+
+impl<T: Individual + Debug + 'static> Archive<T> for MapElites<T> {
+    fn insert(&mut self, candidate: &IndividualWrapper<T>) -> bool {
+        let cell = (self.descriptor)(&candidate.individual);
+
+        match self.cells.get(&cell) {
+            Some(elite) if elite.fitness <= candidate.fitness => false,
+            _ => {
+                self.cells.insert(cell, candidate.clone());
+                true
+            }
+        }
+    }
+
+    fn sample(&self, count: usize, rng: &mut SimRng) -> Vec<&IndividualWrapper<T>> {
+        let elites: Vec<&IndividualWrapper<T>> = self.cells.values().collect();
+        elites.choose_multiple(rng, count).cloned().collect()
+    }
+
+    fn best(&self) -> Option<&IndividualWrapper<T>> {
+        fittest(self.cells.values())
+    }
+
+    fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn box_clone(&self) -> Box<dyn Archive<T>> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use archive::*;
+    use individual::{Individual, IndividualWrapper};
+    use rng;
+
+    #[derive(Debug, Clone)]
+    struct Point {
+        x: f64,
+    }
+
+    impl Individual for Point {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {}
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            self.x.abs()
+        }
+
+        fn reset(&mut self) {}
+
+        fn distance(&self, other: &Point) -> f64 {
+            (self.x - other.x).abs()
+        }
+    }
+
+    fn wrap(x: f64) -> IndividualWrapper<Point> {
+        IndividualWrapper {
+            individual: Point { x },
+            fitness: x.abs(),
+            num_of_mutations: 1,
+            id: 1,
+            age: 0,
+            dirty: false,
+            case_errors: Vec::new(),
+            objectives: Vec::new(),
+        }
+    }
+
+    fn fitness<T: Individual + Debug>(members: &[IndividualWrapper<T>]) -> Vec<T::Fitness> {
+        members.iter().map(|member| member.fitness.clone()).collect()
+    }
+
+    #[test]
+    fn hall_of_fame_keeps_fittest() {
+        let mut archive = HallOfFame::new(3);
+        for &x in &[5.0, 3.0, 4.0, 1.0, 3.0, 9.0, -2.0] {
+            archive.insert(&wrap(x));
+        }
+
+        assert_eq!(fitness(archive.members()), vec![1.0, 2.0, 3.0]);
+        assert_eq!(archive.best().unwrap().individual.x, 1.0);
+        assert!(!archive.insert(&wrap(3.0)));
+        assert_eq!(archive.sample(10, &mut rng::from_seed(1)).len(), 3);
+    }
+
+    #[test]
+    fn epsilon_archive_keeps_one_per_box() {
+        let mut archive = EpsilonArchive::new(1.0, 3);
+        for &x in &[1.5, 1.2, 1.7, 2.9, 2.5, 7.0, 8.0] {
+            archive.insert(&wrap(x));
+        }
+
+        assert_eq!(fitness(archive.members()), vec![1.2, 2.5, 7.0]);
+        assert!(archive.insert(&wrap(0.5)));
+        assert_eq!(fitness(archive.members()), vec![0.5, 1.2, 2.5]);
+        assert!(!archive.insert(&wrap(f64::NAN)));
+    }
+
+    #[test]
+    fn novelty_archive_rejects_similar() {
+        let mut archive = NoveltyArchive::new(1, 1.0, 2);
+        assert!(archive.insert(&wrap(0.0)));
+        assert!(!archive.insert(&wrap(0.5)));
+        assert!(archive.insert(&wrap(2.0)));
+        assert_eq!(archive.novelty(&Point { x: 3.0 }), 1.0);
+
+        // The oldest member is removed.
+        assert!(archive.insert(&wrap(5.0)));
+        assert_eq!(fitness(archive.members()), vec![2.0, 5.0]);
+        assert_eq!(archive.best().unwrap().fitness, 2.0);
+    }
+
+    #[test]
+    fn map_elites_keeps_best_per_cell() {
+        let mut archive = MapElites::new(|point: &Point| vec![(point.x < 0.0) as usize]);
+        for &x in &[3.0, -4.0, 1.0, 2.0, -6.0] {
+            archive.insert(&wrap(x));
+        }
+
+        assert_eq!(archive.len(), 2);
+        assert_eq!(archive.cells()[&vec![0]].individual.x, 1.0);
+        assert_eq!(archive.cells()[&vec![1]].individual.x, -4.0);
+        assert_eq!(archive.best().unwrap().individual.x, 1.0);
+
+        let boxed: Box<dyn Archive<Point>> = Box::new(archive);
+        assert_eq!(boxed.clone().len(), 2);
+    }
+}
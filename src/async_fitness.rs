@@ -0,0 +1,252 @@
+//! This module defines the asynchronous fitness evaluation: individuals whose fitness needs
+//! HTTP calls, a database or an external simulator are evaluated concurrently on a tokio
+//! runtime instead of blocking a thread each. Only available with the feature `tokio`.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+//! Implement `AsyncIndividual` in addition to `Individual` and give each population an
+//! `AsyncEvaluator` with `PopulationBuilder::batch_evaluator`.
+
+use std::future::Future;
+use std::io;
+use std::iter::Enumerate;
+use std::pin::Pin;
+use std::slice;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use tokio::runtime::{self, Runtime};
+
+use individual::{BatchEvaluator, Individual};
+use population::catch_panic;
+
+/// The future returned by `AsyncIndividual::calculate_fitness_async`.
+pub type FitnessFuture<F> = Pin<Box<dyn Future<Output = F> + Send>>;
+
+/// Implement this trait in addition to `Individual` if the fitness is calculated
+/// asynchronously. `Individual::calculate_fitness` is still used for the few single
+/// evaluations outside of the `AsyncEvaluator`, it can for example block on a runtime.
+pub trait AsyncIndividual: Individual {
+    /// Returns the future that calculates the fitness of this individual. Copy what it needs
+    /// from the individual and the context into the future, for example
+    /// `Box::pin(async move { ... })`.
+    fn calculate_fitness_async(&self, context: Arc<Self::Context>) -> FitnessFuture<Self::Fitness>;
+}
+
+/// The `AsyncEvaluator` type. Evaluates the new individuals of a population concurrently on
+/// its own tokio runtime, at most `concurrency` of them at the same time, see
+/// `PopulationBuilder::batch_evaluator`. One evaluator can be shared by several populations.
+#[derive(Debug)]
+pub struct AsyncEvaluator {
+    /// The runtime that drives the futures, for example the timers and sockets they use.
+    runtime: Runtime,
+    /// The maximum number of futures in flight.
+    concurrency: usize,
+}
+
+impl AsyncEvaluator {
+    /// Creates an evaluator with a new multi threaded runtime that evaluates at most
+    /// `concurrency` individuals at the same time (at least one).
+    pub fn new(concurrency: usize) -> io::Result<AsyncEvaluator> {
+        let runtime = runtime::Builder::new_multi_thread().enable_all().build()?;
+        Ok(AsyncEvaluator { runtime, concurrency: concurrency.max(1) })
+    }
+
+    /// Returns the maximum number of individuals evaluated at the same time.
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+}
+
+impl<T: AsyncIndividual> BatchEvaluator<T> for AsyncEvaluator {
+    fn evaluate(
+        &self,
+        individuals: &[&T],
+        context: &Arc<T::Context>,
+    ) -> Vec<Result<T::Fitness, String>> {
+        self.runtime.block_on(Bounded {
+            pending: individuals.iter().enumerate(),
+            context,
+            running: Vec::with_capacity(self.concurrency),
+            results: individuals.iter().map(|_| None).collect(),
+            concurrency: self.concurrency,
+        })
+    }
+}
+
+/// Polls the fitness futures of the given individuals, at most `concurrency` at the same time.
+/// A panic in an individual only fails its own evaluation.
+struct Bounded<'a, T: AsyncIndividual> {
+    /// The individuals whose future has not been started yet, with their index.
+    pending: Enumerate<slice::Iter<'a, &'a T>>,
+    /// The context of the individuals.
+    context: &'a Arc<T::Context>,
+    /// The futures in flight, with the index of their individual.
+    running: Vec<(usize, FitnessFuture<T::Fitness>)>,
+    /// The results in the order of the individuals.
+    results: Vec<Option<Result<T::Fitness, String>>>,
+    /// The maximum number of futures in flight.
+    concurrency: usize,
+}
+
+// The fields are never pinned, the futures are boxed.
+impl<'a, T: AsyncIndividual> Unpin for Bounded<'a, T> {}
+
+impl<'a, T: AsyncIndividual> Future for Bounded<'a, T> {
+    type Output = Vec<Result<T::Fitness, String>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+        loop {
+            while this.running.len() < this.concurrency {
+                let (index, individual) = match this.pending.next() {
+                    Some(next) => next,
+                    None => break,
+                };
+                let context = this.context.clone();
+                match catch_panic(|| individual.calculate_fitness_async(context)) {
+                    Ok(future) => this.running.push((index, future)),
+                    Err(message) => this.results[index] = Some(Err(message)),
+                }
+            }
+            if this.running.is_empty() {
+                let results = this.results.iter_mut().map(|result| result.take());
+                return Poll::Ready(results.map(|result| result.expect("evaluated")).collect());
+            }
+
+            let mut finished = false;
+            let mut position = 0;
+            while position < this.running.len() {
+                let polled = catch_panic(|| this.running[position].1.as_mut().poll(cx));
+                let result = match polled {
+                    Ok(Poll::Pending) => {
+                        position += 1;
+                        continue;
+                    }
+                    Ok(Poll::Ready(fitness)) => Ok(fitness),
+                    Err(message) => Err(message),
+                };
+                let (index, _) = this.running.swap_remove(position);
+                this.results[index] = Some(result);
+                finished = true;
+            }
+            // Start the next futures if there is room now, otherwise wait for a wake up.
+            if !finished {
+                return Poll::Pending;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll};
+
+    use super::{AsyncEvaluator, AsyncIndividual, FitnessFuture};
+    use individual::Individual;
+    use population_builder::PopulationBuilder;
+    use rng::SimRng;
+
+    /// Counts the futures in flight and the most at the same time.
+    #[derive(Debug, Default)]
+    struct Load {
+        current: AtomicUsize,
+        most: AtomicUsize,
+    }
+
+    /// Returns its value after it was polled a few times, like a slow request.
+    struct Slow {
+        value: f64,
+        polls: u32,
+        load: Arc<Load>,
+    }
+
+    impl Future for Slow {
+        type Output = f64;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<f64> {
+            if self.polls == 0 {
+                let current = self.load.current.fetch_add(1, Ordering::SeqCst) + 1;
+                self.load.most.fetch_max(current, Ordering::SeqCst);
+            }
+            self.polls += 1;
+            if self.polls < 3 {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            self.load.current.fetch_sub(1, Ordering::SeqCst);
+            assert!(self.value >= 0.0, "negative value");
+            Poll::Ready(self.value)
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct Remote {
+        x: f64,
+    }
+
+    impl Individual for Remote {
+        type Fitness = f64;
+        type Context = Arc<Load>;
+
+        fn mutate(&mut self, _context: &Arc<Load>, _rng: &mut SimRng) {
+            self.x -= 1.0;
+        }
+
+        fn calculate_fitness(&mut self, _context: &Arc<Load>) -> f64 {
+            panic!("evaluated synchronously");
+        }
+
+        fn reset(&mut self) {}
+    }
+
+    impl AsyncIndividual for Remote {
+        fn calculate_fitness_async(&self, context: Arc<Arc<Load>>) -> FitnessFuture<f64> {
+            let load = (*context).clone();
+            Box::pin(Slow { value: self.x, polls: 0, load })
+        }
+    }
+
+    #[test]
+    fn fitness_is_evaluated_concurrently() {
+        let load = Arc::new(Load::default());
+        let evaluator = Arc::new(AsyncEvaluator::new(3).unwrap());
+        let initial: Vec<Remote> = (0..10).map(|x| Remote { x: f64::from(x) }).collect();
+        let mut population = PopulationBuilder::<Remote>::new()
+            .initial_population(&initial)
+            .context(load.clone())
+            .batch_evaluator(evaluator)
+            .finalize()
+            .unwrap();
+        population.calculate_fitness();
+
+        let fitness: Vec<f64> = population.population.iter().map(|w| w.fitness).collect();
+        let mut expected: Vec<f64> = (0..10).map(f64::from).collect();
+        assert_eq!(fitness, expected);
+        assert_eq!(load.most.load(Ordering::SeqCst), 3);
+        assert_eq!(load.current.load(Ordering::SeqCst), 0);
+
+        // The negative individual panics, only its own evaluation fails.
+        population.population[9].individual.x = -1.0;
+        population.population[9].dirty = true;
+        population.calculate_fitness();
+        assert!(population.panicked.is_some());
+        expected.pop();
+        let fitness: Vec<f64> = population.population[..9].iter().map(|w| w.fitness).collect();
+        assert_eq!(fitness, expected);
+    }
+}
@@ -0,0 +1,249 @@
+//! This module defines standard benchmark functions for continuous optimization and
+//! transformations (shift, rotation, noise) that can be composed with them.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+
+use std::f64::consts::PI;
+use std::fmt::Debug;
+use std::sync::Mutex;
+
+use rand::Rng;
+
+use rng::{self, SimRng};
+
+/// A benchmark function that should be minimized. All the base functions have their
+/// optimum (value 0.0) at the origin.
+///
+/// The transformations can be chained, for example:
+/// `Rastrigin.rotated(random_rotation(10, &mut rng)).shifted(shift).noisy(0.1, 42)`
+pub trait Objective: Debug + Send + Sync {
+    /// Calculates the value of the function at position `x`.
+    fn evaluate(&self, x: &[f64]) -> f64;
+
+    /// Moves the optimum of the function to `shift`: evaluates `f(x - shift)`.
+    fn shifted(self, shift: Vec<f64>) -> Shifted<Self>
+    where
+        Self: Sized,
+    {
+        Shifted { function: self, shift }
+    }
+
+    /// Rotates the search space: evaluates `f(M x)`. The matrix `M` should be orthogonal,
+    /// see `random_rotation`. This makes separable functions non-separable.
+    fn rotated(self, matrix: Vec<Vec<f64>>) -> Rotated<Self>
+    where
+        Self: Sized,
+    {
+        Rotated { function: self, matrix }
+    }
+
+    /// Adds multiplicative noise: evaluates `f(x) * (1 + sigma * |N(0, 1)|)`.
+    /// The noise is drawn from a random number generator with the given seed.
+    fn noisy(self, sigma: f64, seed: u64) -> Noisy<Self>
+    where
+        Self: Sized,
+    {
+        Noisy {
+            function: self,
+            sigma,
+            rng: Mutex::new(rng::from_seed(seed)),
+        }
+    }
+}
+
+/// The sphere function: sum of x_i^2.
+#[derive(Debug, Clone, Copy)]
+pub struct Sphere;
+
+impl Objective for Sphere {
+    fn evaluate(&self, x: &[f64]) -> f64 {
+        x.iter().map(|xi| xi * xi).sum()
+    }
+}
+
+/// The Rastrigin function, highly multimodal with a regular grid of local minima.
+#[derive(Debug, Clone, Copy)]
+pub struct Rastrigin;
+
+impl Objective for Rastrigin {
+    fn evaluate(&self, x: &[f64]) -> f64 {
+        10.0 * x.len() as f64 +
+            x.iter().map(|xi| xi * xi - 10.0 * (2.0 * PI * xi).cos()).sum::<f64>()
+    }
+}
+
+/// The Rosenbrock function, shifted so that the optimum is at the origin
+/// (the original optimum is at (1, 1, ..., 1)).
+#[derive(Debug, Clone, Copy)]
+pub struct Rosenbrock;
+
+impl Objective for Rosenbrock {
+    fn evaluate(&self, x: &[f64]) -> f64 {
+        x.windows(2)
+            .map(|w| {
+                let (a, b) = (w[0] + 1.0, w[1] + 1.0);
+                100.0 * (b - a * a).powi(2) + (1.0 - a).powi(2)
+            })
+            .sum()
+    }
+}
+
+/// The Griewank function, many widespread local minima.
+#[derive(Debug, Clone, Copy)]
+pub struct Griewank;
+
+impl Objective for Griewank {
+    fn evaluate(&self, x: &[f64]) -> f64 {
+        let sum: f64 = x.iter().map(|xi| xi * xi).sum::<f64>() / 4000.0;
+        let product: f64 = x.iter()
+            .enumerate()
+            .map(|(i, xi)| (xi / ((i + 1) as f64).sqrt()).cos())
+            .product();
+        sum - product + 1.0
+    }
+}
+
+/// The Ackley function, a nearly flat outer region with a deep hole at the origin.
+#[derive(Debug, Clone, Copy)]
+pub struct Ackley;
+
+impl Objective for Ackley {
+    fn evaluate(&self, x: &[f64]) -> f64 {
+        let n = x.len() as f64;
+        let squares: f64 = x.iter().map(|xi| xi * xi).sum::<f64>() / n;
+        let cosines: f64 = x.iter().map(|xi| (2.0 * PI * xi).cos()).sum::<f64>() / n;
+        -20.0 * (-0.2 * squares.sqrt()).exp() - cosines.exp() + 20.0 + ::std::f64::consts::E
+    }
+}
+
+/// A function with a shifted optimum, see `Objective::shifted`.
+#[derive(Debug, Clone)]
+pub struct Shifted<F> {
+    function: F,
+    shift: Vec<f64>,
+}
+
+impl<F: Objective> Objective for Shifted<F> {
+    fn evaluate(&self, x: &[f64]) -> f64 {
+        let moved: Vec<f64> = x.iter().zip(self.shift.iter()).map(|(xi, si)| xi - si).collect();
+        self.function.evaluate(&moved)
+    }
+}
+
+/// A function with a rotated search space, see `Objective::rotated`.
+#[derive(Debug, Clone)]
+pub struct Rotated<F> {
+    function: F,
+    matrix: Vec<Vec<f64>>,
+}
+
+impl<F: Objective> Objective for Rotated<F> {
+    fn evaluate(&self, x: &[f64]) -> f64 {
+        let rotated: Vec<f64> = self.matrix
+            .iter()
+            .map(|row| row.iter().zip(x.iter()).map(|(m, xi)| m * xi).sum())
+            .collect();
+        self.function.evaluate(&rotated)
+    }
+}
+
+/// A function with noise, see `Objective::noisy`.
+#[derive(Debug)]
+pub struct Noisy<F> {
+    function: F,
+    sigma: f64,
+    rng: Mutex<SimRng>,
+}
+
+impl<F: Objective> Objective for Noisy<F> {
+    fn evaluate(&self, x: &[f64]) -> f64 {
+        let noise = standard_normal(&mut *self.rng.lock().expect("noise rng poisoned")).abs();
+        self.function.evaluate(x) * (1.0 + self.sigma * noise)
+    }
+}
+
+/// Draws a standard normal distributed value (Box-Muller transform).
+fn standard_normal<R: Rng>(rng: &mut R) -> f64 {
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen::<f64>();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+}
+
+/// Creates a random orthogonal (rotation) matrix of the given dimension, by orthonormalizing
+/// a matrix of normal distributed values (Gram-Schmidt).
+pub fn random_rotation<R: Rng>(dimension: usize, rng: &mut R) -> Vec<Vec<f64>> {
+    let mut rows: Vec<Vec<f64>> = Vec::with_capacity(dimension);
+
+    while rows.len() < dimension {
+        let mut row: Vec<f64> = (0..dimension).map(|_| standard_normal(rng)).collect();
+
+        for other in &rows {
+            let dot: f64 = row.iter().zip(other.iter()).map(|(a, b)| a * b).sum();
+            for (value, o) in row.iter_mut().zip(other.iter()) {
+                *value -= dot * o;
+            }
+        }
+
+        let norm = row.iter().map(|value| value * value).sum::<f64>().sqrt();
+        // Numerically (almost) linear dependent, just try again.
+        if norm > 1e-8 {
+            rows.push(row.into_iter().map(|value| value / norm).collect());
+        }
+    }
+
+    rows
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rng;
+
+    #[test]
+    fn optimum_at_origin() {
+        let origin = vec![0.0; 5];
+        assert_eq!(Sphere.evaluate(&origin), 0.0);
+        assert_eq!(Rastrigin.evaluate(&origin), 0.0);
+        assert_eq!(Rosenbrock.evaluate(&origin), 0.0);
+        assert!(Griewank.evaluate(&origin).abs() < 1e-12);
+        assert!(Ackley.evaluate(&origin).abs() < 1e-12);
+    }
+
+    #[test]
+    fn shifted_optimum() {
+        let shift = vec![1.0, -2.0, 3.0];
+        let function = Rastrigin.shifted(shift.clone());
+        assert_eq!(function.evaluate(&shift), 0.0);
+        assert!(function.evaluate(&[0.0, 0.0, 0.0]) > 0.0);
+    }
+
+    #[test]
+    fn rotation_is_orthogonal() {
+        let matrix = random_rotation(6, &mut rng::from_seed(5));
+        let x = vec![1.0, 2.0, -3.0, 0.5, 0.0, 4.0];
+        let function = Sphere.rotated(matrix);
+        // Rotation does not change the length of a vector.
+        assert!((function.evaluate(&x) - Sphere.evaluate(&x)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn noise_is_reproducible() {
+        let x = vec![1.0, 1.0];
+        let noisy1 = Sphere.noisy(0.5, 3);
+        let noisy2 = Sphere.noisy(0.5, 3);
+        let value = noisy1.evaluate(&x);
+        assert_eq!(value, noisy2.evaluate(&x));
+        assert!(value >= 2.0);
+    }
+}
@@ -0,0 +1,137 @@
+//! This module defines a bounded cache for fitness values, so identical genotypes are
+//! evaluated only once.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+
+use std::collections::{BTreeMap, HashMap};
+
+/// The `FitnessCache` type. Maps genome keys (see `Individual::genome_key`) to fitness values.
+/// If the cache is full the least recently used entry is dropped.
+#[derive(Debug, Clone)]
+pub struct FitnessCache<F = f64> {
+    /// The maximum number of entries.
+    capacity: usize,
+    /// The fitness and the time of the last use for each key.
+    entries: HashMap<u64, (F, u64)>,
+    /// The keys by the time of their last use, the oldest first.
+    recently_used: BTreeMap<u64, u64>,
+    /// Counts the uses, this is the "time" of the last use.
+    clock: u64,
+}
+
+impl<F: Clone> Default for FitnessCache<F> {
+    fn default() -> FitnessCache<F> {
+        FitnessCache {
+            capacity: 0,
+            entries: HashMap::new(),
+            recently_used: BTreeMap::new(),
+            clock: 0,
+        }
+    }
+}
+
+impl<F: Clone> FitnessCache<F> {
+    /// Creates an empty cache for up to `capacity` (at least one) entries.
+    pub fn new(capacity: usize) -> FitnessCache<F> {
+        FitnessCache {
+            capacity: capacity.max(1),
+            ..FitnessCache::default()
+        }
+    }
+
+    /// Returns the fitness stored for the given key, if any.
+    pub fn get(&mut self, key: u64) -> Option<F> {
+        let stamp = self.tick();
+
+        match self.entries.get_mut(&key) {
+            Some(entry) => {
+                self.recently_used.remove(&entry.1);
+                self.recently_used.insert(stamp, key);
+                entry.1 = stamp;
+                Some(entry.0.clone())
+            }
+            None => None,
+        }
+    }
+
+    /// Stores the fitness for the given key. Drops the least recently used entry if the cache
+    /// is full.
+    pub fn insert(&mut self, key: u64, fitness: F) {
+        let stamp = self.tick();
+
+        if let Some((_, old_stamp)) = self.entries.insert(key, (fitness, stamp)) {
+            self.recently_used.remove(&old_stamp);
+        } else if self.entries.len() > self.capacity {
+            let oldest = self.recently_used.keys().next().cloned();
+            if let Some(oldest) = oldest {
+                if let Some(dropped) = self.recently_used.remove(&oldest) {
+                    self.entries.remove(&dropped);
+                }
+            }
+        }
+        self.recently_used.insert(stamp, key);
+    }
+
+    /// Returns the number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes all entries, for example when the fitness function changes.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recently_used.clear();
+    }
+
+    /// Advances the clock and returns the new time.
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FitnessCache;
+
+    #[test]
+    fn least_recently_used_is_dropped() {
+        let mut cache = FitnessCache::new(2);
+        assert_eq!(cache.get(1), None);
+
+        cache.insert(1, 1.5);
+        cache.insert(2, 2.5);
+        assert_eq!(cache.get(1), Some(1.5));
+
+        // Key 2 has not been used for the longest time.
+        cache.insert(3, 3.5);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(2), None);
+        assert_eq!(cache.get(1), Some(1.5));
+        assert_eq!(cache.get(3), Some(3.5));
+
+        // Updating an entry does not drop anything.
+        cache.insert(3, 4.5);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(3), Some(4.5));
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,287 @@
+//! This module defines the checkpoints of a running simulation: the state that is written to a
+//! file by `Simulation::checkpoint` and restored by `Simulation::resume`, so long runs survive
+//! crashes and can be continued with new stop criteria. Only available with the feature
+//! `serialize`, the individuals and their fitness must implement `Serialize` and `Deserialize`.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+//! A checkpoint contains the individuals, the random number generators and the counters of all
+//! populations and the result so far. The configuration (the operators, observers, archives,
+//! the context and the scheduled parameters) is code and not stored: build the simulation
+//! again with the same populations and call `resume` before `run`.
+
+use std::error::Error;
+use std::fmt::{self, Debug};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use individual::{Individual, IndividualWrapper};
+use persist::{self, Format, PersistError};
+use population::{Population, RetireReason};
+use rng::RngState;
+use simulation::{Retirement, Simulation};
+use statistics::{Counters, FitnessStats};
+use history::FitnessHistory;
+
+/// The `CheckpointError` type. The errors of writing and restoring checkpoints.
+#[derive(Debug)]
+pub enum CheckpointError {
+    /// The file could not be read or written, or the checkpoint could not be encoded or
+    /// decoded.
+    File(PersistError),
+    /// The checkpoint has other populations (number or ids) than the simulation.
+    PopulationMismatch,
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CheckpointError::File(ref error) => write!(f, "checkpoint: {}", error),
+            CheckpointError::PopulationMismatch => {
+                write!(f, "the populations of the checkpoint don't match the simulation")
+            }
+        }
+    }
+}
+
+impl Error for CheckpointError {}
+
+impl From<PersistError> for CheckpointError {
+    fn from(error: PersistError) -> CheckpointError {
+        CheckpointError::File(error)
+    }
+}
+
+/// The result of the checkpoint operations.
+pub type Result<V> = ::std::result::Result<V, CheckpointError>;
+
+/// The `PopulationState` type. The state of one population in a checkpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T: Serialize, T::Fitness: Serialize",
+    deserialize = "T: Deserialize<'de>, T::Fitness: Deserialize<'de>"
+))]
+pub struct PopulationState<T: Individual> {
+    /// The id of the population, see `Population::id`.
+    pub id: u32,
+    /// The individuals of the population.
+    pub population: Vec<IndividualWrapper<T>>,
+    /// The children and migrants that have not been evaluated yet, see `Population::hybrids`.
+    pub hybrids: Vec<T>,
+    /// The state of the evolution random number generator.
+    pub rng: RngState,
+    /// The current reset limit, see `Population::reset_limit`.
+    pub reset_limit: u32,
+    /// The current reset counter, see `Population::reset_counter`.
+    pub reset_counter: u32,
+    /// How often the population has found the fittest individual.
+    pub fitness_counter: u64,
+    /// The weight, iteration and streak of the penalty scheme, see `Constraint`.
+    pub penalty: Option<(f64, u32, i64)>,
+    /// The number of resets so far, see `Population::resets`.
+    pub resets: u32,
+    /// The iterations without improvement, see `Population::stagnation`.
+    pub stagnation: u32,
+    /// Why the population has retired, if it has.
+    pub retired: Option<RetireReason>,
+}
+
+impl<T: Individual + Send + Sync + Clone + Debug> PopulationState<T> {
+    /// Captures the state of the given population.
+    pub fn of(population: &Population<T>) -> PopulationState<T> {
+        PopulationState {
+            id: population.id,
+            population: population.population.clone(),
+            hybrids: population.hybrids.clone(),
+            rng: population.rng_state(),
+            reset_limit: population.reset_limit,
+            reset_counter: population.reset_counter,
+            fitness_counter: population.fitness_counter,
+            penalty: population
+                .constraint
+                .as_ref()
+                .map(|constraint| (constraint.weight, constraint.iteration, constraint.streak)),
+            resets: population.resets,
+            stagnation: population.stagnation,
+            retired: population.retired,
+        }
+    }
+
+    /// Restores the captured state into the given population.
+    pub fn restore(self, population: &mut Population<T>) {
+        population.num_of_individuals = self.population.len() as u32;
+        population.population = self.population;
+        population.hybrids = self.hybrids;
+        population.restore_rng_state(&self.rng);
+        population.reset_limit = self.reset_limit;
+        population.reset_counter = self.reset_counter;
+        population.fitness_counter = self.fitness_counter;
+        population.resets = self.resets;
+        population.stagnation = self.stagnation;
+        population.retired = self.retired;
+        if let (Some(constraint), Some((weight, iteration, streak))) =
+            (population.constraint.as_mut(), self.penalty)
+        {
+            constraint.weight = weight;
+            constraint.iteration = iteration;
+            constraint.streak = streak;
+        }
+    }
+}
+
+/// The `Checkpoint` type. The state of a running simulation after an iteration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "T: Serialize, T::Fitness: Serialize",
+    deserialize = "T: Deserialize<'de>, T::Fitness: Deserialize<'de>"
+))]
+pub struct Checkpoint<T: Individual> {
+    /// The number of iterations done so far.
+    pub iteration: u32,
+    /// The state of each population, in the order of `Simulation::habitat`.
+    pub populations: Vec<PopulationState<T>>,
+    /// The fitness at the start of the run, see `SimulationResult::original_fitness`.
+    pub original_fitness: T::Fitness,
+    /// The improvement factor so far.
+    pub improvement_factor: f64,
+    /// The global fittest individuals so far.
+    pub fittest: Vec<IndividualWrapper<T>>,
+    /// The work done by all populations so far.
+    pub counters: Counters,
+    /// How long each iteration took so far, see `SimulationResult::iteration_times`.
+    pub iteration_times: Vec<Duration>,
+    /// The time spent so far, see `SimulationResult::elapsed`.
+    pub elapsed: Duration,
+    /// The iteration in which the fittest individual was found.
+    pub best_iteration: u32,
+    /// The population that found the fittest individual.
+    pub best_population_id: u32,
+    /// The populations that have retired so far.
+    pub retirements: Vec<Retirement>,
+    /// The fitness statistics so far, see `SimulationResult::stats_history`.
+    pub stats_history: Vec<(u32, u32, FitnessStats)>,
+    /// The fitness history so far, see `SimulationResult::history`.
+    pub history: Option<FitnessHistory<T>>,
+    /// The counter for sharing the fittest individual, see `Simulation::share_counter`.
+    pub share_counter: u32,
+}
+
+impl<T: Individual + Send + Sync + Clone + Debug> Checkpoint<T> {
+    /// Captures the state of the given simulation.
+    pub fn of(simulation: &Simulation<T>) -> Checkpoint<T> {
+        let result = &simulation.simulation_result;
+
+        Checkpoint {
+            iteration: result.iteration_counter,
+            populations: simulation.habitat.iter().map(PopulationState::of).collect(),
+            original_fitness: result.original_fitness.clone(),
+            improvement_factor: result.improvement_factor,
+            fittest: result.fittest.clone(),
+            counters: result.counters,
+            iteration_times: result.iteration_times.clone(),
+            elapsed: result.elapsed,
+            best_iteration: result.best_iteration,
+            best_population_id: result.best_population_id,
+            retirements: result.retirements.clone(),
+            stats_history: result.stats_history.clone(),
+            history: result.history.clone(),
+            share_counter: simulation.share_counter,
+        }
+    }
+
+    /// Restores the captured state into the given simulation, which must have the same
+    /// populations (same number and ids). The next `run` continues after the captured
+    /// iteration, see `Simulation::resumed`.
+    pub fn restore(self, simulation: &mut Simulation<T>) -> Result<()> {
+        let ids = self.populations.iter().map(|state| state.id);
+        if !ids.eq(simulation.habitat.iter().map(|population| population.id)) {
+            return Err(CheckpointError::PopulationMismatch);
+        }
+
+        for (state, population) in self.populations.into_iter().zip(&mut simulation.habitat) {
+            state.restore(population);
+        }
+
+        let configuration = simulation.configuration();
+        let result = &mut simulation.simulation_result;
+        result.iteration_counter = self.iteration;
+        result.original_fitness = self.original_fitness;
+        result.improvement_factor = self.improvement_factor;
+        result.fittest = self.fittest;
+        result.counters = self.counters;
+        result.iteration_times = self.iteration_times;
+        result.elapsed = self.elapsed;
+        result.best_iteration = self.best_iteration;
+        result.best_population_id = self.best_population_id;
+        result.retirements = self.retirements;
+        result.stats_history = self.stats_history;
+        result.history = self.history;
+        result.direction = simulation.direction;
+        result.seed = simulation.seed;
+        result.configuration = configuration;
+        result.provenance = simulation.provenance.clone();
+        simulation.share_counter = self.share_counter;
+        simulation.resumed = true;
+
+        Ok(())
+    }
+
+    /// Writes the checkpoint to the given file (bincode), see `persist::save`. A crash while
+    /// writing keeps the old checkpoint.
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()>
+    where
+        T: Serialize,
+        T::Fitness: Serialize,
+    {
+        Ok(persist::save(self, path.as_ref(), Format::Bincode)?)
+    }
+
+    /// Reads a checkpoint written by `write`.
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Checkpoint<T>>
+    where
+        T: DeserializeOwned,
+        T::Fitness: DeserializeOwned,
+    {
+        Ok(persist::load(path.as_ref(), Format::Bincode)?)
+    }
+}
+
+/// Writes a checkpoint of the given simulation to the given file, see `Checkpoint::write`.
+pub fn write_simulation<T>(simulation: &Simulation<T>, path: &Path) -> Result<()>
+where
+    T: Individual + Send + Sync + Clone + Debug + Serialize,
+    T::Fitness: Serialize,
+{
+    Checkpoint::of(simulation).write(path)
+}
+
+/// The `Periodic` type. Writes a checkpoint every `every` iterations while the simulation
+/// runs, see `SimulationBuilder::checkpoint_every`.
+#[derive(Clone)]
+pub struct Periodic<T: Individual + Send + Sync + Debug> {
+    /// The number of iterations between two checkpoints.
+    pub every: u32,
+    /// The file of the checkpoint, it is overwritten each time.
+    pub path: PathBuf,
+    /// Writes the checkpoint, usually `write_simulation`.
+    pub write: fn(&Simulation<T>, &Path) -> Result<()>,
+}
+
+impl<T: Individual + Send + Sync + Debug> Debug for Periodic<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Periodic {{ every: {}, path: {:?} }}", self.every, self.path)
+    }
+}
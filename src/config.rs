@@ -0,0 +1,349 @@
+//! This module defines the declarative configuration of a simulation, read from a TOML or YAML
+//! file, so parameters can be tweaked without recompilation. Only available with the feature
+//! `config`.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+//! The individuals, the operators and the context are code and not part of the configuration:
+//! build the simulation as usual and call `SimulationBuilder::from_config` after all populations
+//! have been added. Every value is optional, missing values keep the settings of the builders.
+//! An example in TOML:
+//!
+//! ```toml
+//! threads = 4
+//! seed = 42
+//! end = { iterations = 5000 }
+//! stagnation_limit = 500
+//! time_limit = 600.0
+//!
+//! [[population]]
+//! mutation = "increasing"
+//! reset_limit_start = 100
+//! reset_limit_end = 1000
+//!
+//! [[population]]
+//! mutation = { rank_proportional = { min = 1, max = 8 } }
+//! ```
+
+use std::error::Error;
+use std::fmt::{self, Debug};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use serde_yaml;
+use toml;
+
+use individual::Individual;
+use population::Population;
+
+/// The `ConfigError` type. The errors of reading a configuration.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file could not be read.
+    Io(io::Error),
+    /// The TOML could not be decoded.
+    Toml(toml::de::Error),
+    /// The YAML could not be decoded.
+    Yaml(serde_yaml::Error),
+    /// The extension of the file is neither ".toml" nor ".yaml" (or ".yml").
+    UnknownFormat(PathBuf),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConfigError::Io(ref error) => write!(f, "file error: {}", error),
+            ConfigError::Toml(ref error) => write!(f, "TOML error: {}", error),
+            ConfigError::Yaml(ref error) => write!(f, "YAML error: {}", error),
+            ConfigError::UnknownFormat(ref path) => {
+                write!(f, "unknown configuration format: {}", path.display())
+            }
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+impl From<io::Error> for ConfigError {
+    fn from(error: io::Error) -> ConfigError {
+        ConfigError::Io(error)
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(error: toml::de::Error) -> ConfigError {
+        ConfigError::Toml(error)
+    }
+}
+
+impl From<serde_yaml::Error> for ConfigError {
+    fn from(error: serde_yaml::Error) -> ConfigError {
+        ConfigError::Yaml(error)
+    }
+}
+
+/// The `End` type. When the simulation finishes, see `SimulationType`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum End {
+    /// After this number of iterations, see `SimulationBuilder::iterations`.
+    Iterations(u32),
+    /// When this improvement factor is reached, see `SimulationBuilder::factor`.
+    Factor(f64),
+    /// When this fitness is reached, see `SimulationBuilder::fitness`.
+    Fitness(f64),
+}
+
+/// The `MutationSchedule` type. How often each individual of a population mutates per
+/// iteration.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MutationSchedule {
+    /// The nth individual mutates n times, see `PopulationBuilder::increasing_mutation_rate`.
+    Increasing,
+    /// The nth individual mutates base^n times, see
+    /// `PopulationBuilder::increasing_exp_mutation_rate`.
+    IncreasingExp(f64),
+    /// One rate per individual, see `PopulationBuilder::mutation_rate`. The rates are repeated
+    /// if there are less rates than individuals.
+    Rates(Vec<u32>),
+    /// Depending on the rank of each individual, see
+    /// `PopulationBuilder::rank_proportional_mutation`.
+    RankProportional {
+        /// The mutations of the fittest individual.
+        min: u32,
+        /// The mutations of the least fit individual.
+        max: u32,
+    },
+}
+
+/// The `PopulationConfig` type. The settings of one population.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PopulationConfig {
+    /// See `PopulationBuilder::label`.
+    pub label: Option<String>,
+    /// See `MutationSchedule`.
+    pub mutation: Option<MutationSchedule>,
+    /// See `PopulationBuilder::reset_limit_start`.
+    pub reset_limit_start: Option<u32>,
+    /// See `PopulationBuilder::reset_limit_end`.
+    pub reset_limit_end: Option<u32>,
+    /// See `PopulationBuilder::reset_limit_increment`.
+    pub reset_limit_increment: Option<u32>,
+    /// See `PopulationBuilder::max_resets`.
+    pub max_resets: Option<u32>,
+    /// See `PopulationBuilder::retire_after`.
+    pub retire_after: Option<u32>,
+    /// See `PopulationBuilder::crossover_rate`.
+    pub crossover_rate: Option<f64>,
+    /// See `PopulationBuilder::elitism`.
+    pub elitism: Option<u32>,
+    /// See `PopulationBuilder::explorer`.
+    pub explorer: Option<bool>,
+    /// The number of individuals, see `Population::resize`. The populations of a simulation may
+    /// have different sizes.
+    pub individuals: Option<u32>,
+}
+
+impl PopulationConfig {
+    /// Overlays the given population with the settings that are present. The population must be
+    /// checked again afterwards, see `population_builder::check`.
+    pub fn apply<T>(&self, population: &mut Population<T>)
+    where
+        T: Individual + Send + Sync + Clone + Debug,
+    {
+        if let Some(ref label) = self.label {
+            population.label = label.clone();
+        }
+
+        match self.mutation {
+            Some(MutationSchedule::Increasing) => {
+                for (wrapper, rate) in population.population.iter_mut().zip(1..) {
+                    wrapper.num_of_mutations = rate;
+                }
+            }
+            Some(MutationSchedule::IncreasingExp(base)) => {
+                for (wrapper, rate) in population.population.iter_mut().zip(1..) {
+                    wrapper.num_of_mutations = base.powi(rate).floor() as u32;
+                }
+            }
+            Some(MutationSchedule::Rates(ref rates)) => {
+                let rates = rates.iter().cycle();
+                for (wrapper, &rate) in population.population.iter_mut().zip(rates) {
+                    wrapper.num_of_mutations = rate;
+                }
+            }
+            Some(MutationSchedule::RankProportional { min, max }) => {
+                population.rank_mutation = Some((min, max));
+            }
+            None => {}
+        }
+
+        if let Some(start) = self.reset_limit_start {
+            population.reset_limit_start = start;
+            population.reset_limit = start;
+        }
+        if let Some(end) = self.reset_limit_end {
+            population.reset_limit_end = end;
+        }
+        if let Some(increment) = self.reset_limit_increment {
+            population.reset_limit_increment = increment;
+        }
+        if let Some(max_resets) = self.max_resets {
+            population.max_resets = Some(max_resets);
+        }
+        if let Some(retire_after) = self.retire_after {
+            population.retire_after = retire_after;
+        }
+        if let Some(crossover_rate) = self.crossover_rate {
+            population.crossover_rate = crossover_rate;
+        }
+        if let Some(elitism) = self.elitism {
+            population.elitism = elitism;
+        }
+        if let Some(explorer) = self.explorer {
+            population.explorer = explorer;
+        }
+        if let Some(individuals) = self.individuals {
+            population.resize(individuals);
+        }
+    }
+}
+
+/// The `Config` type. The settings of a simulation and its populations.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// See `SimulationBuilder::threads`.
+    pub threads: Option<usize>,
+    /// See `SimulationBuilder::seed`.
+    pub seed: Option<u64>,
+    /// See `End`.
+    pub end: Option<End>,
+    /// See `SimulationBuilder::max_iterations`.
+    pub max_iterations: Option<u32>,
+    /// See `SimulationBuilder::stagnation_limit`.
+    pub stagnation_limit: Option<u32>,
+    /// In seconds, see `SimulationBuilder::time_limit`.
+    pub time_limit: Option<f64>,
+    /// See `SimulationBuilder::share_fittest`.
+    pub share_fittest: Option<bool>,
+    /// See `SimulationBuilder::share_every`.
+    pub share_every: Option<u32>,
+    /// See `SimulationBuilder::share_top`.
+    pub share_top: Option<usize>,
+    /// See `SimulationBuilder::share_probability`.
+    pub share_probability: Option<f64>,
+    /// See `SimulationBuilder::asynchronous_islands`.
+    pub asynchronous_islands: Option<bool>,
+    /// See `SimulationBuilder::num_of_global_fittest`.
+    pub num_of_global_fittest: Option<usize>,
+    /// See `SimulationBuilder::output_every`.
+    pub output_every: Option<u32>,
+    /// The settings of the populations, in the order in which they were added to the
+    /// simulation. There may be less entries than populations.
+    #[serde(default, rename = "population")]
+    pub populations: Vec<PopulationConfig>,
+}
+
+impl Config {
+    /// Reads the configuration from a TOML string.
+    pub fn from_toml(text: &str) -> Result<Config, ConfigError> {
+        Ok(toml::from_str(text)?)
+    }
+
+    /// Reads the configuration from a YAML string.
+    pub fn from_yaml(text: &str) -> Result<Config, ConfigError> {
+        Ok(serde_yaml::from_str(text)?)
+    }
+
+    /// Reads the configuration from the given file, the format is chosen by its extension
+    /// (".toml", ".yaml" or ".yml").
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Config, ConfigError> {
+        let path = path.as_ref();
+        let extension = path.extension().and_then(|extension| extension.to_str());
+
+        match extension.map(|extension| extension.to_ascii_lowercase()) {
+            Some(ref extension) if extension == "toml" => {
+                Config::from_toml(&fs::read_to_string(path)?)
+            }
+            Some(ref extension) if extension == "yaml" || extension == "yml" => {
+                Config::from_yaml(&fs::read_to_string(path)?)
+            }
+            _ => Err(ConfigError::UnknownFormat(path.to_path_buf())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Config, ConfigError, End, MutationSchedule, PopulationConfig};
+
+    #[test]
+    fn toml_and_yaml_agree() {
+        let toml = Config::from_toml(
+            r#"
+            threads = 3
+            end = { factor = 0.5 }
+
+            [[population]]
+            mutation = "increasing"
+            reset_limit_end = 0
+
+            [[population]]
+            mutation = { rank_proportional = { min = 1, max = 8 } }
+            "#,
+        )
+        .unwrap();
+        let yaml = Config::from_yaml(
+            "
+            threads: 3
+            end:
+              factor: 0.5
+            population:
+              - mutation: increasing
+                reset_limit_end: 0
+              - mutation:
+                  rank_proportional: { min: 1, max: 8 }
+            ",
+        )
+        .unwrap();
+
+        assert_eq!(toml, yaml);
+        assert_eq!(toml.threads, Some(3));
+        assert_eq!(toml.end, Some(End::Factor(0.5)));
+        assert_eq!(toml.seed, None);
+        assert_eq!(
+            toml.populations[1],
+            PopulationConfig {
+                mutation: Some(MutationSchedule::RankProportional { min: 1, max: 8 }),
+                ..PopulationConfig::default()
+            }
+        );
+    }
+
+    #[test]
+    fn unknown_settings_are_rejected() {
+        match Config::from_toml("thread = 3") {
+            Err(ConfigError::Toml(_)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match Config::from_file("simulation.ini") {
+            Err(ConfigError::UnknownFormat(_)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,288 @@
+//! This module defines the handling of constraints by penalty functions: the violation of the
+//! constraints (see `Constrained::violation`) is added to the fitness, weighted by a penalty
+//! scheme, so `calculate_fitness` only has to compute the objective.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+//! The penalized fitness is `fitness + weight * violation ^ exponent`. The static scheme keeps
+//! the weight fixed, the dynamic scheme (Joines and Houck) raises it with the number of
+//! iterations and the adaptive scheme (Bean and Hadj-Alouane) adjusts it to the feasibility of
+//! the fittest individual in the last iterations.
+//!
+//! Instead of a penalty the population can also be ordered by Deb's feasibility rules
+//! (`feasibility_sort`) or by stochastic ranking (`stochastic_sort`, Runarsson and Yao).
+
+use std::cmp::Ordering;
+
+use rand::Rng;
+
+use fitness::Fitness;
+use individual::{Individual, IndividualWrapper};
+use rng::SimRng;
+
+/// The `Penalty` type. The penalty schemes, see `PopulationBuilder::penalty`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Penalty {
+    /// A fixed weight: `fitness + weight * violation`.
+    Static(f64),
+    /// The weight grows with the iteration t (starting at 1):
+    /// `fitness + (c * t) ^ alpha * violation ^ beta`. Common values are c = 0.5, alpha = 2.0
+    /// and beta = 2.0.
+    Dynamic { c: f64, alpha: f64, beta: f64 },
+    /// The weight starts at `initial`. If the fittest individual has been feasible in each of
+    /// the last `window` iterations, the weight is divided by `decrease`, if it has been
+    /// infeasible in each of them, the weight is multiplied by `increase`. Both factors must be
+    /// larger than 1.0 and should differ to avoid cycles.
+    Adaptive { initial: f64, increase: f64, decrease: f64, window: u32 },
+}
+
+impl Penalty {
+    /// Returns true if all parameters of the scheme are valid: non-negative weights and
+    /// exponents, factors larger than 1.0 and a nonzero window.
+    pub fn is_valid(&self) -> bool {
+        let non_negative = |value: f64| value >= 0.0 && value.is_finite();
+        match *self {
+            Penalty::Static(weight) => non_negative(weight),
+            Penalty::Dynamic { c, alpha, beta } => {
+                non_negative(c) && non_negative(alpha) && non_negative(beta)
+            }
+            Penalty::Adaptive { initial, increase, decrease, window } => {
+                non_negative(initial) && increase > 1.0 && increase.is_finite() &&
+                    decrease > 1.0 && decrease.is_finite() && window > 0
+            }
+        }
+    }
+}
+
+/// The `Constraint` type. The penalty scheme of a population and its current state.
+#[derive(Debug, Clone)]
+pub struct Constraint<T: Individual> {
+    /// The violation of the constraints, see `Constrained::violation`.
+    pub violation: fn(&T) -> f64,
+    /// The penalty scheme, None if the violation is only used to rank the individuals.
+    pub penalty: Option<Penalty>,
+    /// The current weight of the violation.
+    pub weight: f64,
+    /// The number of iterations so far.
+    pub iteration: u32,
+    /// The number of the last iterations in which the fittest individual has been feasible
+    /// (positive) or infeasible (negative), for the adaptive scheme.
+    pub streak: i64,
+}
+
+impl<T: Individual> Constraint<T> {
+    /// Creates the constraint handling with the given violation and penalty scheme.
+    pub fn new(violation: fn(&T) -> f64, penalty: Option<Penalty>) -> Constraint<T> {
+        let weight = match penalty {
+            Some(Penalty::Static(weight)) => weight,
+            Some(Penalty::Adaptive { initial, .. }) => initial,
+            Some(Penalty::Dynamic { .. }) | None => 0.0,
+        };
+
+        Constraint { violation, penalty, weight, iteration: 0, streak: 0 }
+    }
+
+    /// Returns the penalty of the given individual with the current weight, 0.0 for feasible
+    /// individuals.
+    pub fn penalty(&self, individual: &T) -> f64 {
+        let measure = self.measure(individual);
+        if measure > 0.0 { self.weight * measure } else { 0.0 }
+    }
+
+    /// Returns the violation of the individual raised to the exponent of the scheme.
+    pub fn measure(&self, individual: &T) -> f64 {
+        let violation = (self.violation)(individual).max(0.0);
+        match self.penalty {
+            Some(Penalty::Dynamic { beta, .. }) => violation.powf(beta),
+            _ => violation,
+        }
+    }
+
+    /// Returns true if the individual satisfies all constraints.
+    pub fn is_feasible(&self, individual: &T) -> bool {
+        (self.violation)(individual) <= 0.0
+    }
+
+    /// Starts the next iteration: updates the weight according to the scheme, given whether
+    /// the fittest individual of the last iteration is feasible. Returns the change of the
+    /// weight.
+    pub fn next_iteration(&mut self, fittest_feasible: bool) -> f64 {
+        let old_weight = self.weight;
+        self.iteration += 1;
+        match self.penalty {
+            Some(Penalty::Static(_)) | None => {}
+            Some(Penalty::Dynamic { c, alpha, .. }) => {
+                self.weight = (c * f64::from(self.iteration)).powf(alpha);
+            }
+            Some(Penalty::Adaptive { increase, decrease, window, .. }) => {
+                self.streak = match (fittest_feasible, self.streak) {
+                    (true, streak) if streak > 0 => streak + 1,
+                    (true, _) => 1,
+                    (false, streak) if streak < 0 => streak - 1,
+                    (false, _) => -1,
+                };
+                if self.streak >= i64::from(window) {
+                    self.weight /= decrease;
+                    self.streak = 0;
+                } else if self.streak <= -i64::from(window) {
+                    self.weight *= increase;
+                    self.streak = 0;
+                }
+            }
+        }
+        self.weight - old_weight
+    }
+}
+
+/// Sorts the population by Deb's feasibility rules: feasible individuals first, ordered by
+/// fitness, then the infeasible ones ordered by their violation. NaN values come last.
+pub fn feasibility_sort<T: Individual>(
+    population: &mut Vec<IndividualWrapper<T>>,
+    violation: fn(&T) -> f64,
+) {
+    let mut keyed: Vec<(f64, IndividualWrapper<T>)> = population
+        .drain(..)
+        .map(|wrapper| ((violation)(&wrapper.individual), wrapper))
+        .collect();
+    keyed.sort_by(|a, b| match (a.0 <= 0.0, b.0 <= 0.0) {
+        (true, true) => a.1.fitness.compare(&b.1.fitness),
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => a.0.compare(&b.0).then_with(|| a.1.fitness.compare(&b.1.fitness)),
+    });
+    population.extend(keyed.into_iter().map(|(_, wrapper)| wrapper));
+}
+
+/// Sorts the population by stochastic ranking: a bubble sort where two neighbours are compared
+/// by fitness if both are feasible or with the given probability (usually about 0.45),
+/// otherwise by their violation. At most one sweep per individual, it stops early if a sweep
+/// doesn't swap anything. This balances the objective and the constraints without a penalty
+/// weight.
+pub fn stochastic_sort<T: Individual>(
+    population: &mut [IndividualWrapper<T>],
+    violation: fn(&T) -> f64,
+    probability: f64,
+    rng: &mut SimRng,
+) {
+    let mut violations: Vec<f64> =
+        population.iter().map(|wrapper| (violation)(&wrapper.individual)).collect();
+
+    for _ in 0..population.len() {
+        let mut swapped = false;
+        for index in 1..population.len() {
+            let (first, second) = (index - 1, index);
+            let by_fitness = (violations[first] <= 0.0 && violations[second] <= 0.0) ||
+                rng.gen::<f64>() < probability;
+            let order = if by_fitness {
+                population[first].fitness.compare(&population[second].fitness)
+            } else {
+                violations[first].compare(&violations[second])
+            };
+            if order == Ordering::Greater {
+                population.swap(first, second);
+                violations.swap(first, second);
+                swapped = true;
+            }
+        }
+        if !swapped {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{feasibility_sort, stochastic_sort, Constraint, Penalty};
+    use individual::{Individual, IndividualWrapper};
+    use rng::{self, SimRng};
+    use test::wrap;
+
+    #[derive(Debug, Clone)]
+    struct Point {
+        x: f64,
+    }
+
+    impl Individual for Point {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {}
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            self.x
+        }
+
+        fn reset(&mut self) {}
+    }
+
+    // x >= 3
+    fn violation(point: &Point) -> f64 {
+        3.0 - point.x
+    }
+
+    #[test]
+    fn penalty_schemes() {
+        let mut fixed = Constraint::new(violation, Some(Penalty::Static(10.0)));
+        assert_eq!(fixed.penalty(&Point { x: 1.0 }), 20.0);
+        assert_eq!(fixed.penalty(&Point { x: 5.0 }), 0.0);
+        assert_eq!(fixed.next_iteration(false), 0.0);
+
+        let mut dynamic =
+            Constraint::new(violation, Some(Penalty::Dynamic { c: 0.5, alpha: 2.0, beta: 2.0 }));
+        dynamic.next_iteration(true);
+        dynamic.next_iteration(true);
+        // (0.5 * 2) ^ 2 * 2 ^ 2
+        assert_eq!(dynamic.penalty(&Point { x: 1.0 }), 4.0);
+        assert_eq!(dynamic.next_iteration(true), 1.25);
+
+        let adaptive = Penalty::Adaptive { initial: 8.0, increase: 4.0, decrease: 2.0, window: 2 };
+        let mut adaptive = Constraint::new(violation, Some(adaptive));
+        assert_eq!(adaptive.next_iteration(false), 0.0);
+        assert_eq!(adaptive.next_iteration(true), 0.0);
+        assert_eq!(adaptive.next_iteration(true), -4.0);
+        adaptive.next_iteration(false);
+        adaptive.next_iteration(false);
+        assert_eq!(adaptive.weight, 16.0);
+    }
+
+    #[test]
+    fn invalid_schemes() {
+        assert!(!Penalty::Static(-1.0).is_valid());
+        assert!(!Penalty::Dynamic { c: f64::NAN, alpha: 1.0, beta: 1.0 }.is_valid());
+        let adaptive = Penalty::Adaptive { initial: 1.0, increase: 2.0, decrease: 1.0, window: 1 };
+        assert!(!adaptive.is_valid());
+    }
+
+    #[test]
+    fn feasibility_rules() {
+        // Fitness x, feasible for x >= 3.
+        let points: Vec<Point> = [5.0, 1.0, 4.0, 2.0, 3.0].iter().map(|&x| Point { x }).collect();
+        let order = |population: &[IndividualWrapper<Point>]| -> Vec<f64> {
+            population.iter().map(|wrapper| wrapper.fitness).collect()
+        };
+
+        let mut population = wrap(&points);
+        feasibility_sort(&mut population, violation);
+        assert_eq!(order(&population), vec![3.0, 4.0, 5.0, 2.0, 1.0]);
+
+        // Always by violation for infeasible pairs: the same order as the feasibility rules.
+        let mut population = wrap(&points);
+        stochastic_sort(&mut population, violation, 0.0, &mut rng::from_seed(1));
+        assert_eq!(order(&population), vec![3.0, 4.0, 5.0, 2.0, 1.0]);
+
+        // Always by fitness.
+        let mut population = wrap(&points);
+        stochastic_sort(&mut population, violation, 1.0, &mut rng::from_seed(1));
+        assert_eq!(order(&population), vec![1.0, 2.0, 3.0, 4.0, 5.0]);
+    }
+}
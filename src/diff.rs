@@ -0,0 +1,386 @@
+//! This module compares two snapshots of simulations, for example to find out why a resumed or
+//! re-seeded run behaves differently than expected.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+//! A snapshot is any copy of a simulation, for example `simulation.clone()` after `run`,
+//! a branch (`Simulation::branch`) or a simulation of another run with the same individual type.
+//!
+//! Example:
+//!
+//! ```ignore
+//! let before = simulation.clone();
+//! simulation.run(&selector);
+//! println!("{}", diff::compare(&before, &simulation));
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug};
+
+use fitness::Fitness;
+use individual::{Individual, IndividualWrapper};
+use population::Population;
+use rng::RngState;
+use simulation::Simulation;
+
+/// The `FitnessSummary` type. Describes the fitness distribution of a population.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FitnessSummary {
+    /// The number of individuals.
+    pub count: usize,
+    /// The lowest (best) fitness.
+    pub min: f64,
+    /// The highest (worst) fitness.
+    pub max: f64,
+    /// The mean fitness.
+    pub mean: f64,
+    /// The median fitness.
+    pub median: f64,
+    /// The standard deviation of the fitness.
+    pub std_dev: f64,
+}
+
+impl FitnessSummary {
+    /// Summarizes the given fitness values.
+    pub fn of(fitness: &[f64]) -> FitnessSummary {
+        if fitness.is_empty() {
+            return FitnessSummary::default();
+        }
+
+        let mut sorted = fitness.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(::std::cmp::Ordering::Greater));
+        let count = sorted.len();
+        let mean = sorted.iter().sum::<f64>() / count as f64;
+        let median = if count % 2 == 1 {
+            sorted[count / 2]
+        } else {
+            (sorted[count / 2 - 1] + sorted[count / 2]) / 2.0
+        };
+        let variance = sorted.iter().map(|f| (f - mean) * (f - mean)).sum::<f64>() / count as f64;
+
+        FitnessSummary {
+            count,
+            min: sorted[0],
+            max: sorted[count - 1],
+            mean,
+            median,
+            std_dev: variance.sqrt(),
+        }
+    }
+}
+
+/// The `PopulationDiff` type. Compares a population that exists in both snapshots.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PopulationDiff {
+    /// The id of the population.
+    pub id: u32,
+    /// The number of individuals in the second snapshot that also exist in the first one
+    /// (same fitness and a distance of 0.0, see `Individual::distance`).
+    pub overlap: usize,
+    /// The fitness distribution in the first snapshot.
+    pub before: FitnessSummary,
+    /// The fitness distribution in the second snapshot.
+    pub after: FitnessSummary,
+}
+
+impl PopulationDiff {
+    /// The fraction of the individuals in the second snapshot that also exist in the first one.
+    pub fn overlap_fraction(&self) -> f64 {
+        if self.after.count == 0 {
+            0.0
+        } else {
+            self.overlap as f64 / self.after.count as f64
+        }
+    }
+}
+
+/// The `SettingDiff` type. A setting that differs between the snapshots, the values are shown
+/// as text. A setting that exists in only one snapshot has the value `None` in the other one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettingDiff {
+    /// The name of the setting, for example "max_iterations" or "population 2: crossover_rate".
+    pub name: String,
+    /// The value in the first snapshot.
+    pub before: Option<String>,
+    /// The value in the second snapshot.
+    pub after: Option<String>,
+}
+
+/// The `SimulationDiff` type. The result of `compare`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationDiff {
+    /// The number of iterations of both snapshots.
+    pub iterations: (u32, u32),
+    /// The fitness of the fittest individual of both snapshots.
+    pub fittest: (Option<f64>, Option<f64>),
+    /// The populations that exist in both snapshots, by id.
+    pub populations: Vec<PopulationDiff>,
+    /// The ids of the populations that exist only in the first snapshot.
+    pub only_before: Vec<u32>,
+    /// The ids of the populations that exist only in the second snapshot.
+    pub only_after: Vec<u32>,
+    /// All settings (simulation, populations, scheduled parameters and random seeds) that
+    /// differ between the snapshots.
+    pub settings: Vec<SettingDiff>,
+}
+
+/// Compares the two given snapshots of simulations.
+pub fn compare<T>(before: &Simulation<T>, after: &Simulation<T>) -> SimulationDiff
+where
+    T: Individual + Send + Sync + Clone + Debug,
+{
+    let mut populations = Vec::new();
+    let mut only_after = Vec::new();
+
+    for population in &after.habitat {
+        match before.habitat.iter().find(|other| other.id == population.id) {
+            Some(other) => populations.push(compare_population(other, population)),
+            None => only_after.push(population.id),
+        }
+    }
+
+    let only_before = before
+        .habitat
+        .iter()
+        .filter(|population| after.habitat.iter().all(|other| other.id != population.id))
+        .map(|population| population.id)
+        .collect();
+
+    SimulationDiff {
+        iterations: (
+            before.simulation_result.iteration_counter,
+            after.simulation_result.iteration_counter,
+        ),
+        fittest: (
+            before.simulation_result.fittest.first().map(|wrapper| wrapper.fitness.to_f64()),
+            after.simulation_result.fittest.first().map(|wrapper| wrapper.fitness.to_f64()),
+        ),
+        populations,
+        only_before,
+        only_after,
+        settings: compare_settings(settings(before), settings(after)),
+    }
+}
+
+/// Compares the individuals of two snapshots of the same population.
+fn compare_population<T>(before: &Population<T>, after: &Population<T>) -> PopulationDiff
+where
+    T: Individual + Send + Sync + Clone + Debug,
+{
+    let fitness = |population: &[IndividualWrapper<T>]| -> Vec<f64> {
+        population.iter().map(|wrapper| wrapper.fitness.to_f64()).collect()
+    };
+
+    let overlap = after
+        .population
+        .iter()
+        .filter(|wrapper| {
+            before.population.iter().any(|other| {
+                other.fitness == wrapper.fitness &&
+                    other.individual.distance(&wrapper.individual) == 0.0
+            })
+        })
+        .count();
+
+    PopulationDiff {
+        id: after.id,
+        overlap,
+        before: FitnessSummary::of(&fitness(&before.population)),
+        after: FitnessSummary::of(&fitness(&after.population)),
+    }
+}
+
+/// Collects all the settings of a simulation that may differ between runs.
+fn settings<T>(simulation: &Simulation<T>) -> BTreeMap<String, String>
+where
+    T: Individual + Send + Sync + Clone + Debug,
+{
+    let mut settings = BTreeMap::new();
+    let mut add = |name: String, value: String| {
+        settings.insert(name, value);
+    };
+
+    add("type_of_simulation".to_string(), format!("{:?}", simulation.type_of_simulation));
+    add("num_of_threads".to_string(), simulation.num_of_threads.to_string());
+    add("share_fittest".to_string(), simulation.share_fittest.to_string());
+    add("share_every".to_string(), simulation.share_every.to_string());
+    add("share_top".to_string(), simulation.share_top.to_string());
+    add("share_probability".to_string(), simulation.share_probability.to_string());
+    add("migration".to_string(), format!("{:?}", simulation.migration));
+    add("topology".to_string(), format!("{:?}", simulation.topology));
+    add("asynchronous_islands".to_string(), simulation.asynchronous_islands.to_string());
+    add("num_of_global_fittest".to_string(), simulation.num_of_global_fittest.to_string());
+    add("max_iterations".to_string(), simulation.max_iterations.to_string());
+    add("stagnation_limit".to_string(), simulation.stagnation_limit.to_string());
+    add("interbreed_elites".to_string(), simulation.interbreed_elites.to_string());
+    add("check_every".to_string(), format!("{:?}", simulation.check_every));
+    add("mutation_calibration".to_string(), format!("{:?}", simulation.mutation_calibration));
+    add("provenance".to_string(), simulation.provenance.to_string());
+    add("track_diversity".to_string(), simulation.track_diversity.to_string());
+    add("keep_stats_history".to_string(), simulation.keep_stats_history.to_string());
+    add("record_history".to_string(), format!("{:?}", simulation.record_history));
+
+    for (name, value) in simulation.parameters.values() {
+        add(format!("parameter {}", name), value.to_string());
+    }
+
+    for population in &simulation.habitat {
+        let mut add = |name: &str, value: String| {
+            add(format!("population {}: {}", population.id, name), value);
+        };
+        let mutation_rates: Vec<u32> = population
+            .population
+            .iter()
+            .map(|wrapper| wrapper.num_of_mutations)
+            .collect();
+        let seed: String = RngState::of(&population.rng)
+            .seed
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+
+        add("num_of_individuals", population.num_of_individuals.to_string());
+        add("reset_limit_start", population.reset_limit_start.to_string());
+        add("reset_limit_end", population.reset_limit_end.to_string());
+        add("reset_limit_increment", population.reset_limit_increment.to_string());
+        add("fitness_tolerance", population.fitness_tolerance.to_string());
+        add("rank_mutation", format!("{:?}", population.rank_mutation));
+        add("mutation_rates", format!("{:?}", mutation_rates));
+        add("crossover", population.crossover.is_some().to_string());
+        add("crossover_rate", population.crossover_rate.to_string());
+        add("replacement", format!("{:?}", population.replacement));
+        add("survivor_scheme", format!("{:?}", population.survivor_scheme));
+        add("seed", seed);
+    }
+
+    settings
+}
+
+/// Returns the settings whose values differ.
+fn compare_settings(
+    before: BTreeMap<String, String>,
+    mut after: BTreeMap<String, String>,
+) -> Vec<SettingDiff> {
+    let mut result = Vec::new();
+
+    for (name, value) in before {
+        let other = after.remove(&name);
+        if other.as_ref() != Some(&value) {
+            result.push(SettingDiff { name, before: Some(value), after: other });
+        }
+    }
+    for (name, value) in after {
+        result.push(SettingDiff { name, before: None, after: Some(value) });
+    }
+
+    result.sort_by(|a, b| a.name.cmp(&b.name));
+    result
+}
+
+impl fmt::Display for SimulationDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+
+        writeln!(f, "iterations: {} -> {}", self.iterations.0, self.iterations.1)?;
+        writeln!(f, "fittest: {:?} -> {:?}", self.fittest.0, self.fittest.1)?;
+
+        for population in &self.populations {
+            writeln!(
+                f,
+                "population {}: overlap: {:.1}%, best: {} -> {}, median: {} -> {}, \
+                 mean: {} -> {}, std dev: {} -> {}",
+                population.id,
+                population.overlap_fraction() * 100.0,
+                population.before.min,
+                population.after.min,
+                population.before.median,
+                population.after.median,
+                population.before.mean,
+                population.after.mean,
+                population.before.std_dev,
+                population.after.std_dev
+            )?;
+        }
+        for id in &self.only_before {
+            writeln!(f, "population {}: only in the first snapshot", id)?;
+        }
+        for id in &self.only_after {
+            writeln!(f, "population {}: only in the second snapshot", id)?;
+        }
+        for setting in &self.settings {
+            writeln!(
+                f,
+                "{}: {} -> {}",
+                setting.name,
+                value(&setting.before),
+                value(&setting.after)
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use diff::{compare, FitnessSummary};
+    use population_builder::PopulationBuilder;
+    use simulation_builder::SimulationBuilder;
+    use schedule::Schedule;
+    use simulation::SimulationType;
+    use select::MinimizeSelector;
+    use test::Test;
+
+    #[test]
+    fn fitness_summary() {
+        let summary = FitnessSummary::of(&[4.0, 1.0, 3.0, 2.0]);
+        assert_eq!(summary.count, 4);
+        assert_eq!((summary.min, summary.max), (1.0, 4.0));
+        assert_eq!((summary.mean, summary.median), (2.5, 2.5));
+        assert_eq!(summary.std_dev, 1.25f64.sqrt());
+        assert_eq!(FitnessSummary::of(&[]).count, 0);
+    }
+
+    #[test]
+    fn compare_branches() {
+        let population = PopulationBuilder::<Test>::new()
+            .generate_initial_population(10, |_| Test { f: 1.0 })
+            .evolution_seed(1)
+            .finalize()
+            .unwrap();
+        let simulation = SimulationBuilder::<Test>::new()
+            .iterations(10)
+            .add_population(population)
+            .finalize()
+            .unwrap();
+        simulation.parameters.register("sigma", Schedule::Constant(1.0));
+
+        let same = compare(&simulation, &simulation.clone());
+        assert!(same.settings.is_empty());
+        assert_eq!(same.populations[0].overlap, 10);
+
+        let mut branch = simulation.branch(7);
+        branch.type_of_simulation = SimulationType::EndIteration(5);
+        branch.run(&MinimizeSelector::new(2));
+        branch.parameters.get("sigma").unwrap().set(2.0);
+
+        let diff = compare(&simulation, &branch);
+        let names: Vec<&str> = diff.settings.iter().map(|setting| setting.name.as_str()).collect();
+        assert_eq!(names, vec!["parameter sigma", "population 1: seed", "type_of_simulation"]);
+        assert_eq!(diff.iterations, (0, 5));
+        assert_eq!(diff.populations[0].after.median, 1.0);
+        assert!(diff
+            .to_string()
+            .contains("type_of_simulation: EndIteration(10) -> EndIteration(5)"));
+    }
+}
@@ -0,0 +1,626 @@
+//! This module defines the distributed fitness evaluation: the master runs the selection and
+//! the breeding and sends the new individuals over TCP to workers on other machines, which
+//! calculate their fitness. Only available with the feature `distributed`.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+//! The individuals (and their fitness) must implement `Serialize` and `Deserialize`. Each worker
+//! is a small program that serves the evaluations with its own context:
+//!
+//! ```rust,ignore
+//! fn main() {
+//!     let context = load_problem();
+//!     Worker::<Tour>::bind("0.0.0.0:7878", context).unwrap().serve().unwrap();
+//! }
+//! ```
+//!
+//! The master gives its populations a `RemoteEvaluator` with the addresses of the workers, see
+//! `PopulationBuilder::batch_evaluator`. The protocol is simple: for each iteration the master
+//! sends each worker a part of the new individuals and gets their fitness back, all encoded
+//! with bincode. If a worker does not answer, its individuals are sent to the other workers.
+//!
+//! Simulations on different machines can also exchange emigrants (the island model across
+//! machines): give each simulation a `RemoteIslands` with the addresses of the others, see
+//! `SimulationBuilder::remote_islands`.
+//!
+//! The protocol is neither authenticated nor encrypted: anybody who can connect to a worker or
+//! a `RemoteIslands` can send it individuals and read the answers. Only use them in a trusted
+//! network or through a tunnel (VPN, SSH). Received messages larger than
+//! `DEFAULT_MAX_MESSAGE_SIZE` (see `Worker::max_message_size` and
+//! `RemoteIslands::max_message_size`) are rejected, so a wrong length prefix cannot exhaust the
+//! memory.
+
+use std::fmt::Debug;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::marker::PhantomData;
+use std::mem;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use bincode::{self, Options};
+use rayon::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use individual::{BatchEvaluator, Individual, IndividualWrapper};
+use migration::MigrantExchange;
+use population::catch_panic;
+
+/// The most immigrants a `RemoteIslands` keeps until the next migration, older ones are
+/// dropped first.
+pub const MAX_WAITING_IMMIGRANTS: usize = 1024;
+
+/// How long a `RemoteIslands` waits for another simulation to connect or to take its emigrants.
+const PEER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The largest message in bytes that a `Worker` or a `RemoteIslands` accepts by default: 64 MiB.
+pub const DEFAULT_MAX_MESSAGE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Returns the bincode options for reading a message of at most `limit` bytes. The encoding is
+/// the one of `bincode::serialize`, which the sending side uses.
+fn reading(limit: u64) -> impl Options {
+    bincode::options()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(limit)
+}
+
+/// The connection of the master to one worker.
+#[derive(Debug)]
+struct Connection {
+    /// The address of the worker.
+    address: SocketAddr,
+    /// The open connection, if any: the reading and the writing side.
+    stream: Option<(BufReader<TcpStream>, TcpStream)>,
+}
+
+impl Connection {
+    /// Sends the encoded individuals to the worker and returns the fitness of the `count`
+    /// individuals. Connects first if necessary, a failed connection is closed and opened again
+    /// by the next exchange.
+    fn exchange<F: DeserializeOwned>(
+        &mut self,
+        request: &[u8],
+        count: usize,
+        timeout: Option<Duration>,
+    ) -> Result<Vec<Result<F, String>>, String> {
+        let result = self.try_exchange(request, count, timeout);
+        if result.is_err() {
+            self.stream = None;
+        }
+        result.map_err(|error| format!("worker {}: {}", self.address, error))
+    }
+
+    /// Does the work of `exchange`.
+    fn try_exchange<F: DeserializeOwned>(
+        &mut self,
+        request: &[u8],
+        count: usize,
+        timeout: Option<Duration>,
+    ) -> Result<Vec<Result<F, String>>, bincode::Error> {
+        if self.stream.is_none() {
+            let stream = match timeout {
+                Some(timeout) => TcpStream::connect_timeout(&self.address, timeout)?,
+                None => TcpStream::connect(self.address)?,
+            };
+            stream.set_nodelay(true)?;
+            stream.set_read_timeout(timeout)?;
+            stream.set_write_timeout(timeout)?;
+            self.stream = Some((BufReader::new(stream.try_clone()?), stream));
+        }
+        let (ref mut reader, ref mut writer) =
+            *self.stream.as_mut().expect("connected above");
+
+        writer.write_all(request)?;
+        let results: Vec<Result<F, String>> = bincode::deserialize_from(reader)?;
+        if results.len() != count {
+            let message = format!("{} results for {} individuals", results.len(), count);
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message).into());
+        }
+        Ok(results)
+    }
+}
+
+/// The `RemoteEvaluator` type. Sends the new individuals of a population to the workers (see
+/// `Worker`) and collects their fitness, see the module documentation. The individuals are
+/// split evenly between the workers, which are asked in parallel, each one on its own thread.
+#[derive(Debug)]
+pub struct RemoteEvaluator {
+    /// The connections to the workers.
+    workers: Arc<Vec<Mutex<Connection>>>,
+    /// The timeout for connecting, sending and receiving, if any.
+    timeout: Option<Duration>,
+}
+
+impl RemoteEvaluator {
+    /// Creates an evaluator for the workers at the given addresses. The workers are connected
+    /// when they are needed for the first time.
+    pub fn new<A: ToSocketAddrs>(workers: &[A]) -> io::Result<RemoteEvaluator> {
+        let mut connections = Vec::new();
+        for worker in workers {
+            for address in worker.to_socket_addrs()? {
+                connections.push(Mutex::new(Connection { address, stream: None }));
+            }
+        }
+        if connections.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "no worker addresses"));
+        }
+        Ok(RemoteEvaluator { workers: Arc::new(connections), timeout: None })
+    }
+
+    /// Gives up on a worker that does not connect, receive or answer within the given time
+    /// (default: wait forever). Set it well above the time a worker needs for its part of the
+    /// individuals.
+    pub fn timeout(mut self, timeout: Duration) -> RemoteEvaluator {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+impl<T> BatchEvaluator<T> for RemoteEvaluator
+where
+    T: Individual + Serialize + Sync,
+    T::Fitness: DeserializeOwned,
+{
+    /// Individuals that no worker could evaluate fail with the last error, like a panic.
+    fn evaluate(
+        &self,
+        individuals: &[&T],
+        _context: &Arc<T::Context>,
+    ) -> Vec<Result<T::Fitness, String>> {
+        if individuals.is_empty() {
+            return Vec::new();
+        }
+        let count = self.workers.len();
+        let chunk_size = (individuals.len() + count - 1) / count;
+
+        // The threads of the evaluation wait for the network, so they are not taken from the
+        // thread pool of the populations.
+        let requests: Vec<(usize, Result<Vec<u8>, bincode::Error>)> = individuals
+            .chunks(chunk_size)
+            .map(|chunk| (chunk.len(), bincode::serialize(chunk)))
+            .collect();
+        let handles: Vec<_> = requests
+            .into_iter()
+            .enumerate()
+            .map(|(index, (size, request))| {
+                let workers = self.workers.clone();
+                let timeout = self.timeout;
+                thread::spawn(move || {
+                    let request = request.map_err(|error| error.to_string())?;
+                    // The own worker first, then the others in turn.
+                    let mut last_error = String::new();
+                    for offset in 0..count {
+                        let worker = &workers[(index + offset) % count];
+                        let mut connection =
+                            worker.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                        match connection.exchange(&request, size, timeout) {
+                            Ok(results) => return Ok(results),
+                            Err(error) => {
+                                warn!("{}", error);
+                                last_error = error;
+                            }
+                        }
+                    }
+                    Err(last_error)
+                })
+            })
+            .collect();
+
+        let mut results = Vec::with_capacity(individuals.len());
+        for (handle, chunk) in handles.into_iter().zip(individuals.chunks(chunk_size)) {
+            match handle.join() {
+                Ok(Ok(part)) => results.extend(part),
+                Ok(Err(error)) => results.extend(chunk.iter().map(|_| Err(error.clone()))),
+                Err(_) => results.extend(chunk.iter().map(|_| Err("worker thread".to_string()))),
+            }
+        }
+        results
+    }
+}
+
+/// The `Worker` type. Calculates the fitness of the individuals sent by a `RemoteEvaluator`,
+/// see the module documentation.
+#[derive(Debug)]
+pub struct Worker<T: Individual> {
+    /// Accepts the connections of the masters.
+    listener: TcpListener,
+    /// The context of the individuals on this worker.
+    context: Arc<T::Context>,
+    /// The largest request in bytes, see `max_message_size`.
+    max_message_size: u64,
+    /// The worker evaluates individuals of this type.
+    individual: PhantomData<fn() -> T>,
+}
+
+impl<T> Worker<T>
+where
+    T: Individual + DeserializeOwned + Debug + 'static,
+    T::Fitness: Serialize,
+{
+    /// Creates a worker listening at the given address.
+    pub fn bind<A: ToSocketAddrs>(address: A, context: T::Context) -> io::Result<Worker<T>> {
+        Ok(Worker {
+            listener: TcpListener::bind(address)?,
+            context: Arc::new(context),
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            individual: PhantomData,
+        })
+    }
+
+    /// Closes the connection of a master whose request is larger than the given number of
+    /// bytes (default: `DEFAULT_MAX_MESSAGE_SIZE`).
+    pub fn max_message_size(mut self, bytes: u64) -> Worker<T> {
+        self.max_message_size = bytes;
+        self
+    }
+
+    /// Returns the address the worker listens at (useful with port 0).
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Serves the masters until the listener fails, each one on its own thread. The individuals
+    /// of a request are evaluated in parallel, a panic only fails its own individual.
+    pub fn serve(self) -> io::Result<()> {
+        for stream in self.listener.incoming() {
+            let stream = stream?;
+            let context = self.context.clone();
+            let limit = self.max_message_size;
+            thread::spawn(move || {
+                let peer = stream.peer_addr().ok();
+                if let Err(error) = Self::answer(stream, &context, limit) {
+                    warn!("connection of {:?} failed: {}", peer, error);
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// Answers the requests of at most `limit` bytes of one master until it closes the
+    /// connection.
+    fn answer(stream: TcpStream, context: &T::Context, limit: u64) -> Result<(), bincode::Error> {
+        stream.set_nodelay(true)?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = BufWriter::new(stream);
+        loop {
+            let mut individuals: Vec<T> = match reading(limit).deserialize_from(&mut reader) {
+                Ok(individuals) => individuals,
+                // The master has closed the connection.
+                Err(error) => match *error {
+                    bincode::ErrorKind::Io(ref io) if io.kind() == io::ErrorKind::UnexpectedEof => {
+                        return Ok(())
+                    }
+                    _ => return Err(error),
+                },
+            };
+            let results: Vec<Result<T::Fitness, String>> = individuals
+                .par_iter_mut()
+                .map(|individual| catch_panic(|| individual.calculate_fitness(context)))
+                .collect();
+            bincode::serialize_into(&mut writer, &results)?;
+            writer.flush()?;
+        }
+    }
+}
+
+/// The `RemoteIslands` type. Exchanges emigrants with simulations on other machines, see
+/// `SimulationBuilder::remote_islands`. The emigrants are sent in the background, an
+/// unreachable simulation is connected again at the next migration and only gets the latest
+/// emigrants, so slow or lagging simulations never hold the others back. The immigrants wait
+/// until the next migration (at most `MAX_WAITING_IMMIGRANTS`). Dropping it stops listening for
+/// immigrants and closes its socket.
+#[derive(Debug)]
+pub struct RemoteIslands<T: Individual> {
+    /// The address the immigrants arrive at.
+    address: SocketAddr,
+    /// The thread that accepts the connections of the other simulations.
+    listener: Option<JoinHandle<()>>,
+    /// Tells the listening thread to stop, see `Drop`.
+    stopped: Arc<AtomicBool>,
+    /// The largest message in bytes, see `max_message_size`.
+    max_message_size: Arc<AtomicU64>,
+    /// The immigrants that have arrived since the last migration.
+    arrived: Arc<Mutex<Vec<IndividualWrapper<T>>>>,
+    /// The channels to the threads that send the emigrants to the other simulations.
+    peers: Vec<Mutex<Sender<Vec<IndividualWrapper<T>>>>>,
+}
+
+impl<T> RemoteIslands<T>
+where
+    T: Individual + Serialize + DeserializeOwned + Debug + 'static,
+    T::Fitness: Serialize + DeserializeOwned,
+{
+    /// Receives the immigrants at the given address. Add the other simulations with
+    /// `add_peer`.
+    pub fn bind<A: ToSocketAddrs>(address: A) -> io::Result<RemoteIslands<T>> {
+        let listener = TcpListener::bind(address)?;
+        let address = listener.local_addr()?;
+        let arrived = Arc::new(Mutex::new(Vec::new()));
+        let stopped = Arc::new(AtomicBool::new(false));
+        let max_message_size = Arc::new(AtomicU64::new(DEFAULT_MAX_MESSAGE_SIZE));
+
+        let inbox = arrived.clone();
+        let stop = stopped.clone();
+        let limit = max_message_size.clone();
+        let listener = thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stop.load(Ordering::SeqCst) {
+                    break;
+                }
+                let inbox = inbox.clone();
+                let limit = limit.load(Ordering::SeqCst);
+                match stream {
+                    Ok(stream) => {
+                        thread::spawn(move || Self::receive(stream, &inbox, limit));
+                    }
+                    Err(error) => warn!("remote island at {} failed: {}", address, error),
+                }
+            }
+        });
+
+        Ok(RemoteIslands {
+            address,
+            listener: Some(listener),
+            stopped,
+            max_message_size,
+            arrived,
+            peers: Vec::new(),
+        })
+    }
+
+    /// Closes the connection of a simulation that sends more than the given number of bytes at
+    /// once (default: `DEFAULT_MAX_MESSAGE_SIZE`). Applies to the connections accepted from now
+    /// on.
+    pub fn max_message_size(self, bytes: u64) -> RemoteIslands<T> {
+        self.max_message_size.store(bytes, Ordering::SeqCst);
+        self
+    }
+
+    /// Returns the address the immigrants arrive at (useful with port 0).
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Sends the emigrants of each migration also to the simulation at the given address. It
+    /// does not need to run yet.
+    pub fn add_peer<A: ToSocketAddrs>(&mut self, peer: A) -> io::Result<()> {
+        let peer = peer.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "no address of the remote island")
+        })?;
+        let (sender, emigrants) = mpsc::channel();
+        thread::spawn(move || Self::send(peer, &emigrants));
+        self.peers.push(Mutex::new(sender));
+        Ok(())
+    }
+
+    /// Sends the emigrants to the given simulation until the `RemoteIslands` is dropped.
+    fn send(peer: SocketAddr, emigrants: &Receiver<Vec<IndividualWrapper<T>>>) {
+        let mut connection: Option<BufWriter<TcpStream>> = None;
+        while let Ok(mut latest) = emigrants.recv() {
+            // The simulation has lagged behind, only the latest emigrants are still of use.
+            while let Ok(newer) = emigrants.try_recv() {
+                latest = newer;
+            }
+            if connection.is_none() {
+                connection = TcpStream::connect_timeout(&peer, PEER_TIMEOUT)
+                    .and_then(|stream| {
+                        stream.set_write_timeout(Some(PEER_TIMEOUT))?;
+                        Ok(BufWriter::new(stream))
+                    })
+                    .map_err(|error| warn!("remote island {} unreachable: {}", peer, error))
+                    .ok();
+            }
+            let sent = match connection {
+                Some(ref mut stream) => bincode::serialize_into(&mut *stream, &latest)
+                    .and_then(|_| stream.flush().map_err(Into::into)),
+                None => continue,
+            };
+            if let Err(error) = sent {
+                warn!("sending emigrants to remote island {} failed: {}", peer, error);
+                connection = None;
+            }
+        }
+    }
+
+    /// Collects the immigrants sent over the given connection in messages of at most `limit`
+    /// bytes until it is closed.
+    fn receive(stream: TcpStream, arrived: &Mutex<Vec<IndividualWrapper<T>>>, limit: u64) {
+        let peer = stream.peer_addr().ok();
+        let mut reader = BufReader::new(stream);
+        loop {
+            match reading(limit).deserialize_from::<_, Vec<IndividualWrapper<T>>>(&mut reader) {
+                Ok(immigrants) => {
+                    let mut waiting =
+                        arrived.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                    waiting.extend(immigrants);
+                    let surplus = waiting.len().saturating_sub(MAX_WAITING_IMMIGRANTS);
+                    waiting.drain(..surplus);
+                }
+                Err(error) => {
+                    if let bincode::ErrorKind::Io(ref io) = *error {
+                        if io.kind() == io::ErrorKind::UnexpectedEof {
+                            return;
+                        }
+                    }
+                    warn!("receiving immigrants from {:?} failed: {}", peer, error);
+                    return;
+                }
+            }
+        }
+    }
+}
+
+impl<T: Individual> Drop for RemoteIslands<T> {
+    /// Wakes up the listening thread with a connection of its own, so it stops and closes the
+    /// socket. The connections that are already open end when the other simulations close them.
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        let mut address = self.address;
+        if address.ip().is_unspecified() {
+            let loopback: IpAddr = match address {
+                SocketAddr::V4(_) => Ipv4Addr::LOCALHOST.into(),
+                SocketAddr::V6(_) => Ipv6Addr::LOCALHOST.into(),
+            };
+            address.set_ip(loopback);
+        }
+        if TcpStream::connect_timeout(&address, PEER_TIMEOUT).is_ok() {
+            if let Some(listener) = self.listener.take() {
+                let _ = listener.join();
+            }
+        }
+    }
+}
+
+impl<T> MigrantExchange<T> for RemoteIslands<T>
+where
+    T: Individual + Serialize + DeserializeOwned + Debug + Sync + 'static,
+    T::Fitness: Serialize + DeserializeOwned,
+{
+    fn exchange(&self, emigrants: &[IndividualWrapper<T>]) -> Vec<IndividualWrapper<T>> {
+        if !emigrants.is_empty() {
+            for peer in &self.peers {
+                let peer = peer.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                // The sending thread only stops with the `RemoteIslands`.
+                let _ = peer.send(emigrants.to_vec());
+            }
+        }
+        let mut arrived = self.arrived.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        mem::take(&mut *arrived)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{ErrorKind, Read};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use bincode;
+
+    use super::{RemoteEvaluator, RemoteIslands, Worker};
+    use individual::Individual;
+    use migration::MigrantExchange;
+    use population_builder::PopulationBuilder;
+    use rng::SimRng;
+    use test::wrap;
+
+    /// Its fitness is the distance to the target, which only the workers know.
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Guess {
+        x: f64,
+    }
+
+    impl Individual for Guess {
+        type Fitness = f64;
+        type Context = f64;
+
+        fn mutate(&mut self, _context: &f64, _rng: &mut SimRng) {}
+
+        fn calculate_fitness(&mut self, target: &f64) -> f64 {
+            assert!(self.x >= 0.0, "negative guess");
+            (self.x - target).abs()
+        }
+
+        fn reset(&mut self) {}
+    }
+
+    #[test]
+    fn workers_evaluate_remotely() {
+        let worker = Worker::<Guess>::bind("127.0.0.1:0", 10.0).unwrap();
+        let address = worker.local_addr().unwrap();
+        thread::spawn(move || worker.serve());
+        // Nobody listens here any more, its individuals go to the other worker.
+        let dead = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+
+        let evaluator = RemoteEvaluator::new(&[dead, address]).unwrap();
+        let evaluator = evaluator.timeout(Duration::from_secs(10));
+        let initial: Vec<Guess> = (0..7).map(|x| Guess { x: f64::from(x) }).collect();
+        let mut population = PopulationBuilder::<Guess>::new()
+            .initial_population(&initial)
+            .batch_evaluator(Arc::new(evaluator))
+            .finalize()
+            .unwrap();
+        population.calculate_fitness();
+        let fitness: Vec<f64> = population.population.iter().map(|w| w.fitness).collect();
+        assert_eq!(fitness, vec![10.0, 9.0, 8.0, 7.0, 6.0, 5.0, 4.0]);
+
+        // A panic on the worker only fails its own individual.
+        population.population[0].individual.x = -1.0;
+        population.population[0].dirty = true;
+        population.population[1].individual.x = 12.0;
+        population.population[1].dirty = true;
+        population.calculate_fitness();
+        assert!(population.panicked.is_some());
+        assert_eq!(population.population[1].fitness, 2.0);
+    }
+
+    #[test]
+    fn remote_islands_exchange_emigrants() {
+        let mut first = RemoteIslands::<Guess>::bind("127.0.0.1:0").unwrap();
+        let mut second = RemoteIslands::<Guess>::bind("127.0.0.1:0").unwrap();
+        first.add_peer(second.local_addr()).unwrap();
+        second.add_peer(first.local_addr()).unwrap();
+        // Nobody listens here, the others are not held back.
+        let dead = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        first.add_peer(dead).unwrap();
+
+        let emigrants = wrap(&[Guess { x: 1.0 }, Guess { x: 2.0 }]);
+        assert!(first.exchange(&emigrants).is_empty());
+        let mut arrived = Vec::new();
+        for _ in 0..500 {
+            arrived = second.exchange(&[]);
+            if !arrived.is_empty() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let fitness: Vec<f64> = arrived.iter().map(|w| w.fitness).collect();
+        assert_eq!(fitness, vec![1.0, 2.0]);
+        assert!(second.exchange(&[]).is_empty());
+    }
+
+    #[test]
+    fn oversized_messages_are_rejected() {
+        let islands = RemoteIslands::<Guess>::bind("127.0.0.1:0").unwrap().max_message_size(64);
+        let mut stream = TcpStream::connect(islands.local_addr()).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+        let immigrants = wrap(&[Guess { x: 1.0 }, Guess { x: 2.0 }, Guess { x: 3.0 }]);
+        bincode::serialize_into(&mut stream, &immigrants).unwrap();
+
+        // The connection is closed without taking in the immigrants.
+        match stream.read(&mut [0; 1]) {
+            Ok(read) => assert_eq!(read, 0),
+            Err(error) => {
+                // A reset connection is fine, a timeout is not.
+                let kind = error.kind();
+                assert!(kind != ErrorKind::WouldBlock && kind != ErrorKind::TimedOut);
+            }
+        }
+        assert!(islands.exchange(&[]).is_empty());
+    }
+
+    #[test]
+    fn dropped_remote_islands_close_the_socket() {
+        let islands = RemoteIslands::<Guess>::bind("127.0.0.1:0").unwrap();
+        let address = islands.local_addr();
+        drop(islands);
+        assert!(TcpStream::connect(address).is_err());
+    }
+}
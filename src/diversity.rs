@@ -0,0 +1,170 @@
+//! This module defines metrics for the diversity of a population, to diagnose premature
+//! convergence.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+//! The genotype distance is measured with `Individual::distance`, the genotypes are told apart
+//! with `Individual::genome_key`. Individuals that implement neither only get the fitness based
+//! metrics.
+
+use std::collections::HashMap;
+
+use fitness::Fitness;
+use individual::{Individual, IndividualWrapper};
+
+/// The `Diversity` type. The diversity metrics of one population.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Diversity {
+    /// The variance of the fitness values.
+    pub fitness_variance: f64,
+    /// The mean distance between all pairs of individuals, see `Individual::distance`.
+    pub mean_distance: f64,
+    /// The Shannon entropy (in bits) of the genotypes, see `entropy`.
+    pub entropy: f64,
+    /// The number of distinct genotypes, see `entropy`.
+    pub distinct: usize,
+}
+
+impl Diversity {
+    /// Calculates all the diversity metrics of the given population. The mean distance needs
+    /// n * (n - 1) / 2 calls of `Individual::distance`.
+    pub fn of<T: Individual>(population: &[IndividualWrapper<T>]) -> Diversity {
+        let (entropy, distinct) = entropy(population);
+
+        Diversity {
+            fitness_variance: fitness_variance(population),
+            mean_distance: mean_distance(population),
+            entropy,
+            distinct,
+        }
+    }
+}
+
+/// Returns the variance of the fitness values (see `Fitness::to_f64`) of the population, 0.0
+/// if it is empty.
+pub fn fitness_variance<T: Individual>(population: &[IndividualWrapper<T>]) -> f64 {
+    if population.is_empty() {
+        return 0.0;
+    }
+
+    let n = population.len() as f64;
+    let mean = population.iter().map(|wrapper| wrapper.fitness.to_f64()).sum::<f64>() / n;
+    population
+        .iter()
+        .map(|wrapper| (wrapper.fitness.to_f64() - mean) * (wrapper.fitness.to_f64() - mean))
+        .sum::<f64>() / n
+}
+
+/// Returns the mean distance between all pairs of individuals, 0.0 if there are less than
+/// two individuals.
+pub fn mean_distance<T: Individual>(population: &[IndividualWrapper<T>]) -> f64 {
+    let mut sum = 0.0;
+    let mut pairs = 0;
+
+    for (index, first) in population.iter().enumerate() {
+        for second in &population[index + 1..] {
+            sum += first.individual.distance(&second.individual);
+            pairs += 1;
+        }
+    }
+
+    if pairs == 0 { 0.0 } else { sum / f64::from(pairs) }
+}
+
+/// Returns the Shannon entropy (in bits) of the genotypes in the population and the number
+/// of distinct genotypes. Individuals with the same `Individual::genome_key` have the same
+/// genotype. Without a key, individuals with exactly the same fitness (see `Fitness::to_f64`)
+/// count as one genotype.
+/// The entropy is 0.0 if all individuals are equal and log2(n) if all of them differ.
+pub fn entropy<T: Individual>(population: &[IndividualWrapper<T>]) -> (f64, usize) {
+    let mut counts: HashMap<(bool, u64), u32> = HashMap::new();
+
+    for wrapper in population {
+        let class = match wrapper.individual.genome_key() {
+            Some(key) => (true, key),
+            None => (false, wrapper.fitness.to_f64().to_bits()),
+        };
+        *counts.entry(class).or_insert(0) += 1;
+    }
+
+    let n = population.len() as f64;
+    let entropy = counts
+        .values()
+        .map(|&count| {
+            let p = f64::from(count) / n;
+            -p * p.log2()
+        })
+        .sum::<f64>();
+
+    (entropy, counts.len())
+}
+
+#[cfg(test)]
+mod test {
+    use super::Diversity;
+    use individual::{genome_hash, Individual};
+    use rng::SimRng;
+    use test::{Test, wrap};
+
+    #[derive(Debug, Clone)]
+    struct Point {
+        x: i32,
+    }
+
+    impl Individual for Point {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {}
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            0.0
+        }
+
+        fn reset(&mut self) {}
+
+        fn distance(&self, other: &Point) -> f64 {
+            f64::from((self.x - other.x).abs())
+        }
+
+        fn genome_key(&self) -> Option<u64> {
+            Some(genome_hash(&self.x))
+        }
+    }
+
+    #[test]
+    fn diversity_metrics() {
+        let converged = wrap(&vec![Point { x: 3 }; 4]);
+        assert_eq!(Diversity::of(&converged), Diversity {
+            fitness_variance: 0.0,
+            mean_distance: 0.0,
+            entropy: 0.0,
+            distinct: 1,
+        });
+
+        // Pairs: 1, 2, 3, 1, 2, 1
+        let spread = wrap(&[Point { x: 0 }, Point { x: 1 }, Point { x: 2 }, Point { x: 3 }]);
+        let diversity = Diversity::of(&spread);
+        assert_eq!(diversity.mean_distance, 10.0 / 6.0);
+        assert_eq!(diversity.entropy, 2.0);
+        assert_eq!(diversity.distinct, 4);
+
+        // Without genome keys the fitness values tell the individuals apart.
+        let fitness = wrap(&[Test { f: 1.0 }, Test { f: 1.0 }, Test { f: 3.0 }, Test { f: 3.0 }]);
+        let diversity = Diversity::of(&fitness);
+        assert_eq!(diversity.fitness_variance, 1.0);
+        assert_eq!(diversity.entropy, 1.0);
+        assert_eq!(diversity.distinct, 2);
+    }
+}
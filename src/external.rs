@@ -0,0 +1,275 @@
+//! This module defines the fitness evaluation by external programs, so fitness functions written
+//! in other languages or closed-source simulators can be plugged in. Only available with the
+//! feature `serialize`.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+//! Give the populations an `ExternalEvaluator`, see `PopulationBuilder::batch_evaluator`. The
+//! program is started once and then evaluates one individual after the other: it reads each
+//! individual as one line of JSON from its standard input and writes its fitness as one line of
+//! JSON (for example `3.25`) to its standard output. Its standard error is passed through.
+
+use std::ffi::{OsStr, OsString};
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use std::vec;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json;
+
+use individual::{BatchEvaluator, Individual};
+
+/// A running instance of the external program.
+#[derive(Debug)]
+struct Process {
+    /// The program.
+    child: Child,
+    /// Its standard input.
+    input: ChildStdin,
+    /// The lines of its standard output, read by a separate thread.
+    output: Receiver<io::Result<String>>,
+}
+
+impl Process {
+    /// Starts the program.
+    fn spawn(program: &OsStr, args: &[OsString]) -> io::Result<Process> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let input = child.stdin.take().expect("piped");
+        let stdout = child.stdout.take().expect("piped");
+
+        let (lines, output) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                if lines.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Process { child, input, output })
+    }
+
+    /// Sends one individual (a line of JSON) and returns the answer.
+    fn evaluate(&mut self, request: &str, timeout: Option<Duration>) -> Result<String, String> {
+        writeln!(self.input, "{}", request)
+            .and_then(|_| self.input.flush())
+            .map_err(|error| format!("writing to the program failed: {}", error))?;
+        let line = match timeout {
+            Some(timeout) => self.output.recv_timeout(timeout).map_err(|error| match error {
+                RecvTimeoutError::Timeout => format!("no answer within {:?}", timeout),
+                RecvTimeoutError::Disconnected => "the program has ended".to_string(),
+            })?,
+            None => self.output.recv().map_err(|_| "the program has ended".to_string())?,
+        };
+        line.map_err(|error| format!("reading from the program failed: {}", error))
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// The `ExternalEvaluator` type. Calculates the fitness of the individuals with an external
+/// program, see the module documentation. A pool of instances of the program evaluates the
+/// individuals in parallel. An instance that fails or does not answer in time is stopped, the
+/// individual fails like a panic (see `PanicPolicy`) and a new instance is started for the next
+/// one.
+#[derive(Debug)]
+pub struct ExternalEvaluator {
+    /// The program.
+    program: OsString,
+    /// Its arguments.
+    args: Vec<OsString>,
+    /// The instances of the program, started when they are needed.
+    pool: Arc<Vec<Mutex<Option<Process>>>>,
+    /// The time the program may take for one individual, if any.
+    timeout: Option<Duration>,
+}
+
+impl ExternalEvaluator {
+    /// Creates an evaluator that runs the given program (one instance, no timeout).
+    pub fn new<S: AsRef<OsStr>>(program: S) -> ExternalEvaluator {
+        ExternalEvaluator {
+            program: program.as_ref().to_owned(),
+            args: Vec::new(),
+            pool: Arc::new(vec![Mutex::new(None)]),
+            timeout: None,
+        }
+    }
+
+    /// Adds an argument for the program.
+    pub fn arg<S: AsRef<OsStr>>(mut self, arg: S) -> ExternalEvaluator {
+        self.args.push(arg.as_ref().to_owned());
+        self
+    }
+
+    /// Run up to this number of instances of the program at the same time (at least one).
+    /// Default: 1
+    pub fn pool_size(mut self, size: usize) -> ExternalEvaluator {
+        self.pool = Arc::new((0..size.max(1)).map(|_| Mutex::new(None)).collect());
+        self
+    }
+
+    /// Stop an instance that takes longer than this for one individual (default: wait
+    /// forever).
+    pub fn timeout(mut self, timeout: Duration) -> ExternalEvaluator {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+impl<T> BatchEvaluator<T> for ExternalEvaluator
+where
+    T: Individual + Serialize,
+    T::Fitness: DeserializeOwned,
+{
+    fn evaluate(
+        &self,
+        individuals: &[&T],
+        _context: &Arc<T::Context>,
+    ) -> Vec<Result<T::Fitness, String>> {
+        let mut results: Vec<Option<Result<T::Fitness, String>>> =
+            individuals.iter().map(|_| None).collect();
+        let mut requests = Vec::with_capacity(individuals.len());
+        for (index, individual) in individuals.iter().enumerate() {
+            match serde_json::to_string(individual) {
+                Ok(request) => requests.push((index, request)),
+                Err(error) => results[index] = Some(Err(error.to_string())),
+            }
+        }
+
+        // The instances take the next individual when they are done with the last one. Their
+        // threads mostly wait for the program, so they are not taken from the thread pool of
+        // the populations.
+        let instances = self.pool.len().min(requests.len());
+        let queue: Arc<Mutex<vec::IntoIter<(usize, String)>>> =
+            Arc::new(Mutex::new(requests.into_iter()));
+        let (answers, answered) = mpsc::channel();
+        for slot in 0..instances {
+            let pool = self.pool.clone();
+            let queue = queue.clone();
+            let answers = answers.clone();
+            let (program, args, timeout) = (self.program.clone(), self.args.clone(), self.timeout);
+            thread::spawn(move || {
+                let mut instance =
+                    pool[slot].lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                loop {
+                    let next = queue.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).next();
+                    let (index, request) = match next {
+                        Some(next) => next,
+                        None => break,
+                    };
+                    let process = match instance.take() {
+                        Some(process) => Ok(process),
+                        None => Process::spawn(&program, &args)
+                            .map_err(|error| format!("starting {:?} failed: {}", program, error)),
+                    };
+                    // A failed instance is stopped, the next individual gets a new one.
+                    let answer = process.and_then(|mut process| {
+                        let answer = process.evaluate(&request, timeout);
+                        if answer.is_ok() {
+                            *instance = Some(process);
+                        }
+                        answer
+                    });
+                    let fitness = answer.and_then(|line| {
+                        serde_json::from_str::<T::Fitness>(&line)
+                            .map_err(|error| format!("invalid fitness {:?}: {}", line, error))
+                    });
+                    if answers.send((index, fitness)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(answers);
+
+        for (index, fitness) in answered {
+            results[index] = Some(fitness);
+        }
+        results
+            .into_iter()
+            .map(|result| result.unwrap_or_else(|| Err("the evaluation has failed".to_string())))
+            .collect()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::ExternalEvaluator;
+    use individual::{BatchEvaluator, Individual};
+    use rng::SimRng;
+
+    /// Its fitness is calculated by the shell.
+    #[derive(Debug, Clone, Serialize)]
+    struct Value(f64);
+
+    impl Individual for Value {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {}
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            panic!("evaluated internally");
+        }
+
+        fn reset(&mut self) {}
+    }
+
+    #[test]
+    fn external_program_calculates_fitness() {
+        // Answers with the value itself, but hangs on negative values.
+        let script = "while read value; do \
+                      case $value in -*) sleep 5;; esac; \
+                      echo \"$value\"; done";
+        let evaluator = ExternalEvaluator::new("sh")
+            .arg("-c")
+            .arg(script)
+            .pool_size(2)
+            .timeout(Duration::from_millis(500));
+        let values: Vec<Value> = vec![Value(1.0), Value(2.5), Value(-1.0), Value(4.0), Value(5.0)];
+        let individuals: Vec<&Value> = values.iter().collect();
+
+        let results = evaluator.evaluate(&individuals, &Arc::new(()));
+        assert_eq!(results.len(), 5);
+        for (result, value) in results.iter().zip(&values) {
+            match *result {
+                Ok(fitness) => assert_eq!(fitness, value.0),
+                Err(ref message) => {
+                    assert_eq!(value.0, -1.0);
+                    assert!(message.contains("no answer"), "{}", message);
+                }
+            }
+        }
+        assert!(results[2].is_err());
+
+        let missing = ExternalEvaluator::new("/darwin-rs/no/such/program");
+        let results = missing.evaluate(&individuals[..1], &Arc::new(()));
+        assert!(results[0].as_ref().unwrap_err().contains("starting"));
+    }
+}
@@ -0,0 +1,212 @@
+//! This module defines the trait for the fitness values of the individuals (see
+//! `Individual::Fitness`) and its implementations for numbers and tuples.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+//! The individuals are ranked with `PartialOrd` on the fitness itself, so integers, tuples
+//! (compared lexicographically) or custom structs are ranked exactly. `Fitness::compare` makes
+//! this a total order: a fitness that can't be compared (NaN) is always the worst, even worse
+//! than `Fitness::worst`, so sorting never panics. Only the parts of the library that need
+//! arithmetic (statistics, fitness proportionate selection, the improvement factor, the
+//! fitness tolerance and the `EndFitness` target) use the number returned by `Fitness::to_f64`.
+
+use std::cmp::Ordering;
+use std::fmt::Debug;
+
+/// A fitness value, lower is better. Implemented for `f64`, `f32`, the signed integers and
+/// tuples of up to three fitness values (compared lexicographically).
+pub trait Fitness: PartialOrd + Clone + Debug + Send + Sync + 'static {
+    /// Returns the worst possible fitness. It is given to individuals whose evaluation failed
+    /// and to the individuals removed by the clearing procedure.
+    fn worst() -> Self;
+    /// Returns the fitness with the reverse order (for example the negated number). The
+    /// library minimizes this value when the simulation maximizes, see
+    /// `SimulationBuilder::maximize`.
+    fn reverse(&self) -> Self;
+    /// Returns the fitness made worse by the given penalty for the violation of constraints,
+    /// see `PopulationBuilder::penalty`. A negative penalty makes it better again.
+    fn penalize(&self, penalty: f64) -> Self;
+    /// Returns a number for the parts of the library that need arithmetic. It must not be
+    /// smaller for a worse fitness.
+    fn to_f64(&self) -> f64;
+    /// Returns true if the fitness can't be compared, not even with itself (like NaN).
+    fn is_nan(&self) -> bool {
+        self.partial_cmp(self).is_none()
+    }
+    /// Compares two fitness values with a total order, lower is better: like `partial_cmp`,
+    /// but NaN values are equal to each other and worse than any other value. The population
+    /// and all the selectors are sorted with this order.
+    fn compare(&self, other: &Self) -> Ordering {
+        self.partial_cmp(other).unwrap_or_else(|| self.is_nan().cmp(&other.is_nan()))
+    }
+}
+
+impl Fitness for f64 {
+    fn worst() -> f64 {
+        f64::INFINITY
+    }
+
+    fn reverse(&self) -> f64 {
+        -self
+    }
+
+    fn penalize(&self, penalty: f64) -> f64 {
+        self + penalty
+    }
+
+    fn to_f64(&self) -> f64 {
+        *self
+    }
+}
+
+impl Fitness for f32 {
+    fn worst() -> f32 {
+        f32::INFINITY
+    }
+
+    fn reverse(&self) -> f32 {
+        -self
+    }
+
+    fn penalize(&self, penalty: f64) -> f32 {
+        self + penalty as f32
+    }
+
+    fn to_f64(&self) -> f64 {
+        f64::from(*self)
+    }
+}
+
+macro_rules! integer_fitness {
+    ($($integer:ty),*) => {
+        $(
+            /// The penalty is rounded to the nearest integer. The negation saturates, so `MIN`
+            /// becomes `MAX`.
+            impl Fitness for $integer {
+                fn worst() -> $integer {
+                    <$integer>::MAX
+                }
+
+                fn reverse(&self) -> $integer {
+                    self.saturating_neg()
+                }
+
+                fn penalize(&self, penalty: f64) -> $integer {
+                    self.saturating_add(penalty.round() as $integer)
+                }
+
+                fn to_f64(&self) -> f64 {
+                    *self as f64
+                }
+            }
+        )*
+    };
+}
+
+integer_fitness!(i8, i16, i32, i64, isize);
+
+/// Lexicographic: the first value decides, the second one breaks ties. The penalty is added
+/// to the first value, `to_f64` returns the first value.
+impl<A: Fitness, B: Fitness> Fitness for (A, B) {
+    fn worst() -> (A, B) {
+        (A::worst(), B::worst())
+    }
+
+    fn reverse(&self) -> (A, B) {
+        (self.0.reverse(), self.1.reverse())
+    }
+
+    fn penalize(&self, penalty: f64) -> (A, B) {
+        (self.0.penalize(penalty), self.1.clone())
+    }
+
+    fn to_f64(&self) -> f64 {
+        self.0.to_f64()
+    }
+}
+
+/// Lexicographic, like the pairs.
+impl<A: Fitness, B: Fitness, C: Fitness> Fitness for (A, B, C) {
+    fn worst() -> (A, B, C) {
+        (A::worst(), B::worst(), C::worst())
+    }
+
+    fn reverse(&self) -> (A, B, C) {
+        (self.0.reverse(), self.1.reverse(), self.2.reverse())
+    }
+
+    fn penalize(&self, penalty: f64) -> (A, B, C) {
+        (self.0.penalize(penalty), self.1.clone(), self.2.clone())
+    }
+
+    fn to_f64(&self) -> f64 {
+        self.0.to_f64()
+    }
+}
+
+/// Returns the smallest (best) of the given fitness values, `Fitness::worst` if there is none.
+/// NaN values are ignored.
+pub fn minimum<F: Fitness, I: IntoIterator<Item = F>>(values: I) -> F {
+    values.into_iter().fold(F::worst(), |best, value| if value < best { value } else { best })
+}
+
+/// Returns the largest (worst) of the given fitness values, None if there is none. NaN values
+/// are ignored.
+pub fn maximum<F: Fitness, I: IntoIterator<Item = F>>(values: I) -> Option<F> {
+    values.into_iter().filter(|value| !value.is_nan()).fold(None, |worst, value| match worst {
+        Some(worst) if worst >= value => Some(worst),
+        _ => Some(value),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::cmp::Ordering;
+
+    use super::{maximum, minimum, Fitness};
+
+    #[test]
+    fn numbers_and_tuples() {
+        assert_eq!(3i32.penalize(0.5), 4);
+        assert_eq!(i32::MIN.reverse(), i32::MAX);
+        assert!(5i64 < i64::worst());
+        assert_eq!(2.5f32.to_f64(), 2.5);
+
+        // The second value only breaks ties, even beyond the precision of f64.
+        let a = (1i64, 9i64);
+        let b = (1i64, 10i64);
+        let c = (2i64, 0i64);
+        assert!(a < b && b < c);
+        assert!(b.reverse() < a.reverse());
+        assert_eq!(a.penalize(1.0), (2, 9));
+        assert!((i64::MAX - 1, 0i64) < (i64::MAX, 0i64));
+        assert_eq!(c.to_f64(), 2.0);
+        assert!(c < <(i64, i64)>::worst());
+
+        assert_eq!(minimum(vec![2.0, f64::NAN, 1.0]), 1.0);
+        assert_eq!(minimum(Vec::<i32>::new()), i32::MAX);
+        assert_eq!(maximum(vec![b, a, c]), Some(c));
+        assert_eq!(maximum(vec![f64::NAN]), None);
+    }
+
+    #[test]
+    fn nan_is_always_the_worst() {
+        let mut values = [f64::NAN, 1.0, f64::INFINITY, f64::NAN, -1.0];
+        values.sort_by(Fitness::compare);
+        assert_eq!(&values[..3], &[-1.0, 1.0, f64::INFINITY]);
+        assert!(values[3].is_nan() && values[4].is_nan());
+        assert_eq!(f64::NAN.compare(&f64::NAN), Ordering::Equal);
+        assert_eq!(f64::NAN.reverse().compare(&f64::MAX), Ordering::Greater);
+        assert_eq!((1.0, f64::NAN).compare(&(1.0, 2.0)), Ordering::Greater);
+    }
+}
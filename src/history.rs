@@ -0,0 +1,126 @@
+//! This module defines the fitness history of a simulation: the fitness of the fittest
+//! individual after each iteration, so the convergence curve can be plotted after the run.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+//! Enable it with `SimulationBuilder::record_history`. For very long runs the number of
+//! entries can be bounded: when the history is full, every second entry is dropped and from
+//! then on only every second iteration is recorded, and so on. The entries stay evenly
+//! spread over the whole run.
+
+use individual::Individual;
+
+/// The `HistoryEntry` type. The fittest individual after one iteration.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serialize",
+    serde(bound(
+        serialize = "T: ::serde::Serialize, T::Fitness: ::serde::Serialize",
+        deserialize = "T: ::serde::Deserialize<'de>, T::Fitness: ::serde::Deserialize<'de>"
+    ))
+)]
+pub struct HistoryEntry<T: Individual> {
+    /// The iteration, 0 for the initial populations.
+    pub iteration: u32,
+    /// The fitness of the fittest individual as calculated by `Individual::calculate_fitness`,
+    /// also in maximize mode.
+    pub fitness: T::Fitness,
+    /// A copy of the fittest individual, only if snapshots are enabled.
+    pub snapshot: Option<T>,
+}
+
+/// The `FitnessHistory` type. The fitness (and optionally a copy) of the fittest individual
+/// after each recorded iteration, see `SimulationResult::history`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(
+    feature = "serialize",
+    serde(bound(
+        serialize = "T: ::serde::Serialize, T::Fitness: ::serde::Serialize",
+        deserialize = "T: ::serde::Deserialize<'de>, T::Fitness: ::serde::Deserialize<'de>"
+    ))
+)]
+pub struct FitnessHistory<T: Individual> {
+    /// The recorded entries in the order of their iterations.
+    pub entries: Vec<HistoryEntry<T>>,
+    /// The maximum number of entries, 0 means no limit.
+    max_entries: usize,
+    /// Keep a copy of the fittest individual in each entry.
+    snapshots: bool,
+    /// Only every nth iteration is recorded, this doubles each time the history is full.
+    stride: u32,
+}
+
+impl<T: Individual + Clone> FitnessHistory<T> {
+    /// Creates an empty history with at most `max_entries` entries (0 means no limit, otherwise
+    /// at least 2). With `snapshots` each entry keeps a copy of the fittest individual.
+    pub fn new(max_entries: usize, snapshots: bool) -> FitnessHistory<T> {
+        FitnessHistory { entries: Vec::new(), max_entries, snapshots, stride: 1 }
+    }
+
+    /// Records the fittest individual of the given iteration, unless the iteration is skipped
+    /// by the downsampling.
+    pub fn record(&mut self, iteration: u32, fitness: T::Fitness, fittest: &T) {
+        if iteration % self.stride != 0 {
+            return;
+        }
+
+        if self.max_entries > 0 && self.entries.len() >= self.max_entries {
+            self.stride *= 2;
+            let stride = self.stride;
+            self.entries.retain(|entry| entry.iteration % stride == 0);
+            if iteration % stride != 0 {
+                return;
+            }
+        }
+
+        self.entries.push(HistoryEntry {
+            iteration,
+            fitness,
+            snapshot: if self.snapshots { Some(fittest.clone()) } else { None },
+        });
+    }
+
+    /// Returns the recorded iterations and their fitness values, for example to plot the
+    /// convergence curve.
+    pub fn curve(&self) -> Vec<(u32, T::Fitness)> {
+        self.entries.iter().map(|entry| (entry.iteration, entry.fitness.clone())).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FitnessHistory;
+    use test::Test;
+
+    #[test]
+    fn full_history_is_downsampled() {
+        let mut history = FitnessHistory::<Test>::new(4, false);
+        for iteration in 0..10 {
+            history.record(iteration, f64::from(iteration), &Test { f: 0.0 });
+        }
+
+        let iterations: Vec<u32> =
+            history.curve().iter().map(|&(iteration, _)| iteration).collect();
+        assert_eq!(iterations, vec![0, 4, 8]);
+        assert!(history.entries.iter().all(|entry| entry.snapshot.is_none()));
+
+        let mut history = FitnessHistory::<Test>::new(0, true);
+        for iteration in 0..10 {
+            history.record(iteration, 1.0, &Test { f: 1.0 });
+        }
+        assert_eq!(history.entries.len(), 10);
+        assert_eq!(history.entries[9].snapshot.map(|test| test.f), Some(1.0));
+    }
+}
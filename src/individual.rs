@@ -15,57 +15,118 @@
 
 // external modules
 use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::sync::Arc;
+
+use fitness::Fitness;
+use rng::SimRng;
 
 /// A wrapper helper struct for the individuals.
 /// It does the book keeping of the fitness and the number of mutations this individual
 /// has to run in one iteration.
-#[derive(Debug, Clone)]
+#[derive(Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serialize", serde(bound(
+    serialize = "T: ::serde::Serialize, T::Fitness: ::serde::Serialize",
+    deserialize = "T: ::serde::Deserialize<'de>, T::Fitness: ::serde::Deserialize<'de>"
+)))]
 pub struct IndividualWrapper<T: Individual> {
     /// The actual individual, user defined struct.
     pub individual: T,
     /// The current calculated fitness for this individual.
-    pub fitness: f64,
+    pub fitness: T::Fitness,
     /// The number of mutation this individual is doing in one iteration.
     pub num_of_mutations: u32,
     /// The id of the population that this individual belongs to. Just for statistics.
     pub id: u32,
+    /// The number of iterations this individual has survived. Mutated copies keep the age
+    /// of their original, new individuals (children of the crossover, reset individuals)
+    /// start at 0. See `PopulationBuilder::max_age`.
+    pub age: u32,
+    /// True if the genotype has changed since the fitness was calculated, for example by a
+    /// mutation. Only dirty individuals are evaluated, the others keep their fitness.
+    pub dirty: bool,
+    /// The errors on the test cases (see `Individual::case_errors`), calculated together with
+    /// the fitness. Empty if the individual has no test cases or its evaluation failed.
+    pub case_errors: Vec<f64>,
+    /// The values of the objectives (see `Individual::objectives`), refreshed whenever a
+    /// population with `Ranking::Pareto` is sorted. Empty otherwise.
+    pub objectives: Vec<f64>,
+}
+
+impl<T: Individual> Clone for IndividualWrapper<T> {
+    fn clone(&self) -> IndividualWrapper<T> {
+        IndividualWrapper {
+            individual: self.individual.clone(),
+            fitness: self.fitness.clone(),
+            num_of_mutations: self.num_of_mutations,
+            id: self.id,
+            age: self.age,
+            dirty: self.dirty,
+            case_errors: self.case_errors.clone(),
+            objectives: self.objectives.clone(),
+        }
+    }
+
+    /// Reuses the memory of this wrapper and its individual (if `Individual` implements
+    /// `clone_from`), see `Population::offspring_buffer`.
+    fn clone_from(&mut self, source: &IndividualWrapper<T>) {
+        self.individual.clone_from(&source.individual);
+        self.fitness.clone_from(&source.fitness);
+        self.num_of_mutations = source.num_of_mutations;
+        self.id = source.id;
+        self.age = source.age;
+        self.dirty = source.dirty;
+        self.case_errors.clone_from(&source.case_errors);
+        self.objectives.clone_from(&source.objectives);
+    }
 }
 
 /// Implement this for sorting
 impl<T: Individual> PartialEq for IndividualWrapper<T> {
     fn eq(&self, other: &IndividualWrapper<T>) -> bool {
-        self.fitness == other.fitness
+        self.cmp(other) == Ordering::Equal
     }
 }
 
 /// Implement this for sorting
 impl<T: Individual> Eq for IndividualWrapper<T> {}
 
-/// Implement this for sorting
+/// Implement this for sorting, individuals with a NaN fitness are the least fit (see
+/// `Fitness::compare`)
 impl<T: Individual> Ord for IndividualWrapper<T> {
     fn cmp(&self, other: &IndividualWrapper<T>) -> Ordering {
-        self.partial_cmp(other).expect(
-            "Fitness of Individual is NaN",
-        )
+        self.fitness.compare(&other.fitness)
     }
 }
 
 /// Implement this for sorting
 impl<T: Individual> PartialOrd for IndividualWrapper<T> {
     fn partial_cmp(&self, other: &IndividualWrapper<T>) -> Option<Ordering> {
-        self.fitness.partial_cmp(&other.fitness)
+        Some(self.cmp(other))
     }
 }
 
 /// This trait has to be implemented for the user defined struct.
-/// In order to share common data between all individuals use Arc. See TSP and OCR exmaples.
+/// In order to share common data between all individuals use the `Context`. See the OCR example.
 ///
 /// TODO: add serialization, see https://github.com/willi-kappler/darwin-rs/issues/11
 pub trait Individual: Sized + Clone + Send {
-
-    /// indicates whether an individual can cross-breed with another
-    const CAN_CROSSOVER: bool = false;
-
+    /// The type of the fitness, usually `f64`. Integers and tuples (compared
+    /// lexicographically) are ranked exactly, see module fitness.
+    type Fitness: Fitness;
+    /// The type of the data shared by all individuals, for example the input of the problem or
+    /// a large lookup table. It is stored only once per simulation (see
+    /// `SimulationBuilder::context`), shared by all threads and given to `mutate` and
+    /// `calculate_fitness`. Use `()` if there is no such data. Until a context is set, the
+    /// populations use `Default::default()`.
+    type Context: Clone + Default + Debug + Send + Sync;
+    /// Set this to true if the individual implements `evaluate_batch`, so the populations use
+    /// it instead of `calculate_fitness`. Default: false
+    const BATCH: bool = false;
     /// This method mutates the individual. Usually this is a cheap and easy to implement
     /// function. In order to improve the simulation, the user can make this function a bit
     /// "smarter". This is nicely shown in the tsp and tsp2 example. The tsp2 example contains
@@ -81,13 +142,16 @@ pub trait Individual: Sized + Clone + Send {
     /// order by just randomly swaping positions are very slim. So just start with one simple
     /// mutation function (one operation) and add more and more "smarter" mutation types to the
     /// mutate function.
-    fn mutate(&mut self);
+    /// Take all random decisions from `rng`, the random number generator of the population
+    /// (not from `rand::thread_rng`), so a seeded simulation can be repeated exactly.
+    fn mutate(&mut self, context: &Self::Context, rng: &mut SimRng);
     /// This method calculates the fitness for the individual. Usually this is an expensive
     /// operation and a bit more difficult to implement, compared to the mutation method above.
     /// The lower the fitness value, the better (healthier) the individual is and the closer
     /// the individual is to the perfect solution. This can also correspont to the number of
     /// errors like for example in the sudoku or queens problem case.
-    fn calculate_fitness(&mut self) -> f64;
+    /// If the simulation maximizes (see `SimulationBuilder::maximize`) higher values are better.
+    fn calculate_fitness(&mut self, context: &Self::Context) -> Self::Fitness;
     /// This method resets each individual to an initial state.
     /// For example in the "queens" case it would reset the queens position randomly
     /// (or all in the first row).
@@ -96,30 +160,149 @@ pub trait Individual: Sized + Clone + Send {
     /// want to provide some additional information or do some statistics.
     /// It is optional and the default implementation does nothing.
     fn new_fittest_found(&mut self) {}
-
-    /// perform a crossover with anoter Indivual; default implementation returns a copy of self
-    fn crossover(&mut self, other: &mut Self) -> Self {
-        let mine = self.calculate_fitness();
-        let yours = other.calculate_fitness();
-        if mine >= yours {
-            self.clone()
-        } else {
-            other.clone()
-        }
+    /// This method calculates the distance between two individuals (in genotype space).
+    /// It is used to keep the population diverse, for example when individuals with (nearly)
+    /// equal fitness compete for the last places in the population, see
+    /// `PopulationBuilder::fitness_tolerance`.
+    /// It is optional and the default implementation returns 0.0 (all individuals are equal).
+    fn distance(&self, _other: &Self) -> f64 {
+        0.0
+    }
+    /// This method returns the error for each test case, for problems where the fitness is
+    /// made up of many test cases (for example program synthesis). Lower is better.
+    /// It is needed for the `LexicaseSelector`, all individuals must return the same number
+    /// of test cases. It is called after each fitness evaluation and the result is kept in
+    /// `IndividualWrapper::case_errors`.
+    /// It is optional and the default implementation returns no test cases.
+    fn case_errors(&mut self) -> Vec<f64> {
+        Vec::new()
+    }
+    /// This method returns the values of the objectives, for problems with several competing
+    /// objectives (for example cost and quality). Lower is better for each objective. Compute
+    /// them in `calculate_fitness` and keep them in the individual. They are used instead of the
+    /// fitness to sort the population with `Ranking::Pareto` (see module pareto) and kept in
+    /// `IndividualWrapper::objectives`. All individuals must return the same number of values.
+    /// It is optional and the default implementation returns no objectives.
+    fn objectives(&self) -> Vec<f64> {
+        Vec::new()
+    }
+    /// This method returns a cheap lower bound for the fitness, for problems where
+    /// `calculate_fitness` is very expensive but a part of it (for example the first test cases
+    /// or a coarse model) is not. The real fitness must never be lower than this bound.
+    /// If the bound of a new offspring is already worse than the fitness of the worst survivor of
+    /// the population, the full evaluation is skipped since the offspring would die anyway.
+    /// With `SimulationBuilder::maximize` it must be an upper bound instead.
+    /// It is optional and the default implementation returns None (no bound).
+    fn quick_bound(&self) -> Option<Self::Fitness> {
+        None
+    }
+    /// This method returns the (approximate) memory used by the individual in bytes: the size
+    /// of the struct itself plus the heap memory it owns (vectors, strings, ...). Data shared
+    /// between the individuals with an Arc should not be counted.
+    /// It is used to estimate the memory of the simulation, see `SimulationBuilder::memory_limit`.
+    /// It is optional and the default implementation returns only the size of the struct.
+    fn size_hint(&self) -> usize {
+        mem::size_of::<Self>()
     }
+    /// This method returns a key that identifies the genotype of the individual: two individuals
+    /// with the same genotype must have the same key. If the individual implements `Hash`,
+    /// `genome_hash(self)` is an easy way to get one.
+    /// It is used to remove duplicates from the population, see
+    /// `PopulationBuilder::deduplicate`.
+    /// It is optional and the default implementation returns None (no key).
+    fn genome_key(&self) -> Option<u64> {
+        None
+    }
+    /// This method turns the individual into a valid one again, for representations with
+    /// hard structural constraints (for example a permutation or the fixed cells of a sudoku)
+    /// that `mutate` or the crossover may break. It is called after each mutation (after all
+    /// the mutations of one iteration) and on each child of the crossover, before the fitness
+    /// is calculated. A panic is reported as a panic of the mutation or crossover.
+    /// It is optional and the default implementation does nothing.
+    fn repair(&mut self) {}
+    /// This method calculates the fitness of many individuals at once, for fitness functions
+    /// that can be vectorized (SIMD, a GPU kernel, one big matrix operation). It returns the
+    /// fitness of each individual in the same order. If `BATCH` is true, the populations use it
+    /// for all the new individuals of an iteration instead of calling `calculate_fitness` for
+    /// each one (unless they have a `PopulationBuilder::batch_evaluator`). The individuals are
+    /// copies, changes to them are kept. A few single evaluations (for example of the local
+    /// search) still use `calculate_fitness`. Returning None fails all the individuals.
+    /// It is optional and the default implementation returns None (not implemented).
+    fn evaluate_batch(
+        _individuals: &mut [Self],
+        _context: &Self::Context,
+    ) -> Option<Vec<Self::Fitness>> {
+        None
+    }
+}
+
+/// Returns a hash of the given value, for example of the genotype of an individual for
+/// `Individual::genome_key`.
+pub fn genome_hash<H: Hash + ?Sized>(value: &H) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Implement this trait in addition to `Individual` if two individuals can be recombined
+/// (cross-bred) to a new one. Crossover is only used by the populations that have been configured
+/// with `PopulationBuilder::crossover`, individuals without crossover don't need to implement
+/// anything.
+pub trait Crossover: Individual {
+    /// Creates a new individual from this one and `other`. Take all random decisions from
+    /// `rng`, like in `Individual::mutate`.
+    fn crossover(&mut self, other: &mut Self, rng: &mut SimRng) -> Self;
+}
+
+/// Calls `Crossover::crossover`, this is stored in the populations that use crossover.
+pub fn crossover<T: Crossover>(first: &mut T, second: &mut T, rng: &mut SimRng) -> T {
+    first.crossover(second, rng)
+}
+
+/// Implement this trait in addition to `Individual` for problems with constraints. The
+/// violation is turned into a penalty on the fitness by the populations that have been
+/// configured with `PopulationBuilder::penalty`, so `calculate_fitness` only computes the
+/// objective.
+pub trait Constrained: Individual {
+    /// Returns how much the individual violates the constraints, for example the sum of the
+    /// distances to the feasible region. 0.0 (or less) means feasible. Compute it in
+    /// `calculate_fitness` and keep it in the individual, it is called often.
+    fn violation(&self) -> f64;
+}
+
+/// Calls `Constrained::violation`, this is stored in the populations that use a penalty.
+pub fn violation<T: Constrained>(individual: &T) -> f64 {
+    individual.violation()
+}
+
+/// Evaluates all new individuals of a population at once instead of one after the other with
+/// `Individual::calculate_fitness`, see `PopulationBuilder::batch_evaluator`. For example the
+/// `AsyncEvaluator` of module async_fitness (feature `tokio`).
+pub trait BatchEvaluator<T: Individual>: Debug + Send + Sync {
+    /// Returns the fitness of each given individual in the same order, or the message of the
+    /// failed evaluation, which is handled like a panic (see `PanicPolicy`).
+    fn evaluate(
+        &self,
+        individuals: &[&T],
+        context: &Arc<T::Context>,
+    ) -> Vec<Result<T::Fitness, String>>;
 }
 
 #[cfg(test)]
 mod test {
     use super::{IndividualWrapper, Individual};
+    use rng::SimRng;
 
     #[derive(Clone)]
     struct IndividualTest1;
 
     impl Individual for IndividualTest1 {
-        fn mutate(&mut self) {}
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {}
 
-        fn calculate_fitness(&mut self) -> f64 {
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
             0.0
         }
 
@@ -133,12 +316,20 @@ mod test {
             fitness: 1.2,
             num_of_mutations: 21,
             id: 1,
+            age: 0,
+            dirty: false,
+            case_errors: Vec::new(),
+            objectives: Vec::new(),
         };
         let individual2 = IndividualWrapper {
             individual: IndividualTest1,
             fitness: 5.93,
             num_of_mutations: 7,
             id: 1,
+            age: 0,
+            dirty: false,
+            case_errors: Vec::new(),
+            objectives: Vec::new(),
         };
 
         assert!(individual2 > individual1);
@@ -151,12 +342,20 @@ mod test {
             fitness: 3.78,
             num_of_mutations: 21,
             id: 1,
+            age: 0,
+            dirty: false,
+            case_errors: Vec::new(),
+            objectives: Vec::new(),
         };
         let individual2 = IndividualWrapper {
             individual: IndividualTest1,
             fitness: 7.12,
             num_of_mutations: 7,
             id: 1,
+            age: 0,
+            dirty: false,
+            case_errors: Vec::new(),
+            objectives: Vec::new(),
         };
 
         assert!(individual1 < individual2);
@@ -169,12 +368,20 @@ mod test {
             fitness: 21.996,
             num_of_mutations: 11,
             id: 1,
+            age: 0,
+            dirty: false,
+            case_errors: Vec::new(),
+            objectives: Vec::new(),
         };
         let individual2 = IndividualWrapper {
             individual: IndividualTest1,
             fitness: 21.996,
             num_of_mutations: 34,
             id: 1,
+            age: 0,
+            dirty: false,
+            case_errors: Vec::new(),
+            objectives: Vec::new(),
         };
 
         assert!(individual1 == individual2);
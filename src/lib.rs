@@ -23,21 +23,94 @@
 extern crate error_chain;
 #[macro_use]
 extern crate log;
-extern crate jobsteal;
+extern crate rayon;
 
 extern crate rand;
+extern crate rand_chacha;
 extern crate ordered_float;
+#[cfg(any(feature = "serialize", feature = "config"))]
+#[cfg_attr(feature = "serialize", macro_use)]
+extern crate serde;
+#[cfg(feature = "serialize")]
+extern crate bincode;
+#[cfg(feature = "serialize")]
+extern crate serde_json;
+#[cfg(feature = "config")]
+extern crate toml;
+#[cfg(feature = "config")]
+extern crate serde_yaml;
+#[cfg(feature = "tracing")]
+extern crate tracing;
+#[cfg(feature = "plot")]
+extern crate plotters;
+#[cfg(feature = "tokio")]
+extern crate tokio;
+
+/// Enters a `tracing` span with the given name and fields until the end of the current block.
+/// Does nothing without the feature `tracing`.
+macro_rules! trace_span {
+    ($($span:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = ::tracing::info_span!($($span)*).entered();
+    };
+}
+
+/// Records the value of a field of the current `tracing` span, the field must have been
+/// declared as `tracing::field::Empty`. Does nothing without the feature `tracing`.
+macro_rules! trace_record {
+    ($field:expr, $value:expr) => {
+        #[cfg(feature = "tracing")]
+        ::tracing::Span::current().record($field, &$value);
+    };
+}
 
 pub mod individual;
+pub mod fitness;
 pub mod simulation;
 pub mod simulation_builder;
 pub mod population;
 pub mod population_builder;
 pub mod select;
+pub mod rng;
+pub mod local_search;
+pub mod observer;
+pub mod statistics;
+pub mod history;
+pub mod schedule;
+pub mod benchmark;
+pub mod provenance;
+pub mod problems;
+pub mod ops;
+pub mod archive;
+pub mod diff;
+pub mod cache;
+pub mod diversity;
+pub mod species;
+pub mod pareto;
+pub mod constraint;
+pub mod migration;
+#[cfg(feature = "serialize")]
+pub mod persist;
+#[cfg(feature = "serialize")]
+pub mod checkpoint;
+#[cfg(feature = "serialize")]
+pub mod external;
+#[cfg(feature = "config")]
+pub mod config;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "plot")]
+pub mod plot;
+#[cfg(feature = "tokio")]
+pub mod async_fitness;
+#[cfg(feature = "distributed")]
+pub mod distributed;
 pub mod test;
 
-pub use individual::Individual;
-pub use simulation::Simulation;
+pub use individual::{Individual, Crossover, Constrained};
+pub use fitness::Fitness;
+pub use simulation::{Simulation, RunStatus, SimError, SimEvent, CancellationToken};
 pub use simulation_builder::SimulationBuilder;
 pub use population::Population;
 pub use population_builder::PopulationBuilder;
+pub use observer::Observer;
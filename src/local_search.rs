@@ -0,0 +1,128 @@
+//! This module defines the trait for local search strategies (memetic hook) and a simple
+//! hill climber implementation.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+
+use individual::Individual;
+use rng::SimRng;
+use select::Direction;
+
+/// A local search strategy tries to improve a single individual in place.
+/// The evolutionary algorithm usually gets close to the optimum, a cheap local search
+/// afterwards often recovers the last fraction of quality.
+pub trait LocalSearch<T: Individual> {
+    /// Try to improve the given individual which currently has the given `fitness` (as
+    /// calculated by `Individual::calculate_fitness`, higher is better if the simulation
+    /// maximizes).
+    /// At most `budget` fitness evaluations should be used, with the given context (see
+    /// `Individual::Context`) and random number generator.
+    /// Returns the (new) fitness of the individual.
+    fn improve(
+        &self,
+        individual: &mut T,
+        context: &T::Context,
+        rng: &mut SimRng,
+        fitness: T::Fitness,
+        budget: u32,
+    ) -> T::Fitness;
+}
+
+/// A simple hill climber: mutate a copy of the individual and keep it if its fitness
+/// is better. Each step uses one fitness evaluation.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HillClimber {
+    direction: Direction,
+}
+
+impl HillClimber {
+    /// Creates a hill climber that minimizes the fitness.
+    pub fn new() -> HillClimber {
+        HillClimber::default()
+    }
+
+    /// Set the optimization direction (default: `Direction::Minimize`, like the simulation).
+    pub fn direction(mut self, direction: Direction) -> HillClimber {
+        self.direction = direction;
+        self
+    }
+}
+
+impl<T: Individual> LocalSearch<T> for HillClimber {
+    fn improve(
+        &self,
+        individual: &mut T,
+        context: &T::Context,
+        rng: &mut SimRng,
+        fitness: T::Fitness,
+        budget: u32,
+    ) -> T::Fitness {
+        let mut fitness = fitness;
+
+        for _ in 0..budget {
+            let mut candidate = individual.clone();
+            candidate.mutate(context, rng);
+            candidate.repair();
+            let candidate_fitness = candidate.calculate_fitness(context);
+
+            if self.direction.apply(&candidate_fitness) < self.direction.apply(&fitness) {
+                *individual = candidate;
+                fitness = candidate_fitness;
+            }
+        }
+
+        fitness
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{HillClimber, LocalSearch};
+    use individual::Individual;
+    use rng::{self, SimRng};
+    use select::Direction;
+
+    #[derive(Clone)]
+    struct Countdown {
+        value: u32,
+    }
+
+    impl Individual for Countdown {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {
+            self.value = self.value.saturating_sub(1);
+        }
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            self.value as f64
+        }
+
+        fn reset(&mut self) {}
+    }
+
+    #[test]
+    fn hill_climber_respects_budget() {
+        let mut individual = Countdown { value: 10 };
+        let mut rng = rng::from_seed(1);
+        let fitness = HillClimber::new().improve(&mut individual, &(), &mut rng, 10.0, 4);
+
+        assert_eq!(fitness, 6.0);
+        assert_eq!(individual.value, 6);
+
+        let maximizer = HillClimber::new().direction(Direction::Maximize);
+        assert_eq!(maximizer.improve(&mut individual, &(), &mut rng, 6.0, 4), 6.0);
+        assert_eq!(individual.value, 6);
+    }
+}
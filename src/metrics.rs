@@ -0,0 +1,227 @@
+//! This module defines an observer that exports the progress of a simulation as Prometheus
+//! metrics, so long running simulations can be monitored with standard dashboards. Only
+//! available with the feature `metrics`.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+//! Register a `MetricsExporter` with `SimulationBuilder::observer` and either serve the
+//! metrics over HTTP with `serve` (for the Prometheus scraper) or write `render` to a file
+//! (for the textfile collector of the node exporter). The exported metrics are:
+//!
+//! - `darwin_iterations_total`: the finished iterations
+//! - `darwin_evaluations_total`: the fitness evaluations of each population
+//! - `darwin_resets_total`: the resets of each population
+//! - `darwin_best_fitness`: the fitness of the fittest individual of each population
+//! - `darwin_evaluations_per_second`: the evaluations of all populations per second in the last
+//!   iteration
+
+use std::collections::BTreeMap;
+use std::fmt::Write as FmtWrite;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+use individual::Individual;
+use observer::Observer;
+use statistics::IterationStats;
+
+/// The values of all metrics.
+#[derive(Debug, Default)]
+struct Metrics {
+    /// The last finished iteration.
+    iterations: u32,
+    /// The labels of the populations by id.
+    labels: BTreeMap<u32, String>,
+    /// The evaluations of each population so far.
+    evaluations: BTreeMap<u32, u64>,
+    /// The resets of each population so far.
+    resets: BTreeMap<u32, u64>,
+    /// The fitness of the fittest individual of each population.
+    best_fitness: BTreeMap<u32, f64>,
+    /// The evaluations per second in the last iteration.
+    evaluations_per_second: f64,
+    /// When the last iteration has finished.
+    last: Option<Instant>,
+}
+
+/// The `MetricsExporter` type. An observer that collects the metrics of the simulation, see
+/// the module documentation.
+#[derive(Debug, Default)]
+pub struct MetricsExporter {
+    /// The current values, shared with the HTTP server.
+    metrics: Mutex<Metrics>,
+}
+
+impl MetricsExporter {
+    /// Creates an exporter without any values.
+    pub fn new() -> MetricsExporter {
+        MetricsExporter::default()
+    }
+
+    /// Returns the current metrics in the Prometheus text format.
+    pub fn render(&self) -> String {
+        let metrics = self.metrics.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut text = String::new();
+        let population = |id: &u32| {
+            format!("population=\"{}\",label=\"{}\"", id, escape(&metrics.labels[id]))
+        };
+
+        header(&mut text, "darwin_iterations_total", "counter", "The finished iterations.");
+        let _ = writeln!(text, "darwin_iterations_total {}", metrics.iterations);
+
+        header(&mut text, "darwin_evaluations_total", "counter", "The fitness evaluations.");
+        for (id, count) in &metrics.evaluations {
+            let _ = writeln!(text, "darwin_evaluations_total{{{}}} {}", population(id), count);
+        }
+
+        header(&mut text, "darwin_resets_total", "counter", "The resets of the populations.");
+        for (id, resets) in &metrics.resets {
+            let _ = writeln!(text, "darwin_resets_total{{{}}} {}", population(id), resets);
+        }
+
+        header(
+            &mut text,
+            "darwin_best_fitness",
+            "gauge",
+            "The fitness of the fittest individual.",
+        );
+        for (id, fitness) in &metrics.best_fitness {
+            let _ = writeln!(text, "darwin_best_fitness{{{}}} {}", population(id), fitness);
+        }
+
+        header(
+            &mut text,
+            "darwin_evaluations_per_second",
+            "gauge",
+            "The evaluations per second in the last iteration.",
+        );
+        let _ = writeln!(text, "darwin_evaluations_per_second {}", metrics.evaluations_per_second);
+
+        text
+    }
+
+    /// Serves the metrics over HTTP at the given address on a background thread, every request
+    /// gets the current metrics. Returns the bound address (useful with port 0).
+    pub fn serve<A: ToSocketAddrs>(
+        exporter: Arc<MetricsExporter>,
+        address: A,
+    ) -> io::Result<SocketAddr> {
+        let listener = TcpListener::bind(address)?;
+        let address = listener.local_addr()?;
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let answered = stream.and_then(|mut stream| {
+                    // The request itself does not matter.
+                    let mut request = [0; 1024];
+                    let _ = stream.read(&mut request)?;
+                    let body = exporter.render();
+                    write!(
+                        stream,
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\n\
+                         Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                });
+                if let Err(error) = answered {
+                    warn!("metrics request failed: {}", error);
+                }
+            }
+        });
+
+        Ok(address)
+    }
+}
+
+impl<T: Individual> Observer<T> for MetricsExporter {
+    fn iteration_finished(&self, stats: &IterationStats) {
+        let mut metrics = self.metrics.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = Instant::now();
+
+        metrics.iterations = stats.iteration;
+        for &(id, ref label, counters) in &stats.per_population {
+            metrics.labels.insert(id, label.clone());
+            *metrics.evaluations.entry(id).or_insert(0) += counters.evaluations;
+            *metrics.resets.entry(id).or_insert(0) += counters.resets;
+        }
+        for &(id, fitness) in &stats.fitness {
+            metrics.best_fitness.insert(id, fitness.best);
+        }
+        if let Some(last) = metrics.last {
+            let seconds = now.duration_since(last).as_secs_f64();
+            if seconds > 0.0 {
+                metrics.evaluations_per_second = stats.total.evaluations as f64 / seconds;
+            }
+        }
+        metrics.last = Some(now);
+    }
+}
+
+/// Appends the help and type lines of a metric.
+fn header(text: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(text, "# HELP {} {}", name, help);
+    let _ = writeln!(text, "# TYPE {} {}", name, kind);
+}
+
+/// Escapes a label value for the Prometheus text format.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::sync::Arc;
+
+    use super::MetricsExporter;
+    use population_builder::PopulationBuilder;
+    use select::MinimizeSelector;
+    use simulation_builder::SimulationBuilder;
+    use test::Test;
+
+    #[test]
+    fn exporter_serves_metrics() {
+        let exporter = Arc::new(MetricsExporter::new());
+        let population = PopulationBuilder::<Test>::new()
+            .set_id(7)
+            .label("\"quoted\"")
+            .generate_initial_population(10, |_| Test { f: 3.0 })
+            .finalize()
+            .unwrap();
+        let mut simulation = SimulationBuilder::<Test>::new()
+            .iterations(10)
+            .observer(exporter.clone())
+            .add_population(population)
+            .finalize()
+            .unwrap();
+        simulation.run(&MinimizeSelector::new(2));
+
+        let text = exporter.render();
+        assert!(text.contains("darwin_iterations_total 10\n"));
+        let best = "darwin_best_fitness{population=\"7\",label=\"\\\"quoted\\\"\"} 3\n";
+        assert!(text.contains(best));
+        assert!(text.contains("# TYPE darwin_evaluations_total counter\n"));
+
+        let address = MetricsExporter::serve(exporter, "127.0.0.1:0").unwrap();
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(b"GET /metrics HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with(&text));
+    }
+}
@@ -0,0 +1,250 @@
+//! This module defines the migration between the populations of a simulation (the island
+//! model): every few iterations each population sends some of its individuals (the
+//! emigrants) to the other populations.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+//! Enable it with `SimulationBuilder::migration_interval`. Unlike
+//! `SimulationBuilder::share_fittest`, which copies the single global fittest individual into
+//! all populations, the migration lets each population send several individuals of its own
+//! choice, so the populations mix without losing their diversity. The `Topology` decides which
+//! populations send individuals to which other populations. With a `MigrantExchange` the
+//! populations also exchange emigrants with simulations on other machines.
+
+use std::fmt::Debug;
+
+use rand::seq::index;
+use rand::Rng;
+
+use individual::{Individual, IndividualWrapper};
+use rng::SimRng;
+
+/// The `Emigrants` type. Which individuals of a population emigrate, see `Migration`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Emigrants {
+    /// The fittest individuals (default).
+    Best,
+    /// Randomly chosen individuals, each at most once.
+    Random,
+    /// The winner of a tournament between this number of randomly chosen individuals for each
+    /// emigrant. Larger tournaments send fitter individuals.
+    Tournament(usize),
+}
+
+/// The `Acceptance` type. What the immigrants of a population replace, see
+/// `PopulationBuilder::acceptance`. The elite of the population (see
+/// `PopulationBuilder::elitism`) is never replaced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Acceptance {
+    /// The immigrants replace the least fit individuals.
+    ReplaceWorst,
+    /// The immigrants replace randomly chosen individuals, each at most once.
+    ReplaceRandom,
+    /// Only immigrants fitter than the median individual of the population are taken in, they
+    /// replace the least fit individuals.
+    FitterThanMedian,
+}
+
+/// The `Topology` type. Which populations send their emigrants (and the shared fittest
+/// individual, see `SimulationBuilder::share_fittest`) to which other populations, see
+/// `SimulationBuilder::topology`. The populations are identified by their index in the order
+/// in which they were added to the simulation.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Topology {
+    /// Each population sends to all other populations (default).
+    #[default]
+    FullyConnected,
+    /// Each population sends to the next one, the last one to the first one.
+    Ring,
+    /// The first population sends to all other populations, they only send to the first one.
+    Star,
+    /// The populations are laid out row by row in a grid with this number of columns, each one
+    /// sends to its (up to four) horizontal and vertical neighbours. The grid does not wrap
+    /// around.
+    Grid(usize),
+    /// For each population the indices of the populations it sends to. Populations without
+    /// an entry do not send at all.
+    Custom(Vec<Vec<usize>>),
+}
+
+impl Topology {
+    /// Returns the indices of the populations that the population with the given index sends
+    /// to, in a simulation with `count` populations. Never contains the index itself.
+    pub fn neighbours(&self, index: usize, count: usize) -> Vec<usize> {
+        let mut neighbours: Vec<usize> = match *self {
+            Topology::FullyConnected => (0..count).collect(),
+            Topology::Ring => vec![(index + 1) % count],
+            Topology::Star if index == 0 => (1..count).collect(),
+            Topology::Star => vec![0],
+            Topology::Grid(columns) => {
+                let (row, column) = (index / columns, index % columns);
+                let mut neighbours = vec![index + columns];
+                if row > 0 {
+                    neighbours.push(index - columns);
+                }
+                if column > 0 {
+                    neighbours.push(index - 1);
+                }
+                if column + 1 < columns {
+                    neighbours.push(index + 1);
+                }
+                neighbours
+            }
+            Topology::Custom(ref adjacency) => adjacency.get(index).cloned().unwrap_or_default(),
+        };
+        neighbours.retain(|&neighbour| neighbour != index && neighbour < count);
+        neighbours.sort_unstable();
+        neighbours.dedup();
+        neighbours
+    }
+
+    /// Returns false if the topology does not fit a simulation with `count` populations: a
+    /// grid needs at least one column, a custom topology must only name existing populations.
+    pub fn is_valid(&self, count: usize) -> bool {
+        match *self {
+            Topology::Grid(columns) => columns > 0,
+            Topology::Custom(ref adjacency) => {
+                adjacency.len() <= count &&
+                    adjacency.iter().flatten().all(|&neighbour| neighbour < count)
+            }
+            _ => true,
+        }
+    }
+}
+
+/// The `Migration` type. When and which individuals migrate between the populations, see
+/// `SimulationBuilder::migration_interval`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Migration {
+    /// Migrate every nth iteration, 0 means never.
+    pub interval: u32,
+    /// The number of emigrants of each population in each migration.
+    pub migrants: usize,
+    /// How the emigrants are chosen.
+    pub emigrants: Emigrants,
+}
+
+impl Default for Migration {
+    fn default() -> Migration {
+        Migration { interval: 0, migrants: 1, emigrants: Emigrants::Best }
+    }
+}
+
+impl Migration {
+    /// Returns true if the populations migrate after the given iteration.
+    pub fn is_due(&self, iteration: u32) -> bool {
+        self.interval > 0 && iteration % self.interval == 0
+    }
+
+    /// Returns false if no individual could ever emigrate.
+    pub fn is_valid(&self) -> bool {
+        self.migrants > 0 && self.emigrants != Emigrants::Tournament(0)
+    }
+
+    /// Chooses the emigrants of the given population, which is sorted by fitness (the fittest
+    /// individual first). Returns copies, at most as many as there are individuals.
+    pub fn select<T>(
+        &self,
+        population: &[IndividualWrapper<T>],
+        rng: &mut SimRng,
+    ) -> Vec<IndividualWrapper<T>>
+    where
+        T: Individual + Clone,
+    {
+        let size = population.len();
+        let count = self.migrants.min(size);
+
+        match self.emigrants {
+            Emigrants::Best => population[..count].to_vec(),
+            Emigrants::Random => index::sample(rng, size, count)
+                .into_iter()
+                .map(|index| population[index].clone())
+                .collect(),
+            Emigrants::Tournament(contestants) => (0..count)
+                .map(|_| {
+                    // The population is sorted, so the lowest index wins.
+                    let winner = (0..contestants).map(|_| rng.gen_range(0..size)).min();
+                    population[winner.unwrap_or(0)].clone()
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Exchanges emigrants with populations outside of this simulation, for example with
+/// simulations on other machines (see `distributed::RemoteIslands`, feature `distributed`), see
+/// `SimulationBuilder::remote_islands`.
+pub trait MigrantExchange<T: Individual>: Debug + Send + Sync {
+    /// Sends the emigrants of this simulation away and returns the immigrants that have
+    /// arrived since the last call. Must not wait for the other simulations.
+    fn exchange(&self, emigrants: &[IndividualWrapper<T>]) -> Vec<IndividualWrapper<T>>;
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Emigrants, Migration, Topology};
+    use rng;
+    use test::{wrap, Test};
+
+    #[test]
+    fn emigrants_are_selected() {
+        let population = wrap(&[Test { f: 1.0 }, Test { f: 2.0 }, Test { f: 3.0 }, Test { f: 4.0 }]);
+        let mut rng = rng::from_seed(3);
+        let fitness = |migration: &Migration, rng: &mut _| -> Vec<f64> {
+            migration.select(&population, rng).iter().map(|wrapper| wrapper.fitness).collect()
+        };
+
+        let mut migration = Migration { interval: 5, migrants: 2, emigrants: Emigrants::Best };
+        assert!(migration.is_due(10) && !migration.is_due(11));
+        assert_eq!(fitness(&migration, &mut rng), vec![1.0, 2.0]);
+
+        migration.migrants = 10;
+        migration.emigrants = Emigrants::Random;
+        let mut random = fitness(&migration, &mut rng);
+        random.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(random, vec![1.0, 2.0, 3.0, 4.0]);
+
+        // The fittest individual takes part in nearly every tournament of this size.
+        migration.emigrants = Emigrants::Tournament(60);
+        assert!(fitness(&migration, &mut rng).iter().all(|&fitness| fitness == 1.0));
+
+        assert!(!Migration { migrants: 0, ..migration }.is_valid());
+        assert!(!Migration { emigrants: Emigrants::Tournament(0), ..migration }.is_valid());
+        assert!(!Migration::default().is_due(10));
+    }
+
+    #[test]
+    fn topologies_connect_populations() {
+        let all = |topology: &Topology, count: usize| -> Vec<Vec<usize>> {
+            (0..count).map(|index| topology.neighbours(index, count)).collect()
+        };
+
+        assert_eq!(all(&Topology::FullyConnected, 3), vec![vec![1, 2], vec![0, 2], vec![0, 1]]);
+        assert_eq!(all(&Topology::Ring, 3), vec![vec![1], vec![2], vec![0]]);
+        assert_eq!(all(&Topology::Ring, 1), vec![Vec::<usize>::new()]);
+        assert_eq!(all(&Topology::Star, 3), vec![vec![1, 2], vec![0], vec![0]]);
+        // 0 1 2
+        // 3 4
+        assert_eq!(
+            all(&Topology::Grid(3), 5),
+            vec![vec![1, 3], vec![0, 2, 4], vec![1], vec![0, 4], vec![1, 3]]
+        );
+        let custom = Topology::Custom(vec![vec![2, 2, 0], vec![]]);
+        assert_eq!(all(&custom, 3), vec![vec![2], vec![], vec![]]);
+
+        assert!(custom.is_valid(3));
+        assert!(!custom.is_valid(2));
+        assert!(!Topology::Custom(vec![vec![3]]).is_valid(3));
+        assert!(!Topology::Grid(0).is_valid(3));
+    }
+}
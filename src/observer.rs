@@ -0,0 +1,399 @@
+//! This module defines the observer trait, that allows the user to follow a running simulation.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+
+use std::fmt::Debug;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "serialize")]
+use serde_json;
+
+#[cfg(feature = "serialize")]
+use fitness::Fitness;
+use individual::{Individual, IndividualWrapper};
+use population::tag;
+use statistics::IterationStats;
+
+/// The `Phase` type. Each piece of work the simulation does belongs to one of these phases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    /// Calculating the fitness of all individuals before the first iteration.
+    Initialization,
+    /// Creating new individuals: mutation and crossover.
+    Variation,
+    /// Calculating the fitness of the new individuals.
+    Evaluation,
+    /// Selecting the parents for crossover.
+    Selection,
+    /// Sorting and truncating the population, the less fit individuals "die".
+    Replacement,
+    /// Sharing the fittest individual between the populations.
+    Migration,
+    /// Resetting all the individuals of a population when the reset limit is reached.
+    Reset,
+    /// Trying several numbers of mutations before the first iteration, see
+    /// `SimulationBuilder::calibrate_mutations`.
+    Calibration,
+    /// Checking the stop criteria and finishing the simulation.
+    Termination,
+}
+
+/// The `PhaseEvent` type. Tells an observer how long one phase took.
+#[derive(Debug, Clone)]
+pub struct PhaseEvent {
+    /// The phase this event belongs to.
+    pub phase: Phase,
+    /// The id of the population, `None` if the phase concerns the whole simulation.
+    pub population_id: Option<u32>,
+    /// The label of the population, empty if it has none or if the phase concerns the whole
+    /// simulation.
+    pub population_label: String,
+    /// The iteration in which the phase happened, 0 means before the first iteration.
+    pub iteration: u32,
+    /// The time spent in this phase.
+    pub duration: Duration,
+}
+
+/// The `NewFittestEvent` type. Tells an observer that a population has found a new global
+/// fittest individual.
+#[derive(Debug)]
+pub struct NewFittestEvent<'a, T: Individual + 'a> {
+    /// The id of the population that found the individual.
+    pub population_id: u32,
+    /// The label of the population, empty if it has none.
+    pub population_label: String,
+    /// The iteration in which the individual was found.
+    pub iteration: u32,
+    /// The fitness as calculated by `Individual::calculate_fitness`, also in maximize mode.
+    pub fitness: T::Fitness,
+    /// How often this population has found the fittest individual so far.
+    pub fitness_counter: u64,
+    /// The new fittest individual.
+    pub fittest: &'a IndividualWrapper<T>,
+}
+
+/// The `ResetEvent` type. Tells an observer that a population has reset its individuals since
+/// the reset limit was reached.
+#[derive(Debug, Clone)]
+pub struct ResetEvent {
+    /// The id of the population.
+    pub population_id: u32,
+    /// The label of the population, empty if it has none.
+    pub population_label: String,
+    /// The iteration in which the reset happened.
+    pub iteration: u32,
+    /// The new reset limit.
+    pub reset_limit: u32,
+    /// True if the reset limit has reached `reset_limit_end` and starts again at
+    /// `reset_limit_start`.
+    pub restarted: bool,
+}
+
+/// The `CrossoverEvent` type. Tells an observer how many children a population has created by
+/// crossover in one iteration.
+#[derive(Debug, Clone)]
+pub struct CrossoverEvent {
+    /// The id of the population.
+    pub population_id: u32,
+    /// The label of the population, empty if it has none.
+    pub population_label: String,
+    /// The iteration of the crossovers.
+    pub iteration: u32,
+    /// The number of children.
+    pub children: u64,
+}
+
+/// This trait can be implemented to follow a running simulation, for example for profiling.
+/// Register it with `SimulationBuilder::observer`.
+///
+/// All methods are called from the thread that runs the simulation, never from the worker
+/// threads. The populations record their events locally and they are handed over to the
+/// observers after each iteration. All methods have a default implementation that does nothing.
+pub trait Observer<T: Individual>: Debug + Send + Sync {
+    /// Called before each iteration.
+    fn iteration_start(&self, _iteration: u32) {}
+    /// Called for each phase that has been finished.
+    fn phase_finished(&self, _event: &PhaseEvent) {}
+    /// Called when a population has found a new global fittest individual.
+    fn new_fittest(&self, _event: &NewFittestEvent<T>) {}
+    /// Called when a population has reset its individuals.
+    fn reset(&self, _event: &ResetEvent) {}
+    /// Called after each iteration for each population that has created children by
+    /// crossover.
+    fn crossover(&self, _event: &CrossoverEvent) {}
+    /// Called after each iteration with the merged statistics of all populations.
+    /// Before the first iteration it is called once with iteration 0 for the initialization.
+    fn iteration_finished(&self, _stats: &IterationStats) {}
+}
+
+/// The `LoggingObserver` type. Writes the progress of the simulation to the log (see the
+/// `log` crate). It is registered by default, see `SimulationBuilder::logging`.
+#[derive(Debug)]
+pub struct LoggingObserver {
+    /// Only log every nth new fittest individual, see `SimulationBuilder::output_every`.
+    output_every: u32,
+    /// The iterations since the last new fittest individual has been logged.
+    counter: AtomicU32,
+}
+
+impl LoggingObserver {
+    /// Creates a logging observer that logs a new fittest individual only if at least
+    /// `output_every` iterations have passed since the last one.
+    pub fn new(output_every: u32) -> LoggingObserver {
+        LoggingObserver { output_every, counter: AtomicU32::new(0) }
+    }
+}
+
+impl<T: Individual> Observer<T> for LoggingObserver {
+    fn iteration_start(&self, iteration: u32) {
+        self.counter.fetch_add(1, Ordering::Relaxed);
+        debug!("iteration {}", iteration);
+    }
+
+    fn new_fittest(&self, event: &NewFittestEvent<T>) {
+        if self.counter.load(Ordering::Relaxed) >= self.output_every {
+            info!(
+                "new fittest: fitness: {:?}, population id: {}, counter: {}",
+                event.fitness,
+                tag(event.population_id, &event.population_label),
+                event.fitness_counter
+            );
+            self.counter.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn reset(&self, event: &ResetEvent) {
+        let id = tag(event.population_id, &event.population_label);
+        if event.restarted {
+            info!("reset_limit reset to reset_limit_start: {}, id: {}", event.reset_limit, id);
+        }
+        info!("new reset_limit: {}, id: {}", event.reset_limit, id);
+    }
+
+    fn crossover(&self, event: &CrossoverEvent) {
+        debug!(
+            "crossover: children: {}, id: {}",
+            event.children,
+            tag(event.population_id, &event.population_label)
+        );
+    }
+}
+
+/// The `CsvLogger` type. Streams the statistics of each iteration to a CSV file, one line per
+/// population: iteration, population id, best, mean and standard deviation of the fitness (see
+/// `FitnessStats`), evaluations and the milliseconds since the logger was created. The file
+/// is flushed after each iteration, so it can be read while the simulation runs.
+#[derive(Debug)]
+pub struct CsvLogger {
+    /// The CSV file.
+    file: Mutex<BufWriter<File>>,
+    /// When the logger was created.
+    start: Instant,
+}
+
+impl CsvLogger {
+    /// Creates (or truncates) the given file and writes the header line.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<CsvLogger> {
+        let mut file = BufWriter::new(File::create(path)?);
+        writeln!(file, "iteration,population_id,best,mean,std_dev,evaluations,elapsed_ms")?;
+        file.flush()?;
+        Ok(CsvLogger { file: Mutex::new(file), start: Instant::now() })
+    }
+
+    /// Writes the lines of the given iteration.
+    fn write(&self, stats: &IterationStats) -> io::Result<()> {
+        let elapsed_ms = self.start.elapsed().as_secs_f64() * 1000.0;
+        let mut file = self.file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        for &(id, ref fitness) in &stats.fitness {
+            let evaluations = stats
+                .per_population
+                .iter()
+                .find(|&&(population_id, _, _)| population_id == id)
+                .map_or(0, |&(_, _, counters)| counters.evaluations);
+            writeln!(
+                file,
+                "{},{},{},{},{},{},{:.3}",
+                stats.iteration,
+                id,
+                fitness.best,
+                fitness.mean,
+                fitness.std_dev,
+                evaluations,
+                elapsed_ms
+            )?;
+        }
+
+        file.flush()
+    }
+}
+
+impl<T: Individual> Observer<T> for CsvLogger {
+    fn iteration_finished(&self, stats: &IterationStats) {
+        if let Err(error) = self.write(stats) {
+            warn!("CSV statistics of iteration {} not written: {}", stats.iteration, error);
+        }
+    }
+}
+
+/// The `JsonlLogger` type. Writes one JSON object per line to a file (JSON lines), suitable
+/// for jq or log ingestion. Each object has an "event" field: "iteration" with the statistics
+/// of each iteration (see `IterationStats`), "new_fittest" with the fitness and the objectives
+/// of a new fittest individual and "reset". Only available with the feature `serialize`.
+#[cfg(feature = "serialize")]
+#[derive(Debug)]
+pub struct JsonlLogger {
+    /// The JSON lines file.
+    file: Mutex<BufWriter<File>>,
+}
+
+#[cfg(feature = "serialize")]
+impl JsonlLogger {
+    /// Creates (or truncates) the given file.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<JsonlLogger> {
+        Ok(JsonlLogger { file: Mutex::new(BufWriter::new(File::create(path)?)) })
+    }
+
+    /// Writes the given object as one line.
+    fn write(&self, line: &serde_json::Value) {
+        let mut file = self.file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let written = serde_json::to_writer(&mut *file, line)
+            .map_err(io::Error::from)
+            .and_then(|_| writeln!(file))
+            .and_then(|_| file.flush());
+        if let Err(error) = written {
+            warn!("JSON line not written: {}", error);
+        }
+    }
+}
+
+#[cfg(feature = "serialize")]
+impl<T: Individual> Observer<T> for JsonlLogger {
+    fn new_fittest(&self, event: &NewFittestEvent<T>) {
+        self.write(&::serde_json::json!({
+            "event": "new_fittest",
+            "iteration": event.iteration,
+            "population_id": event.population_id,
+            "population_label": event.population_label,
+            "fitness": event.fitness.to_f64(),
+            "objectives": event.fittest.objectives,
+        }));
+    }
+
+    fn reset(&self, event: &ResetEvent) {
+        self.write(&::serde_json::json!({
+            "event": "reset",
+            "iteration": event.iteration,
+            "population_id": event.population_id,
+            "population_label": event.population_label,
+            "reset_limit": event.reset_limit,
+            "restarted": event.restarted,
+        }));
+    }
+
+    fn iteration_finished(&self, stats: &IterationStats) {
+        self.write(&::serde_json::json!({
+            "event": "iteration",
+            "iteration": stats.iteration,
+            "total": stats.total,
+            "per_population": stats.per_population,
+            "improvement_rates": stats.improvement_rates,
+            "fitness": stats.fitness,
+            "diversity": stats.diversity,
+            "species": stats.species,
+        }));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::fs;
+    use std::sync::Arc;
+
+    #[cfg(feature = "serialize")]
+    use serde_json::{self, Value};
+
+    use super::CsvLogger;
+    #[cfg(feature = "serialize")]
+    use super::JsonlLogger;
+    use population_builder::PopulationBuilder;
+    use select::MinimizeSelector;
+    use simulation_builder::SimulationBuilder;
+    use test::Test;
+
+    #[test]
+    fn csv_logger_writes_each_iteration() {
+        let path = env::temp_dir().join("darwin-rs-statistics.csv");
+        let populations = (1..3)
+            .map(|id| {
+                PopulationBuilder::<Test>::new()
+                    .set_id(id)
+                    .generate_initial_population(10, |_| Test { f: 2.0 })
+                    .finalize()
+                    .unwrap()
+            })
+            .collect();
+        let mut simulation = SimulationBuilder::<Test>::new()
+            .iterations(10)
+            .observer(Arc::new(CsvLogger::create(&path).unwrap()))
+            .add_multiple_populations(populations)
+            .finalize()
+            .unwrap();
+        simulation.run(&MinimizeSelector::new(2));
+
+        let csv = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        // The header, the initial populations and ten iterations of two populations.
+        assert_eq!(lines.len(), 1 + 11 * 2);
+        assert_eq!(lines[0], "iteration,population_id,best,mean,std_dev,evaluations,elapsed_ms");
+        assert!(lines[22].starts_with("10,2,2,2,0,"));
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn jsonl_logger_writes_one_object_per_line() {
+        let path = env::temp_dir().join("darwin-rs-events.jsonl");
+        let initial: Vec<Test> = (0..10).map(|i| Test { f: f64::from(i) }).collect();
+        let population = PopulationBuilder::<Test>::new()
+            .set_id(4)
+            .initial_population(&initial)
+            .finalize()
+            .unwrap();
+        let mut simulation = SimulationBuilder::<Test>::new()
+            .iterations(10)
+            .observer(Arc::new(JsonlLogger::create(&path).unwrap()))
+            .add_population(population)
+            .finalize()
+            .unwrap();
+        simulation.run(&MinimizeSelector::new(2));
+
+        let jsonl = fs::read_to_string(&path).unwrap();
+        let lines: Vec<Value> =
+            jsonl.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        let iterations: Vec<&Value> =
+            lines.iter().filter(|line| line["event"] == "iteration").collect();
+        assert_eq!(iterations.len(), 11);
+        let last = iterations[10];
+        assert_eq!(last["iteration"], 10);
+        assert_eq!(last["fitness"][0][0], 4);
+        assert_eq!(last["fitness"][0][1]["best"], 0.0);
+        assert!(last["total"]["evaluations"].is_u64());
+    }
+}
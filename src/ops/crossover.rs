@@ -0,0 +1,134 @@
+//! This module defines the standard crossover operators for vector genomes.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+//! All operators create one child from two parents. The parents should have the same length,
+//! otherwise only the common part is recombined and the child has the length of `second`.
+//! They are not suited for permutations, since the child may contain duplicates.
+//!
+//! Example:
+//!
+//! ```ignore
+//! impl Crossover for MyIndividual {
+//!     fn crossover(&mut self, other: &mut MyIndividual, rng: &mut SimRng) -> MyIndividual {
+//!         let genes = two_point(&self.genes, &other.genes, rng);
+//!         MyIndividual { genes }
+//!     }
+//! }
+//! ```
+
+use rand::Rng;
+
+/// Single point crossover: the child gets the genes of `first` up to a random cut point and
+/// the genes of `second` after it.
+pub fn single_point<T: Clone, R: Rng>(first: &[T], second: &[T], rng: &mut R) -> Vec<T> {
+    let cut = rng.gen_range(0..=first.len().min(second.len()));
+
+    first[..cut].iter().chain(second[cut..].iter()).cloned().collect()
+}
+
+/// Two point crossover: the child gets the genes of `second` between two random cut points
+/// and the genes of `first` outside of them.
+pub fn two_point<T: Clone, R: Rng>(first: &[T], second: &[T], rng: &mut R) -> Vec<T> {
+    let len = first.len().min(second.len());
+    let mut start = rng.gen_range(0..=len);
+    let mut end = rng.gen_range(0..=len);
+    if start > end {
+        ::std::mem::swap(&mut start, &mut end);
+    }
+
+    first[..start]
+        .iter()
+        .chain(second[start..end].iter())
+        .chain(first[end..len].iter())
+        .chain(second[len..].iter())
+        .cloned()
+        .collect()
+}
+
+/// Uniform crossover: each gene of the child is taken from `first` with the probability
+/// `probability` (usually 0.5) and from `second` otherwise.
+pub fn uniform<T: Clone, R: Rng>(
+    first: &[T],
+    second: &[T],
+    probability: f64,
+    rng: &mut R,
+) -> Vec<T> {
+    let len = first.len().min(second.len());
+
+    first[..len]
+        .iter()
+        .zip(second.iter())
+        .map(|(a, b)| if rng.gen_bool(probability) { a.clone() } else { b.clone() })
+        .chain(second[len..].iter().cloned())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use rng;
+    use super::*;
+
+    #[test]
+    fn single_point_keeps_prefix_and_suffix() {
+        let first = vec![0; 10];
+        let second = vec![1; 10];
+        let mut rng = rng::from_seed(1);
+
+        for _ in 0..20 {
+            let child = single_point(&first, &second, &mut rng);
+            assert_eq!(child.len(), 10);
+            let cut = child.iter().position(|&gene| gene == 1).unwrap_or(10);
+            assert!(child[cut..].iter().all(|&gene| gene == 1));
+        }
+    }
+
+    #[test]
+    fn two_point_takes_one_segment() {
+        let first = vec![0; 10];
+        let second = vec![1; 10];
+        let mut rng = rng::from_seed(2);
+
+        for _ in 0..20 {
+            let child = two_point(&first, &second, &mut rng);
+            assert_eq!(child.len(), 10);
+            // At most two changes between 0 and 1 along the child.
+            let changes = child.windows(2).filter(|pair| pair[0] != pair[1]).count();
+            assert!(changes <= 2);
+        }
+    }
+
+    #[test]
+    fn uniform_probability() {
+        let first = vec![0; 1000];
+        let second = vec![1; 1000];
+        let mut rng = rng::from_seed(3);
+
+        assert_eq!(uniform(&first, &second, 1.0, &mut rng), first);
+        assert_eq!(uniform(&first, &second, 0.0, &mut rng), second);
+        let ones = uniform(&first, &second, 0.5, &mut rng).iter().sum::<i32>();
+        assert!(ones > 400 && ones < 600);
+    }
+
+    #[test]
+    fn different_lengths() {
+        let first = vec![0; 3];
+        let second = vec![1; 6];
+        let mut rng = rng::from_seed(4);
+
+        assert_eq!(single_point(&first, &second, &mut rng).len(), 6);
+        assert_eq!(two_point(&first, &second, &mut rng).len(), 6);
+        assert_eq!(uniform(&first, &second, 0.5, &mut rng).len(), 6);
+        assert_eq!(single_point(&second, &first, &mut rng).len(), 3);
+    }
+}
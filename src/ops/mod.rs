@@ -0,0 +1,18 @@
+//! This module contains standard variation operators that can be used to implement
+//! `Individual::mutate` and `Crossover::crossover` for common genome types.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+
+pub mod crossover;
+pub mod real;
@@ -0,0 +1,202 @@
+//! This module defines the standard operators for real valued genomes.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+//! Both operators are the bounded variants by Deb et al. as used in NSGA-II. The genes must lie
+//! within `bounds`, one `(lower, upper)` pair per gene, and so do the results.
+//! The distribution index `eta` controls how far the results spread: a large index (for example
+//! 20) keeps them close to the parents, a small index (for example 2) spreads them widely.
+//!
+//! For small problems with a fixed number of genes the genome can be an array (`[f64; N]`)
+//! instead of a `Vec<f64>`, so it lives inline in the individual. Use `sbx_array` for these.
+//! See the `real_array` example for a comparison of both.
+
+use rand::Rng;
+
+/// Genes closer than this are not crossed.
+const EPSILON: f64 = 1.0e-14;
+
+/// Calculates the spread factor of the simulated binary crossover for a random number `u`.
+/// `beta` describes the distance of the nearer bound relative to the distance of the parents.
+fn spread(u: f64, beta: f64, eta: f64) -> f64 {
+    let alpha = 2.0 - beta.powf(-(eta + 1.0));
+
+    if u <= 1.0 / alpha {
+        (u * alpha).powf(1.0 / (eta + 1.0))
+    } else {
+        (1.0 / (2.0 - u * alpha)).powf(1.0 / (eta + 1.0))
+    }
+}
+
+/// Simulated binary crossover (SBX): creates two children whose genes spread around the genes
+/// of the parents like single point crossover does for binary strings. Each gene is crossed
+/// with the probability 0.5, otherwise it is copied from the parents.
+/// Returns one of the two children, chosen randomly for each gene.
+pub fn sbx<R: Rng>(
+    first: &[f64],
+    second: &[f64],
+    bounds: &[(f64, f64)],
+    eta: f64,
+    rng: &mut R,
+) -> Vec<f64> {
+    first
+        .iter()
+        .zip(second.iter())
+        .zip(bounds.iter())
+        .map(|((&a, &b), &bound)| sbx_gene(a, b, bound, eta, rng))
+        .collect()
+}
+
+/// Simulated binary crossover for fixed-size genomes, see `sbx`. The child is returned inline,
+/// without any heap allocation. With the same random number generator the child is the same
+/// as the one from `sbx`.
+pub fn sbx_array<R: Rng, const N: usize>(
+    first: &[f64; N],
+    second: &[f64; N],
+    bounds: &[(f64, f64); N],
+    eta: f64,
+    rng: &mut R,
+) -> [f64; N] {
+    let mut child = [0.0; N];
+
+    for (gene, ((&a, &b), &bound)) in child
+        .iter_mut()
+        .zip(first.iter().zip(second.iter()).zip(bounds.iter()))
+    {
+        *gene = sbx_gene(a, b, bound, eta, rng);
+    }
+
+    child
+}
+
+/// Crosses a single gene, see `sbx`.
+fn sbx_gene<R: Rng>(a: f64, b: f64, (lower, upper): (f64, f64), eta: f64, rng: &mut R) -> f64 {
+    if !rng.gen_bool(0.5) || (a - b).abs() <= EPSILON {
+        return if rng.gen_bool(0.5) { a } else { b };
+    }
+
+    let (y1, y2) = if a < b { (a, b) } else { (b, a) };
+    let u = rng.gen::<f64>();
+
+    let beta = 1.0 + 2.0 * (y1 - lower) / (y2 - y1);
+    let child1 = 0.5 * ((y1 + y2) - spread(u, beta, eta) * (y2 - y1));
+    let beta = 1.0 + 2.0 * (upper - y2) / (y2 - y1);
+    let child2 = 0.5 * ((y1 + y2) + spread(u, beta, eta) * (y2 - y1));
+
+    let child = if rng.gen_bool(0.5) { child1 } else { child2 };
+    child.max(lower).min(upper)
+}
+
+/// Polynomial mutation: each gene is mutated with the given `probability` (usually
+/// 1 / number of genes). Small changes are much more likely than large ones and the change
+/// never leaves the bounds.
+/// Fixed-size genomes (`[f64; N]`) can be mutated in place as well.
+pub fn polynomial_mutation<R: Rng>(
+    genes: &mut [f64],
+    bounds: &[(f64, f64)],
+    eta: f64,
+    probability: f64,
+    rng: &mut R,
+) {
+    let power = 1.0 / (eta + 1.0);
+
+    for (gene, &(lower, upper)) in genes.iter_mut().zip(bounds.iter()) {
+        if upper <= lower || !rng.gen_bool(probability) {
+            continue;
+        }
+
+        let range = upper - lower;
+        let u = rng.gen::<f64>();
+        let delta = if u <= 0.5 {
+            let xy = 1.0 - (*gene - lower) / range;
+            (2.0 * u + (1.0 - 2.0 * u) * xy.powf(eta + 1.0)).powf(power) - 1.0
+        } else {
+            let xy = 1.0 - (upper - *gene) / range;
+            1.0 - (2.0 * (1.0 - u) + 2.0 * (u - 0.5) * xy.powf(eta + 1.0)).powf(power)
+        };
+
+        *gene = (*gene + delta * range).max(lower).min(upper);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rng;
+    use super::*;
+
+    #[test]
+    fn sbx_stays_within_bounds() {
+        let bounds = vec![(-1.0, 1.0); 5];
+        let first = vec![-0.9, -0.5, 0.0, 0.5, 0.9];
+        let second = vec![0.9, 0.5, 0.0, -0.5, -0.9];
+        let mut rng = rng::from_seed(1);
+
+        for _ in 0..1000 {
+            let child = sbx(&first, &second, &bounds, 2.0, &mut rng);
+            assert_eq!(child.len(), 5);
+            assert!(child.iter().all(|&gene| (-1.0..=1.0).contains(&gene)));
+            // Equal genes are never changed.
+            assert_eq!(child[2], 0.0);
+        }
+    }
+
+    #[test]
+    fn sbx_distribution_index() {
+        let bounds = vec![(0.0, 10.0)];
+        let mut rng = rng::from_seed(2);
+        let mut spread = |eta| {
+            (0..2000)
+                .map(|_| {
+                    let child = sbx(&[4.0], &[6.0], &bounds, eta, &mut rng)[0];
+                    (child - 4.0).abs().min((child - 6.0).abs())
+                })
+                .sum::<f64>()
+        };
+
+        assert!(spread(1.0) > 2.0 * spread(30.0));
+    }
+
+    #[test]
+    fn sbx_array_matches_sbx() {
+        let bounds = [(-5.0, 5.0); 6];
+        let first = [-4.0, -2.0, 0.0, 1.0, 3.0, 4.5];
+        let second = [4.0, 2.0, 0.0, -1.0, -3.0, 0.5];
+
+        for seed in 0..20 {
+            let child = sbx_array(&first, &second, &bounds, 5.0, &mut rng::from_seed(seed));
+            let expected = sbx(&first, &second, &bounds, 5.0, &mut rng::from_seed(seed));
+            assert_eq!(child.to_vec(), expected);
+        }
+    }
+
+    #[test]
+    fn polynomial_mutation_probability_and_bounds() {
+        let bounds = vec![(0.0, 1.0); 100];
+        let mut rng = rng::from_seed(3);
+
+        let mut genes = vec![0.5; 100];
+        polynomial_mutation(&mut genes, &bounds, 20.0, 0.0, &mut rng);
+        assert!(genes.iter().all(|&gene| gene == 0.5));
+
+        for _ in 0..100 {
+            polynomial_mutation(&mut genes, &bounds, 1.0, 1.0, &mut rng);
+            assert!(genes.iter().all(|&gene| (0.0..=1.0).contains(&gene)));
+        }
+        assert!(genes.iter().any(|&gene| gene != 0.5));
+
+        // Fixed genes (lower == upper) are never mutated.
+        let mut fixed = vec![2.0];
+        polynomial_mutation(&mut fixed, &[(2.0, 2.0)], 20.0, 1.0, &mut rng);
+        assert_eq!(fixed, vec![2.0]);
+    }
+}
@@ -0,0 +1,433 @@
+//! This module defines the Pareto dominance for problems with several competing objectives
+//! (for example cost and quality), see `Individual::objectives`.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+//! Like the fitness, each objective is minimized. An individual dominates another one if it
+//! is at least as good in all objectives and better in at least one of them.
+
+use std::cmp::Ordering;
+use std::fmt::Debug;
+
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use archive::Archive;
+use fitness::Fitness;
+use individual::{Individual, IndividualWrapper};
+use rng::SimRng;
+
+/// Returns true if the objectives `a` dominate the objectives `b`. Objectives of different
+/// lengths and NaN values never dominate anything.
+pub fn dominates(a: &[f64], b: &[f64]) -> bool {
+    a.len() == b.len() &&
+        a.iter().zip(b).all(|(a, b)| a <= b) &&
+        a.iter().zip(b).any(|(a, b)| a < b)
+}
+
+/// Sorts the given objective vectors into non-dominated fronts: the first front contains the
+/// indices of the vectors that are not dominated by any other, the second front the ones that
+/// are only dominated by the first front and so on.
+pub fn non_dominated_sort(objectives: &[&[f64]]) -> Vec<Vec<usize>> {
+    let count = objectives.len();
+    // For each vector the ones it dominates and the number of vectors that dominate it.
+    let mut dominated: Vec<Vec<usize>> = vec![Vec::new(); count];
+    let mut dominators = vec![0; count];
+
+    for a in 0..count {
+        for b in (a + 1)..count {
+            if dominates(objectives[a], objectives[b]) {
+                dominated[a].push(b);
+                dominators[b] += 1;
+            } else if dominates(objectives[b], objectives[a]) {
+                dominated[b].push(a);
+                dominators[a] += 1;
+            }
+        }
+    }
+
+    let mut fronts = Vec::new();
+    let mut front: Vec<usize> = (0..count).filter(|&index| dominators[index] == 0).collect();
+    while !front.is_empty() {
+        let mut next = Vec::new();
+        for &index in &front {
+            for &other in &dominated[index] {
+                dominators[other] -= 1;
+                if dominators[other] == 0 {
+                    next.push(other);
+                }
+            }
+        }
+        next.sort_unstable();
+        fronts.push(front);
+        front = next;
+    }
+
+    fronts
+}
+
+/// Refreshes the objectives of the given individuals (see `Individual::objectives`) and sorts
+/// them by their non-dominated front, individuals in the same front by fitness (NaN last).
+pub fn sort<T: Individual>(population: &mut Vec<IndividualWrapper<T>>) {
+    sort_fronts(population, false);
+}
+
+/// Like `sort`, but individuals in the same front are sorted by their crowding distance (the
+/// largest first) like in NSGA-II, so the truncation keeps the front spread out.
+pub fn nsga2_sort<T: Individual>(population: &mut Vec<IndividualWrapper<T>>) {
+    sort_fronts(population, true);
+}
+
+/// Returns the crowding distance of each member of the given front: the sum over all
+/// objectives of the distance between the two neighbours (normalized by the range of the
+/// objective). The members at the boundary of the front get infinity.
+pub fn crowding_distances(objectives: &[&[f64]], front: &[usize]) -> Vec<f64> {
+    let mut distances = vec![0.0; front.len()];
+    let num_of_objectives = front.first().map_or(0, |&index| objectives[index].len());
+
+    // The values of each objective for the members of the front.
+    let columns: Vec<Vec<f64>> = (0..num_of_objectives)
+        .map(|objective| front.iter().map(|&index| objectives[index][objective]).collect())
+        .collect();
+
+    for column in columns {
+        let value = |position: usize| column[position];
+        let mut order: Vec<usize> = (0..front.len()).collect();
+        order.sort_by(|&a, &b| value(a).partial_cmp(&value(b)).unwrap_or(Ordering::Equal));
+
+        let (first, last) = (order[0], order[order.len() - 1]);
+        distances[first] = f64::INFINITY;
+        distances[last] = f64::INFINITY;
+        let range = value(last) - value(first);
+        if range.is_nan() || range <= 0.0 {
+            continue;
+        }
+        for window in order.windows(3) {
+            distances[window[1]] += (value(window[2]) - value(window[0])) / range;
+        }
+    }
+
+    distances
+}
+
+/// Chooses the given number of pairs of parents by binary tournaments: the individual at the
+/// lower position wins. The population must be sorted with `nsga2_sort`, so this compares the
+/// fronts and then the crowding distances.
+pub fn select_parents<T>(
+    population: &[IndividualWrapper<T>],
+    pairs: usize,
+    rng: &mut SimRng,
+) -> Vec<(T, T)>
+where
+    T: Individual + Clone,
+{
+    let mut tournament = || {
+        let first = rng.gen_range(0..population.len());
+        let second = rng.gen_range(0..population.len());
+        population[first.min(second)].individual.clone()
+    };
+
+    (0..pairs).map(|_| (tournament(), tournament())).collect()
+}
+
+/// The `ParetoFront` type. An archive of the non-dominated individuals found so far, ordered
+/// by fitness. An individual is only stored if no member dominates it or has exactly the same
+/// objectives, the members it dominates are removed.
+#[derive(Debug, Clone)]
+pub struct ParetoFront<T: Individual> {
+    members: Vec<IndividualWrapper<T>>,
+}
+
+impl<T: Individual + Clone> Default for ParetoFront<T> {
+    fn default() -> ParetoFront<T> {
+        ParetoFront::new()
+    }
+}
+
+impl<T: Individual + Clone> ParetoFront<T> {
+    /// Creates an empty front.
+    pub fn new() -> ParetoFront<T> {
+        ParetoFront { members: Vec::new() }
+    }
+
+    /// Offers a copy of the given individual, its objectives must be up to date. Returns true
+    /// if it has been stored.
+    pub fn insert(&mut self, candidate: &IndividualWrapper<T>) -> bool {
+        if self.members.iter().any(|member| {
+            member.objectives == candidate.objectives ||
+                dominates(&member.objectives, &candidate.objectives)
+        }) {
+            return false;
+        }
+
+        self.members.retain(|member| !dominates(&candidate.objectives, &member.objectives));
+        let position = self.members
+            .iter()
+            .position(|member| candidate.fitness.compare(&member.fitness) == Ordering::Less)
+            .unwrap_or(self.members.len());
+        self.members.insert(position, candidate.clone());
+        true
+    }
+
+    /// Returns all members, ordered by fitness.
+    pub fn members(&self) -> &[IndividualWrapper<T>] {
+        &self.members
+    }
+
+    /// Iterates over all members, ordered by fitness.
+    pub fn iter(&self) -> ::std::slice::Iter<'_, IndividualWrapper<T>> {
+        self.members.iter()
+    }
+
+    /// Returns the number of members.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Returns true if the front is empty.
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Returns the members whose objectives are within the given bounds: (min, max) for each
+    /// objective, both inclusive. Use infinity for open bounds.
+    pub fn within(&self, bounds: &[(f64, f64)]) -> Vec<&IndividualWrapper<T>> {
+        self.members
+            .iter()
+            .filter(|member| {
+                member.objectives.len() == bounds.len() &&
+                    member
+                        .objectives
+                        .iter()
+                        .zip(bounds)
+                        .all(|(&value, &(min, max))| value >= min && value <= max)
+            })
+            .collect()
+    }
+
+    /// Returns up to `count` knee points of the front, the best compromises between the
+    /// objectives: each objective is normalized to the range 0.0 to 1.0 over the front, so the
+    /// extreme members lie on the plane where the normalized objectives sum up to 1.0. The
+    /// members furthest below this plane (the smallest sums) are the knee points, the most
+    /// pronounced one first.
+    pub fn knee_points(&self, count: usize) -> Vec<&IndividualWrapper<T>> {
+        let num_of_objectives = self.members.first().map_or(0, |member| member.objectives.len());
+        let ranges: Vec<(f64, f64)> = (0..num_of_objectives)
+            .map(|objective| {
+                self.members.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), m| {
+                    (min.min(m.objectives[objective]), max.max(m.objectives[objective]))
+                })
+            })
+            .collect();
+        let normalized_sum = |member: &IndividualWrapper<T>| -> f64 {
+            member
+                .objectives
+                .iter()
+                .zip(&ranges)
+                .map(|(&value, &(min, max))| {
+                    if max > min { (value - min) / (max - min) } else { 0.0 }
+                })
+                .sum()
+        };
+
+        let mut knees: Vec<(f64, &IndividualWrapper<T>)> =
+            self.members.iter().map(|member| (normalized_sum(member), member)).collect();
+        knees.sort_by(|a, b| a.0.compare(&b.0));
+        knees.into_iter().take(count).map(|(_, member)| member).collect()
+    }
+}
+
+impl<T: Individual + Clone + Debug + Send + 'static> Archive<T> for ParetoFront<T> {
+    fn insert(&mut self, candidate: &IndividualWrapper<T>) -> bool {
+        let mut candidate = candidate.clone();
+        candidate.objectives = candidate.individual.objectives();
+        ParetoFront::insert(self, &candidate)
+    }
+
+    fn sample(&self, count: usize, rng: &mut SimRng) -> Vec<&IndividualWrapper<T>> {
+        self.members.choose_multiple(rng, count).collect()
+    }
+
+    fn best(&self) -> Option<&IndividualWrapper<T>> {
+        self.members.first()
+    }
+
+    fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    fn box_clone(&self) -> Box<dyn Archive<T>> {
+        Box::new(self.clone())
+    }
+}
+
+/// Sorts the population by front and within the fronts by crowding distance or fitness.
+fn sort_fronts<T: Individual>(population: &mut Vec<IndividualWrapper<T>>, crowding: bool) {
+    for wrapper in population.iter_mut() {
+        wrapper.objectives = wrapper.individual.objectives();
+    }
+
+    // The front and the crowding distance of each individual.
+    let mut keys = vec![(0, 0.0); population.len()];
+    {
+        let objectives: Vec<&[f64]> =
+            population.iter().map(|wrapper| &wrapper.objectives[..]).collect();
+        for (index, members) in non_dominated_sort(&objectives).into_iter().enumerate() {
+            let distances = if crowding {
+                crowding_distances(&objectives, &members)
+            } else {
+                vec![0.0; members.len()]
+            };
+            for (&member, distance) in members.iter().zip(distances) {
+                keys[member] = (index, distance);
+            }
+        }
+    }
+
+    let mut ranked: Vec<((usize, f64), IndividualWrapper<T>)> =
+        keys.into_iter().zip(population.drain(..)).collect();
+    ranked.sort_by(|&((front_a, distance_a), ref a), &((front_b, distance_b), ref b)| {
+        front_a
+            .cmp(&front_b)
+            .then_with(|| distance_b.partial_cmp(&distance_a).unwrap_or(Ordering::Equal))
+            .then_with(|| a.fitness.compare(&b.fitness))
+    });
+    population.extend(ranked.into_iter().map(|(_, wrapper)| wrapper));
+}
+
+#[cfg(test)]
+mod test {
+    use super::{crowding_distances, dominates, non_dominated_sort, nsga2_sort, sort, ParetoFront};
+    use individual::Individual;
+    use rng::SimRng;
+    use test::wrap;
+
+    #[derive(Debug, Clone)]
+    struct Design {
+        cost: f64,
+        weight: f64,
+    }
+
+    impl Individual for Design {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {}
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            self.cost + self.weight
+        }
+
+        fn reset(&mut self) {}
+
+        fn objectives(&self) -> Vec<f64> {
+            vec![self.cost, self.weight]
+        }
+    }
+
+    #[test]
+    fn dominance() {
+        assert!(dominates(&[1.0, 2.0], &[1.0, 3.0]));
+        assert!(!dominates(&[1.0, 2.0], &[1.0, 2.0]));
+        assert!(!dominates(&[1.0, 4.0], &[2.0, 3.0]));
+        assert!(!dominates(&[1.0], &[2.0, 3.0]));
+        assert!(!dominates(&[f64::NAN, 1.0], &[2.0, 3.0]));
+    }
+
+    #[test]
+    fn fronts() {
+        let objectives: Vec<&[f64]> =
+            vec![&[1.0, 5.0], &[2.0, 6.0], &[5.0, 1.0], &[3.0, 3.0], &[4.0, 4.0], &[6.0, 6.0]];
+        assert_eq!(
+            non_dominated_sort(&objectives),
+            vec![vec![0, 2, 3], vec![1, 4], vec![5]]
+        );
+    }
+
+    #[test]
+    fn sort_by_front() {
+        let designs = [
+            Design { cost: 4.0, weight: 4.0 },
+            Design { cost: 9.0, weight: 0.0 },
+            Design { cost: 1.0, weight: 5.0 },
+            Design { cost: 3.0, weight: 5.0 },
+        ];
+        let mut population = wrap(&designs);
+        sort(&mut population);
+
+        // The first front by fitness (1 + 5, 4 + 4 and 9 + 0), then 3 + 5.
+        let costs: Vec<f64> = population.iter().map(|wrapper| wrapper.individual.cost).collect();
+        assert_eq!(costs, vec![1.0, 4.0, 9.0, 3.0]);
+        assert_eq!(population[0].objectives, vec![1.0, 5.0]);
+    }
+
+    #[test]
+    fn crowding() {
+        let objectives: Vec<&[f64]> = vec![&[0.0, 4.0], &[1.0, 3.0], &[3.0, 1.0], &[4.0, 0.0]];
+        let distances = crowding_distances(&objectives, &[0, 1, 2, 3]);
+        assert_eq!(distances[0], f64::INFINITY);
+        assert_eq!(distances[3], f64::INFINITY);
+        // (3 - 0) / 4 + (4 - 1) / 4
+        assert_eq!(distances[1], 1.5);
+        assert_eq!(distances[2], 1.5);
+
+        let designs = [
+            Design { cost: 0.0, weight: 4.0 },
+            Design { cost: 1.0, weight: 3.0 },
+            Design { cost: 1.5, weight: 2.9 },
+            Design { cost: 4.0, weight: 0.0 },
+            Design { cost: 5.0, weight: 5.0 },
+        ];
+        let mut population = wrap(&designs);
+        nsga2_sort(&mut population);
+        let costs: Vec<f64> = population.iter().map(|wrapper| wrapper.individual.cost).collect();
+        // The boundary points first, the crowded point at 1.0 last in the first front.
+        assert_eq!(costs, vec![0.0, 4.0, 1.5, 1.0, 5.0]);
+
+        let mut front = ParetoFront::new();
+        for wrapper in &population {
+            front.insert(wrapper);
+        }
+        let costs: Vec<f64> = front.iter().map(|wrapper| wrapper.individual.cost).collect();
+        // Ordered by fitness (cost + weight).
+        assert_eq!(costs, vec![0.0, 4.0, 1.0, 1.5]);
+    }
+
+    #[test]
+    fn front_archive() {
+        let designs = [
+            Design { cost: 5.0, weight: 5.0 },
+            Design { cost: 0.0, weight: 4.0 },
+            Design { cost: 1.0, weight: 1.5 },
+            Design { cost: 4.0, weight: 0.0 },
+            Design { cost: 2.0, weight: 2.0 },
+            Design { cost: 1.0, weight: 1.5 },
+        ];
+        let mut population = wrap(&designs);
+        for wrapper in &mut population {
+            wrapper.objectives = wrapper.individual.objectives();
+        }
+        let mut front = ParetoFront::new();
+        let stored: Vec<bool> = population.iter().map(|wrapper| front.insert(wrapper)).collect();
+        // 1 + 1.5 removes 5 + 5 and rejects 2 + 2 and its own copy.
+        assert_eq!(stored, vec![true, true, true, true, false, false]);
+
+        // Ordered by fitness (cost + weight).
+        let costs: Vec<f64> = front.iter().map(|wrapper| wrapper.individual.cost).collect();
+        assert_eq!(costs, vec![1.0, 0.0, 4.0]);
+        assert_eq!(front.within(&[(0.5, 5.0), (0.0, f64::INFINITY)]).len(), 2);
+        // Normalized: (0.25, 0.375), (0, 1) and (1, 0).
+        let knees = front.knee_points(1);
+        assert_eq!(knees[0].individual.cost, 1.0);
+    }
+}
@@ -0,0 +1,132 @@
+//! This module defines how individuals and checkpoints are saved to and loaded from files
+//! (JSON or bincode). Only available with the feature `serialize`.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+//! Save the fittest individuals of a run with `SimulationResult::save_fittest` (or all the
+//! individuals of a population with `Population::save`) and start a later run from them with
+//! `PopulationBuilder::initial_population_from_file`.
+
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use bincode;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json;
+
+/// The `Format` type. The file formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Human readable, can be edited and read by other programs.
+    Json,
+    /// Compact binary format, stores floating point numbers exactly.
+    Bincode,
+}
+
+impl Format {
+    /// Returns the format for the extension of the given path: `Json` for ".json", `Bincode`
+    /// for everything else.
+    pub fn of_path<P: AsRef<Path>>(path: P) -> Format {
+        match path.as_ref().extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("json") => Format::Json,
+            _ => Format::Bincode,
+        }
+    }
+}
+
+/// The `PersistError` type. The errors of saving and loading files.
+#[derive(Debug)]
+pub enum PersistError {
+    /// The file could not be read or written.
+    Io(io::Error),
+    /// The JSON could not be encoded or decoded.
+    Json(serde_json::Error),
+    /// The bincode could not be encoded or decoded.
+    Bincode(bincode::Error),
+}
+
+impl fmt::Display for PersistError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PersistError::Io(ref error) => write!(f, "file error: {}", error),
+            PersistError::Json(ref error) => write!(f, "JSON error: {}", error),
+            PersistError::Bincode(ref error) => write!(f, "bincode error: {}", error),
+        }
+    }
+}
+
+impl Error for PersistError {}
+
+impl From<io::Error> for PersistError {
+    fn from(error: io::Error) -> PersistError {
+        PersistError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for PersistError {
+    fn from(error: serde_json::Error) -> PersistError {
+        PersistError::Json(error)
+    }
+}
+
+impl From<bincode::Error> for PersistError {
+    fn from(error: bincode::Error) -> PersistError {
+        PersistError::Bincode(error)
+    }
+}
+
+/// Saves the value to the given file. It is written to a temporary file next to it first and
+/// then renamed, so a crash while writing keeps the old file.
+pub fn save<V: Serialize + ?Sized>(
+    value: &V,
+    path: &Path,
+    format: Format,
+) -> Result<(), PersistError> {
+    let temporary = path.with_extension("tmp");
+    {
+        let mut file = BufWriter::new(File::create(&temporary)?);
+        match format {
+            Format::Json => serde_json::to_writer_pretty(&mut file, value)?,
+            Format::Bincode => bincode::serialize_into(&mut file, value)?,
+        }
+        file.flush()?;
+    }
+    fs::rename(temporary, path)?;
+    Ok(())
+}
+
+/// Loads a value saved by `save` with the same format.
+pub fn load<V: DeserializeOwned>(path: &Path, format: Format) -> Result<V, PersistError> {
+    let file = BufReader::new(File::open(path)?);
+    match format {
+        Format::Json => Ok(serde_json::from_reader(file)?),
+        Format::Bincode => Ok(bincode::deserialize_from(file)?),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Format;
+
+    #[test]
+    fn format_of_extension() {
+        assert_eq!(Format::of_path("fittest.json"), Format::Json);
+        assert_eq!(Format::of_path("FITTEST.JSON"), Format::Json);
+        assert_eq!(Format::of_path("fittest.bin"), Format::Bincode);
+        assert_eq!(Format::of_path("fittest"), Format::Bincode);
+    }
+}
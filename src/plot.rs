@@ -0,0 +1,187 @@
+//! This module draws the convergence of a simulation: the best and the mean fitness of each
+//! population after each iteration, as SVG or PNG. Only available with the feature `plot`.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+//! Enable the statistics with `SimulationBuilder::keep_stats_history` and call
+//! `SimulationResult::plot_convergence` after the run.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+
+use plotters::coord::Shift;
+use plotters::prelude::*;
+
+use statistics::FitnessStats;
+
+/// The size of the image in pixels.
+const SIZE: (u32, u32) = (1024, 768);
+
+/// The `PlotError` type. The errors of drawing a plot.
+#[derive(Debug)]
+pub enum PlotError {
+    /// There are no statistics to draw, see `SimulationBuilder::keep_stats_history`.
+    NoStatistics,
+    /// The plot could not be drawn or written.
+    Drawing(Box<dyn Error + Send + Sync>),
+}
+
+impl fmt::Display for PlotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PlotError::NoStatistics => write!(f, "no statistics, see keep_stats_history"),
+            PlotError::Drawing(ref error) => write!(f, "drawing error: {}", error),
+        }
+    }
+}
+
+impl Error for PlotError {}
+
+/// The best and the mean fitness of one population after each iteration.
+type Curve = Vec<(u32, f64, f64)>;
+
+/// Draws the best and the mean fitness of each population over the iterations to the given
+/// file: SVG for the extension ".svg", otherwise a bitmap in the format of the extension
+/// (for example ".png"). The statistics are (iteration, population id, statistics) as in
+/// `SimulationResult::stats_history`.
+pub fn convergence(stats: &[(u32, u32, FitnessStats)], path: &Path) -> Result<(), PlotError> {
+    let mut curves: BTreeMap<u32, Curve> = BTreeMap::new();
+    for &(iteration, id, ref stats) in stats {
+        curves.entry(id).or_default().push((iteration, stats.best, stats.mean));
+    }
+    if curves.is_empty() {
+        return Err(PlotError::NoStatistics);
+    }
+
+    let svg = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map_or(false, |extension| extension.eq_ignore_ascii_case("svg"));
+    if svg {
+        draw(SVGBackend::new(path, SIZE).into_drawing_area(), &curves)
+    } else {
+        draw(BitMapBackend::new(path, SIZE).into_drawing_area(), &curves)
+    }
+}
+
+/// Draws the curves onto the given area.
+fn draw<DB>(
+    root: DrawingArea<DB, Shift>,
+    curves: &BTreeMap<u32, Curve>,
+) -> Result<(), PlotError>
+where
+    DB: DrawingBackend,
+    DB::ErrorType: 'static,
+{
+    let boxed = |error: DrawingAreaErrorKind<DB::ErrorType>| PlotError::Drawing(Box::new(error));
+
+    let points = curves.values().flat_map(|curve| curve.iter());
+    let last_iteration = points.clone().map(|&(iteration, _, _)| iteration).max().unwrap_or(0);
+    let values = points.flat_map(|&(_, best, mean)| vec![best, mean]);
+    let (mut low, mut high) = values
+        .filter(|value| value.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), value| {
+            (low.min(value), high.max(value))
+        });
+    if low > high {
+        // Nothing but NaN or infinite values.
+        low = 0.0;
+        high = 1.0;
+    } else if low == high {
+        low -= 1.0;
+        high += 1.0;
+    }
+
+    root.fill(&WHITE).map_err(boxed)?;
+    let mut chart = ChartBuilder::on(&root)
+        .caption("Convergence", ("sans-serif", 24))
+        .margin(16)
+        .x_label_area_size(40)
+        .y_label_area_size(80)
+        .build_cartesian_2d(0..last_iteration.max(1), low..high)
+        .map_err(boxed)?;
+    chart.configure_mesh().x_desc("iteration").y_desc("fitness").draw().map_err(boxed)?;
+
+    for (index, (id, curve)) in curves.iter().enumerate() {
+        let color = Palette99::pick(index).to_rgba();
+        let best = curve.iter().map(|&(iteration, best, _)| (iteration, best));
+        chart
+            .draw_series(LineSeries::new(best, color.stroke_width(2)))
+            .map_err(boxed)?
+            .label(format!("population {} best", id))
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+        let faded = color.mix(0.4);
+        let mean = curve.iter().map(|&(iteration, _, mean)| (iteration, mean));
+        chart
+            .draw_series(LineSeries::new(mean, faded))
+            .map_err(boxed)?
+            .label(format!("population {} mean", id))
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], faded));
+    }
+
+    chart
+        .configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .draw()
+        .map_err(boxed)?;
+    root.present().map_err(boxed)
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+    use std::fs;
+
+    use super::PlotError;
+    use population_builder::PopulationBuilder;
+    use select::MinimizeSelector;
+    use simulation_builder::SimulationBuilder;
+    use test::Test;
+
+    #[test]
+    fn convergence_is_plotted() {
+        let population = PopulationBuilder::<Test>::new()
+            .set_id(1)
+            .generate_initial_population(10, |_| Test { f: 3.0 })
+            .finalize()
+            .unwrap();
+        let mut simulation = SimulationBuilder::<Test>::new()
+            .iterations(10)
+            .keep_stats_history()
+            .add_population(population)
+            .finalize()
+            .unwrap();
+        simulation.run(&MinimizeSelector::new(2));
+
+        let svg = env::temp_dir().join("darwin_rs_convergence_is_plotted.svg");
+        simulation.simulation_result.plot_convergence(&svg).unwrap();
+        let text = fs::read_to_string(&svg).unwrap();
+        assert!(text.starts_with("<svg"));
+        assert!(text.contains("population 1 best"));
+        fs::remove_file(&svg).unwrap();
+
+        let png = env::temp_dir().join("darwin_rs_convergence_is_plotted.png");
+        simulation.simulation_result.plot_convergence(&png).unwrap();
+        assert_eq!(&fs::read(&png).unwrap()[1..4], b"PNG");
+        fs::remove_file(png).unwrap();
+
+        simulation.simulation_result.stats_history.clear();
+        match simulation.simulation_result.plot_convergence(&svg) {
+            Err(PlotError::NoStatistics) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -13,11 +13,207 @@
 //!
 //!
 
-use std::fmt::Debug;
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Debug};
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::slice;
+#[cfg(feature = "serialize")]
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use individual::{Individual, IndividualWrapper};
-use select::Selector;
+use rand::seq::index;
+use rand::Rng;
+use rayon::prelude::*;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
 
+use archive::Archive;
+use cache::FitnessCache;
+use fitness::{self, Fitness};
+use constraint::{self, Constraint};
+use individual::{BatchEvaluator, Individual, IndividualWrapper};
+use migration::Acceptance;
+use pareto;
+use select::{Direction, DynSelector, Selector, SelectorError};
+use rng::{RngState, SimRng};
+use observer::Phase;
+use schedule::Parameter;
+use statistics::{Counters, ImprovementRate};
+use species::{self, Speciation, Species};
+#[cfg(feature = "serialize")]
+use persist::{self, Format, PersistError};
+
+
+/// How the children of the crossover enter the population, see `PopulationBuilder::replacement`.
+/// The fittest individual always survives (elitism).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Replacement {
+    /// The children compete with the mutated and the original individuals and only survive if
+    /// they are fit enough (default).
+    #[default]
+    Compete,
+    /// Before the truncation the children replace the least fit individuals.
+    ReplaceWorst,
+    /// Before the truncation the children replace randomly chosen individuals.
+    ReplaceRandom,
+    /// Before the truncation each child replaces the individual closest to its first parent
+    /// (measured by `Individual::distance`, so usually the parent itself). Without a distance
+    /// implementation this replaces the fittest individuals after the elite.
+    ReplaceParents,
+}
+
+/// Which individuals compete for survival after each iteration, see
+/// `PopulationBuilder::survivor_scheme`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SurvivorScheme {
+    /// (μ+λ): the parents compete with their mutated copies and the children (default).
+    /// The fittest individual never gets lost.
+    #[default]
+    Plus,
+    /// (μ,λ): only the mutated copies and the children compete, the parents always die.
+    /// The population can get worse from one iteration to the next, which helps to escape
+    /// plateaus and to cope with noisy fitness functions.
+    Comma,
+}
+
+/// How the individuals are ranked when the population is sorted and truncated, see
+/// `PopulationBuilder::ranking`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Ranking {
+    /// By fitness, the lowest first (default).
+    #[default]
+    Fitness,
+    /// By Pareto dominance of the objectives (see `Individual::objectives` and module pareto):
+    /// the non-dominated individuals first, then the ones only dominated by them and so on.
+    /// Individuals in the same front are ranked by fitness.
+    Pareto,
+    /// Like `Pareto`, but individuals in the same front are ranked by their crowding distance
+    /// (NSGA-II), so the survivors spread out over the front. The parents of the crossover are
+    /// chosen by binary tournaments on this ranking instead of with the selector, one pair for
+    /// each individual.
+    Nsga2,
+    /// By Deb's feasibility rules for constrained problems (see `Constrained::violation`):
+    /// feasible individuals by fitness first, then the infeasible ones by their violation, see
+    /// `constraint::feasibility_sort`. Use `PopulationBuilder::feasibility_first`.
+    Feasibility,
+    /// By stochastic ranking for constrained problems: neighbours are compared by fitness if
+    /// both are feasible or with the given probability, otherwise by their violation, see
+    /// `constraint::stochastic_sort`. Use `PopulationBuilder::stochastic_ranking`.
+    Stochastic(f64),
+}
+
+/// What happens when the user code (`Individual::mutate`, `Individual::calculate_fitness`,
+/// `Individual::reset`, `Individual::new_fittest_found` or the crossover, also with migrants)
+/// panics, see `PopulationBuilder::panic_policy`.
+/// The panic is always caught, logged together with the offending individual and counted
+/// (`Counters::panics`). The offending individual gets the worst possible fitness (see
+/// `Fitness::worst`),
+/// so it dies in the truncation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PanicPolicy {
+    /// The simulation stops after the current iteration with `SimError::Panicked` (default).
+    #[default]
+    Abort,
+    /// The simulation continues without the offending individual.
+    WorstFitness,
+}
+
+/// The `RetireReason` type. Why a population has retired, see `PopulationBuilder::retire_after`
+/// and `PopulationBuilder::max_resets`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum RetireReason {
+    /// The fittest individual of the population has not improved for `retire_after`
+    /// iterations.
+    Converged,
+    /// The reset limit was reached after `max_resets` resets.
+    ResetsExhausted,
+}
+
+/// The `PanicReport` type. Describes a panic in the user code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PanicReport {
+    /// The id of the population.
+    pub population_id: u32,
+    /// The label of the population, see `PopulationBuilder::label`.
+    pub population_label: String,
+    /// The method that panicked, for example "mutate".
+    pub operation: &'static str,
+    /// The panic message.
+    pub message: String,
+    /// The offending individual (its `Debug` output) as left behind by the panicking method.
+    /// `None` if the panic happened somewhere else, for example in the selector.
+    pub individual: Option<String>,
+}
+
+impl fmt::Display for PanicReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} panicked in population {}: {}",
+            self.operation,
+            tag(self.population_id, &self.population_label),
+            self.message
+        )?;
+        if let Some(ref individual) = self.individual {
+            write!(f, ", individual: {}", individual)?;
+        }
+        Ok(())
+    }
+}
+
+/// The maximum number of mutations to turn a duplicate into a new genotype.
+const MAX_DUPLICATE_MUTATIONS: u32 = 10;
+
+/// Calls the user code `f` and catches a panic. Returns the panic message in case of a panic.
+pub fn catch_panic<R, F: FnOnce() -> R>(f: F) -> Result<R, String> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| panic_message(&*payload))
+}
+
+/// Returns the message of a caught panic.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+/// Where the fitness of an individual comes from, see `Population::evaluate_dirty`.
+enum Source<F> {
+    /// The `Individual::quick_bound`, the individual does not survive anyway.
+    Bound(F),
+    /// The fitness cache.
+    Cached(F),
+    /// A new evaluation, with the genome key for the cache.
+    Fresh(Option<u64>),
+    /// The evaluation of the individual at this index, which has the same genome key.
+    SameAs(usize),
+}
+
+/// The fitness as calculated, the stored fitness and the case errors of an individual, or the
+/// panic message, see `Population::evaluate_dirty`.
+type Evaluated<F> = Result<(F, F, Vec<f64>), String>;
+
+/// Creates the report for a panic of the user code in `operation`.
+fn panic_report<T: Debug>(
+    population_id: u32,
+    population_label: &str,
+    operation: &'static str,
+    message: String,
+    individual: Option<&T>,
+) -> PanicReport {
+    PanicReport {
+        population_id,
+        population_label: population_label.to_string(),
+        operation,
+        message,
+        individual: individual.map(|individual| format!("{:?}", individual)),
+    }
+}
 
 /// The `Population` type. Contains the actual individuals (through a wrapper) and informations
 /// like the `reset_limit`. Use the `PopulationBuilder` in your main program to create populations.
@@ -27,6 +223,11 @@ pub struct Population<T: Individual + Send + Clone + Debug> {
     pub num_of_individuals: u32,
     /// The actual population (vector of individuals).
     pub population: Vec<IndividualWrapper<T>>,
+    /// The individuals left over from the last iteration (dropped by the truncation or, with
+    /// `SurvivorScheme::Comma`, the parents). The next iteration copies the population into
+    /// them (with `clone_from`) and mutates the copies, so large genomes are not allocated
+    /// again in each iteration.
+    pub offspring_buffer: Vec<IndividualWrapper<T>>,
     /// The amount of iteration to wait until all individuals will be resetted.
     /// This calls the `reset` method for each individual.
     pub reset_limit: u32,
@@ -43,50 +244,982 @@ pub struct Population<T: Individual + Send + Clone + Debug> {
     /// the simulation restarts anew with an increased `reset_limit`. This prevents local minima,
     /// but also discards the current fittest individual.
     pub reset_counter: u32,
+    /// The number of resets of all individuals so far.
+    pub resets: u32,
+    /// Retire the population when the reset limit is reached after this number of resets,
+    /// see `PopulationBuilder::max_resets`. Default: None (no limit)
+    pub max_resets: Option<u32>,
+    /// Retire the population if its fittest individual has not improved for this number of
+    /// iterations, see `PopulationBuilder::retire_after`. 0 disables this feature (default).
+    pub retire_after: u32,
+    /// The number of iterations since the fittest individual of the population has improved.
+    pub stagnation: u32,
+    /// Why the population has retired, if it has: retired populations do not evolve any more,
+    /// the others continue. See `SimulationResult::retirements`.
+    pub retired: Option<RetireReason>,
     /// The ID of the population, only used for statistics. For example: which population does
     /// have the most fittest individuals ? This may help you to set the correct parameters for
     /// your simulations.
     pub id: u32,
+    /// An optional user defined label for this population. If it is not empty, it is shown
+    /// together with the `id` in all log lines, which makes it easier to tell the populations
+    /// apart.
+    pub label: String,
     /// Count how often this population has created (found) the fittest individual. This may help
     /// you to fine tune the parameters for the population and the simulation in general.
     pub fitness_counter: u64,
+    /// The random number generator for all the random decisions the library makes while
+    /// evolving this population (for example parent selection). It is seeded by the
+    /// evolution seed, independently from the seed used to create the initial population.
+    pub rng: SimRng,
+    /// The time spent in each phase during the current iteration. These are recorded locally
+    /// by each population and handed over to the observers at the end of the iteration.
+    pub phase_timings: Vec<(Phase, Duration)>,
+    /// The resets during the current iteration: the new reset limit and whether it started
+    /// again at `reset_limit_start`. Handed over to the observers like the phase timings.
+    pub reset_log: Vec<(u32, bool)>,
+    /// Counts the work done by this population during the current iteration. Each population
+    /// has its own counters, so no synchronization between the threads is needed. The simulation
+    /// collects and resets them after each iteration.
+    pub counters: Counters,
+    /// Fitness values that differ by at most this value are treated as equal when the population
+    /// is truncated. The individuals that compete for the last places are then chosen by
+    /// diversity (using `Individual::distance`) instead of by the arbitrary sort order.
+    /// A value of 0.0 disables this feature.
+    pub fitness_tolerance: f64,
+    /// If set to `(min, max)`, the number of mutations of each individual is recalculated
+    /// every iteration from its current rank: the fittest individual mutates `min` times, the
+    /// least fit `max` times and the ones in between proportional to their rank.
+    /// This replaces the mutation rates configured for each slot in the `PopulationBuilder`.
+    pub rank_mutation: Option<(u32, u32)>,
+    /// Children of the elites of this population and migrants from other populations
+    /// (see `SimulationBuilder::interbreed_migrants`). They are evaluated and compete with
+    /// the other individuals in the next iteration.
+    pub hybrids: Vec<T>,
+    /// The crossover operator, if this population uses crossover (see
+    /// `PopulationBuilder::crossover`). Then parents are selected each iteration and their
+    /// children compete with the other individuals.
+    pub crossover: Option<fn(&mut T, &mut T, &mut SimRng) -> T>,
+    /// The fraction of the selected pairs of parents that are recombined. The other pairs pass
+    /// their first parent unchanged as child. Default: 1.0 (all pairs)
+    pub crossover_rate: f64,
+    /// How the children of the crossover enter the population. Default: `Replacement::Compete`
+    pub replacement: Replacement,
+    /// The improvements of the fittest individual per 1000 evaluations over the last
+    /// iterations, updated by the simulation after each iteration.
+    pub improvement_rate: ImprovementRate,
+    /// The archive of this population, if any (see `PopulationBuilder::archive`). It is offered
+    /// the survivors after each iteration.
+    pub archive: Option<Box<dyn Archive<T>>>,
+    /// Which individuals compete for survival. Default: `SurvivorScheme::Plus`
+    pub survivor_scheme: SurvivorScheme,
+    /// The number of the fittest individuals that always survive unchanged, see
+    /// `PopulationBuilder::elitism`. Default: 0
+    pub elitism: u32,
+    /// Individuals older than this number of iterations are replaced by new ones (see
+    /// `PopulationBuilder::max_age`). 0 disables this feature (default).
+    pub max_age: u32,
+    /// The cache for the fitness of the genotypes evaluated by this population, see
+    /// `PopulationBuilder::fitness_cache`. Default: None
+    pub fitness_cache: Option<FitnessCache<T::Fitness>>,
+    /// Replace individuals whose genotype is already in the population by mutated variants,
+    /// see `PopulationBuilder::deduplicate`. Default: false
+    pub deduplicate: bool,
+    /// The selector of this population instead of the one given to `Simulation::run`, see
+    /// `PopulationBuilder::selector`. Default: None
+    pub selector: Option<Arc<dyn DynSelector<T>>>,
+    /// The number of mutations of all individuals, updated by the simulation in each
+    /// iteration, see `PopulationBuilder::mutation_schedule`. Default: None
+    pub mutation_schedule: Option<Parameter>,
+    /// Never take in the individuals shared by other populations, see
+    /// `PopulationBuilder::explorer`. Default: false
+    pub explorer: bool,
+    /// What the immigrants replace, see `PopulationBuilder::acceptance`. Default: None
+    pub acceptance: Option<Acceptance>,
+    /// Evaluates the new individuals all at once instead of `Individual::calculate_fitness`,
+    /// see `PopulationBuilder::batch_evaluator`. Default: None
+    pub batch_evaluator: Option<Arc<dyn BatchEvaluator<T>>>,
+    /// The minimum number of individuals each thread evaluates at a time, see
+    /// `PopulationBuilder::evaluation_chunk`. Default: 1
+    pub evaluation_chunk: usize,
+    /// What happens when the user code panics. Default: `PanicPolicy::Abort`
+    pub panic_policy: PanicPolicy,
+    /// The first panic that should stop the simulation, see `PanicPolicy`. The simulation
+    /// takes it after each iteration.
+    pub panicked: Option<PanicReport>,
+    /// Select the parents for the crossover by species instead of with the selector of the
+    /// simulation, see `PopulationBuilder::speciation`. Default: None
+    pub speciation: Option<Speciation>,
+    /// The species of the last iteration, only filled in with `speciation`.
+    pub species: Vec<Species>,
+    /// How the individuals are ranked. Default: `Ranking::Fitness`
+    pub ranking: Ranking,
+    /// Clear the fitness of the individuals in each niche before the selection, except for
+    /// the fittest ones: (niche radius, number of winners), see `PopulationBuilder::clearing`.
+    /// Default: None
+    pub clearing: Option<(f64, usize)>,
+    /// Add a penalty for the violation of the constraints to the fitness, see
+    /// `PopulationBuilder::penalty`. Default: None
+    pub constraint: Option<Constraint<T>>,
+    /// Whether lower or higher values of `Individual::calculate_fitness` are better, set by
+    /// `SimulationBuilder::maximize`. The population always minimizes: in maximize mode the
+    /// stored fitness (`IndividualWrapper::fitness`) is the negated value. Default: Minimize
+    pub direction: Direction,
+    /// The data shared by all individuals (see `Individual::Context`), set by
+    /// `PopulationBuilder::context` or `SimulationBuilder::context`. Default: `Default::default()`
+    pub context: Arc<T::Context>,
+}
+
+/// Returns the tag of the population with the given id and label, see `Population::tag`.
+pub fn tag(id: u32, label: &str) -> String {
+    if label.is_empty() {
+        format!("{}", id)
+    } else {
+        format!("{} ({})", id, label)
+    }
 }
 
 impl<T: Individual + Send + Sync + Clone + Debug> Population<T> {
+    /// Returns the tag used for this population in log lines: the `id` and if given the `label`.
+    pub fn tag(&self) -> String {
+        tag(self.id, &self.label)
+    }
+
+    /// Returns the settings of this population in one line, see `Simulation::configuration`.
+    pub fn configuration(&self) -> String {
+        format!(
+            "individuals: {}, reset_limit: {}..{} (+{}), max_resets: {:?}, retire_after: {}, \
+             elitism: {}, max_age: {}, \
+             survivor_scheme: {:?}, ranking: {:?}, crossover: {}, crossover_rate: {}, \
+             replacement: {:?}, rank_mutation: {:?}, fitness_tolerance: {}, deduplicate: {}, \
+             fitness_cache: {}, speciation: {:?}, clearing: {:?}, penalty: {:?}, \
+             panic_policy: {:?}, explorer: {}, acceptance: {:?}, evaluation_chunk: {}, \
+             selector: {:?}, mutation_schedule: {:?}",
+            self.num_of_individuals,
+            self.reset_limit_start,
+            self.reset_limit_end,
+            self.reset_limit_increment,
+            self.max_resets,
+            self.retire_after,
+            self.elitism,
+            self.max_age,
+            self.survivor_scheme,
+            self.ranking,
+            self.crossover.is_some(),
+            self.crossover_rate,
+            self.replacement,
+            self.rank_mutation,
+            self.fitness_tolerance,
+            self.deduplicate,
+            self.fitness_cache.is_some(),
+            self.speciation,
+            self.clearing,
+            self.constraint.as_ref().map(|constraint| constraint.penalty),
+            self.panic_policy,
+            self.explorer,
+            self.acceptance,
+            self.evaluation_chunk,
+            self.selector,
+            self.mutation_schedule.as_ref().map(Parameter::get)
+        )
+    }
+
+    /// Changes the number of individuals to `size`: drops the last individuals or repeats the
+    /// existing ones in their order. An empty population stays empty. Check the population
+    /// afterwards, see `population_builder::check`.
+    pub fn resize(&mut self, size: u32) {
+        if !self.population.is_empty() {
+            let original = mem::take(&mut self.population);
+            self.population = original.into_iter().cycle().take(size as usize).collect();
+            self.num_of_individuals = size;
+        }
+    }
+
+    /// Returns the state of the evolution random number generator, for example to store it
+    /// in a checkpoint.
+    pub fn rng_state(&self) -> RngState {
+        RngState::of(&self.rng)
+    }
+
+    /// Restores the state of the evolution random number generator, so the simulation continues
+    /// with exactly the same random sequence as when the state was captured.
+    pub fn restore_rng_state(&mut self, state: &RngState) {
+        self.rng = state.to_rng();
+    }
+
+    /// Records the time spent in the given phase since `start`.
+    fn record_phase(&mut self, phase: Phase, start: Instant) {
+        self.phase_timings.push((phase, start.elapsed()));
+    }
+
+    /// Reduces the sorted population to its original length. If `fitness_tolerance` is set,
+    /// all individuals with a fitness within the tolerance of the last place form one group.
+    /// The fittest individual and the `elitism` fittest are always kept, the other places of that
+    /// group are filled greedily with the candidates that have the largest distance to all the
+    /// survivors.
+    fn truncate_population(&mut self, size: usize) {
+
+        if self.fitness_tolerance <= 0.0 || self.population.len() <= size {
+            if self.population.len() > size {
+                self.offspring_buffer.extend(self.population.drain(size..));
+            }
+            return;
+        }
+
+        // Find the group of (nearly) equal fitness that crosses the truncation boundary.
+        let mut group_start = 0;
+        for index in 1..size {
+            if self.population[index].fitness.to_f64() -
+                self.population[group_start].fitness.to_f64() > self.fitness_tolerance
+            {
+                group_start = index;
+            }
+        }
+        let leader_fitness = self.population[group_start].fitness.to_f64();
+        let group_end = self.population
+            .iter()
+            .skip(size)
+            .position(|wrapper| wrapper.fitness.to_f64() - leader_fitness > self.fitness_tolerance)
+            .map_or(self.population.len(), |position| size + position);
+
+        let kept = group_start.max((self.elitism as usize).min(size));
+        let mut candidates = self.population.split_off(kept);
+        self.offspring_buffer.extend(candidates.drain(group_end - kept..));
+
+        // Elitism: the fittest individual always survives.
+        if self.population.is_empty() {
+            self.population.push(candidates.remove(0));
+        }
+
+        // For each candidate the minimal distance to all the survivors.
+        let mut min_distance: Vec<f64> = candidates
+            .iter()
+            .map(|candidate| {
+                self.population
+                    .iter()
+                    .map(|survivor| candidate.individual.distance(&survivor.individual))
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .collect();
+
+        while self.population.len() < size {
+            let (best, _) = min_distance
+                .iter()
+                .enumerate()
+                .fold((0, f64::NEG_INFINITY), |(best, best_distance), (index, &distance)| {
+                    if distance > best_distance { (index, distance) } else { (best, best_distance) }
+                });
+
+            let chosen = candidates.remove(best);
+            min_distance.remove(best);
+            for (candidate, distance) in candidates.iter().zip(min_distance.iter_mut()) {
+                *distance = distance.min(candidate.individual.distance(&chosen.individual));
+            }
+            self.population.push(chosen);
+        }
+        self.offspring_buffer.extend(candidates);
+
+        // Keep the population sorted.
+        self.population[kept..].sort();
+    }
+
+    /// Removes one individual of the (sorted) population, before the truncation, for each child
+    /// of the crossover according to the `replacement` policy and returns the children that
+    /// take their places. The fittest individual and the elite are never replaced. At most
+    /// `num_of_individuals` minus the elite children are returned, they all survive the
+    /// truncation.
+    fn replace(
+        &mut self,
+        children: Vec<IndividualWrapper<T>>,
+        first_parents: &[T],
+    ) -> Vec<IndividualWrapper<T>> {
+        let protected = self.elitism.max(1) as usize;
+        let places = (self.num_of_individuals as usize).saturating_sub(protected);
+        let mut replaced = Vec::new();
+
+        for (n, child) in children.into_iter().enumerate() {
+            if replaced.len() >= places || self.population.len() <= protected {
+                break;
+            }
+
+            let index = match self.replacement {
+                Replacement::ReplaceRandom => self.rng.gen_range(protected..self.population.len()),
+                Replacement::ReplaceParents => {
+                    let parent = &first_parents[n];
+                    let (closest, _) = self.population.iter().enumerate().skip(protected).fold(
+                        (protected, f64::INFINITY),
+                        |(closest, min), (index, wrapper)| {
+                            let distance = parent.distance(&wrapper.individual);
+                            if distance < min { (index, distance) } else { (closest, min) }
+                        },
+                    );
+                    closest
+                }
+                // The population is sorted, so the last place holds the least fit one.
+                Replacement::Compete | Replacement::ReplaceWorst => self.population.len() - 1,
+            };
+            self.population.remove(index);
+            replaced.push(child);
+        }
+
+        replaced
+    }
+
+    /// Sorts the population, the fittest individual first, see `Ranking`.
+    pub fn sort(&mut self) {
+        let violation = self.constraint.as_ref().map(|constraint| constraint.violation);
+        match (self.ranking, violation) {
+            (Ranking::Pareto, _) => pareto::sort(&mut self.population),
+            (Ranking::Nsga2, _) => pareto::nsga2_sort(&mut self.population),
+            (Ranking::Feasibility, Some(violation)) => {
+                constraint::feasibility_sort(&mut self.population, violation)
+            }
+            (Ranking::Stochastic(probability), Some(violation)) => {
+                constraint::stochastic_sort(
+                    &mut self.population,
+                    violation,
+                    probability,
+                    &mut self.rng,
+                )
+            }
+            // Without a violation (see `population_builder::check`) all individuals are
+            // feasible.
+            _ => self.population.sort(),
+        }
+    }
+
+    /// Sorts the population and sets the number of mutations of each individual
+    /// according to its rank, see `rank_mutation`.
+    fn assign_rank_mutations(&mut self, min: u32, max: u32) {
+        self.sort();
+
+        let last = (self.population.len() - 1).max(1) as f64;
+        let range = f64::from(max.saturating_sub(min));
+        for (rank, wrapper) in self.population.iter_mut().enumerate() {
+            wrapper.num_of_mutations = min + (range * rank as f64 / last).round() as u32;
+        }
+    }
+
+    /// Calculates the fitness of a new offspring. If its `quick_bound` is already worse than
+    /// `worst` (the fitness of the worst survivor, None if there is no such bound) the
+    /// expensive evaluation is skipped and the bound is used as fitness: the offspring is
+    /// removed during the replacement anyway.
+    /// Returns the panic message if the evaluation panics.
+    fn evaluate_offspring(
+        individual: &mut T,
+        context: &T::Context,
+        worst: &Option<T::Fitness>,
+        counters: &mut Counters,
+        cache: &mut Option<FitnessCache<T::Fitness>>,
+        constraint: &Option<Constraint<T>>,
+        direction: Direction,
+    ) -> Result<(T::Fitness, Vec<f64>), String> {
+        match individual.quick_bound().map(|bound| direction.apply(&bound)) {
+            Some(bound) if worst.as_ref().map_or(false, |worst| bound > *worst) => {
+                counters.skipped_evaluations += 1;
+                Ok((bound, Vec::new()))
+            }
+            _ => Self::evaluate(individual, context, counters, cache, constraint, direction),
+        }
+    }
+
+    /// Calculates the fitness of the individual or takes it from the fitness cache, if this
+    /// population has one (see `PopulationBuilder::fitness_cache`), and its case errors (see
+    /// `Individual::case_errors`). The fitness is negated in maximize mode (see `direction`)
+    /// and the penalty for the violation of the constraints is added, the cache keeps the
+    /// fitness as calculated. Returns the panic message if the evaluation panics.
+    fn evaluate(
+        individual: &mut T,
+        context: &T::Context,
+        counters: &mut Counters,
+        cache: &mut Option<FitnessCache<T::Fitness>>,
+        constraint: &Option<Constraint<T>>,
+        direction: Direction,
+    ) -> Result<(T::Fitness, Vec<f64>), String> {
+        let key = cache.as_ref().and_then(|_| individual.genome_key());
+
+        let cached = match (cache.as_mut(), key) {
+            (Some(cache), Some(key)) => cache.get(key),
+            _ => None,
+        };
+        let fitness = match cached {
+            Some(fitness) => {
+                counters.cache_hits += 1;
+                fitness
+            }
+            None => {
+                counters.evaluations += 1;
+                let fitness = catch_panic(|| individual.calculate_fitness(context))?;
+                if let (Some(cache), Some(key)) = (cache.as_mut(), key) {
+                    cache.insert(key, fitness.clone());
+                }
+                fitness
+            }
+        };
+        Self::finish_evaluation(individual, fitness, constraint, direction)
+    }
+
+    /// Turns the fitness as calculated into the stored fitness (see `evaluate`) and calculates
+    /// the case errors. Returns the panic message if the user code panics.
+    fn finish_evaluation(
+        individual: &mut T,
+        fitness: T::Fitness,
+        constraint: &Option<Constraint<T>>,
+        direction: Direction,
+    ) -> Result<(T::Fitness, Vec<f64>), String> {
+        let fitness = direction.apply(&fitness);
+        let fitness = match *constraint {
+            Some(ref constraint) => {
+                fitness.penalize(catch_panic(|| constraint.penalty(individual))?)
+            }
+            None => fitness,
+        };
+        let case_errors = catch_panic(|| individual.case_errors())?;
+        Ok((fitness, case_errors))
+    }
+
+    /// Calculates the fitness of all dirty individuals that are not `broken`, like
+    /// `evaluate_offspring` with the given `worst` bound. The fitness cache is used in the
+    /// order of the individuals, the evaluations themselves run in parallel on the current
+    /// rayon thread pool (see `SimulationBuilder::threads`). Individuals with the same genome
+    /// key are evaluated only once. Returns the reports of the panics.
+    fn evaluate_dirty(
+        &mut self,
+        broken: &[bool],
+        worst: &Option<T::Fitness>,
+    ) -> Vec<PanicReport> {
+        let direction = self.direction;
+
+        // Decide in order where each fitness comes from, like the cache would for one
+        // individual after the other.
+        let mut sources: Vec<Option<Source<T::Fitness>>> = Vec::with_capacity(broken.len());
+        let mut first_of_key: HashMap<u64, usize> = HashMap::new();
+        for ((index, wrapper), &broken) in self.population.iter_mut().enumerate().zip(broken) {
+            if broken || !wrapper.dirty {
+                sources.push(None);
+                continue;
+            }
+            wrapper.dirty = false;
+            let individual = &wrapper.individual;
+            let bound = worst.as_ref().and_then(|worst| {
+                individual
+                    .quick_bound()
+                    .map(|bound| direction.apply(&bound))
+                    .filter(|bound| bound > worst)
+            });
+            let key = self.fitness_cache.as_ref().and_then(|_| individual.genome_key());
+            let source = match (bound, key) {
+                (Some(bound), _) => {
+                    self.counters.skipped_evaluations += 1;
+                    Source::Bound(bound)
+                }
+                (None, Some(key)) => {
+                    let cached = self.fitness_cache.as_mut().and_then(|cache| cache.get(key));
+                    match (cached, first_of_key.get(&key)) {
+                        (Some(fitness), _) => Source::Cached(fitness),
+                        (None, Some(&first)) => Source::SameAs(first),
+                        (None, None) => {
+                            first_of_key.insert(key, index);
+                            Source::Fresh(Some(key))
+                        }
+                    }
+                }
+                (None, None) => Source::Fresh(None),
+            };
+            sources.push(Some(source));
+        }
+
+        // With a batch evaluator or `Individual::evaluate_batch` the new individuals are
+        // evaluated up front, all at once.
+        let mut batch: Vec<Option<Result<T::Fitness, String>>> = vec![None; sources.len()];
+        let fresh: Vec<usize> = sources
+            .iter()
+            .enumerate()
+            .filter(|&(_, source)| matches!(*source, Some(Source::Fresh(_))))
+            .map(|(index, _)| index)
+            .collect();
+        let batched = if let Some(ref evaluator) = self.batch_evaluator {
+            let individuals: Vec<&T> =
+                fresh.iter().map(|&index| &self.population[index].individual).collect();
+            match catch_panic(|| evaluator.evaluate(&individuals, &self.context)) {
+                Ok(results) => {
+                    for (&index, result) in fresh.iter().zip(results) {
+                        batch[index] = Some(result);
+                    }
+                }
+                Err(message) => {
+                    for &index in &fresh {
+                        batch[index] = Some(Err(message.clone()));
+                    }
+                }
+            }
+            true
+        } else if T::BATCH {
+            let mut individuals: Vec<T> =
+                fresh.iter().map(|&index| self.population[index].individual.clone()).collect();
+            let results = catch_panic(|| T::evaluate_batch(&mut individuals, &self.context))
+                .and_then(|results| {
+                    results.ok_or_else(|| "evaluate_batch returned no fitness".to_string())
+                })
+                .and_then(|results| {
+                    if results.len() == fresh.len() {
+                        Ok(results)
+                    } else {
+                        Err(format!("{} results for {} individuals", results.len(), fresh.len()))
+                    }
+                });
+            match results {
+                Ok(results) => {
+                    let evaluated = individuals.into_iter().zip(results);
+                    for (&index, (individual, fitness)) in fresh.iter().zip(evaluated) {
+                        self.population[index].individual = individual;
+                        batch[index] = Some(Ok(fitness));
+                    }
+                }
+                Err(message) => {
+                    for &index in &fresh {
+                        batch[index] = Some(Err(message.clone()));
+                    }
+                }
+            }
+            true
+        } else {
+            false
+        };
+
+        let context = &*self.context;
+        let constraint = &self.constraint;
+        let mut outcomes: Vec<Option<Evaluated<T::Fitness>>> = self
+            .population
+            .par_iter_mut()
+            .zip(sources.par_iter())
+            .zip(batch.par_iter_mut())
+            .with_min_len(self.evaluation_chunk)
+            .map(|((wrapper, source), batched_fitness)| {
+                let individual = &mut wrapper.individual;
+                let calculated = match *source {
+                    Some(Source::Fresh(_)) => match batched_fitness.take() {
+                        Some(result) => result,
+                        None if batched => Err("no fitness from the batch evaluator".to_string()),
+                        None => catch_panic(|| individual.calculate_fitness(context)),
+                    },
+                    Some(Source::Cached(ref fitness)) => Ok(fitness.clone()),
+                    _ => return None,
+                };
+                Some(calculated.and_then(|calculated| {
+                    Self::finish_evaluation(individual, calculated.clone(), constraint, direction)
+                        .map(|(fitness, case_errors)| (calculated, fitness, case_errors))
+                }))
+            })
+            .collect();
+
+        // Count, fill the cache and store the results in order.
+        let id = self.id;
+        let label = self.label.clone();
+        let mut calculated: Vec<Option<T::Fitness>> = vec![None; sources.len()];
+        let mut panics = Vec::new();
+        for (index, source) in sources.into_iter().enumerate() {
+            let result = match source {
+                None => continue,
+                Some(Source::Bound(bound)) => Ok((bound, Vec::new())),
+                Some(Source::Fresh(key)) => {
+                    self.counters.evaluations += 1;
+                    match outcomes[index].take().expect("evaluated in parallel") {
+                        Ok((fitness_calculated, fitness, case_errors)) => {
+                            if let (Some(cache), Some(key)) = (self.fitness_cache.as_mut(), key) {
+                                cache.insert(key, fitness_calculated.clone());
+                            }
+                            calculated[index] = Some(fitness_calculated);
+                            Ok((fitness, case_errors))
+                        }
+                        Err(message) => Err(message),
+                    }
+                }
+                Some(Source::Cached(_)) => {
+                    self.counters.cache_hits += 1;
+                    outcomes[index]
+                        .take()
+                        .expect("evaluated in parallel")
+                        .map(|(_, fitness, case_errors)| (fitness, case_errors))
+                }
+                Some(Source::SameAs(first)) => {
+                    let individual = &mut self.population[index].individual;
+                    match calculated[first].clone() {
+                        Some(fitness) => {
+                            self.counters.cache_hits += 1;
+                            Self::finish_evaluation(individual, fitness, constraint, direction)
+                        }
+                        // The first one has panicked, try again.
+                        None => Self::evaluate(
+                            individual,
+                            context,
+                            &mut self.counters,
+                            &mut self.fitness_cache,
+                            constraint,
+                            direction,
+                        ),
+                    }
+                }
+            };
+
+            let wrapper = &mut self.population[index];
+            let (fitness, case_errors) = result.unwrap_or_else(|message| {
+                panics.push(panic_report(
+                    id,
+                    &label,
+                    "calculate_fitness",
+                    message,
+                    Some(&wrapper.individual),
+                ));
+                (T::Fitness::worst(), Vec::new())
+            });
+            wrapper.fitness = fitness;
+            wrapper.case_errors = case_errors;
+        }
+        panics
+    }
+
+    /// Logs and counts the given panics of the user code. With `PanicPolicy::Abort` the first
+    /// one is kept in `panicked`, so the simulation stops after this iteration.
+    fn handle_panics(&mut self, reports: Vec<PanicReport>) {
+        for report in reports {
+            error!("{}", report);
+            self.counters.panics += 1;
+            if self.panic_policy == PanicPolicy::Abort && self.panicked.is_none() {
+                self.panicked = Some(report);
+            }
+        }
+    }
+
+    /// Calls the user code `f` with the individual at `index` and the random number generator
+    /// of the population on behalf of the simulation (for example
+    /// `Individual::new_fittest_found`) and handles a panic of `operation` like the panics
+    /// during an iteration, see `PanicPolicy`. Returns `None` in case of a panic.
+    pub fn guarded<R, F>(&mut self, index: usize, operation: &'static str, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut T, &mut SimRng) -> R,
+    {
+        let individual = &mut self.population[index].individual;
+        let rng = &mut self.rng;
+        match catch_panic(|| f(individual, rng)) {
+            Ok(result) => Some(result),
+            Err(message) => {
+                let report = panic_report(
+                    self.id,
+                    &self.label,
+                    operation,
+                    message,
+                    Some(individual),
+                );
+                self.handle_panics(vec![report]);
+                None
+            }
+        }
+    }
+
+    /// Starts the next iteration of the penalty scheme (see `PopulationBuilder::penalty`) and
+    /// adds the change of the weight to the fitness of all individuals, so they stay
+    /// comparable with the offspring of this iteration.
+    fn update_penalty(&mut self) {
+        let constraint = match self.constraint {
+            Some(ref mut constraint) => constraint,
+            None => return,
+        };
+        let fittest_feasible = self.population
+            .iter()
+            .min()
+            .map_or(true, |wrapper| constraint.is_feasible(&wrapper.individual));
+        let change = constraint.next_iteration(fittest_feasible);
+        if change == 0.0 {
+            return;
+        }
+        for wrapper in &mut self.population {
+            if wrapper.fitness != T::Fitness::worst() && !wrapper.fitness.is_nan() {
+                let penalty = change * constraint.measure(&wrapper.individual);
+                wrapper.fitness = wrapper.fitness.penalize(penalty);
+            }
+        }
+    }
+
+    /// Resets the individuals at the given indices (see `Individual::reset`), so they start anew
+    /// with age 0, and calculates their fitness.
+    fn renew(&mut self, indices: &[usize]) {
+        let id = self.id;
+        let label = self.label.clone();
+        let mut panics = Vec::new();
+        for &index in indices {
+            let wrapper = &mut self.population[index];
+            let individual = &mut wrapper.individual;
+            let result = match catch_panic(|| individual.reset()) {
+                Ok(()) => Self::evaluate(
+                    individual,
+                    &self.context,
+                    &mut self.counters,
+                    &mut self.fitness_cache,
+                    &self.constraint,
+                    self.direction,
+                )
+                .map_err(|message| ("calculate_fitness", message)),
+                Err(message) => Err(("reset", message)),
+            };
+            let (fitness, case_errors) = result.unwrap_or_else(|(operation, message)| {
+                panics.push(panic_report(id, &label, operation, message, Some(individual)));
+                (T::Fitness::worst(), Vec::new())
+            });
+            wrapper.fitness = fitness;
+            wrapper.case_errors = case_errors;
+            wrapper.age = 0;
+            wrapper.dirty = false;
+        }
+        self.handle_panics(panics);
+    }
+
+    /// Replaces the individuals older than `max_age` (except the elite) by new ones.
+    fn replace_old_individuals(&mut self) {
+        let start = Instant::now();
+        let elitism = self.elitism as usize;
+        let max_age = self.max_age;
+        let old: Vec<usize> = self.population
+            .iter()
+            .enumerate()
+            .skip(elitism)
+            .filter(|&(_, wrapper)| wrapper.age > max_age)
+            .map(|(index, _)| index)
+            .collect();
+
+        if !old.is_empty() {
+            self.renew(&old);
+            self.record_phase(Phase::Reset, start);
+        }
+    }
+
+    /// Mutates each individual whose genotype (see `Individual::genome_key`) is already in the
+    /// (sorted) population until it is a new genotype (at most `MAX_DUPLICATE_MUTATIONS` times)
+    /// and sorts the population again. The elite and individuals without a key are kept.
+    fn replace_duplicates(&mut self) {
+        let id = self.id;
+        let label = self.label.clone();
+        let elitism = self.elitism as usize;
+        let mut keys = HashSet::new();
+        let mut panics = Vec::new();
+
+        for (index, wrapper) in self.population.iter_mut().enumerate() {
+            let key = match wrapper.individual.genome_key() {
+                Some(key) => key,
+                None => continue,
+            };
+            if keys.insert(key) || index < elitism {
+                continue;
+            }
+
+            let individual = &mut wrapper.individual;
+            let context = &*self.context;
+            let rng = &mut self.rng;
+            let counters = &mut self.counters;
+            let known = &keys;
+            let result = match catch_panic(|| {
+                for _ in 0..MAX_DUPLICATE_MUTATIONS {
+                    individual.mutate(context, rng);
+                    individual.repair();
+                    counters.mutations += 1;
+                    if individual.genome_key().map_or(true, |key| !known.contains(&key)) {
+                        break;
+                    }
+                }
+            }) {
+                Ok(()) => Self::evaluate(
+                    individual,
+                    &self.context,
+                    &mut self.counters,
+                    &mut self.fitness_cache,
+                    &self.constraint,
+                    self.direction,
+                )
+                .map_err(|message| ("calculate_fitness", message)),
+                Err(message) => Err(("mutate", message)),
+            };
+            let (fitness, case_errors) = result.unwrap_or_else(|(operation, message)| {
+                panics.push(panic_report(
+                    id,
+                    &label,
+                    operation,
+                    message,
+                    Some(&wrapper.individual),
+                ));
+                (T::Fitness::worst(), Vec::new())
+            });
+            wrapper.fitness = fitness;
+            wrapper.case_errors = case_errors;
+            if let Some(key) = wrapper.individual.genome_key() {
+                keys.insert(key);
+            }
+            self.counters.duplicates += 1;
+        }
+
+        self.handle_panics(panics);
+        self.sort();
+    }
+
+    /// Takes in an individual shared by another population, see
+    /// `SimulationBuilder::share_fittest`. With crossover and `interbreed_elites` > 0 it is
+    /// crossed with this number of elites (see `SimulationBuilder::interbreed_migrants`),
+    /// otherwise it is accepted as configured with `PopulationBuilder::acceptance`. Without an
+    /// acceptance policy it replaces the fittest individual or, with elitism, the least fit one.
+    pub fn receive_migrant(&mut self, migrant: &IndividualWrapper<T>, interbreed_elites: u32) {
+        match self.crossover {
+            Some(crossover) if interbreed_elites > 0 => {
+                let elites = (interbreed_elites as usize).min(self.population.len());
+                for index in 0..elites {
+                    let mut partner = migrant.individual.clone();
+                    let hybrid = self.guarded(index, "crossover", |elite, rng| {
+                        let mut hybrid = crossover(elite, &mut partner, rng);
+                        hybrid.repair();
+                        hybrid
+                    });
+                    self.hybrids.extend(hybrid);
+                    self.counters.crossovers += 1;
+                }
+            }
+            _ if self.acceptance.is_some() => self.accept(slice::from_ref(migrant)),
+            // The elite of the population is never replaced, the shared individual takes the
+            // place of the least fit one.
+            _ if self.elitism > 0 => {
+                if let Some(last) = self.population.last_mut() {
+                    *last = migrant.clone();
+                }
+                self.sort();
+            }
+            _ => self.population[0] = migrant.clone(),
+        }
+    }
+
+    /// Takes in the fittest individuals shared by another population, see
+    /// `SimulationBuilder::share_fittest`: a single one like `receive_migrant`, several ones like
+    /// `receive_migrants`. Explorers (see `PopulationBuilder::explorer`) ignore them.
+    pub fn receive_shared(&mut self, shared: &[IndividualWrapper<T>], interbreed_elites: u32) {
+        if self.explorer {
+            return;
+        }
+        match *shared {
+            [ref migrant] => self.receive_migrant(migrant, interbreed_elites),
+            _ => self.receive_migrants(shared, interbreed_elites),
+        }
+    }
+
+    /// Takes in the emigrants of another population, see `SimulationBuilder::migration_interval`.
+    /// With crossover and `interbreed_elites` > 0 each one is crossed with this number of elites
+    /// like in `receive_migrant`, otherwise they are accepted as configured with
+    /// `PopulationBuilder::acceptance` (default: they replace the least fit individuals).
+    pub fn receive_migrants(&mut self, migrants: &[IndividualWrapper<T>], interbreed_elites: u32) {
+        match self.crossover {
+            Some(_) if interbreed_elites > 0 => {
+                for migrant in migrants {
+                    self.receive_migrant(migrant, interbreed_elites);
+                }
+            }
+            _ => self.accept(migrants),
+        }
+    }
+
+    /// Lets the given immigrants replace individuals after the elite, see `Acceptance`.
+    fn accept(&mut self, immigrants: &[IndividualWrapper<T>]) {
+        let elites = (self.elitism as usize).min(self.population.len());
+        let replaceable = self.population.len() - elites;
+        let count = immigrants.len().min(replaceable);
+
+        match self.acceptance.unwrap_or(Acceptance::ReplaceWorst) {
+            Acceptance::ReplaceWorst => {
+                let residents = self.population.iter_mut().rev();
+                for (resident, immigrant) in residents.zip(&immigrants[..count]) {
+                    *resident = immigrant.clone();
+                }
+            }
+            Acceptance::ReplaceRandom => {
+                let chosen = index::sample(&mut self.rng, replaceable, count);
+                for (index, immigrant) in chosen.into_iter().zip(immigrants) {
+                    self.population[elites + index] = immigrant.clone();
+                }
+            }
+            Acceptance::FitterThanMedian => {
+                let median = match self.population.get(self.population.len() / 2) {
+                    Some(median) => median.fitness.clone(),
+                    None => return,
+                };
+                let fitter = immigrants.iter().filter(|immigrant| immigrant.fitness < median);
+                let residents = self.population.iter_mut().rev().take(replaceable);
+                for (resident, immigrant) in residents.zip(fitter) {
+                    *resident = immigrant.clone();
+                }
+            }
+        }
+        self.sort();
+    }
+
     /// Just calculates the fitness for each individual.
     /// Usually this is the most computational expensive operation, so optimize the
     /// `calculate_fitness` method of your data structure ;-)
+    /// Individuals whose `calculate_fitness` panics get the fitness infinity, see `PanicPolicy`.
+    /// Only the dirty individuals (see `IndividualWrapper::dirty`) are evaluated, in parallel.
     pub fn calculate_fitness(&mut self) {
-        for wrapper in &mut self.population {
-            wrapper.fitness = wrapper.individual.calculate_fitness();
-        }
+        let broken = vec![false; self.population.len()];
+        let panics = self.evaluate_dirty(&broken, &None);
+        self.handle_panics(panics);
     }
 
     /// This is the body that gets called for every iteration.
     /// This function does the following:
     ///
     /// 1. Check if the reset limit is reached. If it is, this whole population is
-    /// discarded and re-initialized from the start. All the information about the
-    /// current fittest individual is lost. This is done to avoid local minima.
+    ///    discarded and re-initialized from the start. All the information about the
+    ///    current fittest individual is lost. This is done to avoid local minima.
+    ///    Individuals older than `max_age` are re-initialized as well.
     ///
     /// 2. Clone the current population.
     ///
     /// 3. Mutate the current population using the `mutate_population` function.
     ///
     /// 4. Merge the newly mutated population and the original cloned population into one big
-    /// population twice the size.
+    ///    population twice the size.
     ///
     /// 5. Sort this new big population by fitness. So the fittest individual is at position 0.
     ///
     /// 6. Truncated the big population to its original size and thus gets rid of all the less fittest
-    /// individuals (they "die").
+    ///    individuals (they "die").
     ///
     /// 7. Check if the fittest individual (at index 0) in the current sorted population is better
-    /// (= fitter) than the global fittest individual of the whole simulation. If yes, the global
-    /// fittest individual is replaced.
+    ///    (= fitter) than the global fittest individual of the whole simulation. If yes, the global
+    ///    fittest individual is replaced.
     ///
     /// 8. Calculate the new improvement factor and prepare for the next iteration.
-    pub fn run_body<S>(&mut self, selector: &S)
+    ///
+    /// Returns an error if the selection of the parents for the crossover fails.
+    ///
+    /// Panics of the user code are caught, see `PanicPolicy`. A panic outside of the user code
+    /// called for single individuals (for example in the selector) always stops the
+    /// simulation, the population may then be incomplete.
+    pub fn run_body<S>(&mut self, selector: &S) -> Result<(), SelectorError>
+    where
+        S: Selector<T>,
+    {
+        trace_span!(
+            "run_body",
+            population = self.id,
+            label = %self.label,
+            fitness = ::tracing::field::Empty
+        );
+        let result = catch_panic(|| self.run_iteration(selector)).unwrap_or_else(|message| {
+            let report = panic_report::<T>(self.id, &self.label, "run_body", message, None);
+            error!("{}", report);
+            self.counters.panics += 1;
+            self.panicked = Some(report);
+            Ok(())
+        });
+        trace_record!("fitness", self.direction.apply(&self.population[0].fitness).to_f64());
+        result
+    }
+
+    /// One iteration of this population, see `run_body`.
+    fn run_iteration<S>(&mut self, selector: &S) -> Result<(), SelectorError>
     where
         S: Selector<T>,
     {
@@ -97,104 +1230,1434 @@ impl<T: Individual + Send + Sync + Clone + Debug> Population<T> {
 
             // Check if reset limit is reached
             if self.reset_counter > self.reset_limit {
+                if self.max_resets.map_or(false, |max_resets| self.resets >= max_resets) {
+                    info!("population {} retired after {} resets", self.tag(), self.resets);
+                    self.retired = Some(RetireReason::ResetsExhausted);
+                    return Ok(());
+                }
+
                 self.reset_limit += self.reset_limit_increment;
-                if self.reset_limit >= self.reset_limit_end {
+                let restarted = self.reset_limit >= self.reset_limit_end;
+                if restarted {
                     self.reset_limit = self.reset_limit_start;
-                    info!(
-                        "reset_limit reset to reset_limit_start: {}, id: {}",
-                        self.reset_limit_start,
-                        self.id
-                    );
                 }
                 self.reset_counter = 0;
-                info!(
-                    "new reset_limit: {}, id: {}, counter: {}",
-                    self.reset_limit,
-                    self.id,
-                    self.fitness_counter
-                );
+                self.reset_log.push((self.reset_limit, restarted));
 
                 // Kill all individuals since we are most likely stuck in a local minimum.
                 // Why is it so ? Because the simulation is still running and the exit criteria
                 // hasn't been reached yet!
-                // Keep number of mutations.
-                for wrapper in &mut self.population {
-                    wrapper.individual.reset();
-                    wrapper.fitness = wrapper.individual.calculate_fitness();
+                // Keep number of mutations and the elite.
+                let start = Instant::now();
+                let elitism = self.elitism as usize;
+                if elitism > 0 {
+                    self.sort();
                 }
+                let renewed: Vec<usize> = (elitism..self.population.len()).collect();
+                self.renew(&renewed);
+                self.resets += 1;
+                self.counters.resets += 1;
+                self.record_phase(Phase::Reset, start);
             }
         }
 
-        println!("-- orig pop size: {}", self.population.len());
+        if self.max_age > 0 {
+            self.replace_old_individuals();
+        }
+
+        self.update_penalty();
+
+        let best_fitness =
+            fitness::minimum(self.population.iter().map(|wrapper| wrapper.fitness.clone()));
+
+        if let Some((min, max)) = self.rank_mutation {
+            self.assign_rank_mutations(min, max);
+        }
+        if let Some(ref schedule) = self.mutation_schedule {
+            let num_of_mutations = schedule.get().round().max(0.0) as u32;
+            for wrapper in &mut self.population {
+                wrapper.num_of_mutations = num_of_mutations;
+            }
+        }
 
-        // Keep original population.
-        let orig_population = self.population.clone();
+        // Mutate copies of the original population (the parents) in the offspring buffer, which
+        // then becomes the population.
+        let mutation_rates: Vec<u32> =
+            self.population.iter().map(|wrapper| wrapper.num_of_mutations).collect();
+        let mut offspring = mem::take(&mut self.offspring_buffer);
+        offspring.clone_from(&self.population);
+        let mut parents = mem::replace(&mut self.population, offspring);
 
         // Mutate population
-        for wrapper in &mut self.population {
-            for _ in 0..wrapper.num_of_mutations {
-                // Maybe add super optimization ?
-                // See https://github.com/willi-kappler/darwin-rs/issues/10
-                wrapper.individual.mutate();
+        let start = Instant::now();
+        let mut panics = Vec::new();
+        let mut broken = vec![false; self.population.len()];
+        let context = &*self.context;
+        let rng = &mut self.rng;
+        for (wrapper, broken) in self.population.iter_mut().zip(broken.iter_mut()) {
+            let individual = &mut wrapper.individual;
+            let num_of_mutations = wrapper.num_of_mutations;
+            let result = catch_panic(|| {
+                for _ in 0..num_of_mutations {
+                    // Maybe add super optimization ?
+                    // See https://github.com/willi-kappler/darwin-rs/issues/10
+                    individual.mutate(context, &mut *rng);
+                }
+                if num_of_mutations > 0 {
+                    individual.repair();
+                }
+            });
+            if let Err(message) = result {
+                panics.push(panic_report(
+                    self.id,
+                    &self.label,
+                    "mutate",
+                    message,
+                    Some(individual),
+                ));
+                *broken = true;
+            }
+            wrapper.dirty |= num_of_mutations > 0;
+            self.counters.mutations += u64::from(wrapper.num_of_mutations);
+        }
+        self.record_phase(Phase::Variation, start);
+
+        // The fitness of the worst individual that survives in any case. Offspring within the
+        // fitness tolerance may still survive because of their diversity.
+        // Without the parents there is no such individual.
+        let worst = match self.survivor_scheme {
+            SurvivorScheme::Plus => {
+                fitness::maximum(parents.iter().map(|wrapper| wrapper.fitness.clone()))
+                    .map(|worst| worst.penalize(self.fitness_tolerance.max(0.0)))
+            }
+            SurvivorScheme::Comma => None,
+        };
+
+        let start = Instant::now();
+        for (wrapper, &broken) in self.population.iter_mut().zip(&broken) {
+            if broken {
+                wrapper.fitness = T::Fitness::worst();
+                wrapper.case_errors.clear();
             }
-            wrapper.fitness = wrapper.individual.calculate_fitness();
         }
+        // Unchanged individuals keep their fitness.
+        panics.extend(self.evaluate_dirty(&broken, &worst));
+        self.record_phase(Phase::Evaluation, start);
+        self.handle_panics(panics);
 
         // Append original (unmutated) population to new (mutated) population.
-        self.population.extend(orig_population.iter().cloned());
+        // Without the parents at least the elite competes with the offspring.
+        match self.survivor_scheme {
+            SurvivorScheme::Plus => self.population.append(&mut parents),
+            SurvivorScheme::Comma if self.elitism > 0 => {
+                parents.sort();
+                let elitism = (self.elitism as usize).min(parents.len());
+                self.population.extend(parents.drain(..elitism));
+            }
+            SurvivorScheme::Comma => {}
+        }
+        // The remaining parents are reused in the next iteration.
+        self.offspring_buffer = parents;
 
-        println!("-- mutated pop size: {}", self.population.len());
+        // Children that enter the population after the truncation, see `Replacement`.
+        let mut offspring: Vec<IndividualWrapper<T>> = Vec::new();
+        let mut first_parents: Vec<T> = Vec::new();
 
         // ** start cross-over code from RsGenetic
         // Perform selection
-        if T::CAN_CROSSOVER {
-            println!("@@ crossing over w/ population of {}", self.population.len());
-            let parents: Vec<(T, T)> = selector
-                .select(
-                    self.population
-                        .iter()
-                        .map(|w| w.individual.clone())
-                        .collect::<Vec<T>>()
-                        .as_slice(),
-                )
-                .expect("select failed");
+        if let Some(crossover) = self.crossover {
+            let start = Instant::now();
+            let cleared = self.clearing.map(|(radius, capacity)| {
+                species::clear(&mut self.population, radius, capacity)
+            });
+            let selected = {
+                trace_span!("selection", population = self.id);
+                match self.speciation {
+                    Some(speciation) => {
+                        let (parents, species) =
+                            species::select_parents(&self.population, speciation, &mut self.rng);
+                        self.species = species;
+                        Ok(parents)
+                    }
+                    None if self.ranking == Ranking::Nsga2 => {
+                        self.sort();
+                        let pairs = self.num_of_individuals as usize;
+                        Ok(pareto::select_parents(&self.population, pairs, &mut self.rng))
+                    }
+                    None => match self.selector {
+                        Some(ref own) => own.select_parents(&self.population, &mut self.rng),
+                        None => selector.select(&self.population, &mut self.rng),
+                    },
+                }
+            };
+            if let Some(cleared) = cleared {
+                species::restore(&mut self.population, &cleared);
+            }
+            let parents: Vec<(T, T)> = selected?;
+            self.record_phase(Phase::Selection, start);
 
             // Create children from the selected parents and mutate them.
-
+            let start = Instant::now();
+            let mut children: Vec<T> = Vec::with_capacity(parents.len());
             for (mut a, mut b) in parents {
-                let mut hyb = a.crossover(&mut b);
-                let fit = hyb.calculate_fitness();
-                println!("@@ hyb fit: {} x {} -> {}", a.calculate_fitness(), b.calculate_fitness(), fit);
-                self.population.push( IndividualWrapper {
+                if self.replacement == Replacement::ReplaceParents {
+                    first_parents.push(a.clone());
+                }
+                if self.crossover_rate >= 1.0 || self.rng.gen_bool(self.crossover_rate) {
+                    trace_span!("crossover", population = self.id);
+                    let rng = &mut self.rng;
+                    let result = catch_panic(|| {
+                        let mut child = crossover(&mut a, &mut b, rng);
+                        child.repair();
+                        child
+                    });
+                    match result {
+                        Ok(child) => children.push(child),
+                        // Without a child the first parent takes its place.
+                        Err(message) => {
+                            let report = panic_report(
+                                self.id,
+                                &self.label,
+                                "crossover",
+                                message,
+                                Some(&a),
+                            );
+                            self.handle_panics(vec![report]);
+                            children.push(a);
+                        }
+                    }
+                    self.counters.crossovers += 1;
+                } else {
+                    children.push(a);
+                }
+            }
+            self.record_phase(Phase::Variation, start);
+
+            // Children that replace other individuals always need their real fitness.
+            let bound = match self.replacement {
+                Replacement::Compete => worst.clone(),
+                _ => None,
+            };
+            let start = Instant::now();
+            for mut hyb in children {
+                let (fit, case_errors) = Self::evaluate_offspring(
+                    &mut hyb,
+                    &self.context,
+                    &bound,
+                    &mut self.counters,
+                    &mut self.fitness_cache,
+                    &self.constraint,
+                    self.direction,
+                )
+                .unwrap_or_else(|message| {
+                    let report = panic_report(
+                        self.id,
+                        &self.label,
+                        "calculate_fitness",
+                        message,
+                        Some(&hyb),
+                    );
+                    self.handle_panics(vec![report]);
+                    (T::Fitness::worst(), Vec::new())
+                });
+                let wrapper = IndividualWrapper {
                     individual: hyb,
                     fitness: fit,
                     num_of_mutations: 1,
                     id: self.id,
-                });
+                    age: 0,
+                    dirty: false,
+                    case_errors,
+                    objectives: Vec::new(),
+                };
+                if self.replacement == Replacement::Compete {
+                    self.population.push(wrapper);
+                } else {
+                    offspring.push(wrapper);
+                }
             }
-
-            // Kill off parts of the population at random to make room for the children
-            //self.kill_off(children.len());
+            self.record_phase(Phase::Evaluation, start);
             // ** end cross-over code from RsGenetic
         }
 
-        println!("@@ after crossing over: {}", self.population.len());
+        // Children of migrants and elites, created during the last migration.
+        if !self.hybrids.is_empty() {
+            let start = Instant::now();
+            let id = self.id;
+            let label = self.label.clone();
+            let mut panics = Vec::new();
+            for mut hybrid in self.hybrids.drain(..) {
+                let (fitness, case_errors) = Self::evaluate_offspring(
+                    &mut hybrid,
+                    &self.context,
+                    &worst,
+                    &mut self.counters,
+                    &mut self.fitness_cache,
+                    &self.constraint,
+                    self.direction,
+                )
+                .unwrap_or_else(|message| {
+                    panics.push(panic_report(
+                        id,
+                        &label,
+                        "calculate_fitness",
+                        message,
+                        Some(&hybrid),
+                    ));
+                    (T::Fitness::worst(), Vec::new())
+                });
+                self.population.push(IndividualWrapper {
+                    individual: hybrid,
+                    fitness,
+                    num_of_mutations: 1,
+                    id: self.id,
+                    age: 0,
+                    dirty: false,
+                    case_errors,
+                    objectives: Vec::new(),
+                });
+            }
+            self.record_phase(Phase::Evaluation, start);
+            self.handle_panics(panics);
+        }
 
         // Sort by fitness
+        let start = Instant::now();
         // Use random choice, see https://github.com/willi-kappler/darwin-rs/issues/7
-        self.population.sort();
+        self.sort();
+
+        // The children that replace other individuals always survive, the remaining places
+        // are filled by the truncation.
+        let children = if offspring.is_empty() {
+            Vec::new()
+        } else {
+            self.replace(offspring, &first_parents)
+        };
 
         // Reduce population to original length.
-        self.population.truncate(self.num_of_individuals as usize);
+        let size = self.num_of_individuals as usize - children.len();
+        self.truncate_population(size);
 
-        println!("@@ now we've got {}, fitnesses: {:?}", self.population.len(),
-                 [self.population[0].fitness, self.population[1].fitness, self.population[2].fitness]);
+        if !children.is_empty() {
+            self.population.extend(children);
+            self.sort();
+        }
+
+        if self.deduplicate {
+            self.replace_duplicates();
+        }
 
         // Restore original number of mutation rate, since these will be lost because of sorting.
-        for (individual, orig_individual) in
-            self.population.iter_mut().zip(orig_population.iter())
-        {
-            individual.num_of_mutations = orig_individual.num_of_mutations;
+        for (individual, &num_of_mutations) in self.population.iter_mut().zip(&mutation_rates) {
+            individual.num_of_mutations = num_of_mutations;
+        }
+        self.record_phase(Phase::Replacement, start);
+
+        if self.population[0].fitness < best_fitness {
+            self.counters.improvements += 1;
+            self.stagnation = 0;
+        } else {
+            self.stagnation += 1;
+        }
+
+        if self.retire_after > 0 && self.stagnation >= self.retire_after {
+            info!(
+                "population {} retired, no improvement for {} iterations",
+                self.tag(),
+                self.stagnation
+            );
+            self.retired = Some(RetireReason::Converged);
+        }
+
+        for wrapper in &mut self.population {
+            wrapper.age += 1;
+        }
+
+        if let Some(ref mut archive) = self.archive {
+            for wrapper in &self.population {
+                archive.insert(wrapper);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Tries each of the given numbers of mutations for `iterations` iterations on a copy of
+    /// this population and measures the improvement of the fittest individual per fitness
+    /// evaluation. The copy with the best rate replaces this population (so the work is not
+    /// wasted) and its number of mutations is used for all individuals from now on.
+    /// If no candidate improves the fittest individual, the population is left unchanged.
+    /// Populations with `rank_mutation` are not calibrated.
+    /// Returns the chosen number of mutations, or an error if the selection of the parents for
+    /// the crossover fails.
+    pub fn calibrate_mutations<S>(
+        &mut self,
+        selector: &S,
+        candidates: &[u32],
+        iterations: u32,
+    ) -> Result<Option<u32>, SelectorError>
+    where
+        S: Selector<T>,
+    {
+        if self.rank_mutation.is_some() {
+            return Ok(None);
         }
+
+        let start = Instant::now();
+        let initial =
+            fitness::minimum(self.population.iter().map(|wrapper| wrapper.fitness.clone()))
+                .to_f64();
+        let mut best: Option<(f64, u32, Population<T>)> = None;
+
+        for &num_of_mutations in candidates {
+            let mut trial = self.clone();
+            trial.counters = Counters::default();
+            for wrapper in &mut trial.population {
+                wrapper.num_of_mutations = num_of_mutations;
+            }
+
+            for _ in 0..iterations {
+                trial.run_body(selector)?;
+            }
+
+            self.counters.merge(&trial.counters);
+            let rate = (initial - trial.population[0].fitness.to_f64()) /
+                trial.counters.evaluations.max(1) as f64;
+            info!(
+                "calibration: num_of_mutations: {}, improvement per evaluation: {}, id: {}",
+                num_of_mutations,
+                rate,
+                self.tag()
+            );
+
+            if rate > 0.0 && best.as_ref().map_or(true, |&(best_rate, _, _)| rate > best_rate) {
+                best = Some((rate, num_of_mutations, trial));
+            }
+        }
+
+        let chosen = best.map(|(_, num_of_mutations, mut trial)| {
+            trial.counters = self.counters;
+            trial.phase_timings = mem::take(&mut self.phase_timings);
+            *self = trial;
+            num_of_mutations
+        });
+        self.record_phase(Phase::Calibration, start);
+
+        Ok(chosen)
+    }
+}
+
+/// Saving the individuals, only available with the feature `serialize`, see module persist.
+#[cfg(feature = "serialize")]
+impl<T: Individual + Send + Sync + Clone + Debug + Serialize> Population<T> {
+    /// Saves the individuals of this population (fittest first after a run) to the given file,
+    /// see `PopulationBuilder::initial_population_from_file`.
+    pub fn save<P: AsRef<Path>>(&self, path: P, format: Format) -> Result<(), PersistError> {
+        let individuals: Vec<&T> =
+            self.population.iter().map(|wrapper| &wrapper.individual).collect();
+        persist::save(&individuals, path.as_ref(), format)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    use constraint::Penalty;
+    use individual::{genome_hash, Constrained, Crossover, Individual, IndividualWrapper};
+    use migration::Acceptance;
+    use population::{Population, Ranking, Replacement, SurvivorScheme};
+    use population_builder::PopulationBuilder;
+    use rand::Rng;
+    use rng::SimRng;
+    use schedule::Parameter;
+    use select::{MaximizeSelector, MinimizeSelector, SelectorError, TournamentSelector};
+    use test::wrap;
+
+    #[derive(Debug, Clone)]
+    struct Point {
+        x: f64,
+        fitness: f64,
+    }
+
+    impl Individual for Point {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {}
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            self.fitness
+        }
+
+        fn reset(&mut self) {}
+
+        fn distance(&self, other: &Point) -> f64 {
+            (self.x - other.x).abs()
+        }
+    }
+
+    impl Crossover for Point {
+        fn crossover(&mut self, other: &mut Point, _rng: &mut SimRng) -> Point {
+            let x = (self.x + other.x) / 2.0;
+            Point { x, fitness: x }
+        }
+    }
+
+    #[test]
+    fn population_uses_its_own_operators() {
+        let initial: Vec<Point> =
+            [0.0, 1.0, 2.0, 3.0].iter().map(|&x| Point { x, fitness: x }).collect();
+        let schedule = Parameter::new(2.4);
+        let mut population = PopulationBuilder::<Point>::new()
+            .initial_population(&initial)
+            .crossover()
+            .selector(TournamentSelector::new(2, 9))
+            .mutation_schedule(schedule.clone())
+            .finalize()
+            .unwrap();
+        population.calculate_fitness();
+
+        // The tournament needs more individuals, the selector of the simulation would not.
+        let required = SelectorError::PopulationTooSmall { required: 10, actual: 8 };
+        assert_eq!(population.run_body(&MinimizeSelector::new(2)), Err(required));
+        assert!(population.population.iter().all(|wrapper| wrapper.num_of_mutations == 2));
+
+        population.selector = Some(Arc::new(MaximizeSelector::new(2)));
+        schedule.set(0.0);
+        population.run_body(&MinimizeSelector::new(2)).unwrap();
+        assert!(population.population.iter().all(|wrapper| wrapper.num_of_mutations == 0));
+        assert!(population.configuration().contains("selector: Some(MaximizeSelector"));
+    }
+
+    #[test]
+    fn clearing_selects_parents_from_all_niches() {
+        let initial: Vec<Point> = [0.0, 1.0, 2.0, 100.0, 101.0, 102.0]
+            .iter()
+            .map(|&x| Point { x, fitness: x })
+            .collect();
+        let run = |clearing: bool| {
+            let mut builder = PopulationBuilder::<Point>::new()
+                .initial_population(&initial)
+                .crossover()
+                .replacement(Replacement::ReplaceWorst)
+                .reset_limit_end(0);
+            if clearing {
+                builder = builder.clearing(10.0, 1);
+            }
+            let mut population = builder.finalize().unwrap();
+            population.calculate_fitness();
+            population.run_body(&MinimizeSelector::new(2)).unwrap();
+            population.population.iter().map(|w| w.fitness).collect::<Vec<f64>>()
+        };
+
+        // The two fittest individuals are in the same niche.
+        assert_eq!(run(false), vec![0.0, 0.0, 0.0, 1.0, 1.0, 2.0]);
+        // Only the fittest individual of each niche keeps its fitness for the selection, the
+        // cleared individuals get their fitness back afterwards.
+        assert_eq!(run(true), vec![0.0, 0.0, 1.0, 1.0, 2.0, 50.0]);
+    }
+
+    #[derive(Debug, Clone)]
+    struct Descent {
+        x: f64,
+    }
+
+    impl Individual for Descent {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {
+            self.x -= 1.0;
+        }
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            self.x
+        }
+
+        fn reset(&mut self) {}
+    }
+
+    impl Constrained for Descent {
+        // x >= 3
+        fn violation(&self) -> f64 {
+            3.0 - self.x
+        }
+    }
+
+    #[test]
+    fn penalty_keeps_the_population_feasible() {
+        let initial = [Descent { x: 10.0 }, Descent { x: 11.0 }, Descent { x: 12.0 }];
+        let run = |penalty: Option<Penalty>| {
+            let mut builder = PopulationBuilder::<Descent>::new()
+                .initial_population(&initial)
+                .reset_limit_end(0);
+            if let Some(penalty) = penalty {
+                builder = builder.penalty(penalty);
+            }
+            let mut population = builder.finalize().unwrap();
+            population.calculate_fitness();
+            for _ in 0..20 {
+                population.run_body(&MinimizeSelector::new(2)).unwrap();
+            }
+            population.population[0].individual.x
+        };
+
+        assert!(run(None) < 0.0);
+        assert_eq!(run(Some(Penalty::Static(10.0))), 3.0);
+        let dynamic = Penalty::Dynamic { c: 1.0, alpha: 1.0, beta: 1.0 };
+        assert_eq!(run(Some(dynamic)), 3.0);
+
+        let invalid = PopulationBuilder::<Descent>::new()
+            .initial_population(&initial)
+            .penalty(Penalty::Static(f64::NAN))
+            .finalize();
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn feasible_individuals_rank_first() {
+        let initial = [Descent { x: 10.0 }, Descent { x: 11.0 }, Descent { x: 12.0 }];
+        let run = |builder: PopulationBuilder<Descent>| {
+            let mut population =
+                builder.initial_population(&initial).reset_limit_end(0).finalize().unwrap();
+            population.calculate_fitness();
+            for _ in 0..20 {
+                population.run_body(&MinimizeSelector::new(2)).unwrap();
+            }
+            population.population[0].individual.x
+        };
+
+        assert_eq!(run(PopulationBuilder::new().feasibility_first()), 3.0);
+        assert_eq!(run(PopulationBuilder::new().stochastic_ranking(0.0)), 3.0);
+        let invalid = PopulationBuilder::<Descent>::new()
+            .initial_population(&initial)
+            .ranking(Ranking::Feasibility)
+            .finalize();
+        assert!(invalid.is_err());
+    }
+
+    #[derive(Debug, Clone)]
+    struct Clamped {
+        x: f64,
+    }
+
+    impl Individual for Clamped {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {
+            self.x -= 5.0;
+        }
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            self.x
+        }
+
+        fn reset(&mut self) {}
+
+        // x >= 0
+        fn repair(&mut self) {
+            self.x = self.x.max(0.0);
+        }
+    }
+
+    impl Crossover for Clamped {
+        fn crossover(&mut self, other: &mut Clamped, _rng: &mut SimRng) -> Clamped {
+            Clamped { x: self.x - other.x }
+        }
+    }
+
+    #[test]
+    fn offspring_are_repaired() {
+        let mut population = PopulationBuilder::<Clamped>::new()
+            .initial_population(&[Clamped { x: 1.0 }, Clamped { x: 2.0 }, Clamped { x: 8.0 }])
+            .crossover()
+            .reset_limit_end(0)
+            .finalize()
+            .unwrap();
+        population.calculate_fitness();
+        population.run_body(&MinimizeSelector::new(2)).unwrap();
+
+        let values: Vec<f64> = population.population.iter().map(|w| w.individual.x).collect();
+        assert_eq!(values, vec![0.0, 0.0, 0.0]);
+        assert!(population.population.iter().all(|wrapper| wrapper.fitness == 0.0));
+    }
+
+    #[derive(Debug, Clone)]
+    struct Design {
+        cost: f64,
+        weight: f64,
+    }
+
+    impl Individual for Design {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {}
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            self.cost
+        }
+
+        fn reset(&mut self) {}
+
+        fn objectives(&self) -> Vec<f64> {
+            vec![self.cost, self.weight]
+        }
+    }
+
+    #[test]
+    fn pareto_ranking_keeps_the_front() {
+        let initial = [
+            Design { cost: 1.0, weight: 9.0 },
+            Design { cost: 2.0, weight: 9.0 },
+            Design { cost: 5.0, weight: 1.0 },
+            Design { cost: 6.0, weight: 0.0 },
+        ];
+        let run = |ranking| {
+            let mut population = PopulationBuilder::<Design>::new()
+                .initial_population(&initial)
+                .ranking(ranking)
+                .reset_limit_end(0)
+                .finalize()
+                .unwrap();
+            population.calculate_fitness();
+            population.run_body(&MinimizeSelector::new(2)).unwrap();
+            population.population.iter().map(|w| w.individual.cost).collect::<Vec<f64>>()
+        };
+
+        assert_eq!(run(Ranking::Fitness), vec![1.0, 1.0, 2.0, 2.0]);
+        // The design with the cost 2.0 is dominated by the one with the cost 1.0.
+        assert_eq!(run(Ranking::Pareto), vec![1.0, 1.0, 5.0, 5.0]);
+    }
+
+    #[test]
+    fn truncation_prefers_diverse_individuals_on_plateau() {
+        let initial: Vec<Point> = (0..4).map(|i| Point { x: i as f64, fitness: 1.0 }).collect();
+        let mut population = PopulationBuilder::<Point>::new()
+            .initial_population(&initial)
+            .fitness_tolerance(0.5)
+            .finalize()
+            .unwrap();
+        population.calculate_fitness();
+
+        // Add clones of the first individual, they all have the same fitness.
+        for _ in 0..4 {
+            population.population.push(IndividualWrapper {
+                individual: Point { x: 0.0, fitness: 1.0 },
+                fitness: 1.0,
+                num_of_mutations: 1,
+                id: 1,
+                age: 0,
+                dirty: false,
+                case_errors: Vec::new(),
+                objectives: Vec::new(),
+            });
+        }
+        population.population.sort();
+        population.truncate_population(4);
+
+        let mut xs: Vec<f64> = population.population.iter().map(|w| w.individual.x).collect();
+        xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(xs, vec![0.0, 1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn nan_fitness_sorts_last() {
+        let initial: Vec<Point> = [2.0, f64::NAN, 1.0, f64::INFINITY]
+            .iter()
+            .map(|&fitness| Point { x: 0.0, fitness })
+            .collect();
+        let mut population = PopulationBuilder::<Point>::new()
+            .initial_population(&initial)
+            .finalize()
+            .unwrap();
+        population.calculate_fitness();
+        population.sort();
+
+        let fitness: Vec<f64> = population.population.iter().map(|w| w.fitness).collect();
+        assert_eq!(&fitness[..3], &[1.0, 2.0, f64::INFINITY]);
+        assert!(fitness[3].is_nan());
+    }
+
+    #[derive(Debug, Clone)]
+    struct Bounded {
+        value: f64,
+    }
+
+    impl Individual for Bounded {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {
+            self.value += 10.0;
+        }
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            self.value
+        }
+
+        fn reset(&mut self) {}
+
+        fn quick_bound(&self) -> Option<f64> {
+            Some(self.value - 1.0)
+        }
+    }
+
+    #[test]
+    fn hopeless_offspring_are_not_evaluated() {
+        let initial: Vec<Bounded> = (0..4).map(|i| Bounded { value: i as f64 }).collect();
+        let mut population = PopulationBuilder::<Bounded>::new()
+            .initial_population(&initial)
+            .reset_limit_end(0)
+            .finalize()
+            .unwrap();
+        population.calculate_fitness();
+        population.counters = Default::default();
+
+        population.run_body(&MinimizeSelector::new(2)).unwrap();
+        // All mutated individuals have a bound of at least 9.0, worse than the worst survivor.
+        assert_eq!(population.counters.evaluations, 0);
+        assert_eq!(population.counters.skipped_evaluations, 4);
+        let fitness: Vec<f64> = population.population.iter().map(|w| w.fitness).collect();
+        assert_eq!(fitness, vec![0.0, 1.0, 2.0, 3.0]);
+    }
+
+    #[derive(Debug, Clone)]
+    struct Pair {
+        value: f64,
+    }
+
+    impl Individual for Pair {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {}
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            self.value
+        }
+
+        fn reset(&mut self) {}
+
+        fn distance(&self, other: &Pair) -> f64 {
+            (self.value - other.value).abs()
+        }
+    }
+
+    impl Crossover for Pair {
+        fn crossover(&mut self, other: &mut Pair, _rng: &mut SimRng) -> Pair {
+            Pair { value: self.value + other.value }
+        }
+    }
+
+    /// The fitness is `value`, the distance is measured by `position`.
+    #[derive(Debug, Clone)]
+    struct Placed {
+        value: f64,
+        position: f64,
+    }
+
+    impl Individual for Placed {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {}
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            self.value
+        }
+
+        fn reset(&mut self) {}
+
+        fn distance(&self, other: &Placed) -> f64 {
+            (self.position - other.position).abs()
+        }
+    }
+
+    impl Crossover for Placed {
+        fn crossover(&mut self, _other: &mut Placed, _rng: &mut SimRng) -> Placed {
+            Placed { value: 100.0, position: 100.0 }
+        }
+    }
+
+    #[test]
+    fn replacement_policies() {
+        // The children (sum of two parents) are less fit than all other individuals.
+        let initial: Vec<Pair> = (10..30).map(|i| Pair { value: i as f64 }).collect();
+        let run = |replacement| {
+            let mut population = PopulationBuilder::<Pair>::new()
+                .initial_population(&initial)
+                .crossover()
+                .replacement(replacement)
+                .reset_limit_end(0)
+                .evolution_seed(5)
+                .finalize()
+                .unwrap();
+            population.calculate_fitness();
+            population.run_body(&MinimizeSelector::new(4)).unwrap();
+            population.population.iter().map(|w| w.individual.value).collect::<Vec<f64>>()
+        };
+
+        let compete = run(Replacement::Compete);
+        assert!(compete.iter().all(|&value| value < 20.0));
+
+        let worst = run(Replacement::ReplaceWorst);
+        assert_eq!(worst.len(), 20);
+        assert_eq!(worst.iter().filter(|&&value| value >= 20.0).count(), 2);
+        assert_eq!(&worst[..18], &compete[..18]);
+
+        let random = run(Replacement::ReplaceRandom);
+        assert_eq!(random[0], 10.0);
+        assert_eq!(random.iter().filter(|&&value| value >= 20.0).count(), 2);
+
+        // The parents are the fittest individuals, the least fit ones stay.
+        let parents = run(Replacement::ReplaceParents);
+        assert_eq!(parents[0], 10.0);
+        assert_eq!(&parents[16..18], &[19.0, 19.0]);
+        assert_eq!(parents.iter().filter(|&&value| value >= 20.0).count(), 2);
+
+        // The least fit individual (9) is the only parent, it is placed next to 0 and 1.
+        let initial: Vec<Placed> = (0..10)
+            .map(|i| Placed { value: f64::from(i), position: f64::from((i + 1) % 10) })
+            .collect();
+        let mut population = PopulationBuilder::<Placed>::new()
+            .initial_population(&initial)
+            .crossover()
+            .replacement(Replacement::ReplaceParents)
+            .reset_limit_end(0)
+            .finalize()
+            .unwrap();
+        population.calculate_fitness();
+        population.run_body(&MaximizeSelector::new(2)).unwrap();
+
+        // The child replaces its parent, which would not have survived the truncation anyway.
+        // Replacing after the truncation would have removed the second copy of 0 instead.
+        let values: Vec<f64> = population.population.iter().map(|w| w.individual.value).collect();
+        assert_eq!(values, vec![0.0, 0.0, 1.0, 1.0, 2.0, 2.0, 3.0, 3.0, 4.0, 100.0]);
+    }
+
+    #[test]
+    fn immigrants_are_accepted_by_policy() {
+        let initial: Vec<Point> = (0..6).map(|i| Point { x: 0.0, fitness: f64::from(i) }).collect();
+        let immigrants = wrap(&[Point { x: 1.0, fitness: 1.5 }, Point { x: 1.0, fitness: 9.0 }]);
+        let receive = |acceptance: Option<Acceptance>, immigrants: &[_]| {
+            let builder = PopulationBuilder::<Point>::new()
+                .initial_population(&initial)
+                .elitism(1)
+                .evolution_seed(3);
+            let builder = match acceptance {
+                Some(acceptance) => builder.acceptance(acceptance),
+                None => builder,
+            };
+            let mut population = builder.finalize().unwrap();
+            population.calculate_fitness();
+            population.receive_shared(immigrants, 0);
+            population.population.iter().map(|w| w.fitness).collect::<Vec<f64>>()
+        };
+
+        // Without a policy the immigrants replace the least fit individuals.
+        let worst = vec![0.0, 1.0, 1.5, 2.0, 3.0, 9.0];
+        assert_eq!(receive(None, &immigrants), worst);
+        assert_eq!(receive(Some(Acceptance::ReplaceWorst), &immigrants), worst);
+        let first = vec![0.0, 1.0, 1.5, 2.0, 3.0, 4.0];
+        assert_eq!(receive(Some(Acceptance::ReplaceWorst), &immigrants[..1]), first);
+
+        let random = receive(Some(Acceptance::ReplaceRandom), &immigrants);
+        assert_eq!(random[0], 0.0);
+        assert!(random.contains(&1.5) && random.contains(&9.0));
+        assert_eq!(random.len(), 6);
+
+        // The median is 3, so only the first immigrant is fit enough.
+        assert_eq!(receive(Some(Acceptance::FitterThanMedian), &immigrants), first);
+    }
+
+    #[derive(Debug, Clone)]
+    struct Drift {
+        x: f64,
+    }
+
+    impl Individual for Drift {
+        type Fitness = f64;
+        type Context = ();
+
+        // Each mutation makes the individual worse by 10.
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {
+            self.x += 10.0;
+        }
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            self.x
+        }
+
+        fn reset(&mut self) {}
+    }
+
+    #[test]
+    fn comma_scheme_discards_parents() {
+        let initial: Vec<Drift> = (0..5).map(|i| Drift { x: i as f64 }).collect();
+        let run = |scheme| {
+            let mut population = PopulationBuilder::<Drift>::new()
+                .initial_population(&initial)
+                .survivor_scheme(scheme)
+                .reset_limit_end(0)
+                .finalize()
+                .unwrap();
+            population.calculate_fitness();
+            population.run_body(&MinimizeSelector::new(2)).unwrap();
+            population.population.iter().map(|w| w.fitness).collect::<Vec<f64>>()
+        };
+
+        assert_eq!(run(SurvivorScheme::Plus), vec![0.0, 1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(run(SurvivorScheme::Comma), vec![10.0, 11.0, 12.0, 13.0, 14.0]);
+    }
+
+    #[test]
+    fn elite_always_survives() {
+        let drift: Vec<Drift> = (0..5).map(|i| Drift { x: i as f64 }).collect();
+        let mut population = PopulationBuilder::<Drift>::new()
+            .initial_population(&drift)
+            .survivor_scheme(SurvivorScheme::Comma)
+            .elitism(2)
+            .reset_limit_end(0)
+            .finalize()
+            .unwrap();
+        population.calculate_fitness();
+        population.run_body(&MinimizeSelector::new(2)).unwrap();
+        let fitness: Vec<f64> = population.population.iter().map(|w| w.fitness).collect();
+        assert_eq!(fitness, vec![0.0, 1.0, 10.0, 11.0, 12.0]);
+
+        // The children of the crossover never replace the elite.
+        let initial: Vec<Pair> = (10..30).map(|i| Pair { value: i as f64 }).collect();
+        for seed in 0..10 {
+            let mut population = PopulationBuilder::<Pair>::new()
+                .initial_population(&initial)
+                .crossover()
+                .replacement(Replacement::ReplaceRandom)
+                .elitism(5)
+                .reset_limit_end(0)
+                .evolution_seed(seed)
+                .finalize()
+                .unwrap();
+            population.calculate_fitness();
+            population.run_body(&MinimizeSelector::new(4)).unwrap();
+            let values: Vec<f64> =
+                population.population.iter().map(|w| w.individual.value).collect();
+            // Pair doesn't mutate, so each parent competes with an identical copy.
+            assert_eq!(&values[..5], &[10.0, 10.0, 11.0, 11.0, 12.0]);
+        }
+
+        assert!(PopulationBuilder::<Drift>::new()
+            .initial_population(&drift)
+            .elitism(5)
+            .finalize()
+            .is_err());
+    }
+
+    #[derive(Debug, Clone)]
+    struct Aging {
+        x: f64,
+    }
+
+    impl Individual for Aging {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {}
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            self.x
+        }
+
+        fn reset(&mut self) {
+            self.x = 100.0;
+        }
+    }
+
+    #[test]
+    fn old_individuals_are_replaced() {
+        let initial: Vec<Aging> = (0..4).map(|i| Aging { x: f64::from(i) }).collect();
+        let mut population = PopulationBuilder::<Aging>::new()
+            .initial_population(&initial)
+            .max_age(2)
+            .elitism(1)
+            .reset_limit_end(0)
+            .finalize()
+            .unwrap();
+        population.calculate_fitness();
+
+        for _ in 0..3 {
+            population.run_body(&MinimizeSelector::new(2)).unwrap();
+        }
+        let ages: Vec<u32> = population.population.iter().map(|w| w.age).collect();
+        assert_eq!(ages, vec![3, 3, 3, 3]);
+
+        // All but the elite are too old now.
+        population.run_body(&MinimizeSelector::new(2)).unwrap();
+        let state: Vec<(f64, u32)> =
+            population.population.iter().map(|w| (w.fitness, w.age)).collect();
+        assert_eq!(state, vec![(0.0, 4), (0.0, 4), (100.0, 1), (100.0, 1)]);
+    }
+
+    #[derive(Debug, Clone)]
+    struct Step {
+        x: u32,
+    }
+
+    impl Individual for Step {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {
+            self.x += 1;
+        }
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            f64::from(self.x)
+        }
+
+        fn reset(&mut self) {}
+
+        fn genome_key(&self) -> Option<u64> {
+            Some(genome_hash(&self.x))
+        }
+    }
+
+    #[test]
+    fn duplicates_are_replaced() {
+        let initial: Vec<Step> = (0..4).map(|x| Step { x }).collect();
+        let run = |deduplicate| {
+            let mut builder = PopulationBuilder::<Step>::new()
+                .initial_population(&initial)
+                .reset_limit_end(0);
+            if deduplicate {
+                builder = builder.deduplicate();
+            }
+            let mut population = builder.finalize().unwrap();
+            population.calculate_fitness();
+            population.run_body(&MinimizeSelector::new(2)).unwrap();
+            let fitness: Vec<f64> = population.population.iter().map(|w| w.fitness).collect();
+            (fitness, population.counters.duplicates)
+        };
+
+        // Without deduplication the parents 0 and 1 survive together with their mutated copies.
+        assert_eq!(run(false), (vec![0.0, 1.0, 1.0, 2.0], 0));
+        assert_eq!(run(true), (vec![0.0, 1.0, 2.0, 3.0], 2));
+    }
+
+    #[test]
+    fn fitness_cache_skips_known_genotypes() {
+        let initial: Vec<Step> = (0..4).map(|x| Step { x }).collect();
+        let mut population = PopulationBuilder::<Step>::new()
+            .initial_population(&initial)
+            .fitness_cache(10)
+            .reset_limit_end(0)
+            .finalize()
+            .unwrap();
+        population.calculate_fitness();
+        population.run_body(&MinimizeSelector::new(2)).unwrap();
+
+        // Only the mutated copy 4 is a new genotype.
+        assert_eq!(population.counters.evaluations, 5);
+        assert_eq!(population.counters.cache_hits, 3);
+        assert_eq!(population.fitness_cache.as_ref().unwrap().len(), 5);
+    }
+
+    #[test]
+    fn same_genomes_are_evaluated_once() {
+        let initial: Vec<Step> = [1, 2, 1, 1].iter().map(|&x| Step { x }).collect();
+        let mut population = PopulationBuilder::<Step>::new()
+            .initial_population(&initial)
+            .fitness_cache(10)
+            .finalize()
+            .unwrap();
+        population.calculate_fitness();
+
+        // The individuals are evaluated in parallel, the copies of 1 still hit the cache.
+        assert_eq!(population.counters.evaluations, 2);
+        assert_eq!(population.counters.cache_hits, 2);
+        let fitness: Vec<f64> = population.population.iter().map(|w| w.fitness).collect();
+        assert_eq!(fitness, vec![1.0, 2.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn unchanged_individuals_are_not_evaluated() {
+        let initial: Vec<Step> = (0..4).map(|x| Step { x }).collect();
+        let mut population = PopulationBuilder::<Step>::new()
+            .initial_population(&initial)
+            .mutation_rate(vec![0, 1, 0, 1])
+            .reset_limit_end(0)
+            .finalize()
+            .unwrap();
+        population.calculate_fitness();
+        population.calculate_fitness();
+        assert_eq!(population.counters.evaluations, 4);
+
+        // Only the two mutated individuals are evaluated.
+        population.run_body(&MinimizeSelector::new(2)).unwrap();
+        assert_eq!(population.counters.evaluations, 6);
+        assert!(population.population.iter().all(|w| !w.dirty));
+        assert!(population.population.iter().all(|w| w.fitness == f64::from(w.individual.x)));
+    }
+
+    #[derive(Debug)]
+    struct Genome {
+        genes: Vec<u32>,
+    }
+
+    impl Clone for Genome {
+        fn clone(&self) -> Genome {
+            Genome { genes: self.genes.clone() }
+        }
+
+        fn clone_from(&mut self, source: &Genome) {
+            self.genes.clone_from(&source.genes);
+        }
+    }
+
+    impl Individual for Genome {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {
+            self.genes[0] += 1;
+        }
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            f64::from(self.genes[0])
+        }
+
+        fn reset(&mut self) {}
+    }
+
+    #[test]
+    fn offspring_reuse_the_genomes_of_the_last_iteration() {
+        let initial: Vec<Genome> = (0..5).map(|x| Genome { genes: vec![x; 100] }).collect();
+        let mut population = PopulationBuilder::<Genome>::new()
+            .initial_population(&initial)
+            .reset_limit_end(0)
+            .finalize()
+            .unwrap();
+        population.calculate_fitness();
+        let genomes = |population: &Population<Genome>| -> HashSet<usize> {
+            population
+                .population
+                .iter()
+                .chain(&population.offspring_buffer)
+                .map(|wrapper| wrapper.individual.genes.as_ptr() as usize)
+                .collect()
+        };
+
+        // The first iteration fills the buffer, the later ones allocate no genomes.
+        population.run_body(&MinimizeSelector::new(2)).unwrap();
+        assert_eq!(population.offspring_buffer.len(), 5);
+        let allocated = genomes(&population);
+        for _ in 0..3 {
+            population.run_body(&MinimizeSelector::new(2)).unwrap();
+            assert_eq!(genomes(&population), allocated);
+        }
+        assert_eq!(population.population[0].fitness, 0.0);
+    }
+
+    #[test]
+    fn individuals_are_evaluated_in_chunks() {
+        let initial: Vec<Step> = (0..10).map(|x| Step { x }).collect();
+        let build = |chunk: usize| {
+            PopulationBuilder::<Step>::new()
+                .initial_population(&initial)
+                .evaluation_chunk(chunk)
+                .finalize()
+        };
+        for &chunk in &[1, 3, 100] {
+            let mut population = build(chunk).unwrap();
+            population.calculate_fitness();
+            assert_eq!(population.counters.evaluations, 10);
+            assert!(population.population.iter().all(|w| w.fitness == f64::from(w.individual.x)));
+        }
+        assert!(build(0).is_err());
+    }
+
+    #[derive(Debug, Clone)]
+    struct Vectorized {
+        x: u32,
+        batches: u32,
+    }
+
+    impl Individual for Vectorized {
+        type Fitness = f64;
+        type Context = ();
+        const BATCH: bool = true;
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {
+            self.x += 1;
+        }
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            panic!("evaluated one by one");
+        }
+
+        fn reset(&mut self) {}
+
+        fn evaluate_batch(individuals: &mut [Vectorized], _context: &()) -> Option<Vec<f64>> {
+            for individual in individuals.iter_mut() {
+                individual.batches += 1;
+            }
+            Some(individuals.iter().map(|individual| f64::from(individual.x)).collect())
+        }
+    }
+
+    #[test]
+    fn individuals_are_evaluated_in_batches() {
+        let initial: Vec<Vectorized> = (0..4).map(|x| Vectorized { x, batches: 0 }).collect();
+        let mut population = PopulationBuilder::<Vectorized>::new()
+            .initial_population(&initial)
+            .mutation_rate(vec![0, 1, 0, 1])
+            .reset_limit_end(0)
+            .finalize()
+            .unwrap();
+        population.calculate_fitness();
+        assert_eq!(population.counters.evaluations, 4);
+        assert_eq!(population.counters.panics, 0);
+        assert!(population.population.iter().all(|w| w.individual.batches == 1));
+
+        population.run_body(&MinimizeSelector::new(2)).unwrap();
+        assert_eq!(population.counters.panics, 0);
+        assert!(population.population.iter().all(|w| w.fitness == f64::from(w.individual.x)));
+        assert!(population.population.iter().any(|w| w.individual.batches == 2));
+    }
+
+    #[derive(Debug, Clone)]
+    struct Tied {
+        x: i64,
+    }
+
+    impl Individual for Tied {
+        // The first value is always equal, the second one is beyond the precision of f64.
+        type Fitness = (i64, i64);
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {
+            self.x -= 1;
+        }
+
+        fn calculate_fitness(&mut self, _context: &()) -> (i64, i64) {
+            (0, self.x)
+        }
+
+        fn reset(&mut self) {}
+    }
+
+    #[test]
+    fn tuple_fitness_ranks_exactly() {
+        let big = 1 << 60;
+        let initial: Vec<Tied> = (0..4).map(|i| Tied { x: big + i }).collect();
+        let mut population = PopulationBuilder::<Tied>::new()
+            .initial_population(&initial)
+            .reset_limit_end(0)
+            .finalize()
+            .unwrap();
+        population.calculate_fitness();
+        population.run_body(&MinimizeSelector::new(2)).unwrap();
+
+        let fitness: Vec<i64> = population.population.iter().map(|w| w.fitness.1).collect();
+        assert_eq!(fitness, vec![big - 1, big, big, big + 1]);
+    }
+
+    #[test]
+    fn crossover_rate_limits_recombination() {
+        let initial: Vec<Pair> = (0..20).map(|i| Pair { value: i as f64 }).collect();
+        let build = |rate| {
+            PopulationBuilder::<Pair>::new()
+                .initial_population(&initial)
+                .crossover()
+                .crossover_rate(rate)
+                .reset_limit_end(0)
+                .evolution_seed(3)
+                .finalize()
+                .unwrap()
+        };
+
+        let mut never = build(0.0);
+        never.run_body(&MinimizeSelector::new(4)).unwrap();
+        assert_eq!(never.counters.crossovers, 0);
+
+        let mut always = build(1.0);
+        always.run_body(&MinimizeSelector::new(4)).unwrap();
+        assert_eq!(always.counters.crossovers, 2);
+
+        assert!(PopulationBuilder::<Pair>::new()
+            .initial_population(&initial)
+            .crossover_rate(1.5)
+            .finalize()
+            .is_err());
+    }
+
+    #[test]
+    fn rank_mutation_follows_fitness() {
+        let initial: Vec<Point> = (0..5)
+            .map(|i| Point { x: 0.0, fitness: (4 - i) as f64 })
+            .collect();
+        let mut population = PopulationBuilder::<Point>::new()
+            .initial_population(&initial)
+            .rank_proportional_mutation(1, 9)
+            .finalize()
+            .unwrap();
+        population.calculate_fitness();
+        population.assign_rank_mutations(1, 9);
+
+        let rates: Vec<(f64, u32)> = population.population
+            .iter()
+            .map(|w| (w.fitness, w.num_of_mutations))
+            .collect();
+        assert_eq!(rates, vec![(0.0, 1), (1.0, 3), (2.0, 5), (3.0, 7), (4.0, 9)]);
+    }
+
+    /// Takes a random step with the generator of the population.
+    #[derive(Debug, Clone)]
+    struct Walker {
+        x: f64,
+    }
+
+    impl Individual for Walker {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), rng: &mut SimRng) {
+            self.x += rng.gen_range(-1.0..1.0);
+        }
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            self.x.abs()
+        }
+
+        fn reset(&mut self) {}
+    }
+
+    impl Crossover for Walker {
+        fn crossover(&mut self, other: &mut Walker, rng: &mut SimRng) -> Walker {
+            Walker { x: if rng.gen::<bool>() { self.x } else { other.x } }
+        }
+    }
+
+    #[test]
+    fn same_seed_same_mutations() {
+        let run = |seed| {
+            let mut population = PopulationBuilder::<Walker>::new()
+                .initial_population(&[Walker { x: 5.0 }, Walker { x: -5.0 }, Walker { x: 9.0 }])
+                .crossover()
+                .evolution_seed(seed)
+                .finalize()
+                .unwrap();
+            population.calculate_fitness();
+            for _ in 0..10 {
+                population.run_body(&MinimizeSelector::new(2)).unwrap();
+            }
+            population.population.iter().map(|w| w.individual.x).collect::<Vec<f64>>()
+        };
+
+        assert_eq!(run(1), run(1));
+        assert_ne!(run(1), run(2));
     }
 }
@@ -13,11 +13,27 @@
 //!
 //!
 
-use std;
 use std::fmt::Debug;
+#[cfg(feature = "serialize")]
+use std::path::Path;
+use std::sync::Arc;
+#[cfg(feature = "serialize")]
+use serde::de::DeserializeOwned;
 
-use individual::{Individual, IndividualWrapper};
-use population::Population;
+use archive::Archive;
+use cache::FitnessCache;
+use constraint::{Constraint, Penalty};
+use fitness::Fitness;
+use individual::{self, BatchEvaluator, Constrained, Crossover, Individual, IndividualWrapper};
+#[cfg(feature = "serialize")]
+use persist::{self, Format, PersistError};
+use migration::Acceptance;
+use population::{PanicPolicy, Population, Ranking, Replacement, SurvivorScheme};
+use rng::{self, SimRng};
+use schedule::Parameter;
+use select::{Direction, Selector};
+use species::Speciation;
+use statistics::{Counters, ImprovementRate};
 
 /// This is a helper struct in order to build (configure) a valid population.
 /// See builder pattern: https://en.wikipedia.org/wiki/Builder_pattern
@@ -26,12 +42,27 @@ use population::Population;
 pub struct PopulationBuilder<T: Individual + Send + Clone + Debug> {
     /// The actual simulation
     population: Population<T>,
+    /// The random number generator used to create the initial population.
+    init_rng: SimRng,
 }
 
 error_chain! {
     errors {
         IndividualsTooLow
         LimitEndTooLow
+        CrossoverRateInvalid
+        ElitismTooLarge
+        SpeciationInvalid
+        ClearingInvalid
+        PenaltyInvalid
+        RankingInvalid
+        EvaluationChunkInvalid
+    }
+}
+
+impl<T: Individual + Clone + Send + Debug> Default for PopulationBuilder<T> {
+    fn default() -> PopulationBuilder<T> {
+        PopulationBuilder::new()
     }
 }
 
@@ -50,8 +81,48 @@ impl<T: Individual + Clone + Send + Debug> PopulationBuilder<T> {
                 reset_limit_increment: 1000,
                 reset_counter: 0,
                 id: 1,
+                label: String::new(),
                 fitness_counter: 0,
+                rng: rng::from_entropy(),
+                phase_timings: Vec::new(),
+                reset_log: Vec::new(),
+                counters: Counters::default(),
+                fitness_tolerance: 0.0,
+                rank_mutation: None,
+                hybrids: Vec::new(),
+                offspring_buffer: Vec::new(),
+                crossover: None,
+                crossover_rate: 1.0,
+                replacement: Replacement::default(),
+                improvement_rate: ImprovementRate::default(),
+                archive: None,
+                survivor_scheme: SurvivorScheme::default(),
+                elitism: 0,
+                max_age: 0,
+                resets: 0,
+                max_resets: None,
+                retire_after: 0,
+                stagnation: 0,
+                retired: None,
+                fitness_cache: None,
+                deduplicate: false,
+                explorer: false,
+                acceptance: None,
+                batch_evaluator: None,
+                evaluation_chunk: 1,
+                selector: None,
+                mutation_schedule: None,
+                panic_policy: PanicPolicy::default(),
+                panicked: None,
+                speciation: None,
+                species: Vec::new(),
+                clearing: None,
+                constraint: None,
+                direction: Direction::Minimize,
+                ranking: Ranking::default(),
+                context: Arc::new(T::Context::default()),
             },
+            init_rng: rng::from_entropy(),
         }
     }
 
@@ -62,24 +133,74 @@ impl<T: Individual + Clone + Send + Debug> PopulationBuilder<T> {
         for individual in individuals {
             self.population.population.push(IndividualWrapper {
                 individual: (*individual).clone(),
-                fitness: std::f64::MAX,
+                fitness: T::Fitness::worst(),
                 num_of_mutations: 1,
                 id: self.population.id,
+                age: 0,
+                dirty: true,
+                case_errors: Vec::new(),
+                objectives: Vec::new(),
             });
         }
 
         self
     }
 
+    /// Creates the initial population from the individuals saved in the given file, for
+    /// example by `SimulationResult::save_fittest` or `Population::save`, to warm-start a run.
+    /// The format is chosen by the extension (see `Format::of_path`). Only available with the
+    /// feature `serialize`.
+    #[cfg(feature = "serialize")]
+    pub fn initial_population_from_file<P: AsRef<Path>>(
+        self,
+        path: P,
+    ) -> ::std::result::Result<PopulationBuilder<T>, PersistError>
+    where
+        T: DeserializeOwned,
+    {
+        let path = path.as_ref();
+        let individuals: Vec<T> = persist::load(path, Format::of_path(path))?;
+        Ok(self.initial_population(&individuals))
+    }
+
+    /// Creates the initial population by calling the function `generate` `count` times.
+    /// The function gets the initialization random number generator, so the initial
+    /// population only depends on the seed given to `init_seed`.
+    /// If you want to use a fixed seed call `init_seed` before this method.
+    pub fn generate_initial_population<F>(self, count: u32, mut generate: F) -> PopulationBuilder<T>
+    where
+        F: FnMut(&mut SimRng) -> T,
+    {
+        let mut init_rng = self.init_rng.clone();
+        let individuals: Vec<T> = (0..count).map(|_| generate(&mut init_rng)).collect();
+        let mut builder = self.initial_population(&individuals);
+        builder.init_rng = init_rng;
+        builder
+    }
+
+    /// Sets the seed for the initialization random number generator used by
+    /// `generate_initial_population`. By default it is seeded from the operating system.
+    /// Together with `evolution_seed` this allows to keep the initial population fixed while
+    /// varying the random decisions during the simulation (or vice versa).
+    pub fn init_seed(mut self, seed: u64) -> PopulationBuilder<T> {
+        self.init_rng = rng::from_seed(seed);
+        self
+    }
+
+    /// Sets the seed for the evolution random number generator of the population. This one is
+    /// used for all the random decisions during the simulation, independently from the
+    /// initialization seed. By default it is seeded from the operating system.
+    pub fn evolution_seed(mut self, seed: u64) -> PopulationBuilder<T> {
+        self.population.rng = rng::from_seed(seed);
+        self
+    }
+
     /// Configures the mutation rates (number of mutation runs) for all the individuals
     /// in the population: The first individual will mutate once, the second will mutate twice,
     /// the nth individual will Mutate n-times per iteration.
     pub fn increasing_mutation_rate(mut self) -> PopulationBuilder<T> {
-        let mut mutation_rate = 1;
-
-        for wrapper in &mut self.population.population {
+        for (wrapper, mutation_rate) in self.population.population.iter_mut().zip(1..) {
             wrapper.num_of_mutations = mutation_rate;
-            mutation_rate += 1;
         }
 
         self
@@ -91,11 +212,8 @@ impl<T: Individual + Clone + Send + Debug> PopulationBuilder<T> {
     /// all the individuals. The first individual will mutate base^1 times, the second will
     /// mutate base^2 times, and nth will mutate base^n times per iteration.
     pub fn increasing_exp_mutation_rate(mut self, base: f64) -> PopulationBuilder<T> {
-        let mut mutation_rate = 1;
-
-        for wrapper in &mut self.population.population {
+        for (wrapper, mutation_rate) in self.population.population.iter_mut().zip(1..) {
             wrapper.num_of_mutations = base.powi(mutation_rate).floor() as u32;
-            mutation_rate += 1;
         }
 
         self
@@ -109,10 +227,7 @@ impl<T: Individual + Clone + Send + Debug> PopulationBuilder<T> {
         assert!(self.population.population.len() == mutation_rate.len());
 
         for (individual, mutation_rate) in
-            self.population.population.iter_mut().zip(
-                mutation_rate
-                    .into_iter(),
-            )
+            self.population.population.iter_mut().zip(mutation_rate)
         {
             individual.num_of_mutations = mutation_rate;
         }
@@ -120,6 +235,34 @@ impl<T: Individual + Clone + Send + Debug> PopulationBuilder<T> {
         self
     }
 
+    /// Configures the mutation rates (number of mutation runs) depending on the rank of each
+    /// individual: Each iteration the population is sorted, the fittest individual mutates
+    /// `min` times, the least fit individual mutates `max` times and the others in between
+    /// proportional to their rank. This overrides the other mutation rate settings.
+    pub fn rank_proportional_mutation(mut self, min: u32, max: u32) -> PopulationBuilder<T> {
+        self.population.rank_mutation = Some((min, max));
+        self
+    }
+
+    /// Enables crossover for this population (default: off). Each iteration the selector
+    /// chooses pairs of parents and their children (see `Crossover::crossover`) compete with
+    /// the mutated and the original individuals.
+    pub fn crossover(mut self) -> PopulationBuilder<T>
+    where
+        T: Crossover,
+    {
+        self.population.crossover = Some(individual::crossover::<T>);
+        self
+    }
+
+    /// Sets the fraction of the selected pairs of parents that are actually recombined
+    /// (default: 1.0). The other pairs pass their first parent unchanged as child.
+    /// Must be between 0.0 and 1.0, only used together with `crossover`.
+    pub fn crossover_rate(mut self, crossover_rate: f64) -> PopulationBuilder<T> {
+        self.population.crossover_rate = crossover_rate;
+        self
+    }
+
     /// Configures the reset limit for the population. If reset_limit_end is greater than zero
     /// then a reset counter is increased each iteration. If that counter is greater than the
     /// limit, all individuals will be resetted, the limit will be increased by 1000 and the
@@ -146,6 +289,25 @@ impl<T: Individual + Clone + Send + Debug> PopulationBuilder<T> {
         self
     }
 
+    /// Retire the population when the reset limit is reached after this number of resets
+    /// (default: no limit), instead of resetting it again: its reset budget is exhausted. A
+    /// retired population does not evolve any more while the other populations continue, see
+    /// `SimulationResult::retirements`. Only used if the reset limit is enabled (see
+    /// `reset_limit_end`).
+    pub fn max_resets(mut self, max_resets: u32) -> PopulationBuilder<T> {
+        self.population.max_resets = Some(max_resets);
+        self
+    }
+
+    /// Retire the population if its fittest individual has not improved for this number of
+    /// iterations (default: 0, disabled): it has converged. A retired population does not evolve
+    /// any more while the other populations continue, see `SimulationResult::retirements`.
+    /// If all populations have retired, the simulation finishes with `RunStatus::Retired`.
+    pub fn retire_after(mut self, iterations: u32) -> PopulationBuilder<T> {
+        self.population.retire_after = iterations;
+        self
+    }
+
     /// Set the population id. Currently this is only used for statistics.
     pub fn set_id(mut self, id: u32) -> PopulationBuilder<T> {
         for individual in &mut self.population.population {
@@ -156,19 +318,349 @@ impl<T: Individual + Clone + Send + Debug> PopulationBuilder<T> {
         self
     }
 
+    /// Fitness values that differ by at most `tolerance` are treated as equal when the population
+    /// is truncated after each iteration. The individuals that compete for the last places are
+    /// chosen by diversity (see `Individual::distance`), so the population stays spread out on
+    /// fitness plateaus instead of filling up with clones. Default: 0.0 (disabled)
+    pub fn fitness_tolerance(mut self, tolerance: f64) -> PopulationBuilder<T> {
+        self.population.fitness_tolerance = tolerance;
+        self
+    }
+
+    /// Set how the children of the crossover enter the population (default:
+    /// `Replacement::Compete`, they only survive if they are fit enough). With the other
+    /// policies the children always survive the iteration and replace the least fit, random or
+    /// parent individuals, which keeps more genetic material of the crossover in the population.
+    /// Only has an effect together with `crossover`.
+    pub fn replacement(mut self, replacement: Replacement) -> PopulationBuilder<T> {
+        self.population.replacement = replacement;
+        self
+    }
+
+    /// Set the number of iterations over which the improvement rate of this population is
+    /// measured (see `Population::improvement_rate`). Default: 100
+    pub fn improvement_window(mut self, iterations: usize) -> PopulationBuilder<T> {
+        self.population.improvement_rate = ImprovementRate::new(iterations);
+        self
+    }
+
+    /// Set an archive for this population, for example a `HallOfFame` or `MapElites` (see
+    /// module archive). It is offered the survivors of the population after each iteration.
+    /// Access it later with `Population::archive`.
+    pub fn archive<A: Archive<T> + 'static>(mut self, archive: A) -> PopulationBuilder<T> {
+        self.population.archive = Some(Box::new(archive));
+        self
+    }
+
+    /// Set which individuals compete for survival after each iteration (default:
+    /// `SurvivorScheme::Plus`, the parents compete with their offspring). With
+    /// `SurvivorScheme::Comma` the parents always die, see `SurvivorScheme`.
+    pub fn survivor_scheme(mut self, scheme: SurvivorScheme) -> PopulationBuilder<T> {
+        self.population.survivor_scheme = scheme;
+        self
+    }
+
+    /// Set the number of the fittest individuals that always survive the iteration unchanged
+    /// (default: 0). They are never discarded by the truncation (even with `fitness_tolerance`
+    /// or `SurvivorScheme::Comma`), never reset and never replaced by the children of the
+    /// crossover or by shared individuals (see `SimulationBuilder::share_fittest`).
+    /// Must be less than the number of individuals.
+    pub fn elitism(mut self, elitism: u32) -> PopulationBuilder<T> {
+        self.population.elitism = elitism;
+        self
+    }
+
+    /// Set the maximum age of the individuals (default: 0, disabled). At the beginning of each
+    /// iteration all individuals that have survived more than `max_age` iterations are reset
+    /// (see `Individual::reset`) and start anew with age 0, except the elite (see `elitism`).
+    /// Mutated copies inherit the age of their original, so this age-based turnover
+    /// continuously brings new genetic material into the population and fights premature
+    /// convergence, without discarding the whole population like the `reset_limit`.
+    pub fn max_age(mut self, max_age: u32) -> PopulationBuilder<T> {
+        self.population.max_age = max_age;
+        self
+    }
+
+    /// Cache the fitness of up to `capacity` genotypes (default: no cache), so identical
+    /// genotypes are evaluated only once. This pays off if `calculate_fitness` is expensive
+    /// and the same genotypes come up again and again, for example the copies of the fittest
+    /// individual with `SimulationBuilder::share_fittest`. If the cache is full the least
+    /// recently used genotype is dropped.
+    /// The individuals must implement `Individual::genome_key`. Don't use this with noisy
+    /// fitness functions, the first fitness value of a genotype would be kept for good.
+    pub fn fitness_cache(mut self, capacity: usize) -> PopulationBuilder<T> {
+        self.population.fitness_cache = Some(FitnessCache::new(capacity));
+        self
+    }
+
+    /// Set the data shared by all individuals of this population (see `Individual::Context`),
+    /// default: `Default::default()`. `SimulationBuilder::context` sets it for all populations.
+    pub fn context(mut self, context: T::Context) -> PopulationBuilder<T> {
+        self.population.context = Arc::new(context);
+        self
+    }
+
+    /// Remove duplicates from the population after each iteration (default: off). Especially
+    /// with `SimulationBuilder::share_fittest` identical genotypes pile up, which wastes
+    /// evaluations and diversity. Each individual whose genotype is already in the population
+    /// (the individuals must implement `Individual::genome_key`) is mutated until it is a new
+    /// one. The elite (see `elitism`) is kept as is.
+    pub fn deduplicate(mut self) -> PopulationBuilder<T> {
+        self.population.deduplicate = true;
+        self
+    }
+
+    /// Select the parents of this population with the given selector instead of the one given
+    /// to `Simulation::run` (default: none), so the populations of one simulation can use
+    /// different selection pressures. Together with `crossover`, `crossover_rate` and
+    /// `mutation_schedule` one population can for example do heavy crossover while another one
+    /// only mutates.
+    pub fn selector<S>(mut self, selector: S) -> PopulationBuilder<T>
+    where
+        S: Selector<T> + Send + 'static,
+    {
+        self.population.selector = Some(Arc::new(selector));
+        self
+    }
+
+    /// Set the number of mutations of all individuals in each iteration to the (rounded) value
+    /// of the given parameter, which the simulation updates from its schedule at the beginning
+    /// of each iteration (see `SimulationBuilder::parameters`), for example many mutations at
+    /// first and fewer later on. This overrides the other mutation rate settings.
+    pub fn mutation_schedule(mut self, parameter: Parameter) -> PopulationBuilder<T> {
+        self.population.mutation_schedule = Some(parameter);
+        self
+    }
+
+    /// Make this population an explorer (default: off): it never takes in the fittest
+    /// individuals shared by the other populations (see `SimulationBuilder::share_fittest`), so
+    /// it keeps searching its own region of the search space. It still shares its own fittest
+    /// individuals and takes part in the migration (`SimulationBuilder::migration_interval`).
+    pub fn explorer(mut self) -> PopulationBuilder<T> {
+        self.population.explorer = true;
+        self
+    }
+
+    /// Set what the individuals shared by other populations (see
+    /// `SimulationBuilder::share_fittest`) and the immigrants (see
+    /// `SimulationBuilder::migration_interval`) replace in this population. Default: a single
+    /// shared individual replaces the fittest individual or, with elitism, the least fit one,
+    /// several ones replace the least fit individuals. Not used when the migrants are crossed
+    /// with the elite, see `SimulationBuilder::interbreed_migrants`.
+    pub fn acceptance(mut self, acceptance: Acceptance) -> PopulationBuilder<T> {
+        self.population.acceptance = Some(acceptance);
+        self
+    }
+
+    /// Evaluate the new individuals of each iteration all at once with the given evaluator
+    /// (default: none, each one with `Individual::calculate_fitness`, in parallel), for example
+    /// concurrently with an `async_fitness::AsyncEvaluator` (feature `tokio`). A few single
+    /// evaluations (for example of the local search) still use `Individual::calculate_fitness`.
+    pub fn batch_evaluator(
+        mut self,
+        evaluator: Arc<dyn BatchEvaluator<T>>,
+    ) -> PopulationBuilder<T> {
+        self.population.batch_evaluator = Some(evaluator);
+        self
+    }
+
+    /// Give each thread at least this number of individuals at a time when they are evaluated in
+    /// parallel (default: 1, at least 1). With the default the individuals are spread over the
+    /// threads one by one, which balances fitness functions whose cost varies a lot (for
+    /// example the rendering of the OCR example). Cheap fitness functions are faster with
+    /// larger chunks, since each chunk costs a bit of scheduling.
+    pub fn evaluation_chunk(mut self, individuals: usize) -> PopulationBuilder<T> {
+        self.population.evaluation_chunk = individuals;
+        self
+    }
+
+    /// Set what happens when `mutate`, `calculate_fitness`, `reset` or the crossover of an
+    /// individual panics (default: `PanicPolicy::Abort`, the simulation stops after the
+    /// current iteration and returns the panic in its `RunStatus`).
+    /// With `PanicPolicy::WorstFitness` the offending individual just dies.
+    pub fn panic_policy(mut self, policy: PanicPolicy) -> PopulationBuilder<T> {
+        self.population.panic_policy = policy;
+        self
+    }
+
+    /// Select the parents for the crossover by species (like in NEAT) instead of with the
+    /// selector of the simulation: in each iteration the individuals are clustered into
+    /// species by their distance (see `Individual::distance`), an individual joins the first
+    /// species whose fittest member is closer than `threshold`. The `pairs` pairs of parents
+    /// are divided among the species by their shared fitness and chosen by binary tournaments
+    /// within each species, see module species. This keeps several niches alive.
+    /// Needs `crossover`, `threshold` and `pairs` must be larger than zero. The species are
+    /// reported in `IterationStats::species`.
+    pub fn speciation(mut self, threshold: f64, pairs: usize) -> PopulationBuilder<T> {
+        self.population.speciation = Some(Speciation { threshold, pairs });
+        self
+    }
+
+    /// Apply the clearing procedure (a niching method for multimodal problems) before the
+    /// parents for the crossover are selected: within each niche (the individuals closer than
+    /// `radius` to the fittest individual of the niche, see `Individual::distance`) only the
+    /// `capacity` fittest individuals keep their fitness, the others get the worst fitness for
+    /// the selection. So the parents come from many niches instead of only the best one.
+    /// Needs `crossover`, `radius` and `capacity` must be larger than zero.
+    pub fn clearing(mut self, radius: f64, capacity: usize) -> PopulationBuilder<T> {
+        self.population.clearing = Some((radius, capacity));
+        self
+    }
+
+    /// Set how the individuals are ranked when the population is sorted and truncated
+    /// (default: `Ranking::Fitness`). For problems with several competing objectives use
+    /// `Ranking::Pareto` or `Ranking::Nsga2` and implement `Individual::objectives`. The
+    /// non-dominated individuals are collected in `SimulationResult::pareto_front`.
+    pub fn ranking(mut self, ranking: Ranking) -> PopulationBuilder<T> {
+        self.population.ranking = ranking;
+        self
+    }
+
+    /// Add a penalty for the violation of the constraints (see `Constrained::violation`) to the
+    /// fitness of each individual (default: no penalty), weighted by the given scheme, see
+    /// module constraint. With the dynamic and the adaptive scheme the weight changes at the
+    /// beginning of each iteration and the fitness of all individuals is adjusted, the fittest
+    /// individuals recorded by the simulation keep their fitness.
+    pub fn penalty(mut self, penalty: Penalty) -> PopulationBuilder<T>
+    where
+        T: Constrained,
+    {
+        let penalty = Some(penalty);
+        self.population.constraint = Some(Constraint::new(individual::violation::<T>, penalty));
+        self
+    }
+
+    /// Rank the individuals by Deb's feasibility rules (see `Constrained::violation`) when the
+    /// population is sorted and truncated: a feasible individual always beats an infeasible
+    /// one, two feasible ones are compared by fitness and two infeasible ones by their
+    /// violation. No penalty weight needs to be tuned. See `Ranking::Feasibility`.
+    pub fn feasibility_first(mut self) -> PopulationBuilder<T>
+    where
+        T: Constrained,
+    {
+        self.population.ranking = Ranking::Feasibility;
+        self.constrained()
+    }
+
+    /// Rank the individuals by stochastic ranking (see `Constrained::violation`) when the
+    /// population is sorted and truncated: two neighbours are compared by fitness if both are
+    /// feasible or with the given `probability` (usually about 0.45), otherwise by their
+    /// violation. Must be between 0.0 and 1.0. See `Ranking::Stochastic`.
+    pub fn stochastic_ranking(mut self, probability: f64) -> PopulationBuilder<T>
+    where
+        T: Constrained,
+    {
+        self.population.ranking = Ranking::Stochastic(probability);
+        self.constrained()
+    }
+
+    /// Registers the violation of the constraints without a penalty, unless a penalty has
+    /// already been set.
+    fn constrained(mut self) -> PopulationBuilder<T>
+    where
+        T: Constrained,
+    {
+        if self.population.constraint.is_none() {
+            self.population.constraint = Some(Constraint::new(individual::violation::<T>, None));
+        }
+        self
+    }
+
+    /// Set a label for the population, for example "aggressive-mutators". It is shown
+    /// together with the id in all log lines.
+    pub fn label(mut self, label: &str) -> PopulationBuilder<T> {
+        self.population.label = label.to_string();
+        self
+    }
+
     /// This checks the configuration of the simulation and returns an PopError or Ok if no PopErrors
     /// where found.
     pub fn finalize(self) -> Result<Population<T>> {
-        match self.population {
-            Population { num_of_individuals: 0...2, .. } => {
-                Err(ErrorKind::IndividualsTooLow.into())
-            }
-            Population {
-                reset_limit_start: start,
-                reset_limit_end: end,
-                ..
-            } if (end > 0) && (start >= end) => Err(ErrorKind::LimitEndTooLow.into()),
-            _ => Ok(self.population),
+        check(&self.population)?;
+        Ok(self.population)
+    }
+}
+
+/// Checks the configuration of the given population, see `PopulationBuilder::finalize`. Use this
+/// if the population has been changed after it was built.
+pub fn check<T: Individual + Send + Clone + Debug>(population: &Population<T>) -> Result<()> {
+    match *population {
+        Population { num_of_individuals: 0..=2, .. } => {
+            Err(ErrorKind::IndividualsTooLow.into())
         }
+        Population {
+            reset_limit_start: start,
+            reset_limit_end: end,
+            ..
+        } if (end > 0) && (start >= end) => Err(ErrorKind::LimitEndTooLow.into()),
+        Population { crossover_rate, .. } if !(0.0..=1.0).contains(&crossover_rate) => {
+            Err(ErrorKind::CrossoverRateInvalid.into())
+        }
+        Population { elitism, num_of_individuals, .. } if elitism >= num_of_individuals => {
+            Err(ErrorKind::ElitismTooLarge.into())
+        }
+        Population { speciation: Some(speciation), crossover, .. }
+            if crossover.is_none() || speciation.threshold.is_nan() ||
+                speciation.threshold <= 0.0 || speciation.pairs == 0 => {
+            Err(ErrorKind::SpeciationInvalid.into())
+        }
+        Population { clearing: Some((radius, capacity)), crossover, .. }
+            if crossover.is_none() || radius.is_nan() || radius <= 0.0 || capacity == 0 => {
+            Err(ErrorKind::ClearingInvalid.into())
+        }
+        Population { constraint: Some(ref constraint), .. }
+            if constraint.penalty.map_or(false, |penalty| !penalty.is_valid()) => {
+            Err(ErrorKind::PenaltyInvalid.into())
+        }
+        Population { evaluation_chunk: 0, .. } => Err(ErrorKind::EvaluationChunkInvalid.into()),
+        Population { ranking: Ranking::Feasibility, constraint: None, .. } |
+        Population { ranking: Ranking::Stochastic(_), constraint: None, .. } => {
+            Err(ErrorKind::RankingInvalid.into())
+        }
+        Population { ranking: Ranking::Stochastic(probability), .. }
+            if !(0.0..=1.0).contains(&probability) => {
+            Err(ErrorKind::RankingInvalid.into())
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::Rng;
+
+    use super::PopulationBuilder;
+    use population::Population;
+    use test::Test;
+
+    #[test]
+    fn init_seed_is_independent_of_evolution_seed() {
+        let build = |init_seed, evolution_seed| {
+            PopulationBuilder::<Test>::new()
+                .init_seed(init_seed)
+                .evolution_seed(evolution_seed)
+                .generate_initial_population(8, |rng| Test { f: rng.gen_range(0.0..100.0) })
+                .finalize()
+                .unwrap()
+        };
+        let values = |population: &Population<Test>| -> Vec<f64> {
+            population.population.iter().map(|wrapper| wrapper.individual.f).collect()
+        };
+
+        let mut first = build(7, 1);
+        let mut second = build(7, 2);
+        let mut third = build(8, 1);
+
+        // The same init seed gives the same initial population for every evolution seed.
+        assert_eq!(values(&first), values(&second));
+        assert_ne!(values(&first), values(&third));
+
+        // The evolution seed alone decides the random decisions during the simulation.
+        let draw = |population: &mut Population<Test>| -> Vec<u64> {
+            (0..4).map(|_| population.rng.gen()).collect()
+        };
+        let first_draws = draw(&mut first);
+        assert_ne!(first_draws, draw(&mut second));
+        assert_eq!(first_draws, draw(&mut third));
     }
 }
@@ -0,0 +1,18 @@
+//! This module contains ready to use implementations of classic problems. They are useful as
+//! templates for your own individuals and as benchmarks for the library itself.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+
+pub mod sudoku;
+pub mod nqueens;
@@ -0,0 +1,221 @@
+//! This module defines the N queens problem with a permutation encoding and an incremental
+//! fitness calculation.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+
+use std::mem;
+
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use individual::Individual;
+use rng::{self, SimRng};
+
+/// The `Queens` type. Places N queens on an N x N board, so that no queen attacks another one.
+/// The queen in column `c` is placed in row `rows[c]`, and `rows` is always a permutation, so
+/// there is exactly one queen in each row and each column. Only the diagonals can contain
+/// conflicts.
+///
+/// The number of queens on each diagonal is kept up to date, so the mutation (swapping the rows
+/// of two queens) updates the number of conflicts in constant time and `calculate_fitness` just
+/// returns it. This scales to boards with 10_000 queens and more.
+///
+/// The mutation uses the random number generator of the population. Since `reset` doesn't get
+/// one, each board also has its own generator for the reset, seeded from the generator given
+/// to `new`, so a run can be repeated with the same seed.
+#[derive(Debug, Clone)]
+pub struct Queens {
+    /// The row of the queen in each column.
+    rows: Vec<usize>,
+    /// The number of queens on each diagonal (row + column).
+    diagonals: Vec<u32>,
+    /// The number of queens on each anti diagonal (row - column + N - 1).
+    anti_diagonals: Vec<u32>,
+    /// The number of pairs of queens that attack each other.
+    conflicts: u64,
+    /// The random number generator for the reset.
+    rng: SimRng,
+}
+
+impl Queens {
+    /// Creates a new board with `n` queens at random positions.
+    pub fn new<R: Rng>(n: usize, rng: &mut R) -> Queens {
+        let mut queens = Queens {
+            rows: (0..n).collect(),
+            diagonals: vec![0; 2 * n.max(1) - 1],
+            anti_diagonals: vec![0; 2 * n.max(1) - 1],
+            conflicts: 0,
+            rng: rng::from_seed(rng.gen()),
+        };
+        queens.reset();
+        queens
+    }
+
+    /// Returns the row of the queen in each column.
+    pub fn rows(&self) -> &[usize] {
+        &self.rows
+    }
+
+    /// Returns the number of pairs of queens that attack each other. 0 means solved.
+    pub fn conflicts(&self) -> u64 {
+        self.conflicts
+    }
+
+    /// Counts all the conflicts from scratch.
+    fn recount(&mut self) {
+        for count in self.diagonals.iter_mut().chain(self.anti_diagonals.iter_mut()) {
+            *count = 0;
+        }
+        self.conflicts = 0;
+
+        for col in 0..self.rows.len() {
+            self.place(col);
+        }
+    }
+
+    /// The indices of the two diagonals of the queen in the given column.
+    fn diagonals_of(&self, col: usize) -> (usize, usize) {
+        let row = self.rows[col];
+        (row + col, row + self.rows.len() - 1 - col)
+    }
+
+    /// Adds the queen in the given column to its diagonals.
+    fn place(&mut self, col: usize) {
+        let (d, a) = self.diagonals_of(col);
+        self.conflicts += u64::from(self.diagonals[d] + self.anti_diagonals[a]);
+        self.diagonals[d] += 1;
+        self.anti_diagonals[a] += 1;
+    }
+
+    /// Removes the queen in the given column from its diagonals.
+    fn lift(&mut self, col: usize) {
+        let (d, a) = self.diagonals_of(col);
+        self.diagonals[d] -= 1;
+        self.anti_diagonals[a] -= 1;
+        self.conflicts -= u64::from(self.diagonals[d] + self.anti_diagonals[a]);
+    }
+
+    /// Swaps the rows of the queens in the two columns and updates the conflicts.
+    fn swap(&mut self, first: usize, second: usize) {
+        self.lift(first);
+        self.lift(second);
+        self.rows.swap(first, second);
+        self.place(first);
+        self.place(second);
+    }
+}
+
+impl Individual for Queens {
+    type Fitness = f64;
+    type Context = ();
+
+    /// Swaps the rows of two random queens.
+    fn mutate(&mut self, _context: &(), rng: &mut SimRng) {
+        if self.rows.len() < 2 {
+            return;
+        }
+
+        let first = rng.gen_range(0..self.rows.len());
+        let second = rng.gen_range(0..self.rows.len());
+        if first != second {
+            self.swap(first, second);
+        }
+    }
+
+    /// The number of pairs of queens that attack each other, updated by each mutation.
+    fn calculate_fitness(&mut self, _context: &()) -> f64 {
+        self.conflicts as f64
+    }
+
+    fn reset(&mut self) {
+        self.rows.shuffle(&mut self.rng);
+        self.recount();
+    }
+
+    /// The number of queens that are placed in different rows.
+    fn distance(&self, other: &Queens) -> f64 {
+        self.rows.iter().zip(other.rows.iter()).filter(|&(a, b)| a != b).count() as f64
+    }
+
+    fn size_hint(&self) -> usize {
+        mem::size_of::<Queens>() + self.rows.capacity() * mem::size_of::<usize>() +
+            (self.diagonals.capacity() + self.anti_diagonals.capacity()) * mem::size_of::<u32>()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use individual::Individual;
+    use rng;
+    use super::Queens;
+
+    #[test]
+    fn incremental_conflicts_match_recount() {
+        let mut rng = rng::from_seed(1);
+        let mut queens = Queens::new(200, &mut rng);
+        for _ in 0..10_000 {
+            queens.mutate(&(), &mut rng);
+        }
+
+        let incremental = queens.conflicts();
+        queens.recount();
+        assert_eq!(incremental, queens.conflicts());
+        assert_eq!(queens.calculate_fitness(&()), incremental as f64);
+    }
+
+    #[test]
+    fn known_solution_has_no_conflicts() {
+        let mut queens = Queens::new(8, &mut rng::from_seed(1));
+        queens.rows = vec![0, 4, 7, 5, 2, 6, 1, 3];
+        queens.recount();
+        assert_eq!(queens.conflicts(), 0);
+
+        // All queens on one diagonal: every pair attacks each other.
+        queens.rows = (0..8).collect();
+        queens.recount();
+        assert_eq!(queens.conflicts(), 28);
+    }
+
+    #[test]
+    fn same_seed_same_board() {
+        let run = |seed| {
+            let mut rng = rng::from_seed(seed);
+            let mut queens = Queens::new(50, &mut rng);
+            for _ in 0..100 {
+                queens.mutate(&(), &mut rng);
+            }
+            queens.reset();
+            queens.mutate(&(), &mut rng);
+            queens.rows().to_vec()
+        };
+        assert_eq!(run(1), run(1));
+        assert_ne!(run(1), run(2));
+    }
+
+    #[test]
+    fn large_board() {
+        let mut rng = rng::from_seed(2);
+        let mut queens = Queens::new(10_000, &mut rng);
+        let mut sorted = queens.rows().to_vec();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..10_000).collect::<Vec<usize>>());
+
+        for _ in 0..100_000 {
+            queens.mutate(&(), &mut rng);
+        }
+        let incremental = queens.conflicts();
+        queens.recount();
+        assert_eq!(incremental, queens.conflicts());
+    }
+}
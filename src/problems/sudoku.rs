@@ -0,0 +1,307 @@
+//! This module defines a sudoku solver with a row permutation encoding.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+
+use std::mem;
+use std::sync::Arc;
+
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use individual::{Crossover, Individual};
+use rng::{self, SimRng};
+
+/// The `Sudoku` type. Each row of the grid is a permutation of the digits 1 - 9 that keeps the
+/// given digits in place, so the rows are always valid and only the columns and the 3x3 boxes
+/// can contain errors. The mutation swaps two free cells in one row, which keeps this property.
+/// This converges much faster than assigning random digits to the free cells.
+///
+/// Use `random` to create the initial population:
+/// `PopulationBuilder::new().generate_initial_population(100, |rng| sudoku.random(rng))`.
+/// The crossover takes whole rows from the parents, enable it with `PopulationBuilder::crossover`.
+/// Grids that have been changed by other means are made valid again by `Individual::repair`.
+///
+/// The mutation and the crossover use the random number generator of the population. Since
+/// `reset` and `repair` don't get one, each individual also has its own generator, seeded from
+/// the generator given to `new` or `random`, so a run can be repeated with the same seeds.
+#[derive(Debug, Clone)]
+pub struct Sudoku {
+    /// The current grid, row by row.
+    grid: Vec<u8>,
+    /// The puzzle: the given digits, 0 for a free cell.
+    givens: Arc<Vec<u8>>,
+    /// The columns of the free cells for each row.
+    free: Arc<Vec<Vec<usize>>>,
+    /// The random number generator for the reset and the repair.
+    rng: SimRng,
+}
+
+impl Sudoku {
+    /// Creates a new individual for the given puzzle (81 cells row by row, 0 for a free cell).
+    /// Returns `None` if the puzzle has the wrong size, invalid digits or duplicate digits in
+    /// one row.
+    pub fn new<R: Rng>(puzzle: &[u8], rng: &mut R) -> Option<Sudoku> {
+        if puzzle.len() != 81 || puzzle.iter().any(|&digit| digit > 9) {
+            return None;
+        }
+
+        let mut free = Vec::with_capacity(9);
+        for row in puzzle.chunks(9) {
+            let mut given: Vec<u8> = row.iter().cloned().filter(|&digit| digit > 0).collect();
+            let count = given.len();
+            given.sort_unstable();
+            given.dedup();
+            if given.len() != count {
+                return None;
+            }
+            free.push((0..9).filter(|&col| row[col] == 0).collect());
+        }
+
+        let template = Sudoku {
+            grid: puzzle.to_vec(),
+            givens: Arc::new(puzzle.to_vec()),
+            free: Arc::new(free),
+            rng: rng::from_seed(0),
+        };
+
+        Some(template.random(rng))
+    }
+
+    /// Creates a new individual for the same puzzle with randomly filled rows.
+    pub fn random<R: Rng>(&self, rng: &mut R) -> Sudoku {
+        let mut sudoku = self.clone();
+        sudoku.rng = rng::from_seed(rng.gen());
+        sudoku.reset();
+        sudoku
+    }
+
+    /// Returns the current grid, row by row.
+    pub fn grid(&self) -> &[u8] {
+        &self.grid
+    }
+
+    /// Fills the free cells of the given row with a random permutation of the missing digits.
+    fn shuffle_row(&mut self, row: usize) {
+        let given = &self.givens[row * 9..(row + 1) * 9];
+        let mut missing: Vec<u8> = (1..10).filter(|digit| !given.contains(digit)).collect();
+        missing.shuffle(&mut self.rng);
+
+        for (&col, digit) in self.free[row].iter().zip(missing) {
+            self.grid[row * 9 + col] = digit;
+        }
+    }
+
+    /// Counts the missing digits in the nine cells given by `index`.
+    fn errors<F: Fn(usize) -> usize>(&self, index: F) -> f64 {
+        let mut seen = [false; 10];
+        for i in 0..9 {
+            seen[self.grid[index(i)] as usize] = true;
+        }
+        seen[1..].iter().filter(|&&found| !found).count() as f64
+    }
+}
+
+impl Individual for Sudoku {
+    type Fitness = f64;
+    type Context = ();
+
+    /// Swaps two free cells in a random row.
+    fn mutate(&mut self, _context: &(), rng: &mut SimRng) {
+        let rows: Vec<usize> = (0..9).filter(|&row| self.free[row].len() > 1).collect();
+
+        if let Some(&row) = rows.choose(rng) {
+            let mut cols = self.free[row].choose_multiple(rng, 2);
+            let (a, b) = (*cols.next().unwrap(), *cols.next().unwrap());
+            self.grid.swap(row * 9 + a, row * 9 + b);
+        }
+    }
+
+    /// The number of missing digits in all the columns and boxes. 0 means solved.
+    fn calculate_fitness(&mut self, _context: &()) -> f64 {
+        let mut result = 0.0;
+
+        for n in 0..9 {
+            result += self.errors(|i| i * 9 + n);
+            let (box_row, box_col) = ((n / 3) * 3, (n % 3) * 3);
+            result += self.errors(|i| (box_row + i / 3) * 9 + box_col + i % 3);
+        }
+
+        result
+    }
+
+    fn reset(&mut self) {
+        for row in 0..9 {
+            self.shuffle_row(row);
+        }
+    }
+
+    /// The puzzle and the free cells are shared between all individuals.
+    fn size_hint(&self) -> usize {
+        mem::size_of::<Sudoku>() + self.grid.capacity()
+    }
+
+    /// Restores the given digits and turns each row into a permutation again: free cells with
+    /// a digit that is given or already used in the same row get one of the missing digits.
+    /// Valid grids are not changed.
+    fn repair(&mut self) {
+        for row in 0..9 {
+            let mut used = [false; 10];
+            for col in 0..9 {
+                let given = self.givens[row * 9 + col];
+                if given > 0 {
+                    self.grid[row * 9 + col] = given;
+                    used[given as usize] = true;
+                }
+            }
+
+            let mut conflicts = Vec::new();
+            for &col in &self.free[row] {
+                let digit = self.grid[row * 9 + col] as usize;
+                if digit == 0 || digit > 9 || used[digit] {
+                    conflicts.push(col);
+                } else {
+                    used[digit] = true;
+                }
+            }
+
+            let missing = (1..10).filter(|&digit| !used[digit as usize]);
+            for (col, digit) in conflicts.into_iter().zip(missing) {
+                self.grid[row * 9 + col] = digit;
+            }
+        }
+    }
+
+    /// The number of cells that differ.
+    fn distance(&self, other: &Sudoku) -> f64 {
+        self.grid.iter().zip(other.grid.iter()).filter(|&(a, b)| a != b).count() as f64
+    }
+}
+
+impl Crossover for Sudoku {
+    /// Takes each row from one of the two parents, so all rows stay valid.
+    fn crossover(&mut self, other: &mut Sudoku, rng: &mut SimRng) -> Sudoku {
+        let mut child = self.clone();
+        child.rng = rng::from_seed(rng.gen());
+
+        for row in 0..9 {
+            if rng.gen::<bool>() {
+                let cells = row * 9..(row + 1) * 9;
+                child.grid[cells.clone()].copy_from_slice(&other.grid[cells]);
+            }
+        }
+
+        child
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use individual::{Crossover, Individual};
+    use rng;
+    use super::Sudoku;
+
+    const SOLVED: [u8; 81] = [
+        5, 3, 4, 6, 7, 8, 9, 1, 2,
+        6, 7, 2, 1, 9, 5, 3, 4, 8,
+        1, 9, 8, 3, 4, 2, 5, 6, 7,
+        8, 5, 9, 7, 6, 1, 4, 2, 3,
+        4, 2, 6, 8, 5, 3, 7, 9, 1,
+        7, 1, 3, 9, 2, 4, 8, 5, 6,
+        9, 6, 1, 5, 3, 7, 2, 8, 4,
+        2, 8, 7, 4, 1, 9, 6, 3, 5,
+        3, 4, 5, 2, 8, 6, 1, 7, 9,
+    ];
+
+    fn is_row_permutation(sudoku: &Sudoku) -> bool {
+        sudoku.grid().chunks(9).all(|row| {
+            let mut digits = row.to_vec();
+            digits.sort_unstable();
+            digits == (1..10).collect::<Vec<u8>>()
+        })
+    }
+
+    #[test]
+    fn solved_grid_has_no_errors() {
+        let mut sudoku = Sudoku::new(&SOLVED, &mut rng::from_seed(1)).unwrap();
+        assert_eq!(sudoku.calculate_fitness(&()), 0.0);
+    }
+
+    #[test]
+    fn rows_stay_permutations() {
+        let mut puzzle = SOLVED;
+        for cell in puzzle.iter_mut().skip(54) {
+            *cell = 0;
+        }
+        let mut rng = rng::from_seed(1);
+        let mut sudoku = Sudoku::new(&puzzle, &mut rng).unwrap();
+        let mut other = sudoku.random(&mut rng);
+        assert!(is_row_permutation(&sudoku));
+
+        for _ in 0..100 {
+            sudoku.mutate(&(), &mut rng);
+        }
+        assert!(is_row_permutation(&sudoku));
+        assert_eq!(&sudoku.grid()[..54], &SOLVED[..54]);
+        assert!(is_row_permutation(&sudoku.crossover(&mut other, &mut rng)));
+    }
+
+    #[test]
+    fn same_seed_same_grids() {
+        let mut puzzle = SOLVED;
+        for cell in puzzle.iter_mut().skip(27) {
+            *cell = 0;
+        }
+        let run = |seed| {
+            let mut rng = rng::from_seed(seed);
+            let mut sudoku = Sudoku::new(&puzzle, &mut rng).unwrap();
+            let mut other = sudoku.random(&mut rng);
+            for _ in 0..50 {
+                sudoku.mutate(&(), &mut rng);
+                other.reset();
+            }
+            sudoku.crossover(&mut other, &mut rng).grid().to_vec()
+        };
+        assert_eq!(run(1), run(1));
+        assert_ne!(run(1), run(3));
+    }
+
+    #[test]
+    fn repair_restores_permutations() {
+        let mut puzzle = SOLVED;
+        for cell in puzzle.iter_mut().skip(54) {
+            *cell = 0;
+        }
+        let mut sudoku = Sudoku::new(&puzzle, &mut rng::from_seed(1)).unwrap();
+        let valid = sudoku.grid().to_vec();
+        sudoku.repair();
+        assert_eq!(sudoku.grid(), &valid[..]);
+
+        // Overwrite a given digit and duplicate a digit in a free row.
+        sudoku.grid[0] = 9;
+        sudoku.grid[60] = sudoku.grid[61];
+        sudoku.grid[80] = 0;
+        sudoku.repair();
+        assert_eq!(sudoku.grid()[0], 5);
+        assert!(is_row_permutation(&sudoku));
+        assert_eq!(&sudoku.grid()[..54], &SOLVED[..54]);
+    }
+
+    #[test]
+    fn invalid_puzzle() {
+        let mut puzzle = SOLVED;
+        puzzle[1] = 5;
+        assert!(Sudoku::new(&puzzle, &mut rng::from_seed(1)).is_none());
+        assert!(Sudoku::new(&SOLVED[1..], &mut rng::from_seed(1)).is_none());
+    }
+}
@@ -0,0 +1,67 @@
+//! This module defines the provenance information of a simulation run: which software
+//! on which machine produced the results.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+
+use std::env::consts;
+use std::fmt;
+use std::thread;
+
+/// The `Provenance` type. It is filled in by `SimulationBuilder::finalize` and written at the
+/// start of each run, so archived results can be traced back to the software that produced them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    /// The version of darwin-rs.
+    pub crate_version: String,
+    /// The revision of the user code (for example a git commit hash), if given with
+    /// `SimulationBuilder::code_revision`.
+    pub code_revision: Option<String>,
+    /// The number of threads used by the simulation.
+    pub num_of_threads: usize,
+    /// The number of CPUs available on the machine (0 if unknown).
+    pub available_cpus: usize,
+    /// The operating system, for example "linux".
+    pub os: String,
+    /// The CPU architecture, for example "x86_64".
+    pub arch: String,
+}
+
+impl Provenance {
+    /// Collects the provenance information for the current machine.
+    pub fn collect(code_revision: Option<String>, num_of_threads: usize) -> Provenance {
+        Provenance {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            code_revision,
+            num_of_threads,
+            available_cpus: thread::available_parallelism().map_or(0, |cpus| cpus.get()),
+            os: consts::OS.to_string(),
+            arch: consts::ARCH.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Provenance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "darwin-rs {}, code revision: {}, threads: {}, cpus: {}, os: {}, arch: {}",
+            self.crate_version,
+            self.code_revision.as_ref().map_or("unknown", |revision| revision.as_str()),
+            self.num_of_threads,
+            self.available_cpus,
+            self.os,
+            self.arch
+        )
+    }
+}
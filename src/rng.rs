@@ -0,0 +1,129 @@
+//! This module defines the random number generator used by the library.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+/// The random number generator used for all random decisions made by the library.
+/// It is seedable, so a simulation can be repeated with exactly the same random sequence.
+pub type SimRng = ChaCha8Rng;
+
+/// Creates a new random number generator from the given seed.
+/// The same seed always produces the same random sequence.
+pub fn from_seed(seed: u64) -> SimRng {
+    SimRng::seed_from_u64(seed)
+}
+
+/// Creates the random number generator of population n for the given global seed (see
+/// `SimulationBuilder::seed`): the generator of the seed with the stream n, so the populations
+/// draw independent random sequences.
+pub fn for_population(seed: u64, n: u64) -> SimRng {
+    let mut rng = from_seed(seed);
+    rng.set_stream(n);
+    rng
+}
+
+/// Creates a new random number generator seeded from the operating system.
+/// Use this if you don't care about reproducibility.
+pub fn from_entropy() -> SimRng {
+    SimRng::from_entropy()
+}
+
+/// The complete state of a random number generator: the seed and the position in the random
+/// stream. Store it together with a checkpoint, so a resumed simulation continues with exactly
+/// the same random sequence as the original one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct RngState {
+    /// The seed of the generator.
+    pub seed: [u8; 32],
+    /// The stream number of the generator.
+    pub stream: u64,
+    /// The position (in 32 bit words) inside the stream.
+    pub word_pos: u128,
+}
+
+impl RngState {
+    /// The size of the state in bytes, see `to_bytes`.
+    pub const SIZE: usize = 56;
+
+    /// Captures the current state of the given random number generator.
+    pub fn of(rng: &SimRng) -> RngState {
+        RngState {
+            seed: rng.get_seed(),
+            stream: rng.get_stream(),
+            word_pos: rng.get_word_pos(),
+        }
+    }
+
+    /// Creates a random number generator that continues exactly where the captured one was.
+    pub fn to_rng(&self) -> SimRng {
+        let mut rng = SimRng::from_seed(self.seed);
+        rng.set_stream(self.stream);
+        rng.set_word_pos(self.word_pos);
+        rng
+    }
+
+    /// Returns the state as bytes (little endian): seed, stream and word position.
+    pub fn to_bytes(&self) -> [u8; RngState::SIZE] {
+        let mut bytes = [0; RngState::SIZE];
+        bytes[..32].copy_from_slice(&self.seed);
+        bytes[32..40].copy_from_slice(&self.stream.to_le_bytes());
+        bytes[40..].copy_from_slice(&self.word_pos.to_le_bytes());
+        bytes
+    }
+
+    /// Reads the state from bytes written by `to_bytes`. Returns `None` if the length is wrong.
+    pub fn from_bytes(bytes: &[u8]) -> Option<RngState> {
+        if bytes.len() != RngState::SIZE {
+            return None;
+        }
+
+        let mut seed = [0; 32];
+        seed.copy_from_slice(&bytes[..32]);
+        let mut stream = [0; 8];
+        stream.copy_from_slice(&bytes[32..40]);
+        let mut word_pos = [0; 16];
+        word_pos.copy_from_slice(&bytes[40..]);
+
+        Some(RngState {
+            seed,
+            stream: u64::from_le_bytes(stream),
+            word_pos: u128::from_le_bytes(word_pos),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::Rng;
+    use super::{from_seed, RngState};
+
+    #[test]
+    fn restored_rng_continues_sequence() {
+        let mut original = from_seed(42);
+        for _ in 0..17 {
+            original.gen::<u32>();
+        }
+
+        let bytes = RngState::of(&original).to_bytes();
+        let mut restored = RngState::from_bytes(&bytes).unwrap().to_rng();
+
+        let expected: Vec<u64> = (0..10).map(|_| original.gen()).collect();
+        let actual: Vec<u64> = (0..10).map(|_| restored.gen()).collect();
+        assert_eq!(expected, actual);
+        assert!(RngState::from_bytes(&bytes[1..]).is_none());
+    }
+}
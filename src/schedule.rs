@@ -0,0 +1,227 @@
+//! This module defines schedules for operator parameters and a registry that updates them
+//! while the simulation is running.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The `Schedule` type. Describes how the value of a parameter changes over the iterations,
+/// for example to let the step size of a mutation shrink as the simulation converges.
+#[derive(Clone)]
+pub enum Schedule {
+    /// The value never changes.
+    Constant(f64),
+    /// The value changes linearly from `start` to `end` during the first `iterations`
+    /// iterations and stays at `end` afterwards.
+    Linear {
+        /// The value at iteration 0.
+        start: f64,
+        /// The value after `iterations` iterations.
+        end: f64,
+        /// The number of iterations to go from `start` to `end`.
+        iterations: u32,
+    },
+    /// The value is `start * factor^iteration`, but never goes below `min`.
+    Exponential {
+        /// The value at iteration 0.
+        start: f64,
+        /// The factor that is applied each iteration, usually a bit less than 1.0.
+        factor: f64,
+        /// The lower bound for the value.
+        min: f64,
+    },
+    /// The value is calculated by a user defined function of the iteration.
+    Custom(Arc<dyn Fn(u32) -> f64 + Send + Sync>),
+}
+
+impl fmt::Debug for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Schedule::Constant(value) => write!(f, "Constant({})", value),
+            Schedule::Linear { start, end, iterations } => {
+                write!(f, "Linear {{ start: {}, end: {}, iterations: {} }}", start, end, iterations)
+            }
+            Schedule::Exponential { start, factor, min } => {
+                write!(f, "Exponential {{ start: {}, factor: {}, min: {} }}", start, factor, min)
+            }
+            Schedule::Custom(_) => write!(f, "Custom(..)"),
+        }
+    }
+}
+
+impl Schedule {
+    /// Returns the value of the schedule for the given iteration.
+    pub fn value(&self, iteration: u32) -> f64 {
+        match *self {
+            Schedule::Constant(value) => value,
+            Schedule::Linear { start, end, iterations } => {
+                if iteration >= iterations {
+                    end
+                } else {
+                    start + (end - start) * (iteration as f64 / iterations as f64)
+                }
+            }
+            Schedule::Exponential { start, factor, min } => {
+                (start * factor.powf(iteration as f64)).max(min)
+            }
+            Schedule::Custom(ref function) => function(iteration),
+        }
+    }
+}
+
+/// The `Parameter` type. A handle to the current value of a scheduled parameter.
+/// It is cheap to clone and can be shared with individuals and operators in different threads.
+/// Reading the value does not need any lock.
+#[derive(Debug, Clone)]
+pub struct Parameter {
+    value: Arc<AtomicU64>,
+}
+
+impl Parameter {
+    /// Creates a new parameter with the given value.
+    pub fn new(value: f64) -> Parameter {
+        Parameter { value: Arc::new(AtomicU64::new(value.to_bits())) }
+    }
+
+    /// Returns the current value of the parameter.
+    pub fn get(&self) -> f64 {
+        f64::from_bits(self.value.load(Ordering::Relaxed))
+    }
+
+    /// Sets a new value for the parameter.
+    pub fn set(&self, value: f64) {
+        self.value.store(value.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// The `ParameterRegistry` type. Holds all the scheduled operator parameters of a simulation.
+/// Register it with `SimulationBuilder::parameters`, then the simulation updates all the
+/// parameters at the beginning of each iteration.
+#[derive(Debug, Clone, Default)]
+pub struct ParameterRegistry {
+    entries: Arc<Mutex<Vec<(String, Schedule, Parameter)>>>,
+}
+
+impl ParameterRegistry {
+    /// Creates a new empty registry.
+    pub fn new() -> ParameterRegistry {
+        ParameterRegistry::default()
+    }
+
+    /// Registers a new parameter with the given name and schedule and returns the handle to it.
+    /// The parameter starts with the value of the schedule at iteration 0.
+    pub fn register(&self, name: &str, schedule: Schedule) -> Parameter {
+        let parameter = Parameter::new(schedule.value(0));
+        self.entries.lock().expect("parameter registry poisoned").push((
+            name.to_string(),
+            schedule,
+            parameter.clone(),
+        ));
+        parameter
+    }
+
+    /// Returns the handle to the parameter with the given name, if it is registered.
+    pub fn get(&self, name: &str) -> Option<Parameter> {
+        self.entries
+            .lock()
+            .expect("parameter registry poisoned")
+            .iter()
+            .find(|entry| entry.0 == name)
+            .map(|entry| entry.2.clone())
+    }
+
+    /// Returns a copy of this registry with new parameters that start with the current values.
+    /// Changes to the copy do not affect this registry and vice versa. Handles to the old
+    /// parameters still point to this registry, get the new ones with `get`.
+    pub fn detached(&self) -> ParameterRegistry {
+        let entries = self.entries
+            .lock()
+            .expect("parameter registry poisoned")
+            .iter()
+            .map(|(name, schedule, parameter)| {
+                (name.clone(), schedule.clone(), Parameter::new(parameter.get()))
+            })
+            .collect();
+        ParameterRegistry { entries: Arc::new(Mutex::new(entries)) }
+    }
+
+    /// Returns the name and the current value of all parameters, in the order of registration.
+    pub fn values(&self) -> Vec<(String, f64)> {
+        self.entries
+            .lock()
+            .expect("parameter registry poisoned")
+            .iter()
+            .map(|(name, _, parameter)| (name.clone(), parameter.get()))
+            .collect()
+    }
+
+    /// Sets all the parameters to the value of their schedule for the given iteration.
+    pub fn update(&self, iteration: u32) {
+        for (_, schedule, parameter) in
+            self.entries.lock().expect("parameter registry poisoned").iter()
+        {
+            parameter.set(schedule.value(iteration));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use super::{Schedule, ParameterRegistry};
+
+    #[test]
+    fn linear_schedule() {
+        let schedule = Schedule::Linear { start: 1.0, end: 0.0, iterations: 4 };
+        assert_eq!(schedule.value(0), 1.0);
+        assert_eq!(schedule.value(2), 0.5);
+        assert_eq!(schedule.value(10), 0.0);
+    }
+
+    #[test]
+    fn exponential_schedule() {
+        let schedule = Schedule::Exponential { start: 8.0, factor: 0.5, min: 1.5 };
+        assert_eq!(schedule.value(1), 4.0);
+        assert_eq!(schedule.value(2), 2.0);
+        assert_eq!(schedule.value(3), 1.5);
+    }
+
+    #[test]
+    fn registry_updates_parameters() {
+        let registry = ParameterRegistry::new();
+        let sigma = registry.register("sigma", Schedule::Custom(Arc::new(|i| i as f64 * 2.0)));
+        assert_eq!(sigma.get(), 0.0);
+
+        registry.update(3);
+        assert_eq!(sigma.get(), 6.0);
+        assert_eq!(registry.get("sigma").unwrap().get(), 6.0);
+        assert!(registry.get("step").is_none());
+    }
+
+    #[test]
+    fn detached_registry_is_independent() {
+        let registry = ParameterRegistry::new();
+        let sigma = registry.register("sigma", Schedule::Linear { start: 1.0, end: 0.0, iterations: 4 });
+        registry.update(2);
+
+        let copy = registry.detached();
+        assert_eq!(copy.get("sigma").unwrap().get(), 0.5);
+
+        copy.update(4);
+        assert_eq!(copy.get("sigma").unwrap().get(), 0.0);
+        assert_eq!(sigma.get(), 0.5);
+    }
+}
@@ -0,0 +1,163 @@
+// file: combinator.rs
+//
+// Copyright 2015-2017 The RsGenetic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rand::Rng;
+
+use individual::{Individual, IndividualWrapper};
+use rng::SimRng;
+use super::*;
+
+/// Combines the parents of two selectors.
+#[derive(Clone, Debug)]
+pub struct ChainSelector<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A, B> ChainSelector<A, B> {
+    /// Create and return a chain selector.
+    ///
+    /// Such a selector returns all the parents of the `first` selector followed by all the
+    /// parents of the `second` selector. For example `MinimizeSelector::new(2)` followed by
+    /// `TournamentSelector::new(18, 5)` always breeds the two elites and selects the other
+    /// parents by tournament. Chain more selectors with `then`.
+    pub fn new(first: A, second: B) -> ChainSelector<A, B> {
+        ChainSelector { first, second }
+    }
+
+    /// Appends another selector to this chain.
+    pub fn then<C>(self, next: C) -> ChainSelector<ChainSelector<A, B>, C> {
+        ChainSelector::new(self, next)
+    }
+}
+
+impl<I, A, B> Selector<I> for ChainSelector<A, B>
+where
+    I: Individual + Clone + Send,
+    A: Selector<I>,
+    B: Selector<I>,
+{
+    fn select(
+        &self,
+        population: &[IndividualWrapper<I>],
+        rng: &mut SimRng,
+    ) -> Result<Parents<I>, SelectorError> {
+        let mut result = self.first.select(population, rng)?;
+        result.extend(self.second.select(population, rng)?);
+        Ok(result)
+    }
+}
+
+/// Randomly mixes the parents of two selectors.
+#[derive(Clone, Debug)]
+pub struct MixSelector<A, B> {
+    first: A,
+    second: B,
+    probability: f64,
+}
+
+impl<A, B> MixSelector<A, B> {
+    /// Create and return a mix selector.
+    ///
+    /// Such a selector runs both selectors and takes each pair of parents from the `first`
+    /// selector with the given `probability` and from the `second` selector otherwise.
+    /// For example this can mix a greedy selector with a random one.
+    /// Both selectors should select the same number of parents, the result has the length of
+    /// the shorter one.
+    ///
+    /// * `probability`: must be between zero and one.
+    pub fn new(first: A, second: B, probability: f64) -> MixSelector<A, B> {
+        MixSelector { first, second, probability }
+    }
+}
+
+impl<I, A, B> Selector<I> for MixSelector<A, B>
+where
+    I: Individual + Clone + Send,
+    A: Selector<I>,
+    B: Selector<I>,
+{
+    fn select(
+        &self,
+        population: &[IndividualWrapper<I>],
+        rng: &mut SimRng,
+    ) -> Result<Parents<I>, SelectorError> {
+        if !(0.0..=1.0).contains(&self.probability) {
+            return Err(SelectorError::InvalidParameter(format!(
+                "`probability`: {}, should be between zero and one",
+                self.probability
+            )))
+        }
+
+        let first = self.first.select(population, rng)?;
+        let second = self.second.select(population, rng)?;
+
+        Ok(first
+            .into_iter()
+            .zip(second)
+            .map(|(a, b)| if rng.gen_bool(self.probability) { a } else { b })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use select::*;
+    use test::{Test, wrap};
+    use rng;
+
+    #[test]
+    fn test_chain() {
+        let selector = ChainSelector::new(MinimizeSelector::new(2), MaximizeSelector::new(2))
+            .then(MinimizeSelector::new(4));
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        let parents = selector.select(&wrap(&population), &mut rng::from_seed(1)).unwrap();
+        let first: Vec<f64> = parents.iter().map(|p| p.0.f).collect();
+        assert_eq!(first, vec![0.0, 99.0, 0.0, 2.0]);
+    }
+
+    #[test]
+    fn test_chain_error() {
+        let selector = ChainSelector::new(MinimizeSelector::new(2), MinimizeSelector::new(3));
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        assert_eq!(
+            selector.select(&wrap(&population), &mut rng::from_seed(1)).unwrap_err(),
+            SelectorError::InvalidCount(3)
+        );
+    }
+
+    #[test]
+    fn test_mix() {
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        let only_first =
+            MixSelector::new(MinimizeSelector::new(10), MaximizeSelector::new(10), 1.0);
+        let parents = only_first.select(&wrap(&population), &mut rng::from_seed(1)).unwrap();
+        assert_eq!(5, parents.len());
+        assert!(parents.iter().all(|p| p.0.f < 10.0));
+
+        let mixed = MixSelector::new(MinimizeSelector::new(40), MaximizeSelector::new(40), 0.5);
+        let parents = mixed.select(&wrap(&population), &mut rng::from_seed(1)).unwrap();
+        assert!(parents.iter().any(|p| p.0.f < 50.0));
+        assert!(parents.iter().any(|p| p.0.f >= 50.0));
+    }
+
+    #[test]
+    fn test_mix_invalid_probability() {
+        let selector = MixSelector::new(MinimizeSelector::new(2), MaximizeSelector::new(2), 1.5);
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        assert!(selector.select(&wrap(&population), &mut rng::from_seed(1)).is_err());
+    }
+}
@@ -0,0 +1,236 @@
+// file: lexicase.rs
+//
+// Copyright 2015-2017 The RsGenetic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use individual::{Individual, IndividualWrapper};
+use rng::SimRng;
+use super::*;
+
+/// Selects phenotypes by filtering the population on randomly ordered test cases.
+#[derive(Clone, Copy, Debug)]
+pub struct LexicaseSelector {
+    count: usize,
+    replacement: bool,
+}
+
+impl LexicaseSelector {
+    /// Create and return a lexicase selector.
+    ///
+    /// For each parent the test cases (see `Individual::case_errors`) are shuffled. Starting with
+    /// the whole population, only the phenotypes with the lowest error on the first case are kept,
+    /// then the lowest error on the second case and so on, until one phenotype is left or all cases
+    /// are used. The parent is chosen randomly from the remaining phenotypes.
+    /// The case errors are always minimized, independent of any `Direction`. A NaN error counts
+    /// as the worst error, phenotypes without case errors are the worst in every case.
+    ///
+    /// * `count`: must be larger than zero, a multiple of two and less than the population size.
+    pub fn new(count: usize) -> LexicaseSelector {
+        LexicaseSelector {
+            count,
+            replacement: true,
+        }
+    }
+
+    /// Select each phenotype at most once (default: a phenotype may be selected several times,
+    /// even as both parents of a pair). With small populations this keeps a few phenotypes from
+    /// dominating the parents.
+    pub fn without_replacement(mut self) -> LexicaseSelector {
+        self.replacement = false;
+        self
+    }
+}
+
+/// Returns the error of the phenotype on the given case. Missing errors (for example after a
+/// caught panic) and NaN count as the worst possible error.
+fn case_error<I: Individual>(wrapper: &IndividualWrapper<I>, case: usize) -> f64 {
+    match wrapper.case_errors.get(case) {
+        Some(&error) if !error.is_nan() => error,
+        _ => f64::INFINITY,
+    }
+}
+
+/// Runs one lexicase selection among the `candidates` and returns the index of the selected
+/// phenotype.
+fn select_one<I: Individual>(
+    population: &[IndividualWrapper<I>],
+    num_of_cases: usize,
+    mut candidates: Vec<usize>,
+    rng: &mut SimRng,
+) -> usize {
+    let mut cases: Vec<usize> = (0..num_of_cases).collect();
+    cases.shuffle(rng);
+
+    for case in cases {
+        if candidates.len() == 1 {
+            break;
+        }
+        let best = candidates
+            .iter()
+            .map(|&candidate| case_error(&population[candidate], case))
+            .fold(f64::INFINITY, f64::min);
+        // The candidates with the best error always remain, even if it is infinite.
+        candidates.retain(|&candidate| case_error(&population[candidate], case) <= best);
+    }
+
+    candidates[rng.gen_range(0..candidates.len())]
+}
+
+impl<I> Selector<I> for LexicaseSelector
+where
+    I: Individual + Clone + Send,
+{
+    fn select(
+        &self,
+        population: &[IndividualWrapper<I>],
+        rng: &mut SimRng,
+    ) -> Result<Parents<I>, SelectorError> {
+        check_count(self.count, population.len(), self.count + 1)?;
+
+        // The case errors are calculated together with the fitness, see `IndividualWrapper`.
+        let num_of_cases = population
+            .iter()
+            .map(|wrapper| wrapper.case_errors.len())
+            .max()
+            .unwrap_or(0);
+        let mismatch = population.iter().any(|wrapper| {
+            !wrapper.case_errors.is_empty() && wrapper.case_errors.len() != num_of_cases
+        });
+        if num_of_cases == 0 || mismatch {
+            return Err(SelectorError::InvalidFitness(
+                "all individuals must return the same (non zero) number of case errors for \
+                 lexicase selection".to_string()
+            ))
+        }
+
+        let mut available: Vec<usize> = (0..population.len()).collect();
+        let mut select = |rng: &mut SimRng| {
+            let selected = select_one(population, num_of_cases, available.clone(), rng);
+            if !self.replacement {
+                available.retain(|&index| index != selected);
+            }
+            population[selected].individual.clone()
+        };
+
+        let mut result: Parents<I> = Vec::new();
+        for _ in 0..(self.count / 2) {
+            let first = select(rng);
+            let second = select(rng);
+            result.push((first, second));
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use select::*;
+    use individual::Individual;
+    use test::{Test, wrap};
+    use rng;
+
+    #[derive(Debug, Clone)]
+    struct Cases {
+        errors: Vec<f64>,
+    }
+
+    impl Individual for Cases {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {}
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            self.errors.iter().sum()
+        }
+
+        fn reset(&mut self) {}
+
+        fn case_errors(&mut self) -> Vec<f64> {
+            self.errors.clone()
+        }
+    }
+
+    #[test]
+    fn test_count_odd() {
+        let selector = LexicaseSelector::new(3);
+        let population = vec![Cases { errors: vec![0.0, 1.0] }; 10];
+        assert!(selector.select(&wrap(&population), &mut rng::from_seed(1)).is_err());
+    }
+
+    #[test]
+    fn test_no_cases() {
+        let selector = LexicaseSelector::new(2);
+        let population: Vec<Test> = (0..10).map(|i: usize| Test { f: i as f64 }).collect();
+        assert!(selector.select(&wrap(&population), &mut rng::from_seed(1)).is_err());
+    }
+
+    #[test]
+    fn test_specialists_survive() {
+        // Each specialist solves one case perfectly, the generalist is mediocre in all cases
+        // but has the best total fitness. Lexicase never selects the generalist.
+        let mut population = vec![
+            Cases { errors: vec![0.0, 9.0, 9.0] },
+            Cases { errors: vec![9.0, 0.0, 9.0] },
+            Cases { errors: vec![9.0, 9.0, 0.0] },
+            Cases { errors: vec![1.0, 1.0, 1.0] },
+        ];
+        population.extend(vec![Cases { errors: vec![10.0, 10.0, 10.0] }; 6]);
+
+        let selector = LexicaseSelector::new(8);
+        let parents = selector.select(&wrap(&population), &mut rng::from_seed(1)).unwrap();
+        assert_eq!(4, parents.len());
+        for (a, b) in parents {
+            for parent in &[a, b] {
+                assert!(parent.errors.contains(&0.0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_without_replacement() {
+        let selector = LexicaseSelector::new(8).without_replacement();
+        let population: Vec<Cases> = (0..10)
+            .map(|i| Cases { errors: vec![i as f64, (9 - i) as f64] })
+            .collect();
+        let parents = selector.select(&wrap(&population), &mut rng::from_seed(1)).unwrap();
+        let mut selected: Vec<f64> = parents
+            .iter()
+            .flat_map(|p| vec![p.0.errors[0], p.1.errors[0]])
+            .collect();
+        selected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        selected.dedup();
+        assert_eq!(8, selected.len());
+    }
+
+    #[test]
+    fn test_nan_errors() {
+        // All phenotypes have a NaN error on the second case, the first one is still selected.
+        let mut population = vec![Cases { errors: vec![0.0, f64::NAN] }];
+        population.extend(vec![Cases { errors: vec![f64::NAN, f64::NAN] }; 5]);
+        population.push(Cases { errors: vec![] });
+
+        let selector = LexicaseSelector::new(4);
+        for seed in 0..10 {
+            let parents = selector.select(&wrap(&population), &mut rng::from_seed(seed)).unwrap();
+            for (a, b) in parents {
+                assert_eq!(a.errors[0], 0.0);
+                assert_eq!(b.errors[0], 0.0);
+            }
+        }
+    }
+}
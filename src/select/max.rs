@@ -14,10 +14,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use Individual;
+use individual::{Individual, IndividualWrapper};
+use rng::SimRng;
 use super::*;
 
-/// Selects best performing phenotypes from the population.
+/// Selects the phenotypes with the highest fitness from the population.
 #[derive(Clone, Copy, Debug)]
 pub struct MaximizeSelector {
     count: usize,
@@ -26,12 +27,13 @@ pub struct MaximizeSelector {
 impl MaximizeSelector {
     /// Create and return a maximizing selector.
     ///
-    /// Such a selector selects only the `count` best performing phenotypes
-    /// as parents.
+    /// Such a selector selects only the `count` phenotypes with the highest fitness
+    /// as parents. Since the simulation minimizes the fitness, use the `MinimizeSelector`
+    /// unless your fitness function is inverted.
     ///
     /// * `count`: must be larger than zero, a multiple of two and less than the population size.
     pub fn new(count: usize) -> MaximizeSelector {
-        MaximizeSelector { count: count }
+        MaximizeSelector { count }
     }
 }
 
@@ -39,43 +41,12 @@ impl<I> Selector<I> for MaximizeSelector
 where
     I: Individual + Clone + Send,
 {
-    fn select(&self, population: &[I]) -> Result<Parents<I>, String> {
-        if self.count == 0 || self.count % 2 != 0 || self.count * 2 >= population.len() {
-            return Err(format!("Invalid parameter `count`: {}. Should be larger than zero, a \
-                                multiple of two and less than half the population size.",
-                               self.count
-            ))
-        }
-
-        let mut scored = Vec::new();
-        for _ind in population {
-            let mut ind = _ind.clone();
-            let score = ind.calculate_fitness();
-            scored.push((score, ind));
-        }
-        println!("@@ {} scored inds", scored.len());
-        scored.sort_by(|ref x, ref y| {
-            x.0.partial_cmp(&y.0).unwrap_or(Ordering::Less)
-        });
-
-        println!("@@ sort -> first few: {:?}", [scored[0].0, scored[1].0, scored[2].0,
-                                                scored[ scored.len() - 1 ].0]);
-
-        let trunc: Vec<I> = scored
-            .into_iter()
-            .take(self.count)
-            .map(|(_, ind)| ind)
-            .collect();
-
-        println!("@@ after trunc, {}", trunc.len());
-        let mut index = 0;
-        let mut result: Parents<I> = Vec::new();
-        while index < trunc.len() {
-            result.push((trunc[index].clone(), trunc[index + 1].clone()));
-            index += 2;
-        }
-        println!("@@ {} parents", result.len());
-        Ok(result)
+    fn select(
+        &self,
+        population: &[IndividualWrapper<I>],
+        _rng: &mut SimRng,
+    ) -> Result<Parents<I>, SelectorError> {
+        select_best(population, self.count, Direction::Maximize)
     }
 }
 
@@ -83,34 +54,35 @@ where
 mod tests {
     use ordered_float::OrderedFloat;
     use select::*;
-    use test::Test;
+    use test::{Test, wrap};
+    use rng;
 
     #[test]
     fn test_count_zero() {
         let selector = MaximizeSelector::new(0);
         let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
-        assert!(selector.select(&population).is_err());
+        assert!(selector.select(&wrap(&population), &mut rng::from_seed(1)).is_err());
     }
 
     #[test]
     fn test_count_odd() {
         let selector = MaximizeSelector::new(5);
         let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
-        assert!(selector.select(&population).is_err());
+        assert!(selector.select(&wrap(&population), &mut rng::from_seed(1)).is_err());
     }
 
     #[test]
     fn test_count_too_large() {
         let selector = MaximizeSelector::new(100);
         let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
-        assert!(selector.select(&population).is_err());
+        assert!(selector.select(&wrap(&population), &mut rng::from_seed(1)).is_err());
     }
 
     #[test]
     fn test_result_size() {
         let selector = MaximizeSelector::new(20);
         let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
-        assert_eq!(20, selector.select(&population).unwrap().len() * 2);
+        assert_eq!(20, selector.select(&wrap(&population), &mut rng::from_seed(1)).unwrap().len() * 2);
     }
 
     #[test]
@@ -119,9 +91,9 @@ mod tests {
         let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
         // The greatest fitness should be 99.
         assert!(
-            selector.select(&population).unwrap()[0]
+            selector.select(&wrap(&population), &mut rng::from_seed(1)).unwrap()[0]
                 .0
-                .calculate_fitness() == 99.0
+                .calculate_fitness(&()) == 99.0
         );
     }
 
@@ -129,11 +101,11 @@ mod tests {
     fn test_contains_best() {
         let selector = MaximizeSelector::new(2);
         let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
-        let mut parents = selector.select(&population).unwrap()[0];
-        let fit1 = parents.0.calculate_fitness();
+        let mut parents = selector.select(&wrap(&population), &mut rng::from_seed(1)).unwrap()[0];
+        let fit1 = parents.0.calculate_fitness(&());
         let mut all_fitness = Vec::new();
         for i in population {
-            all_fitness.push(OrderedFloat(i.clone().calculate_fitness()));
+            all_fitness.push(OrderedFloat(i.clone().calculate_fitness(&())));
         }
         let max_fitness = all_fitness.iter().max().unwrap();
 
@@ -0,0 +1,76 @@
+// file: min.rs
+//
+// Copyright 2015-2017 The RsGenetic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use individual::{Individual, IndividualWrapper};
+use rng::SimRng;
+use super::*;
+
+/// Selects the phenotypes with the lowest fitness from the population.
+#[derive(Clone, Copy, Debug)]
+pub struct MinimizeSelector {
+    count: usize,
+}
+
+impl MinimizeSelector {
+    /// Create and return a minimizing selector.
+    ///
+    /// Such a selector selects only the `count` phenotypes with the lowest fitness
+    /// (the best ones, since the simulation minimizes the fitness) as parents.
+    ///
+    /// * `count`: must be larger than zero, a multiple of two and less than the population size.
+    pub fn new(count: usize) -> MinimizeSelector {
+        MinimizeSelector { count }
+    }
+}
+
+impl<I> Selector<I> for MinimizeSelector
+where
+    I: Individual + Clone + Send,
+{
+    fn select(
+        &self,
+        population: &[IndividualWrapper<I>],
+        _rng: &mut SimRng,
+    ) -> Result<Parents<I>, SelectorError> {
+        select_best(population, self.count, Direction::Minimize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use select::*;
+    use test::{Test, wrap};
+    use rng;
+
+    #[test]
+    fn test_count_too_large() {
+        let selector = MinimizeSelector::new(100);
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        assert!(selector.select(&wrap(&population), &mut rng::from_seed(1)).is_err());
+    }
+
+    #[test]
+    fn test_result_ok() {
+        let selector = MinimizeSelector::new(20);
+        let population: Vec<Test> = (0..100).rev().map(|i: usize| Test { f: i as f64 }).collect();
+        let parents = selector.select(&wrap(&population), &mut rng::from_seed(1)).unwrap();
+        assert_eq!(10, parents.len());
+        assert_eq!(0.0, parents[0].0.f);
+        for (a, b) in parents {
+            assert!(a.f < 20.0 && b.f < 20.0);
+        }
+    }
+}
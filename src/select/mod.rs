@@ -22,33 +22,218 @@
 //! number of selected parents.
 
 mod max;
-//mod tournament;
+mod min;
+mod tournament;
+mod roulette;
+mod rank;
+mod truncation;
+mod lexicase;
+mod combinator;
 //mod stochastic;
 
-use std::cmp::PartialOrd;
-use std::cmp::Ordering;
 
-use Individual;
+use rand::distributions::{Distribution, WeightedIndex};
+use std::error;
+use std::fmt;
+
+use fitness::Fitness;
+use individual::{Individual, IndividualWrapper};
+use rng::SimRng;
 use std::fmt::Debug;
 
 pub use self::max::MaximizeSelector;
-//pub use self::tournament::TournamentSelector;
+pub use self::min::MinimizeSelector;
+pub use self::tournament::TournamentSelector;
+pub use self::roulette::RouletteSelector;
+pub use self::rank::RankSelector;
+pub use self::truncation::TruncationSelector;
+pub use self::lexicase::LexicaseSelector;
+pub use self::combinator::{ChainSelector, MixSelector};
 //pub use self::stochastic::StochasticSelector;
 
 /// `Parents` come in a `Vec` of two `T`'s.
 pub type Parents<T> = Vec<(T, T)>;
 
+/// The `SelectorError` type. Returned by `Selector::select` if the parameters of the selector
+/// do not fit the population or the selection fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelectorError {
+    /// The population does not contain any individual.
+    EmptyPopulation,
+    /// The parameter `count` must be larger than zero and a multiple of two.
+    InvalidCount(usize),
+    /// The population has fewer individuals than the selector needs.
+    PopulationTooSmall {
+        /// The minimum number of individuals needed.
+        required: usize,
+        /// The actual number of individuals.
+        actual: usize,
+    },
+    /// Another parameter of the selector is invalid (for example the number of participants
+    /// of a tournament).
+    InvalidParameter(String),
+    /// The fitness values (or test case errors) can not be used by this selector, for example
+    /// because they are not finite.
+    InvalidFitness(String),
+}
+
+impl fmt::Display for SelectorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SelectorError::EmptyPopulation => write!(f, "the population is empty"),
+            SelectorError::InvalidCount(count) => write!(
+                f,
+                "invalid parameter `count`: {}, should be larger than zero and a multiple of two",
+                count
+            ),
+            SelectorError::PopulationTooSmall { required, actual } => write!(
+                f,
+                "population too small: {} individuals, at least {} needed",
+                actual,
+                required
+            ),
+            SelectorError::InvalidParameter(ref message) => {
+                write!(f, "invalid parameter: {}", message)
+            }
+            SelectorError::InvalidFitness(ref message) => write!(f, "invalid fitness: {}", message),
+        }
+    }
+}
+
+impl error::Error for SelectorError {}
+
+/// Checks the parameter `count` that most selectors have: it must be larger than zero and a
+/// multiple of two, and the population must contain at least `required` individuals.
+fn check_count(count: usize, actual: usize, required: usize) -> Result<(), SelectorError> {
+    if actual == 0 {
+        Err(SelectorError::EmptyPopulation)
+    } else if count == 0 || count % 2 == 1 {
+        Err(SelectorError::InvalidCount(count))
+    } else if actual < required {
+        Err(SelectorError::PopulationTooSmall { required, actual })
+    } else {
+        Ok(())
+    }
+}
+
+/// Spins the weighted wheel and returns the drawn index. Without `replacement` the weight of
+/// the drawn index is set to zero, so it can not be drawn again.
+fn spin(
+    wheel: &mut WeightedIndex<f64>,
+    replacement: bool,
+    rng: &mut SimRng,
+) -> Result<usize, SelectorError> {
+    let index = wheel.sample(rng);
+    if !replacement {
+        wheel
+            .update_weights(&[(index, &0.0)])
+            .map_err(|e| SelectorError::InvalidFitness(format!("invalid weights: {}", e)))?;
+    }
+    Ok(index)
+}
+
+/// The optimization direction of a selector: are lower or higher fitness values better ?
+/// The simulation itself always minimizes the fitness, so the default is `Minimize`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Direction {
+    /// Lower fitness values are better.
+    #[default]
+    Minimize,
+    /// Higher fitness values are better.
+    Maximize,
+}
+
+impl Direction {
+    /// Turns a fitness value into a score where lower is always better, so the selectors
+    /// only have to deal with one direction.
+    pub fn score(self, fitness: f64) -> f64 {
+        match self {
+            Direction::Minimize => fitness,
+            Direction::Maximize => -fitness,
+        }
+    }
+
+    /// Like `score` for any fitness type: returns the fitness itself or its reverse, see
+    /// `Fitness::reverse`.
+    pub fn apply<F: Fitness>(self, fitness: &F) -> F {
+        match self {
+            Direction::Minimize => fitness.clone(),
+            Direction::Maximize => fitness.reverse(),
+        }
+    }
+}
+
+/// Selects the `count` phenotypes with the best (lowest) score in the given direction and
+/// pairs neighbouring ones.
+fn select_best<I>(
+    population: &[IndividualWrapper<I>],
+    count: usize,
+    direction: Direction,
+) -> Result<Parents<I>, SelectorError>
+where
+    I: Individual + Clone,
+{
+    check_count(count, population.len(), count * 2 + 1)?;
+
+    let mut scored: Vec<(I::Fitness, &I)> = population
+        .iter()
+        .map(|wrapper| (direction.apply(&wrapper.fitness), &wrapper.individual))
+        .collect();
+    scored.sort_by(|x, y| x.0.compare(&y.0));
+
+    Ok(scored[..count]
+        .chunks(2)
+        .map(|pair| (pair[0].1.clone(), pair[1].1.clone()))
+        .collect())
+}
+
 
 /// A `Selector` can select `Parents` for a new iteration of a `Simulation`.
 pub trait Selector<I>: Debug + Clone + Sync
 where
     I: Individual + Send + Clone + Sized,
 {
-    /// Select elements from a `population` for breeding.
+    /// Select elements from a `population` for breeding. The wrappers already contain the
+    /// fitness of each individual, so there is no need to calculate it again.
     ///
     /// If invalid parameters are supplied or the algorithm fails, this function returns an
-    /// `Err(String)`, containing a message indicating the error.
+    /// `Err(SelectorError)` indicating the error.
     ///
     /// Otherwise it contains a vector of parent pairs wrapped in `Ok`.
-    fn select(&self, population: &[I]) -> Result<Parents<I>, String>;
+    ///
+    /// All random decisions must be drawn from `rng`, which is the evolution random number
+    /// generator of the population. This keeps the simulation reproducible.
+    fn select(
+        &self,
+        population: &[IndividualWrapper<I>],
+        rng: &mut SimRng,
+    ) -> Result<Parents<I>, SelectorError>;
+}
+
+/// An object safe version of `Selector`, so each population can carry its own selector, see
+/// `PopulationBuilder::selector`. It is implemented for all selectors.
+pub trait DynSelector<I>: Debug + Send + Sync
+where
+    I: Individual + Send + Clone + Sized,
+{
+    /// Selects the parents like `Selector::select`.
+    fn select_parents(
+        &self,
+        population: &[IndividualWrapper<I>],
+        rng: &mut SimRng,
+    ) -> Result<Parents<I>, SelectorError>;
+}
+
+impl<I, S> DynSelector<I> for S
+where
+    I: Individual + Send + Clone + Sized,
+    S: Selector<I> + Send,
+{
+    fn select_parents(
+        &self,
+        population: &[IndividualWrapper<I>],
+        rng: &mut SimRng,
+    ) -> Result<Parents<I>, SelectorError> {
+        self.select(population, rng)
+    }
 }
@@ -0,0 +1,156 @@
+// file: rank.rs
+//
+// Copyright 2015-2017 The RsGenetic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rand::distributions::WeightedIndex;
+
+use individual::{Individual, IndividualWrapper};
+use rng::SimRng;
+use super::*;
+
+/// Selects phenotypes with a probability depending on their rank, not on their raw fitness.
+#[derive(Clone, Copy, Debug)]
+pub struct RankSelector {
+    count: usize,
+    direction: Direction,
+    replacement: bool,
+}
+
+impl RankSelector {
+    /// Create and return a rank based selector.
+    ///
+    /// The population is sorted by fitness (lower is better, see `direction`) and each phenotype
+    /// gets the weight `population size - rank`: the fittest has the weight n, the least fit
+    /// the weight 1.
+    /// In contrast to the `RouletteSelector` the scale of the fitness values does not matter,
+    /// only their order.
+    ///
+    /// * `count`: must be larger than zero, a multiple of two and less than the population size.
+    pub fn new(count: usize) -> RankSelector {
+        RankSelector {
+            count,
+            direction: Direction::default(),
+            replacement: true,
+        }
+    }
+
+    /// Set the optimization direction (default: `Direction::Minimize`, like the simulation).
+    pub fn direction(mut self, direction: Direction) -> RankSelector {
+        self.direction = direction;
+        self
+    }
+
+    /// Select each phenotype at most once (default: a phenotype may be selected several times,
+    /// even as both parents of a pair). With small populations this keeps a few phenotypes from
+    /// dominating the parents.
+    pub fn without_replacement(mut self) -> RankSelector {
+        self.replacement = false;
+        self
+    }
+}
+
+impl<I> Selector<I> for RankSelector
+where
+    I: Individual + Clone + Send,
+{
+    fn select(
+        &self,
+        population: &[IndividualWrapper<I>],
+        rng: &mut SimRng,
+    ) -> Result<Parents<I>, SelectorError> {
+        check_count(self.count, population.len(), self.count + 1)?;
+
+        let mut scored: Vec<(I::Fitness, usize)> = population
+            .iter()
+            .enumerate()
+            .map(|(index, wrapper)| (self.direction.apply(&wrapper.fitness), index))
+            .collect();
+        scored.sort_by(|x, y| x.0.compare(&y.0));
+
+        let n = scored.len();
+        let mut wheel = WeightedIndex::new((0..n).map(|rank| (n - rank) as f64))
+            .map_err(|e| SelectorError::InvalidFitness(format!("invalid rank weights: {}", e)))?;
+
+        let mut result: Parents<I> = Vec::new();
+        for _ in 0..(self.count / 2) {
+            let first = scored[spin(&mut wheel, self.replacement, rng)?].1;
+            let second = scored[spin(&mut wheel, self.replacement, rng)?].1;
+            result.push((
+                population[first].individual.clone(),
+                population[second].individual.clone(),
+            ));
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use select::*;
+    use test::{Test, wrap};
+    use rng;
+
+    #[test]
+    fn test_count_zero() {
+        let selector = RankSelector::new(0);
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        assert!(selector.select(&wrap(&population), &mut rng::from_seed(1)).is_err());
+    }
+
+    #[test]
+    fn test_count_odd() {
+        let selector = RankSelector::new(5);
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        assert!(selector.select(&wrap(&population), &mut rng::from_seed(1)).is_err());
+    }
+
+    #[test]
+    fn test_count_too_large() {
+        let selector = RankSelector::new(100);
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        assert!(selector.select(&wrap(&population), &mut rng::from_seed(1)).is_err());
+    }
+
+    #[test]
+    fn test_result_size() {
+        let selector = RankSelector::new(20);
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        assert_eq!(20, selector.select(&wrap(&population), &mut rng::from_seed(1)).unwrap().len() * 2);
+    }
+
+    #[test]
+    fn test_scale_does_not_matter() {
+        let selector = RankSelector::new(20);
+        let linear: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        let skewed: Vec<Test> = (0..100).map(|i: usize| Test { f: (i as f64).exp() }).collect();
+        let parents1 = selector.select(&wrap(&linear), &mut rng::from_seed(3)).unwrap();
+        let parents2 = selector.select(&wrap(&skewed), &mut rng::from_seed(3)).unwrap();
+        for (p1, p2) in parents1.iter().zip(parents2.iter()) {
+            assert_eq!(p1.0.f.exp(), p2.0.f);
+            assert_eq!(p1.1.f.exp(), p2.1.f);
+        }
+    }
+
+    #[test]
+    fn test_without_replacement() {
+        let selector = RankSelector::new(8).without_replacement();
+        let population: Vec<Test> = (0..10).map(|i: usize| Test { f: i as f64 }).collect();
+        let parents = selector.select(&wrap(&population), &mut rng::from_seed(1)).unwrap();
+        let mut selected: Vec<f64> = parents.iter().flat_map(|p| vec![p.0.f, p.1.f]).collect();
+        selected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        selected.dedup();
+        assert_eq!(8, selected.len());
+    }
+}
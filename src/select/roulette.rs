@@ -0,0 +1,183 @@
+// file: roulette.rs
+//
+// Copyright 2015-2017 The RsGenetic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rand::distributions::WeightedIndex;
+
+use individual::{Individual, IndividualWrapper};
+use rng::SimRng;
+use super::*;
+
+/// Selects phenotypes with a probability proportional to their (inverse) fitness.
+#[derive(Clone, Copy, Debug)]
+pub struct RouletteSelector {
+    count: usize,
+    direction: Direction,
+    replacement: bool,
+}
+
+impl RouletteSelector {
+    /// Create and return a roulette wheel (fitness proportionate) selector.
+    ///
+    /// Since a lower fitness is better (see `direction`), each phenotype gets the weight
+    /// `1 / (1 + fitness - lowest fitness)`: the fittest phenotype has the weight 1 and the
+    /// weight gets smaller the worse the fitness is. This also works for negative fitness values.
+    /// Phenotypes with the worst possible score (infinity, for example the fitness of an
+    /// individual whose code panicked, see `PanicPolicy`) get the weight zero.
+    ///
+    /// * `count`: must be larger than zero, a multiple of two and less than the population size.
+    pub fn new(count: usize) -> RouletteSelector {
+        RouletteSelector {
+            count,
+            direction: Direction::default(),
+            replacement: true,
+        }
+    }
+
+    /// Set the optimization direction (default: `Direction::Minimize`, like the simulation).
+    pub fn direction(mut self, direction: Direction) -> RouletteSelector {
+        self.direction = direction;
+        self
+    }
+
+    /// Select each phenotype at most once (default: a phenotype may be selected several times,
+    /// even as both parents of a pair). With small populations this keeps a few phenotypes from
+    /// dominating the parents.
+    pub fn without_replacement(mut self) -> RouletteSelector {
+        self.replacement = false;
+        self
+    }
+}
+
+impl<I> Selector<I> for RouletteSelector
+where
+    I: Individual + Clone + Send,
+{
+    fn select(
+        &self,
+        population: &[IndividualWrapper<I>],
+        rng: &mut SimRng,
+    ) -> Result<Parents<I>, SelectorError> {
+        check_count(self.count, population.len(), self.count + 1)?;
+
+        let fitness: Vec<f64> = population
+            .iter()
+            .map(|wrapper| self.direction.score(wrapper.fitness.to_f64()))
+            .collect();
+        if fitness.iter().any(|f| f.is_nan() || *f == f64::NEG_INFINITY) {
+            return Err(SelectorError::InvalidFitness(
+                "fitness values must be finite for roulette wheel selection".to_string()
+            ))
+        }
+
+        let lowest = fitness.iter().cloned().fold(f64::INFINITY, f64::min);
+        let weights = fitness.iter().map(|&f| {
+            if f.is_finite() { 1.0 / (1.0 + f - lowest) } else { 0.0 }
+        });
+        let mut wheel = WeightedIndex::new(weights)
+            .map_err(|e| SelectorError::InvalidFitness(format!("invalid roulette wheel: {}", e)))?;
+
+        let mut result: Parents<I> = Vec::new();
+        for _ in 0..(self.count / 2) {
+            let first = spin(&mut wheel, self.replacement, rng)?;
+            let second = spin(&mut wheel, self.replacement, rng)?;
+            result.push((
+                population[first].individual.clone(),
+                population[second].individual.clone(),
+            ));
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use select::*;
+    use test::{Test, wrap};
+    use rng;
+
+    #[test]
+    fn test_count_zero() {
+        let selector = RouletteSelector::new(0);
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        assert!(selector.select(&wrap(&population), &mut rng::from_seed(1)).is_err());
+    }
+
+    #[test]
+    fn test_count_odd() {
+        let selector = RouletteSelector::new(5);
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        assert!(selector.select(&wrap(&population), &mut rng::from_seed(1)).is_err());
+    }
+
+    #[test]
+    fn test_count_too_large() {
+        let selector = RouletteSelector::new(100);
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        assert!(selector.select(&wrap(&population), &mut rng::from_seed(1)).is_err());
+    }
+
+    #[test]
+    fn test_result_size() {
+        let selector = RouletteSelector::new(20);
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        assert_eq!(20, selector.select(&wrap(&population), &mut rng::from_seed(1)).unwrap().len() * 2);
+    }
+
+    #[test]
+    fn test_prefers_lower_fitness() {
+        let selector = RouletteSelector::new(50);
+        let mut population: Vec<Test> = vec![Test { f: 1000.0 }; 99];
+        population.push(Test { f: -3.0 });
+        let parents = selector.select(&wrap(&population), &mut rng::from_seed(1)).unwrap();
+        let best = parents
+            .iter()
+            .flat_map(|&(a, b)| vec![a, b])
+            .filter(|ind| ind.f == -3.0)
+            .count();
+        assert!(best > 10);
+    }
+
+    #[test]
+    fn test_nan_fitness() {
+        let selector = RouletteSelector::new(2);
+        let mut population: Vec<Test> = (0..10).map(|i: usize| Test { f: i as f64 }).collect();
+        population[3].f = f64::NAN;
+        assert!(selector.select(&wrap(&population), &mut rng::from_seed(1)).is_err());
+    }
+
+    #[test]
+    fn test_infinite_fitness() {
+        let selector = RouletteSelector::new(8);
+        let mut population: Vec<Test> = (0..10).map(|i: usize| Test { f: i as f64 }).collect();
+        population[3].f = f64::INFINITY;
+        let parents = selector.select(&wrap(&population), &mut rng::from_seed(1)).unwrap();
+        assert!(parents.iter().all(|p| p.0.f.is_finite() && p.1.f.is_finite()));
+
+        population[5].f = f64::NEG_INFINITY;
+        assert!(selector.select(&wrap(&population), &mut rng::from_seed(1)).is_err());
+    }
+
+    #[test]
+    fn test_without_replacement() {
+        let selector = RouletteSelector::new(8).without_replacement();
+        let population: Vec<Test> = (0..10).map(|i: usize| Test { f: i as f64 }).collect();
+        let parents = selector.select(&wrap(&population), &mut rng::from_seed(1)).unwrap();
+        let mut selected: Vec<f64> = parents.iter().flat_map(|p| vec![p.0.f, p.1.f]).collect();
+        selected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        selected.dedup();
+        assert_eq!(8, selected.len());
+    }
+}
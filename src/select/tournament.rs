@@ -0,0 +1,241 @@
+// file: tournament.rs
+//
+// Copyright 2015-2017 The RsGenetic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use individual::{Individual, IndividualWrapper};
+use rng::SimRng;
+use schedule::Parameter;
+use super::*;
+
+/// Runs several tournaments, and selects best performing phenotypes from each tournament.
+#[derive(Clone, Debug)]
+pub struct TournamentSelector {
+    count: usize,
+    participants: usize,
+    participants_parameter: Option<Parameter>,
+    direction: Direction,
+    replacement: bool,
+}
+
+impl TournamentSelector {
+    /// Create and return a tournament selector.
+    ///
+    /// Such a selector runs `count / 2` tournaments, each with `participants` participants.
+    /// From each tournament, the best 2 phenotypes (lowest fitness, see `direction`) are
+    /// selected, yielding `count` parents. The more participants, the higher the selection
+    /// pressure.
+    ///
+    /// * `count`: must be larger than zero, a multiple of two and less than the population size.
+    /// * `participants`: must be larger than one and less than the population size.
+    pub fn new(count: usize, participants: usize) -> TournamentSelector {
+        TournamentSelector {
+            count,
+            participants,
+            participants_parameter: None,
+            direction: Direction::default(),
+            replacement: true,
+        }
+    }
+
+    /// Set the optimization direction (default: `Direction::Minimize`, like the simulation).
+    pub fn direction(mut self, direction: Direction) -> TournamentSelector {
+        self.direction = direction;
+        self
+    }
+
+    /// Select each phenotype at most once (default: a phenotype may be selected several times,
+    /// even as both parents of a pair). With small populations this keeps a few phenotypes from
+    /// dominating the parents.
+    pub fn without_replacement(mut self) -> TournamentSelector {
+        self.replacement = false;
+        self
+    }
+
+    /// Let the number of participants follow a scheduled parameter (see `ParameterRegistry`),
+    /// for example to increase the selection pressure over time. The value is rounded and
+    /// kept between two and the population size minus one.
+    pub fn scheduled_participants(mut self, parameter: Parameter) -> TournamentSelector {
+        self.participants_parameter = Some(parameter);
+        self
+    }
+}
+
+impl<I> Selector<I> for TournamentSelector
+where
+    I: Individual + Clone + Send,
+{
+    fn select(
+        &self,
+        population: &[IndividualWrapper<I>],
+        rng: &mut SimRng,
+    ) -> Result<Parents<I>, SelectorError> {
+        check_count(self.count, population.len(), self.count * 2 + 1)?;
+        let participants = match self.participants_parameter {
+            Some(ref parameter) => {
+                let scheduled = parameter.get().round().max(2.0) as usize;
+                scheduled.min(population.len() - 1)
+            }
+            None => self.participants,
+        };
+        if participants < 2 {
+            return Err(SelectorError::InvalidParameter(format!(
+                "`participants`: {}, should be larger than one",
+                participants
+            )))
+        }
+        if participants >= population.len() {
+            return Err(SelectorError::PopulationTooSmall {
+                required: participants + 1,
+                actual: population.len(),
+            })
+        }
+
+        // The phenotypes that can still take part, only used without replacement.
+        let mut available: Vec<usize> = (0..population.len()).collect();
+        let mut result: Parents<I> = Vec::new();
+        for _ in 0..(self.count / 2) {
+            let mut tournament: Vec<(I::Fitness, usize)> = if self.replacement {
+                (0..participants)
+                    .map(|_| rng.gen_range(0..population.len()))
+                    .map(|index| (self.direction.apply(&population[index].fitness), index))
+                    .collect()
+            } else {
+                available
+                    .choose_multiple(rng, participants.min(available.len()))
+                    .map(|&index| (self.direction.apply(&population[index].fitness), index))
+                    .collect()
+            };
+            tournament.sort_by(|x, y| x.0.compare(&y.0));
+
+            let (first, second) = (tournament[0].1, tournament[1].1);
+            if !self.replacement {
+                available.retain(|&index| index != first && index != second);
+            }
+            result.push((
+                population[first].individual.clone(),
+                population[second].individual.clone(),
+            ));
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use select::*;
+    use test::{Test, wrap};
+    use rng;
+    use schedule::Parameter;
+
+    #[test]
+    fn test_count_zero() {
+        let selector = TournamentSelector::new(0, 3);
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        assert!(selector.select(&wrap(&population), &mut rng::from_seed(1)).is_err());
+    }
+
+    #[test]
+    fn test_count_odd() {
+        let selector = TournamentSelector::new(5, 3);
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        assert!(selector.select(&wrap(&population), &mut rng::from_seed(1)).is_err());
+    }
+
+    #[test]
+    fn test_count_too_large() {
+        let selector = TournamentSelector::new(100, 3);
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        assert!(selector.select(&wrap(&population), &mut rng::from_seed(1)).is_err());
+    }
+
+    #[test]
+    fn test_participants_too_small() {
+        let selector = TournamentSelector::new(20, 1);
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        assert!(selector.select(&wrap(&population), &mut rng::from_seed(1)).is_err());
+    }
+
+    #[test]
+    fn test_participants_too_large() {
+        let selector = TournamentSelector::new(20, 100);
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        assert!(selector.select(&wrap(&population), &mut rng::from_seed(1)).is_err());
+    }
+
+    #[test]
+    fn test_result_size() {
+        let selector = TournamentSelector::new(20, 5);
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        assert_eq!(20, selector.select(&wrap(&population), &mut rng::from_seed(1)).unwrap().len() * 2);
+    }
+
+    #[test]
+    fn test_winner_is_fitter() {
+        let selector = TournamentSelector::new(20, 5);
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        for (first, second) in selector.select(&wrap(&population), &mut rng::from_seed(1)).unwrap() {
+            assert!(first.f <= second.f);
+        }
+    }
+
+    #[test]
+    fn test_maximize() {
+        let selector = TournamentSelector::new(20, 5).direction(Direction::Maximize);
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        for (first, second) in selector.select(&wrap(&population), &mut rng::from_seed(1)).unwrap() {
+            assert!(first.f >= second.f);
+        }
+    }
+
+    #[test]
+    fn test_scheduled_participants() {
+        let parameter = Parameter::new(500.0);
+        let selector = TournamentSelector::new(20, 0).scheduled_participants(parameter.clone());
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        // 99 participants out of 100, the winner is always one of the best.
+        let parents = selector.select(&wrap(&population), &mut rng::from_seed(1)).unwrap();
+        let mean = parents.iter().map(|p| p.0.f).sum::<f64>() / parents.len() as f64;
+        assert!(mean < 5.0);
+
+        parameter.set(1.0);
+        assert_eq!(20, selector.select(&wrap(&population), &mut rng::from_seed(1)).unwrap().len() * 2);
+    }
+
+    #[test]
+    fn test_same_seed_same_result() {
+        let selector = TournamentSelector::new(20, 5);
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        let parents1 = selector.select(&wrap(&population), &mut rng::from_seed(7)).unwrap();
+        let parents2 = selector.select(&wrap(&population), &mut rng::from_seed(7)).unwrap();
+        for (p1, p2) in parents1.iter().zip(parents2.iter()) {
+            assert_eq!(p1.0.f, p2.0.f);
+            assert_eq!(p1.1.f, p2.1.f);
+        }
+    }
+
+    #[test]
+    fn test_without_replacement() {
+        let selector = TournamentSelector::new(8, 5).without_replacement();
+        let population: Vec<Test> = (0..17).map(|i: usize| Test { f: i as f64 }).collect();
+        let parents = selector.select(&wrap(&population), &mut rng::from_seed(1)).unwrap();
+        let mut selected: Vec<f64> = parents.iter().flat_map(|p| vec![p.0.f, p.1.f]).collect();
+        selected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        selected.dedup();
+        assert_eq!(8, selected.len());
+    }
+}
@@ -0,0 +1,140 @@
+// file: truncation.rs
+//
+// Copyright 2015-2017 The RsGenetic Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use individual::{Individual, IndividualWrapper};
+use rng::SimRng;
+use super::*;
+
+/// Selects a fraction of the best performing phenotypes from the population.
+#[derive(Clone, Copy, Debug)]
+pub struct TruncationSelector {
+    fraction: f64,
+    direction: Direction,
+}
+
+impl TruncationSelector {
+    /// Create and return a truncation selector.
+    ///
+    /// Such a selector selects the best (lowest fitness, see `direction`) `fraction` of the
+    /// population as parents, for example 0.2 for the best 20%. The number of parents is rounded
+    /// down to a multiple of two and neighbouring phenotypes (by fitness) form a pair.
+    ///
+    /// * `fraction`: must be larger than zero and at most one, and select at least two parents.
+    pub fn new(fraction: f64) -> TruncationSelector {
+        TruncationSelector {
+            fraction,
+            direction: Direction::default(),
+        }
+    }
+
+    /// Set the optimization direction (default: `Direction::Minimize`, like the simulation).
+    pub fn direction(mut self, direction: Direction) -> TruncationSelector {
+        self.direction = direction;
+        self
+    }
+}
+
+impl<I> Selector<I> for TruncationSelector
+where
+    I: Individual + Clone + Send,
+{
+    fn select(
+        &self,
+        population: &[IndividualWrapper<I>],
+        _rng: &mut SimRng,
+    ) -> Result<Parents<I>, SelectorError> {
+        if !(self.fraction > 0.0 && self.fraction <= 1.0) {
+            return Err(SelectorError::InvalidParameter(format!(
+                "`fraction`: {}, should be larger than zero and at most one",
+                self.fraction
+            )))
+        }
+        let count = (self.fraction * population.len() as f64).floor() as usize / 2 * 2;
+        if count < 2 {
+            return Err(SelectorError::PopulationTooSmall {
+                required: (2.0 / self.fraction).ceil() as usize,
+                actual: population.len(),
+            })
+        }
+
+        let mut scored: Vec<(I::Fitness, &I)> = population
+            .iter()
+            .map(|wrapper| (self.direction.apply(&wrapper.fitness), &wrapper.individual))
+            .collect();
+        scored.sort_by(|x, y| x.0.compare(&y.0));
+
+        Ok(scored[..count]
+            .chunks(2)
+            .map(|pair| (pair[0].1.clone(), pair[1].1.clone()))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use select::*;
+    use test::{Test, wrap};
+    use rng;
+
+    #[test]
+    fn test_fraction_zero() {
+        let selector = TruncationSelector::new(0.0);
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        assert!(selector.select(&wrap(&population), &mut rng::from_seed(1)).is_err());
+    }
+
+    #[test]
+    fn test_fraction_too_large() {
+        let selector = TruncationSelector::new(1.5);
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        assert!(selector.select(&wrap(&population), &mut rng::from_seed(1)).is_err());
+    }
+
+    #[test]
+    fn test_too_few_parents() {
+        let selector = TruncationSelector::new(0.01);
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        assert!(selector.select(&wrap(&population), &mut rng::from_seed(1)).is_err());
+    }
+
+    #[test]
+    fn test_result_size() {
+        let selector = TruncationSelector::new(0.25);
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        // 25 rounded down to an even number of parents.
+        assert_eq!(24, selector.select(&wrap(&population), &mut rng::from_seed(1)).unwrap().len() * 2);
+    }
+
+    #[test]
+    fn test_selects_best() {
+        let selector = TruncationSelector::new(0.1);
+        let population: Vec<Test> = (0..100).rev().map(|i: usize| Test { f: i as f64 }).collect();
+        let parents = selector.select(&wrap(&population), &mut rng::from_seed(1)).unwrap();
+        for (a, b) in parents {
+            assert!(a.f < 10.0 && b.f < 10.0);
+        }
+    }
+
+    #[test]
+    fn test_maximize() {
+        let selector = TruncationSelector::new(0.1).direction(Direction::Maximize);
+        let population: Vec<Test> = (0..100).map(|i: usize| Test { f: i as f64 }).collect();
+        let parents = selector.select(&wrap(&population), &mut rng::from_seed(1)).unwrap();
+        for (a, b) in parents {
+            assert!(a.f >= 90.0 && b.f >= 90.0);
+        }
+    }
+}
@@ -14,12 +14,43 @@
 //!
 
 use std::time::Instant;
-use std::fmt::Debug;
-use jobsteal::make_pool;
+use std::error::Error;
+use std::fmt::{self, Debug};
+use std::mem;
+use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+#[cfg(any(feature = "serialize", feature = "plot"))]
+use std::path::Path;
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use rand::Rng;
 
+use archive::Archive;
+use fitness::Fitness;
 use individual::{Individual, IndividualWrapper};
-use population::Population;
-use select::Selector;
+use population::{tag, PanicReport, Population, Ranking, RetireReason};
+use select::{Direction, Selector, SelectorError};
+use local_search::LocalSearch;
+use migration::{MigrantExchange, Migration, Topology};
+use observer::{CrossoverEvent, NewFittestEvent, Observer, Phase, PhaseEvent, ResetEvent};
+use diversity::Diversity;
+use statistics::{Counters, FitnessStats, IterationStats};
+use history::FitnessHistory;
+use schedule::ParameterRegistry;
+use provenance::Provenance;
+use pareto::ParetoFront;
+use rng::{self, SimRng};
+#[cfg(feature = "serialize")]
+use checkpoint::{self, Checkpoint, Periodic};
+#[cfg(feature = "serialize")]
+use persist::{self, Format, PersistError};
+#[cfg(feature = "serialize")]
+use serde::{de::DeserializeOwned, Serialize};
+#[cfg(feature = "plot")]
+use plot::{self, PlotError};
 
 /// The `SimulationType` type. Speficies the criteria on how a simulation should stop.
 #[derive(Debug, Clone)]
@@ -35,6 +66,317 @@ pub enum SimulationType {
     /// That means the relation between the very first fitness and the current fitness of the
     /// fittest individual.
     EndFactor(f64),
+    /// Finish the simulation when the combined criteria are met, see `EndCondition`.
+    EndCondition(EndCondition),
+}
+
+/// The `EndCondition` type. Criteria to finish a simulation that can be combined, for example
+/// "the target fitness is reached or ten minutes have elapsed, but only after at least 1000
+/// iterations":
+///
+/// ```
+/// use std::time::Duration;
+/// use darwin_rs::simulation::EndCondition;
+///
+/// let condition = EndCondition::All(vec![
+///     EndCondition::Any(vec![
+///         EndCondition::Fitness(0.0),
+///         EndCondition::TimeLimit(Duration::from_secs(600)),
+///     ]),
+///     EndCondition::Iterations(1000),
+/// ]);
+/// ```
+///
+/// The conditions are checked after each iteration (see `StopCondition::Target`), the safety
+/// net of `max_iterations` and `stagnation_limit` applies as well.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EndCondition {
+    /// At least this number of iterations is done, `RunStatus::IterationLimit`.
+    Iterations(u32),
+    /// The fittest individual has at least this fitness (like `EndFitness`),
+    /// `RunStatus::TargetReached`.
+    Fitness(f64),
+    /// The improvement factor has reached this value (like `EndFactor`),
+    /// `RunStatus::TargetReached`.
+    Factor(f64),
+    /// This wall-clock time has elapsed since the start of `run`, `RunStatus::TimeLimit`.
+    TimeLimit(Duration),
+    /// The fittest individual has not improved for this number of iterations,
+    /// `RunStatus::Stagnated`.
+    Stagnation(u32),
+    /// At least one of the conditions is met, the status is the one of the first met condition.
+    Any(Vec<EndCondition>),
+    /// All the conditions are met, the status is the one of the first condition. Must not be
+    /// empty.
+    All(Vec<EndCondition>),
+}
+
+impl EndCondition {
+    /// Returns true if all combinations (`Any` and `All`) contain at least one condition.
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            EndCondition::Any(ref conditions) | EndCondition::All(ref conditions) => {
+                !conditions.is_empty() && conditions.iter().all(EndCondition::is_valid)
+            }
+            _ => true,
+        }
+    }
+}
+
+/// The stop conditions that are evaluated during the simulation, see `CheckIntervals`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopCondition {
+    /// The target of the simulation types `EndFitness` and `EndFactor`.
+    Target,
+    /// The `stagnation_limit`.
+    Stagnation,
+}
+
+/// How often each stop condition is evaluated: every n-th iteration, default: 1 (every
+/// iteration). For cheap fitness functions with thousands of iterations per second an
+/// expensive stop condition can dominate the runtime, so it can be checked less often.
+/// The simulation may then run up to n - 1 iterations longer than necessary.
+/// `max_iterations` is always checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CheckIntervals {
+    /// The interval for `StopCondition::Target`.
+    pub target: u32,
+    /// The interval for `StopCondition::Stagnation`.
+    pub stagnation: u32,
+}
+
+impl Default for CheckIntervals {
+    fn default() -> CheckIntervals {
+        CheckIntervals {
+            target: 1,
+            stagnation: 1,
+        }
+    }
+}
+
+impl CheckIntervals {
+    /// Returns the interval of the given stop condition.
+    pub fn get(&self, condition: StopCondition) -> u32 {
+        match condition {
+            StopCondition::Target => self.target,
+            StopCondition::Stagnation => self.stagnation,
+        }
+    }
+
+    /// Sets the interval of the given stop condition.
+    pub fn set(&mut self, condition: StopCondition, every: u32) {
+        match condition {
+            StopCondition::Target => self.target = every,
+            StopCondition::Stagnation => self.stagnation = every,
+        }
+    }
+
+    /// Returns true if the given stop condition has to be evaluated in this iteration.
+    pub fn due(&self, condition: StopCondition, iteration: u32) -> bool {
+        let every = self.get(condition);
+        every <= 1 || iteration % every == 0
+    }
+}
+
+/// The `RunStatus` type. Tells why the simulation has finished.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunStatus {
+    /// The target fitness or improvement factor (`EndFitness` or `EndFactor`) has been reached.
+    TargetReached,
+    /// The number of iterations has been reached: all iterations of `EndIteration` are done, or
+    /// `EndFitness` and `EndFactor` gave up after `max_iterations` iterations.
+    IterationLimit,
+    /// `EndFitness` and `EndFactor` gave up since the fittest individual did not improve for
+    /// `stagnation_limit` iterations. Maybe the target is unreachable.
+    Stagnated,
+    /// The `time_limit` has been reached.
+    TimeLimit,
+    /// The custom end condition has been met, see `SimulationBuilder::end_when`.
+    ConditionMet,
+    /// All populations have retired, see `PopulationBuilder::retire_after` and
+    /// `PopulationBuilder::max_resets`.
+    Retired,
+    /// The simulation has been cancelled, see `CancellationToken`.
+    Cancelled,
+    /// The simulation has been paused, see `Simulation::pause_handle`. The next `run` continues
+    /// after the last iteration.
+    Paused,
+    /// The simulation has been stopped by an error after the current iteration.
+    Error(SimError),
+}
+
+/// The `SimError` type. The errors that stop a running simulation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SimError {
+    /// The selection of the parents failed in the population with the given id and label, for
+    /// example because the parameters of the selector do not fit the population size.
+    SelectionFailed(u32, String, SelectorError),
+    /// The user code panicked, see `PanicPolicy`.
+    Panicked(PanicReport),
+    /// The estimated memory (first value) exceeded the memory limit (second value) during the
+    /// run, for example because an archive grew, see `SimulationBuilder::memory_limit`.
+    MemoryLimitExceeded(usize, usize),
+    /// The thread pool for the populations and the fitness evaluation could not be created,
+    /// see `SimulationBuilder::threads`.
+    ThreadPool(String),
+}
+
+impl fmt::Display for SimError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SimError::SelectionFailed(id, ref label, ref error) => {
+                write!(f, "selection failed in population {}: {}", tag(id, label), error)
+            }
+            SimError::Panicked(ref report) => write!(f, "{}", report),
+            SimError::MemoryLimitExceeded(estimate, limit) => {
+                write!(f, "estimated memory {} exceeds the limit of {} bytes", estimate, limit)
+            }
+            SimError::ThreadPool(ref error) => {
+                write!(f, "cannot create the thread pool: {}", error)
+            }
+        }
+    }
+}
+
+impl Error for SimError {}
+
+/// The `SimEvent` type. The events that a running simulation sends to its subscribers, see
+/// `Simulation::subscribe`. The fitness values are the ones calculated by
+/// `Individual::calculate_fitness`, also in maximize mode.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimEvent<F> {
+    /// A population has found a new global fittest individual.
+    NewFittest {
+        /// The fitness of the new fittest individual.
+        fitness: F,
+        /// The id of the population that found it.
+        population_id: u32,
+        /// The iteration in which it was found.
+        iteration: u32,
+    },
+    /// A population has reset its individuals since the reset limit was reached.
+    Reset {
+        /// The id of the population.
+        population_id: u32,
+        /// The iteration of the reset.
+        iteration: u32,
+    },
+    /// The fittest individual has been shared between all populations, see
+    /// `SimulationBuilder::share_fittest`.
+    Migration {
+        /// The id of the population that found the shared individual.
+        population_id: u32,
+        /// The iteration of the migration.
+        iteration: u32,
+    },
+    /// The run has finished. A paused run does not publish it, see `RunStatus::Paused`.
+    Finished {
+        /// Why the run has finished.
+        status: RunStatus,
+        /// The last iteration.
+        iteration: u32,
+    },
+}
+
+/// Sends the event to all subscribers and drops the ones whose receiver is gone.
+fn publish<F: Clone>(subscribers: &mut Vec<Sender<SimEvent<F>>>, event: SimEvent<F>) {
+    subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+}
+
+/// The `EndWhen` type. A custom end condition, see `SimulationBuilder::end_when`.
+#[derive(Clone)]
+pub struct EndWhen(pub Arc<dyn Fn(&IterationStats) -> bool + Send + Sync>);
+
+impl Debug for EndWhen {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "EndWhen")
+    }
+}
+
+/// The `CancellationToken` type. Stops running simulations gracefully after the current
+/// iteration, for example from another thread or a Ctrl-C handler, see
+/// `SimulationBuilder::cancellation_token`. Clones share the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    /// True once the simulations have been cancelled.
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Creates a token that has not been cancelled.
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Cancels all simulations that use this token, `run` returns `RunStatus::Cancelled` with
+    /// the result so far. The token stays cancelled, further runs stop after their first
+    /// iteration.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns true if the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// The `Pause` type. Pauses a running simulation between two iterations, for example from
+/// another thread, see `Simulation::pause_handle`. Clones share the same switch.
+#[derive(Debug, Clone, Default)]
+pub struct Pause {
+    /// True while the simulation is paused.
+    paused: Arc<AtomicBool>,
+}
+
+impl Pause {
+    /// Pauses the simulation after the current iteration, `run` returns `RunStatus::Paused`.
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Lets the next `run` continue the paused simulation.
+    pub fn unpause(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+    }
+
+    /// Returns true if the simulation is paused.
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+}
+
+/// The `BestSnapshot` type. The fittest individual of a running simulation, for example for
+/// a UI thread that renders it while the evolution continues, see `Simulation::best`. Clones
+/// share the same individual.
+#[derive(Debug, Clone)]
+pub struct BestSnapshot<T: Individual> {
+    /// The current fittest individual, None before the first run.
+    best: Arc<RwLock<Option<Arc<IndividualWrapper<T>>>>>,
+}
+
+impl<T: Individual> BestSnapshot<T> {
+    /// Creates a snapshot without an individual.
+    pub fn new() -> BestSnapshot<T> {
+        BestSnapshot { best: Arc::new(RwLock::new(None)) }
+    }
+
+    /// Returns the current fittest individual, None before the first run. Its fitness is
+    /// stored like the fitness in `SimulationResult::fittest`, see `SimulationResult::fitness`.
+    pub fn get(&self) -> Option<Arc<IndividualWrapper<T>>> {
+        self.best.read().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+
+    /// Replaces the individual, readers get either the old or the new one.
+    fn replace(&self, best: Option<Arc<IndividualWrapper<T>>>) {
+        *self.best.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = best;
+    }
+}
+
+impl<T: Individual> Default for BestSnapshot<T> {
+    fn default() -> BestSnapshot<T> {
+        BestSnapshot::new()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -64,17 +406,110 @@ pub struct Simulation<T: Individual + Send + Sync + Debug> {
     /// (i.e. the size of the "high score list")
     pub num_of_global_fittest: usize,
     /// Do not output every time a new fittest individual is found, only every nth times.
-    /// n == output_every
+    /// n == output_every. Used by the default `LoggingObserver`, see `SimulationBuilder::logging`.
     pub output_every: u32,
-    /// Counter that will be incremented every iteration. If output_every_counter > output_every then
-    /// the new fittest individual will be written to the log.
-    pub output_every_counter: u32,
     /// Only share the most fittest individual between the populations if the counter reaches
     /// this value: share_counter >= share_every.
     pub share_every: u32,
+    /// The number of global fittest individuals that are shared, see
+    /// `SimulationBuilder::share_top`. Default: 1
+    pub share_top: usize,
+    /// The probability that a new fittest individual is shared, see
+    /// `SimulationBuilder::share_probability`. Default: 1.0
+    pub share_probability: f64,
     /// Counter that will be incremented every iteration. If share_counter >= share_every then the
     /// most fittest individual is shared between all the populations.
     pub share_counter: u32,
+    /// The observers that follow this simulation, see `SimulationBuilder::observer`.
+    pub observers: Vec<Arc<dyn Observer<T>>>,
+    /// The channels of the subscribers, see `subscribe`.
+    pub subscribers: Vec<Sender<SimEvent<T::Fitness>>>,
+    /// The scheduled operator parameters, they are updated at the beginning of each iteration.
+    pub parameters: ParameterRegistry,
+    /// Which software on which machine runs this simulation, filled in by
+    /// `SimulationBuilder::finalize`.
+    pub provenance: Provenance,
+    /// The maximum number of iterations for the simulation types `EndFitness` and `EndFactor`,
+    /// default: 1_000_000. This is a safety net, so a simulation with an unreachable target does
+    /// not run forever.
+    pub max_iterations: u32,
+    /// Stop the simulation types `EndFitness` and `EndFactor` if the fittest individual has not
+    /// improved for this number of iterations. 0 disables this check (default).
+    pub stagnation_limit: u32,
+    /// If this is larger than zero and the populations use crossover, the shared fittest
+    /// individual does not replace the fittest individual of each population. Instead it is
+    /// crossed with this number of elites of each population, see
+    /// `SimulationBuilder::interbreed_migrants`. Default: 0
+    pub interbreed_elites: u32,
+    /// How often the stop conditions are evaluated, see `SimulationBuilder::check_every`.
+    pub check_every: CheckIntervals,
+    /// Try these numbers of mutations for the given number of iterations on each population
+    /// before the simulation starts and keep the best one, see
+    /// `SimulationBuilder::calibrate_mutations`. Default: None
+    pub mutation_calibration: Option<(Vec<u32>, u32)>,
+    /// The archive of the whole simulation, if any (see `SimulationBuilder::archive`). It is
+    /// offered the survivors of all populations after each iteration.
+    pub archive: Option<Box<dyn Archive<T>>>,
+    /// Calculate the diversity metrics of all populations after each iteration, see
+    /// `SimulationBuilder::track_diversity`. Default: false
+    pub track_diversity: bool,
+    /// Keep the fitness statistics of all iterations in `SimulationResult::stats_history`, see
+    /// `SimulationBuilder::keep_stats_history`. Default: false
+    pub keep_stats_history: bool,
+    /// Record the fitness history in `SimulationResult::history`: the maximum number of
+    /// entries (0 means no limit) and whether to keep snapshots, see
+    /// `SimulationBuilder::record_history`. Default: None
+    pub record_history: Option<(usize, bool)>,
+    /// Stop the simulation after the iteration in which this time has elapsed, whatever the
+    /// simulation type. Default: None (no time limit)
+    pub time_limit: Option<Duration>,
+    /// Stop the simulation after the iteration for whose statistics this returns true, see
+    /// `SimulationBuilder::end_when`. Default: None
+    pub end_when: Option<EndWhen>,
+    /// Cancel this token (for example from another thread) to stop the simulation after the
+    /// current iteration, see `SimulationBuilder::cancellation_token`. Branches and clones of
+    /// the simulation share it.
+    pub cancelled: CancellationToken,
+    /// Pauses the simulation between two iterations, see `pause_handle`. Branches and clones of
+    /// the simulation share it.
+    pub pause: Pause,
+    /// The fittest individual so far, updated while the simulation runs: clone it before `run`
+    /// and call `BestSnapshot::get` from another thread, see `best_snapshot`. Branches get
+    /// their own.
+    pub best: BestSnapshot<T>,
+    /// Stop the simulation with `SimError::MemoryLimitExceeded` after the iteration in which
+    /// the estimated memory exceeds this number of bytes, see `SimulationBuilder::memory_limit`.
+    /// Default: None (no memory limit)
+    pub memory_limit: Option<usize>,
+    /// When and which individuals migrate between the populations, see
+    /// `SimulationBuilder::migration_interval`. Default: never
+    pub migration: Migration,
+    /// Which populations send individuals to which other populations, see
+    /// `SimulationBuilder::topology`. Default: `Topology::FullyConnected`
+    pub topology: Topology,
+    /// Exchanges emigrants with populations outside of this simulation, see
+    /// `SimulationBuilder::remote_islands`. Default: None
+    pub remote_islands: Option<Arc<dyn MigrantExchange<T>>>,
+    /// Run each population on its own long-lived thread without waiting for the others, see
+    /// `SimulationBuilder::asynchronous_islands`. Default: false
+    pub asynchronous_islands: bool,
+    /// Whether lower or higher fitness values are better, see `SimulationBuilder::maximize`.
+    /// Default: `Direction::Minimize`
+    pub direction: Direction,
+    /// The data shared by all individuals, see `SimulationBuilder::context`.
+    /// Default: `Default::default()`
+    pub context: Arc<T::Context>,
+    /// The global seed from which the random number generators of all populations are derived,
+    /// see `SimulationBuilder::seed`. Default: None (each population keeps its own generator)
+    pub seed: Option<u64>,
+    /// True if the state has been restored from a checkpoint (see `resume`) or a run has been
+    /// stopped in between (see `steps`): the next `run` continues after the last iteration and
+    /// keeps the result so far, instead of starting anew. `run` clears it.
+    pub resumed: bool,
+    /// Write a checkpoint while the simulation runs, see `SimulationBuilder::checkpoint_every`.
+    /// Default: None
+    #[cfg(feature = "serialize")]
+    pub checkpoint_every: Option<Periodic<T>>,
 }
 
 /// The `SimulationResult` Type. Holds the simulation results:
@@ -86,169 +521,3208 @@ pub struct SimulationResult<T: Individual + Send + Sync + Clone + Debug> {
     /// current fitness.
     pub improvement_factor: f64,
     /// The very first calculated fitness, when the simulation just started.
-    pub original_fitness: f64,
+    pub original_fitness: T::Fitness,
     /// Vector of fittest individuals. This will change during the simulation as soon as a new
     /// more fittest individual is found and pushed into the first position (index 0).
     pub fittest: Vec<IndividualWrapper<T>>,
     /// How many iteration did the simulation run.
     pub iteration_counter: u32,
+    /// The work done by all populations during the whole simulation.
+    pub counters: Counters,
+    /// How long each iteration took, starting with iteration 1. Continued runs (see
+    /// `Simulation::resumed`) append their iterations.
+    pub iteration_times: Vec<Duration>,
+    /// The time spent in the runs so far: the initialization and all iterations.
+    pub elapsed: Duration,
+    /// The latest improvement rate of each population: (population id, improvements per 1000
+    /// evaluations over its rolling window), see `ImprovementRate`.
+    pub improvement_rates: Vec<(u32, f64)>,
+    /// The fitness statistics of each population after each iteration: (iteration, population
+    /// id, statistics), starting with iteration 0 for the initial populations. Only filled in
+    /// if enabled with `SimulationBuilder::keep_stats_history`.
+    pub stats_history: Vec<(u32, u32, FitnessStats)>,
+    /// The fitness of the fittest individual after each iteration, see `FitnessHistory`. Only
+    /// recorded if enabled with `SimulationBuilder::record_history`.
+    pub history: Option<FitnessHistory<T>>,
+    /// All the non-dominated individuals found by the populations with `Ranking::Pareto` or
+    /// `Ranking::Nsga2`, see `ParetoFront`. Empty for single objective simulations.
+    pub pareto_front: ParetoFront<T>,
+    /// Whether lower or higher fitness values are better. In maximize mode the fitness values
+    /// of the individuals and `original_fitness` are negated, see `fitness`.
+    pub direction: Direction,
+    /// The global seed of the run, see `SimulationBuilder::seed`.
+    pub seed: Option<u64>,
+    /// The effective configuration of the simulation and all its populations at the start of
+    /// the run, see `Simulation::configuration`.
+    pub configuration: String,
+    /// Which software on which machine produced this result.
+    pub provenance: Provenance,
+    /// The iteration in which the current fittest individual (index 0 of `fittest`) was found,
+    /// 0 if it is from the initial population.
+    pub best_iteration: u32,
+    /// The id of the population that found the current fittest individual.
+    pub best_population_id: u32,
+    /// The populations that have retired so far, in the order of their retirement.
+    pub retirements: Vec<Retirement>,
+}
+
+/// The `Retirement` type. Which population has retired in which iteration and why.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Retirement {
+    /// The id of the population.
+    pub population_id: u32,
+    /// The iteration in which the population has retired.
+    pub iteration: u32,
+    /// Why the population has retired.
+    pub reason: RetireReason,
+}
+
+impl<T: Individual + Send + Sync + Clone + Debug> SimulationResult<T> {
+    /// Returns the fitness of the given individual as calculated by
+    /// `Individual::calculate_fitness`, also in maximize mode.
+    pub fn fitness(&self, wrapper: &IndividualWrapper<T>) -> T::Fitness {
+        self.direction.apply(&wrapper.fitness)
+    }
+
+    /// Returns the average time of an iteration, None before the first iteration.
+    pub fn average_iteration_time(&self) -> Option<Duration> {
+        let iterations = self.iteration_times.len() as u32;
+        if iterations == 0 {
+            None
+        } else {
+            Some(self.iteration_times.iter().sum::<Duration>() / iterations)
+        }
+    }
+
+    /// Returns the fitness evaluations per second over the runs so far (see `elapsed`), 0.0 if
+    /// no time has been spent yet.
+    pub fn evaluations_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.counters.evaluations as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    /// Recalculates the `improvement_factor` from the fittest individual. In maximize mode
+    /// this is original / fittest, so the factor always falls as the fitness improves. Both
+    /// fitness values are converted with `Fitness::to_f64`.
+    fn update_improvement_factor(&mut self) {
+        if let Some(best) = self.fittest.first() {
+            let (best, original) = (best.fitness.to_f64(), self.original_fitness.to_f64());
+            self.improvement_factor = match self.direction {
+                Direction::Minimize => best / original,
+                Direction::Maximize => original / best,
+            };
+        }
+    }
+
+    /// Applies the given local search strategy to all the fittest individuals after the
+    /// simulation has finished. Each of them may use up to `budget` fitness evaluations.
+    /// Afterwards the fittest individuals are sorted again, so the best one is at index 0.
+    /// Returns the fitness before and after the local search for each individual
+    /// (in the original order). A local search that returns a NaN fitness is ignored.
+    /// The local search and the report use the fitness as calculated by
+    /// `Individual::calculate_fitness`, also in maximize mode. Pass the context of the
+    /// simulation (`Simulation::context`) and a random number generator (see `rng::from_seed`).
+    pub fn polish<L>(
+        &mut self,
+        local_search: &L,
+        context: &T::Context,
+        rng: &mut SimRng,
+        budget: u32,
+    ) -> Vec<(T::Fitness, T::Fitness)>
+    where
+        L: LocalSearch<T>,
+    {
+        let mut report = Vec::with_capacity(self.fittest.len());
+
+        for wrapper in &mut self.fittest {
+            let before = self.direction.apply(&wrapper.fitness);
+            let original = wrapper.individual.clone();
+            let after = local_search.improve(
+                &mut wrapper.individual,
+                context,
+                rng,
+                before.clone(),
+                budget,
+            );
+            if after.is_nan() {
+                // The result can't be compared, keep the individual as it was.
+                wrapper.individual = original;
+            } else {
+                wrapper.fitness = self.direction.apply(&after);
+                wrapper.case_errors = wrapper.individual.case_errors();
+            }
+            report.push((before, self.direction.apply(&wrapper.fitness)));
+        }
+
+        // Individuals with a NaN fitness go to the end.
+        self.fittest.sort();
+
+        self.update_improvement_factor();
+
+        report
+    }
+}
+
+/// Saving the result, only available with the feature `serialize`, see module persist.
+#[cfg(feature = "serialize")]
+impl<T: Individual + Send + Sync + Clone + Debug + Serialize> SimulationResult<T> {
+    /// Saves the fittest individuals (best first) to the given file, for example to start a
+    /// later run from them with `PopulationBuilder::initial_population_from_file`. Only the
+    /// individuals are saved, not their fitness.
+    pub fn save_fittest<P: AsRef<Path>>(
+        &self,
+        path: P,
+        format: Format,
+    ) -> Result<(), PersistError> {
+        let fittest: Vec<&T> = self.fittest.iter().map(|wrapper| &wrapper.individual).collect();
+        persist::save(&fittest, path.as_ref(), format)
+    }
+}
+
+/// Plotting the result, only available with the feature `plot`, see module plot.
+#[cfg(feature = "plot")]
+impl<T: Individual + Send + Sync + Clone + Debug> SimulationResult<T> {
+    /// Draws the best and the mean fitness of each population over the iterations to the
+    /// given file, as SVG for the extension ".svg" and otherwise as bitmap (for example PNG).
+    /// Needs the statistics of `SimulationBuilder::keep_stats_history`.
+    pub fn plot_convergence<P: AsRef<Path>>(&self, path: P) -> Result<(), PlotError> {
+        plot::convergence(&self.stats_history, path.as_ref())
+    }
+}
+
+/// The `IterationSummary` type. The outcome of one iteration, see `Simulation::steps`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IterationSummary<F> {
+    /// The number of the iteration, starting with 1 (0 if the simulation failed before the
+    /// first iteration).
+    pub iteration: u32,
+    /// The fitness of the fittest individual so far, as calculated by the individual (see
+    /// `SimulationResult::fitness`).
+    pub fitness: F,
+    /// The improvement factor so far.
+    pub improvement_factor: f64,
+    /// True if a new fittest individual has been found in this iteration.
+    pub new_fittest: bool,
+    /// Why the simulation has finished after this iteration, None if it goes on.
+    pub status: Option<RunStatus>,
+}
+
+/// The `IslandReport` type. What a population running on its own thread reports after each of
+/// its iterations, see `SimulationBuilder::asynchronous_islands`.
+struct IslandReport<T: Individual> {
+    /// The index of the population in the habitat.
+    index: usize,
+    /// The id of the population.
+    population_id: u32,
+    /// The label of the population.
+    population_label: String,
+    /// The iteration of the population.
+    iteration: u32,
+    /// The work done in this iteration.
+    counters: Counters,
+    /// The fittest individual of the population, if it has improved in this iteration.
+    fittest: Option<IndividualWrapper<T>>,
+    /// Why the population has retired in this iteration, if it has.
+    retired: Option<RetireReason>,
+    /// The failed selection, if any.
+    failed: Option<SelectorError>,
+    /// The panic that stops the simulation, if any (see `PanicPolicy`).
+    panicked: Option<PanicReport>,
+}
+
+/// The `Arrival` type. What an island receives from the other islands.
+enum Arrival<T: Individual> {
+    /// The fittest individuals of an island that has improved, see
+    /// `SimulationBuilder::share_fittest`.
+    Shared(Vec<IndividualWrapper<T>>),
+    /// The emigrants of a migration, see `SimulationBuilder::migration_interval`.
+    Emigrants(Vec<IndividualWrapper<T>>),
+}
+
+/// The `Island` type. Runs one population on its own thread until the simulation stops,
+/// see `SimulationBuilder::asynchronous_islands`. The iterations themselves run on the shared
+/// evaluation pool.
+struct Island<'a, T: Individual> {
+    /// The index of the population in the habitat.
+    index: usize,
+    /// The first and the last iteration to run.
+    iterations: (u32, u32),
+    /// Share each improvement with the other islands, but at most every nth iteration.
+    share_every: Option<u32>,
+    /// See `Simulation::share_top`.
+    share_top: usize,
+    /// See `Simulation::share_probability`.
+    share_probability: f64,
+    /// See `Simulation::migration`.
+    migration: Migration,
+    /// See `Simulation::interbreed_elites`.
+    interbreed_elites: u32,
+    /// The individuals sent by the other islands.
+    migrants: Receiver<Arrival<T>>,
+    /// The channels to the other islands.
+    neighbours: Vec<Sender<Arrival<T>>>,
+    /// The channel to the coordinating thread.
+    reports: Sender<IslandReport<T>>,
+    /// Set by the coordinating thread to stop all islands.
+    stop: &'a AtomicBool,
+    /// The threads that evaluate the individuals of all islands, see
+    /// `SimulationBuilder::threads`.
+    pool: &'a ThreadPool,
+}
+
+impl<'a, T: Individual + Send + Sync + Clone + Debug> Island<'a, T> {
+    /// Runs the given population until the last iteration, until it retires or fails or until
+    /// the simulation stops.
+    fn run<S>(self, population: &mut Population<T>, selector: &S)
+    where
+        S: Selector<T>,
+    {
+        let mut best = population.population[0].fitness.clone();
+        let mut share_counter = 0;
+
+        for iteration in self.iterations.0..self.iterations.1 + 1 {
+            if self.stop.load(Ordering::SeqCst) {
+                break;
+            }
+
+            for arrival in self.migrants.try_iter() {
+                match arrival {
+                    Arrival::Shared(shared) => {
+                        population.receive_shared(&shared, self.interbreed_elites)
+                    }
+                    Arrival::Emigrants(migrants) => {
+                        population.receive_migrants(&migrants, self.interbreed_elites)
+                    }
+                }
+            }
+            let failed = self.pool.install(|| population.run_body(selector)).err();
+            // Nobody hands the resets and phases over to the observers.
+            population.reset_log.clear();
+            population.phase_timings.clear();
+
+            let fittest = &population.population[0];
+            let fittest = if fittest.fitness < best {
+                best = fittest.fitness.clone();
+                let fittest = fittest.clone();
+                population.guarded(0, "new_fittest_found", |individual, _| {
+                    individual.new_fittest_found()
+                });
+                Some(fittest)
+            } else {
+                None
+            };
+            share_counter += 1;
+            if let (Some(share_every), true) = (self.share_every, fittest.is_some()) {
+                if share_counter >= share_every && population.rng.gen_bool(self.share_probability)
+                {
+                    let top = self.share_top.min(population.population.len());
+                    let shared = &population.population[..top];
+                    for neighbour in &self.neighbours {
+                        // An island that has already stopped does not need it any more.
+                        let _ = neighbour.send(Arrival::Shared(shared.to_vec()));
+                    }
+                    share_counter = 0;
+                }
+            }
+            if self.migration.is_due(iteration) {
+                let emigrants = self.migration.select(&population.population, &mut population.rng);
+                for neighbour in &self.neighbours {
+                    let _ = neighbour.send(Arrival::Emigrants(emigrants.clone()));
+                }
+            }
+
+            let counters = mem::take(&mut population.counters);
+            population.improvement_rate.push(&counters);
+            let done = failed.is_some() ||
+                population.retired.is_some() ||
+                population.panicked.is_some();
+            let report = IslandReport {
+                index: self.index,
+                population_id: population.id,
+                population_label: population.label.clone(),
+                iteration,
+                counters,
+                fittest,
+                retired: population.retired,
+                failed,
+                panicked: population.panicked.clone(),
+            };
+            if self.reports.send(report).is_err() || done {
+                break;
+            }
+        }
+    }
+}
+
+/// The `Steps` type. Runs a simulation one iteration at a time, see `Simulation::steps`.
+pub struct Steps<'a, T, S>
+where
+    T: Individual + Send + Sync + Clone + Debug + 'a,
+    S: Selector<T> + 'a,
+{
+    /// The running simulation.
+    simulation: &'a mut Simulation<T>,
+    /// Selects the parents in each iteration.
+    selector: &'a S,
+    /// The threads that run the populations and evaluate the individuals, or the status if
+    /// they could not be created.
+    pool: Result<ThreadPool, RunStatus>,
+    /// The start of the run, for the time limit.
+    start_time: Instant,
+    /// True after the fitness and the result have been initialized.
+    started: bool,
+    /// True after the last iteration.
+    finished: bool,
+}
+
+impl<'a, T, S> Iterator for Steps<'a, T, S>
+where
+    T: Individual + Send + Sync + Clone + Debug,
+    S: Selector<T>,
+{
+    type Item = IterationSummary<T::Fitness>;
+
+    fn next(&mut self) -> Option<IterationSummary<T::Fitness>> {
+        if self.finished {
+            return None;
+        }
+
+        let pool = match self.pool {
+            Ok(ref pool) => pool,
+            Err(ref failed) => {
+                self.finished = true;
+                let status = self.simulation.finish(self.start_time, failed.clone());
+                return Some(self.simulation.summary(Some(status)));
+            }
+        };
+
+        if !self.started {
+            self.started = true;
+            self.start_time = Instant::now();
+            if let Some(failed) = self.simulation.start(pool, self.selector) {
+                self.finished = true;
+                let status = self.simulation.finish(self.start_time, failed);
+                return Some(self.simulation.summary(Some(status)));
+            }
+        }
+
+        let status = self.simulation.advance(pool, self.selector, self.start_time);
+        let status = status.map(|status| {
+            self.finished = true;
+            // A paused simulation continues with the next run.
+            self.simulation.resumed = status == RunStatus::Paused;
+            self.simulation.finish(self.start_time, status)
+        });
+        Some(self.simulation.summary(status))
+    }
+}
+
+impl<'a, T, S> Drop for Steps<'a, T, S>
+where
+    T: Individual + Send + Sync + Clone + Debug,
+    S: Selector<T>,
+{
+    /// A simulation that has been started but not finished continues with the next run.
+    fn drop(&mut self) {
+        if self.started && !self.finished {
+            self.simulation.resumed = true;
+        }
+    }
 }
 
 /// This implements the the functions `run`, `print_fitness` and `update_results` (private)
 /// for the struct `Simulation`.
 impl<T: Individual + Send + Sync + Clone + Debug> Simulation<T> {
+    /// Returns a channel that receives the events of all following runs (see `SimEvent`), so a
+    /// GUI or a service can monitor the simulation from another thread. Sending an event does
+    /// not wait for the receiver. Dropping the receiver ends the subscription.
+    pub fn subscribe(&mut self) -> Receiver<SimEvent<T::Fitness>> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+
     /// This actually runs the simulation.
     /// Depending on the type of simulation (`EndIteration`, `EndFactor` or `EndFitness`)
     /// the iteration loop will check for the stop condition accordingly.
-    pub fn run<S>(&mut self, selector: &S)
+    /// Returns why the simulation has finished, see `RunStatus`.
+    /// After `resume` the run continues after the iteration of the checkpoint, the iterations
+    /// of `EndIteration` include the ones before the checkpoint.
+    pub fn run<S>(&mut self, selector: &S) -> RunStatus
     where
         S: Selector<T>,
     {
+        trace_span!("run", populations = self.habitat.len(), seed = ?self.seed);
+        if self.asynchronous_islands {
+            return self.run_islands(selector);
+        }
+        self.steps(selector)
+            .last()
+            .and_then(|summary| summary.status)
+            .unwrap_or(RunStatus::IterationLimit)
+    }
 
-        // Initialize timer
-        let start_time = Instant::now();
-
-        // Calculate the fitness for all individuals in all populations at the beginning.
-        for population in &mut self.habitat {
-            population.calculate_fitness();
+    /// Runs the simulation step by step: the returned iterator runs one iteration of all
+    /// populations per call of `next` and yields its summary, so the caller can interleave its
+    /// own logic (UI updates, logging, stopping early) with the simulation. The summary of the
+    /// last iteration contains the `RunStatus`, the iterator ends after it. The time limit
+    /// counts from the first call of `next`.
+    /// If the iterator is dropped before the simulation has finished, the next `steps`, `step`
+    /// or `run` continues where it stopped (see `resumed`).
+    pub fn steps<'a, S>(&'a mut self, selector: &'a S) -> Steps<'a, T, S>
+    where
+        S: Selector<T>,
+    {
+        Steps {
+            pool: self.thread_pool(),
+            simulation: self,
+            selector,
+            start_time: Instant::now(),
+            started: false,
+            finished: false,
         }
+    }
 
-        let mut iteration_counter = 0;
-        let mut pool = make_pool(self.num_of_threads).unwrap();
+    /// Creates the thread pool that runs the populations and evaluates the individuals, see
+    /// `SimulationBuilder::threads`. Returns `RunStatus::Error` if this failed.
+    fn thread_pool(&self) -> Result<ThreadPool, RunStatus> {
+        ThreadPoolBuilder::new().num_threads(self.num_of_threads).build().map_err(|error| {
+            error!("cannot create the thread pool: {}", error);
+            RunStatus::Error(SimError::ThreadPool(error.to_string()))
+        })
+    }
 
-        // Initialize:
-        // - The fittest individual.
-        // - The fitness at the beginning of the simulation. This is uesed to calculate the
-        //   overall improvement later on.
-        self.simulation_result = SimulationResult {
-            improvement_factor: 0.0,
-            original_fitness: self.habitat[0].population[0].fitness,
-            fittest: vec![self.habitat[0].population[0].clone()],
-            iteration_counter: 0,
+    /// Runs the simulation with one long-lived thread per population, see
+    /// `SimulationBuilder::asynchronous_islands`. The individuals of all populations are
+    /// evaluated on one pool of `num_of_threads` threads. This thread collects the reports of
+    /// the islands and stops them when the simulation has finished.
+    fn run_islands<S>(&mut self, selector: &S) -> RunStatus
+    where
+        S: Selector<T>,
+    {
+        let start_time = Instant::now();
+        let pool = match self.thread_pool() {
+            Ok(pool) => pool,
+            Err(failed) => return self.finish(start_time, failed),
         };
+        if let Some(failed) = self.start(&pool, selector) {
+            return self.finish(start_time, failed);
+        }
 
-        info!(
-            "original_fitness: {}",
-            self.simulation_result.original_fitness
-        );
+        let first_iteration = self.simulation_result.iteration_counter + 1;
+        let last_iteration = match self.type_of_simulation {
+            SimulationType::EndIteration(end_iteration) => end_iteration,
+            _ => self.max_iterations,
+        };
+        let share_every = if self.share_fittest { Some(self.share_every) } else { None };
+        let islands_start = Instant::now();
 
-        // Check which type of simulation to run.
-        match self.type_of_simulation {
-            SimulationType::EndIteration(end_iteration) => {
-                for _ in 0..end_iteration {
-                    pool.scope(|scope| for population in &mut self.habitat {
-                        scope.submit(move || population.run_body(selector));
-                    });
+        // The islands own their populations while they run.
+        let mut habitat = mem::take(&mut self.habitat);
+        let stop = AtomicBool::new(false);
+        let (report_sender, reports) = mpsc::channel();
+        let (migrant_senders, migrant_receivers): (Vec<_>, Vec<_>) =
+            habitat.iter().map(|_| mpsc::channel()).unzip();
+        let mut found = vec![0; habitat.len()];
+        let mut status = None;
 
-                    self.update_results();
+        thread::scope(|scope| {
+            let populations = habitat.iter_mut().zip(migrant_receivers).enumerate();
+            for (index, (population, migrants)) in populations {
+                if population.retired.is_some() {
+                    continue;
                 }
-                self.simulation_result.iteration_counter = end_iteration;
+                let neighbours = self
+                    .topology
+                    .neighbours(index, migrant_senders.len())
+                    .into_iter()
+                    .map(|neighbour| migrant_senders[neighbour].clone())
+                    .collect();
+                let island = Island {
+                    index,
+                    iterations: (first_iteration, last_iteration),
+                    share_every,
+                    share_top: self.share_top,
+                    share_probability: self.share_probability,
+                    migration: self.migration,
+                    interbreed_elites: self.interbreed_elites,
+                    migrants,
+                    neighbours,
+                    reports: report_sender.clone(),
+                    stop: &stop,
+                    pool: &pool,
+                };
+                scope.spawn(move || island.run(population, selector));
             }
+            drop(report_sender);
 
-            SimulationType::EndFactor(end_factor) => {
-                loop {
-                    iteration_counter += 1;
-                    pool.scope(|scope| for population in &mut self.habitat {
-                        scope.submit(move || population.run_body(selector));
-                    });
+            for report in reports {
+                let stopped = self.coordinate(report, &mut found, start_time);
+                if status.is_none() && stopped.is_some() {
+                    status = stopped;
+                    stop.store(true, Ordering::SeqCst);
+                }
+            }
+        });
 
-                    self.update_results();
+        self.habitat = habitat;
+        self.simulation_result.elapsed += islands_start.elapsed();
+        // The panic is already in the status.
+        self.first_panic();
+        let status = status.unwrap_or_else(|| {
+            if self.habitat.iter().all(|population| population.retired.is_some()) {
+                RunStatus::Retired
+            } else {
+                RunStatus::IterationLimit
+            }
+        });
+        self.finish(start_time, status)
+    }
 
-                    if self.simulation_result.improvement_factor <= end_factor {
-                        break;
-                    }
+    /// Takes in the report of an island, see `run_islands`. `found` counts the new fittest
+    /// individuals of each island. Returns why the simulation has finished or None if it goes
+    /// on.
+    fn coordinate(
+        &mut self,
+        report: IslandReport<T>,
+        found: &mut [u64],
+        start_time: Instant,
+    ) -> Option<RunStatus> {
+        let IslandReport { population_id, population_label, .. } = report;
+        let result = &mut self.simulation_result;
+        result.counters.merge(&report.counters);
+        result.iteration_counter = result.iteration_counter.max(report.iteration);
+
+        if let Some(fittest) = report.fittest {
+            if fittest.fitness < result.fittest[0].fitness {
+                result.best_iteration = report.iteration;
+                result.best_population_id = population_id;
+                result.fittest.insert(0, fittest);
+                result.fittest.truncate(self.num_of_global_fittest);
+                result.update_improvement_factor();
+                found[report.index] += 1;
+
+                let event = NewFittestEvent {
+                    population_id,
+                    population_label: population_label.clone(),
+                    iteration: report.iteration,
+                    fitness: self.direction.apply(&result.fittest[0].fitness),
+                    fitness_counter: found[report.index],
+                    fittest: &result.fittest[0],
+                };
+                for observer in &self.observers {
+                    observer.new_fittest(&event);
                 }
-                self.simulation_result.iteration_counter = iteration_counter;
+                let event = SimEvent::NewFittest {
+                    fitness: event.fitness,
+                    population_id,
+                    iteration: report.iteration,
+                };
+                publish(&mut self.subscribers, event);
+                self.publish_best();
             }
+        }
 
-            SimulationType::EndFitness(end_fitness) => {
-                loop {
-                    iteration_counter += 1;
-                    pool.scope(|scope| for population in &mut self.habitat {
-                        scope.submit(move || population.run_body(selector));
-                    });
-
-                    self.update_results();
+        if let Some(reason) = report.retired {
+            info!("population {} retired in iteration {}", population_id, report.iteration);
+            self.simulation_result.retirements.push(Retirement {
+                population_id,
+                iteration: report.iteration,
+                reason,
+            });
+        }
+        if let Some(error) = report.failed {
+            error!("selection failed in population {}: {}", population_id, error);
+            let error = SimError::SelectionFailed(population_id, population_label, error);
+            return Some(RunStatus::Error(error));
+        }
+        if let Some(report) = report.panicked {
+            return Some(RunStatus::Error(SimError::Panicked(report)));
+        }
 
-                    if self.simulation_result.fittest[0].fitness <= end_fitness {
-                        break;
-                    }
+        let iteration = self.simulation_result.iteration_counter;
+        let stagnation = iteration - self.simulation_result.best_iteration;
+        match self.type_of_simulation {
+            SimulationType::EndIteration(_) => self.interrupted(start_time),
+            SimulationType::EndFactor(_) | SimulationType::EndFitness(_) => {
+                if self.target_reached() {
+                    return Some(RunStatus::TargetReached);
                 }
-                self.simulation_result.iteration_counter = iteration_counter;
+                self.target_out_of_reach(iteration, stagnation)
+                    .or_else(|| self.interrupted(start_time))
             }
-        } // End of match
+            SimulationType::EndCondition(ref condition) => self
+                .condition_met(condition, iteration, start_time)
+                .or_else(|| self.target_out_of_reach(iteration, stagnation))
+                .or_else(|| self.interrupted(start_time)),
+        }
+    }
 
-        let elapsed = start_time.elapsed();
+    /// Returns the fittest individual so far, None before the first run. It is replaced
+    /// whenever a new fittest individual is found, so a clone of `best` taken before `run`
+    /// always sees a complete individual from another thread.
+    pub fn best_snapshot(&self) -> Option<Arc<IndividualWrapper<T>>> {
+        self.best.get()
+    }
 
-        self.total_time_in_ms = elapsed.as_secs() as f64 * 1000.0 +
-            elapsed.subsec_nanos() as f64 / 1000_000.0;
+    /// Runs one iteration of all populations, see `steps`. Each call creates a new thread
+    /// pool, so prefer `steps` to run many short iterations.
+    pub fn step<S>(&mut self, selector: &S) -> Option<IterationSummary<T::Fitness>>
+    where
+        S: Selector<T>,
+    {
+        self.steps(selector).next()
     }
 
-    /// This is a helper function that the user can call after the simulation stops in order to
-    /// see all the fitness values for all the individuals that participated to the overall
-    /// improvement.
-    pub fn print_fitness(&self) {
-        for wrapper in &self.simulation_result.fittest {
+    /// Prepares a run: calculates the fitness of all individuals and initializes the result,
+    /// unless the simulation continues (see `resumed`). Returns `RunStatus::Error` if this
+    /// failed.
+    fn start<S>(&mut self, pool: &ThreadPool, selector: &S) -> Option<RunStatus>
+    where
+        S: Selector<T>,
+    {
+        let started = Instant::now();
+        let resumed = mem::replace(&mut self.resumed, false);
+
+        if !resumed {
+            info!("{}", self.provenance);
+        }
+
+        // Calculate the fitness for all individuals in all populations at the beginning.
+        let phase_start = Instant::now();
+        pool.install(|| {
+            self.habitat.par_iter_mut().for_each(|population| population.calculate_fitness());
+        });
+        self.notify_phase(Phase::Initialization, None, 0, phase_start);
+
+        if resumed {
+            // Continue with the result so far.
+            debug!("continued after iteration {}", self.simulation_result.iteration_counter);
+            self.publish_best();
+            self.first_panic()
+        } else {
+            // Initialize:
+            // - The fittest individual.
+            // - The fitness at the beginning of the simulation. This is uesed to calculate the
+            //   overall improvement later on.
+            self.simulation_result = SimulationResult {
+                improvement_factor: 0.0,
+                original_fitness: self.habitat[0].population[0].fitness.clone(),
+                fittest: vec![self.habitat[0].population[0].clone()],
+                iteration_counter: 0,
+                counters: Counters::default(),
+                iteration_times: Vec::new(),
+                elapsed: Duration::default(),
+                improvement_rates: Vec::new(),
+                stats_history: Vec::new(),
+                history: self
+                    .record_history
+                    .map(|(max_entries, snapshots)| FitnessHistory::new(max_entries, snapshots)),
+                pareto_front: ParetoFront::new(),
+                direction: self.direction,
+                seed: self.seed,
+                configuration: self.configuration(),
+                provenance: self.provenance.clone(),
+                best_iteration: 0,
+                best_population_id: self.habitat[0].id,
+                retirements: Vec::new(),
+            };
             info!(
-                "fitness: {}, num_of_mutations: {}, population: {}",
-                wrapper.fitness,
-                wrapper.num_of_mutations,
-                wrapper.id
+                "original_fitness: {:?}",
+                self.direction.apply(&self.simulation_result.original_fitness)
             );
+
+            let failed = self.calibrate(pool, selector).or_else(|| self.first_panic());
+            self.publish_best();
+            self.collect_counters(0, started);
+            self.record_history(0);
+            failed
         }
     }
 
-    /// Update the internal state of the simulation: Has a new fittest individual been found ?
-    /// Do we want to share it across all the other populations ?
-    /// Also calculates the improvement factor.
-    fn update_results(&mut self) {
-        // Determine the fittest individual of all populations.
-        let mut new_fittest_found = false;
+    /// Runs the next iteration of all populations and checks the stop criteria. Returns why the
+    /// simulation has finished or None if it goes on.
+    /// The fittest individual has stagnated since the iteration in which it was found (see
+    /// `SimulationResult::best_iteration`).
+    fn advance<S>(
+        &mut self,
+        pool: &ThreadPool,
+        selector: &S,
+        start_time: Instant,
+    ) -> Option<RunStatus>
+    where
+        S: Selector<T>,
+    {
+        let iteration = self.simulation_result.iteration_counter + 1;
 
-        // Increment the output counter
-        // Only write an output if the max value output_every is reached
-        self.output_every_counter += 1;
+        if self.pause.is_paused() {
+            return Some(RunStatus::Paused);
+        }
 
-        for population in &mut self.habitat {
-            if population.population[0].fitness < self.simulation_result.fittest[0].fitness {
-                new_fittest_found = true;
-                self.simulation_result.fittest.insert(
-                    0,
-                    population.population[0]
-                        .clone(),
-                );
-                // See https://github.com/willi-kappler/darwin-rs/issues/12
-                self.simulation_result.fittest.truncate(
-                    self.num_of_global_fittest,
-                );
-                population.fitness_counter += 1;
-                if self.output_every_counter >= self.output_every {
-                    info!(
-                        "new fittest: fitness: {}, population id: {}, counter: {}",
-                        population.population[0].fitness,
-                        population.id,
-                        population.fitness_counter
-                    );
-                    self.output_every_counter = 0
-                }
-                // Call methond `new_fittest_found` of the newly found fittest individual.
-                // The default implementation for this method does nothing.
-                population.population[0].individual.new_fittest_found();
+        if let SimulationType::EndIteration(end_iteration) = self.type_of_simulation {
+            if iteration > end_iteration {
+                return Some(RunStatus::IterationLimit);
             }
         }
 
-        // Now copy the most fittest individual back to each population
-        // if the user has specified it and the share_every count is reached
-        self.share_counter += 1;
-        if self.share_fittest && new_fittest_found && (self.share_counter >= self.share_every) {
-            for population in &mut self.habitat {
-                population.population[0] = self.simulation_result.fittest[0].clone();
-            }
-            self.share_counter = 0;
+        for observer in &self.observers {
+            observer.iteration_start(iteration);
+        }
+
+        trace_span!("iteration", iteration, fitness = ::tracing::field::Empty);
+        let started = Instant::now();
+        self.parameters.update(iteration);
+        let failed = self.evolve(pool, selector, iteration);
+        let stats = self.update_results(iteration, started);
+        trace_record!(
+            "fitness",
+            self.direction.apply(&self.simulation_result.fittest[0].fitness).to_f64()
+        );
+
+        if let Some(failed) = failed.or_else(|| self.first_panic()) {
+            return Some(failed);
         }
 
-        self.simulation_result.improvement_factor = self.simulation_result.fittest[0].fitness /
-            self.simulation_result.original_fitness;
+        if self.habitat.iter().all(|population| population.retired.is_some()) {
+            info!("all populations have retired after iteration {}", iteration);
+            return Some(RunStatus::Retired);
+        }
+
+        if let Some(EndWhen(ref predicate)) = self.end_when {
+            if self.check_every.due(StopCondition::Target, iteration) && predicate(&stats) {
+                return Some(RunStatus::ConditionMet);
+            }
+        }
+
+        // Check which type of simulation to run.
+        match self.type_of_simulation {
+            SimulationType::EndIteration(end_iteration) => {
+                self.interrupted(start_time).or(if iteration >= end_iteration {
+                    Some(RunStatus::IterationLimit)
+                } else {
+                    None
+                })
+            }
+
+            SimulationType::EndFactor(_) | SimulationType::EndFitness(_) => {
+                if self.check_every.due(StopCondition::Target, iteration) && self.target_reached()
+                {
+                    return Some(RunStatus::TargetReached);
+                }
+
+                let stagnation = iteration - self.simulation_result.best_iteration;
+                self.target_out_of_reach(iteration, stagnation)
+                    .or_else(|| self.interrupted(start_time))
+            }
+
+            SimulationType::EndCondition(ref condition) => {
+                if self.check_every.due(StopCondition::Target, iteration) {
+                    if let Some(status) = self.condition_met(condition, iteration, start_time) {
+                        return Some(status);
+                    }
+                }
+
+                let stagnation = iteration - self.simulation_result.best_iteration;
+                self.target_out_of_reach(iteration, stagnation)
+                    .or_else(|| self.interrupted(start_time))
+            }
+        }
+    }
+
+    /// Checks the given end condition after the given iteration: returns the status if it is
+    /// met.
+    fn condition_met(
+        &self,
+        condition: &EndCondition,
+        iteration: u32,
+        start_time: Instant,
+    ) -> Option<RunStatus> {
+        let result = &self.simulation_result;
+        let met = |reached: bool, status: RunStatus| if reached { Some(status) } else { None };
+
+        match *condition {
+            EndCondition::Iterations(iterations) => {
+                met(iteration >= iterations, RunStatus::IterationLimit)
+            }
+            EndCondition::Fitness(fitness) => met(
+                result.fittest[0].fitness.to_f64() <= self.direction.score(fitness),
+                RunStatus::TargetReached,
+            ),
+            EndCondition::Factor(factor) => {
+                met(result.improvement_factor <= factor, RunStatus::TargetReached)
+            }
+            EndCondition::TimeLimit(limit) => {
+                met(start_time.elapsed() >= limit, RunStatus::TimeLimit)
+            }
+            EndCondition::Stagnation(iterations) => met(
+                iteration - result.best_iteration >= iterations,
+                RunStatus::Stagnated,
+            ),
+            EndCondition::Any(ref conditions) => conditions
+                .iter()
+                .find_map(|condition| self.condition_met(condition, iteration, start_time)),
+            EndCondition::All(ref conditions) => {
+                let mut statuses = conditions
+                    .iter()
+                    .map(|condition| self.condition_met(condition, iteration, start_time));
+                let first = statuses.next().and_then(|status| status);
+                if statuses.all(|status| status.is_some()) {
+                    first
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Returns the summary of the last iteration with the given status.
+    fn summary(&self, status: Option<RunStatus>) -> IterationSummary<T::Fitness> {
+        let result = &self.simulation_result;
+
+        IterationSummary {
+            iteration: result.iteration_counter,
+            fitness: result.fitness(&result.fittest[0]),
+            improvement_factor: result.improvement_factor,
+            new_fittest: result.best_iteration == result.iteration_counter,
+            status,
+        }
+    }
+
+    /// Returns `RunStatus::Cancelled` if the simulation has been cancelled or
+    /// `RunStatus::TimeLimit` if the time limit has been reached since `start_time`.
+    fn interrupted(&self, start_time: Instant) -> Option<RunStatus> {
+        if self.cancelled.is_cancelled() {
+            Some(RunStatus::Cancelled)
+        } else if self.time_limit.map_or(false, |limit| start_time.elapsed() >= limit) {
+            Some(RunStatus::TimeLimit)
+        } else {
+            self.memory_limit.and_then(|limit| {
+                let estimate = self.estimated_memory();
+                if estimate > limit {
+                    error!("estimated memory {} exceeds the limit {}", estimate, limit);
+                    Some(RunStatus::Error(SimError::MemoryLimitExceeded(estimate, limit)))
+                } else {
+                    None
+                }
+            })
+        }
+    }
+
+    /// Records the total run time and tells the observers that the simulation has finished.
+    /// The subscribers only get `SimEvent::Finished` if the run has not just been paused.
+    fn finish(&mut self, start_time: Instant, status: RunStatus) -> RunStatus {
+        let phase_start = Instant::now();
+        let elapsed = start_time.elapsed();
+
+        self.total_time_in_ms = elapsed.as_secs() as f64 * 1000.0 +
+            elapsed.subsec_nanos() as f64 / 1_000_000.0;
+
+        let iteration = self.simulation_result.iteration_counter;
+        self.notify_phase(Phase::Termination, None, iteration, phase_start);
+        if status != RunStatus::Paused {
+            let event = SimEvent::Finished { status: status.clone(), iteration };
+            publish(&mut self.subscribers, event);
+        }
+
+        status
+    }
+
+    /// Runs the given iteration of all populations that have not retired in parallel and
+    /// records the populations that retire in it. Returns `RunStatus::Error` for the first
+    /// population whose selection failed.
+    fn evolve<S>(&mut self, pool: &ThreadPool, selector: &S, iteration: u32) -> Option<RunStatus>
+    where
+        S: Selector<T>,
+    {
+        let mut results: Vec<Result<(), SelectorError>> = vec![Ok(()); self.habitat.len()];
+        let active: Vec<bool> =
+            self.habitat.iter().map(|population| population.retired.is_none()).collect();
+
+        // The spans of the populations belong to the current iteration, also on the worker
+        // threads.
+        #[cfg(feature = "tracing")]
+        let iteration_span = ::tracing::Span::current();
+
+        pool.install(|| {
+            self.habitat
+                .par_iter_mut()
+                .zip(results.par_iter_mut())
+                .zip(active.par_iter())
+                .filter(|&(_, &active)| active)
+                .for_each(|((population, result), _)| {
+                    #[cfg(feature = "tracing")]
+                    let _span = iteration_span.enter();
+                    *result = population.run_body(selector)
+                });
+        });
+
+        for (population, active) in self.habitat.iter().zip(active) {
+            if let (true, Some(reason)) = (active, population.retired) {
+                self.simulation_result.retirements.push(Retirement {
+                    population_id: population.id,
+                    iteration,
+                    reason,
+                });
+            }
+        }
+
+        self.first_failure(results)
+    }
+
+    /// Calibrates the number of mutations of all populations in parallel, if
+    /// `mutation_calibration` is set. Returns `RunStatus::Error` for the first
+    /// population whose selection failed.
+    fn calibrate<S>(&mut self, pool: &ThreadPool, selector: &S) -> Option<RunStatus>
+    where
+        S: Selector<T>,
+    {
+        let (candidates, iterations) = match self.mutation_calibration {
+            Some((ref candidates, iterations)) => (candidates.clone(), iterations),
+            None => return None,
+        };
+        let mut results: Vec<Result<(), SelectorError>> = vec![Ok(()); self.habitat.len()];
+
+        pool.install(|| {
+            self.habitat.par_iter_mut().zip(results.par_iter_mut()).for_each(
+                |(population, result)| {
+                    *result = population
+                        .calibrate_mutations(selector, &candidates, iterations)
+                        .map(|_| ());
+                },
+            );
+        });
+
+        self.dispatch_population_phases(0);
+        self.first_failure(results)
+    }
+
+    /// Logs the first failed selection and turns it into `SimError::SelectionFailed`.
+    /// Without failed selections returns the first panic, see `first_panic`.
+    fn first_failure(&mut self, results: Vec<Result<(), SelectorError>>) -> Option<RunStatus> {
+        self.habitat
+            .iter()
+            .zip(results)
+            .find_map(|(population, result)| {
+                result.err().map(|error| {
+                    error!("selection failed in population {}: {}", population.tag(), error);
+                    RunStatus::Error(SimError::SelectionFailed(
+                        population.id,
+                        population.label.clone(),
+                        error,
+                    ))
+                })
+            })
+            .or_else(|| self.first_panic())
+    }
+
+    /// Takes the panics that stop the simulation from all populations (see `PanicPolicy`) and
+    /// turns the first one into `SimError::Panicked`.
+    fn first_panic(&mut self) -> Option<RunStatus> {
+        self.habitat
+            .iter_mut()
+            .filter_map(|population| population.panicked.take())
+            .collect::<Vec<PanicReport>>()
+            .into_iter()
+            .next()
+            .map(|report| RunStatus::Error(SimError::Panicked(report)))
+    }
+
+    /// Checks the stop criteria of the simulation types `EndFitness` and `EndFactor`.
+    fn target_reached(&self) -> bool {
+        match self.type_of_simulation {
+            SimulationType::EndIteration(_) | SimulationType::EndCondition(_) => false,
+            SimulationType::EndFactor(end_factor) => {
+                self.simulation_result.improvement_factor <= end_factor
+            }
+            SimulationType::EndFitness(end_fitness) => {
+                self.simulation_result.fittest[0].fitness.to_f64() <=
+                    self.direction.score(end_fitness)
+            }
+        }
+    }
+
+    /// Checks the safety net for the simulation types `EndFitness` and `EndFactor`:
+    /// returns the status if the simulation should give up.
+    fn target_out_of_reach(&self, iteration: u32, stagnation: u32) -> Option<RunStatus> {
+        if iteration >= self.max_iterations {
+            warn!("target not reached after max_iterations: {}", self.max_iterations);
+            Some(RunStatus::IterationLimit)
+        } else if self.stagnation_limit > 0 && stagnation >= self.stagnation_limit &&
+            self.check_every.due(StopCondition::Stagnation, iteration)
+        {
+            warn!("target not reached, no improvement for {} iterations", stagnation);
+            Some(RunStatus::Stagnated)
+        } else {
+            None
+        }
+    }
+
+    /// Returns a handle that pauses this simulation between two iterations, for example from
+    /// another thread or an observer. Get it before `run`: after `Pause::pause` the run returns
+    /// `RunStatus::Paused`, the populations can be inspected or modified and the next `run`
+    /// continues after `Pause::unpause`.
+    pub fn pause_handle(&self) -> Pause {
+        self.pause.clone()
+    }
+
+    /// Estimates the peak memory of the simulation in bytes, based on `Individual::size_hint`
+    /// (the largest individual of each population counts for all of them). During an iteration
+    /// a population holds up to three copies of each individual (the original, the mutated and
+    /// the backup) and one more for the parents and children of the crossover. The global
+    /// fittest individuals (`num_of_global_fittest`) and the current contents of the archives
+    /// (see `Archive::size_hint`) are added on top.
+    /// This is a rough estimate, allocator overhead and the memory of the selectors are ignored.
+    pub fn estimated_memory(&self) -> usize {
+        let overhead = mem::size_of::<IndividualWrapper<T>>() - mem::size_of::<T>();
+        let mut largest = 0;
+        let mut total = 0;
+
+        for population in &self.habitat {
+            let size = population
+                .population
+                .iter()
+                .map(|wrapper| wrapper.individual.size_hint())
+                .max()
+                .unwrap_or(0) + overhead;
+            let copies = if population.crossover.is_some() { 4 } else { 3 };
+            total += size * copies * population.num_of_individuals as usize;
+            total += population.archive.as_ref().map_or(0, |archive| archive.size_hint());
+            largest = largest.max(size);
+        }
+
+        total += self.archive.as_ref().map_or(0, |archive| archive.size_hint());
+        total + largest * self.num_of_global_fittest
+    }
+
+    /// Returns the effective configuration of this simulation and all its populations, one
+    /// line for the simulation and one for each population. Together with the seed it is
+    /// recorded in the result of each run (`SimulationResult::configuration`), so the run can
+    /// be reproduced and audited later.
+    pub fn configuration(&self) -> String {
+        let mut lines = vec![format!(
+            "simulation: {:?}, threads: {}, direction: {:?}, seed: {:?}, max_iterations: {}, \
+             stagnation_limit: {}, time_limit: {:?}, check_every: {:?}, share_fittest: {}, \
+             share_every: {}, share_top: {}, share_probability: {}, interbreed_elites: {}, \
+             num_of_global_fittest: {}, \
+             mutation_calibration: {:?}, memory_limit: {:?}, asynchronous_islands: {}, \
+             migration: {:?}, topology: {:?}, remote_islands: {:?}",
+            self.type_of_simulation,
+            self.num_of_threads,
+            self.direction,
+            self.seed,
+            self.max_iterations,
+            self.stagnation_limit,
+            self.time_limit,
+            self.check_every,
+            self.share_fittest,
+            self.share_every,
+            self.share_top,
+            self.share_probability,
+            self.interbreed_elites,
+            self.num_of_global_fittest,
+            self.mutation_calibration,
+            self.memory_limit,
+            self.asynchronous_islands,
+            self.migration,
+            self.topology,
+            self.remote_islands
+        )];
+        for population in &self.habitat {
+            lines.push(format!("population {}: {}", population.tag(), population.configuration()));
+        }
+        lines.join("\n")
+    }
+
+    /// Creates an independent copy of this simulation, for example to continue a promising
+    /// state after `run` with different settings (simulation type, selector, ...) in several
+    /// branches and compare the outcomes. Each branch should get its own `seed`: the random
+    /// number generator of population n is reseeded with `seed + n`.
+    /// The scheduled parameters are copied (see `ParameterRegistry::detached`), so the branches
+    /// can run in parallel. The observers are shared between all branches.
+    pub fn branch(&self, seed: u64) -> Simulation<T> {
+        let mut branch = self.clone();
+
+        for (population, n) in branch.habitat.iter_mut().zip(0..) {
+            population.rng = rng::from_seed(seed.wrapping_add(n));
+        }
+        branch.parameters = self.parameters.detached();
+        branch.best = BestSnapshot::new();
+        branch.best.replace(self.best.get());
+
+        branch
+    }
+
+    /// Tells all observers that the given phase has been finished.
+    fn notify_phase(&self, phase: Phase, population_id: Option<u32>, iteration: u32, start: Instant) {
+        let event = PhaseEvent {
+            phase,
+            population_id,
+            population_label: String::new(),
+            iteration,
+            duration: start.elapsed(),
+        };
+
+        for observer in &self.observers {
+            observer.phase_finished(&event);
+        }
+    }
+
+    /// Collects and resets the counters of all populations, adds them to the total counters of
+    /// the simulation, records the time since the iteration has `started` and hands the
+    /// statistics of this iteration over to the observers. Returns the statistics.
+    fn collect_counters(&mut self, iteration: u32, started: Instant) -> IterationStats {
+        let mut stats = IterationStats {
+            iteration,
+            duration: started.elapsed(),
+            total: Counters::default(),
+            per_population: Vec::with_capacity(self.habitat.len()),
+            improvement_rates: Vec::with_capacity(self.habitat.len()),
+            fitness: Vec::with_capacity(self.habitat.len()),
+            diversity: Vec::new(),
+            species: Vec::new(),
+        };
+
+        for population in &mut self.habitat {
+            let counters = population.counters;
+            population.counters = Counters::default();
+            population.improvement_rate.push(&counters);
+            stats.total.merge(&counters);
+            stats.per_population.push((population.id, population.label.clone(), counters));
+            stats
+                .improvement_rates
+                .push((population.id, population.improvement_rate.per_1000_evaluations()));
+            let fitness = FitnessStats::of(&population.population, self.direction);
+            stats.fitness.push((population.id, fitness));
+            if self.keep_stats_history {
+                self.simulation_result.stats_history.push((iteration, population.id, fitness));
+            }
+            if self.track_diversity {
+                stats.diversity.push((population.id, Diversity::of(&population.population)));
+            }
+            if population.speciation.is_some() {
+                stats.species.push((population.id, population.species.clone()));
+            }
+        }
+
+        self.simulation_result.counters.merge(&stats.total);
+        self.simulation_result.elapsed += stats.duration;
+        if iteration > 0 {
+            self.simulation_result.iteration_times.push(stats.duration);
+        }
+        self.simulation_result.improvement_rates = stats.improvement_rates.clone();
+
+        for observer in &self.observers {
+            for &(population_id, ref population_label, counters) in &stats.per_population {
+                if counters.crossovers > 0 {
+                    observer.crossover(&CrossoverEvent {
+                        population_id,
+                        population_label: population_label.clone(),
+                        iteration,
+                        children: counters.crossovers,
+                    });
+                }
+            }
+            observer.iteration_finished(&stats);
+        }
+
+        stats
+    }
+
+    /// Hands the current fittest individual over to `best`.
+    fn publish_best(&self) {
+        self.best.replace(Some(Arc::new(self.simulation_result.fittest[0].clone())));
+    }
+
+    /// Records the current fittest individual in the fitness history, if enabled.
+    fn record_history(&mut self, iteration: u32) {
+        let result = &mut self.simulation_result;
+        if let Some(ref mut history) = result.history {
+            let fittest = &result.fittest[0];
+            let fitness = result.direction.apply(&fittest.fitness);
+            history.record(iteration, fitness, &fittest.individual);
+        }
+    }
+
+    /// Hands over the phases and resets recorded by the populations during the last iteration
+    /// to the observers. This is done after all populations have finished, so the worker threads
+    /// never have to synchronize.
+    fn dispatch_population_phases(&mut self, iteration: u32) {
+        for population in &mut self.habitat {
+            for (reset_limit, restarted) in population.reset_log.drain(..) {
+                let event = ResetEvent {
+                    population_id: population.id,
+                    population_label: population.label.clone(),
+                    iteration,
+                    reset_limit,
+                    restarted,
+                };
+
+                for observer in &self.observers {
+                    observer.reset(&event);
+                }
+                let event = SimEvent::Reset { population_id: population.id, iteration };
+                publish(&mut self.subscribers, event);
+            }
+
+            for (phase, duration) in population.phase_timings.drain(..) {
+                let event = PhaseEvent {
+                    phase,
+                    population_id: Some(population.id),
+                    population_label: population.label.clone(),
+                    iteration,
+                    duration,
+                };
+
+                for observer in &self.observers {
+                    observer.phase_finished(&event);
+                }
+            }
+        }
+    }
+
+    /// This is a helper function that the user can call after the simulation stops in order to
+    /// see all the fitness values for all the individuals that participated to the overall
+    /// improvement.
+    pub fn print_fitness(&self) {
+        for wrapper in &self.simulation_result.fittest {
+            let tag = self.habitat
+                .iter()
+                .find(|population| population.id == wrapper.id)
+                .map_or_else(|| format!("{}", wrapper.id), |population| population.tag());
+            info!(
+                "fitness: {:?}, num_of_mutations: {}, population: {}",
+                self.direction.apply(&wrapper.fitness),
+                wrapper.num_of_mutations,
+                tag
+            );
+        }
+    }
+
+    /// Update the internal state of the simulation: Has a new fittest individual been found ?
+    /// Do we want to share it across all the other populations ?
+    /// Also calculates the improvement factor. Returns the statistics of the iteration, which
+    /// has `started` at the given time.
+    fn update_results(&mut self, iteration: u32, started: Instant) -> IterationStats {
+        self.simulation_result.iteration_counter = iteration;
+        self.dispatch_population_phases(iteration);
+        let stats = self.collect_counters(iteration, started);
+
+        if let Some(ref mut archive) = self.archive {
+            for population in &self.habitat {
+                for wrapper in &population.population {
+                    archive.insert(wrapper);
+                }
+            }
+        }
+
+        // Determine the fittest individual of all populations.
+        let mut new_fittest_found = false;
+
+        for population in &mut self.habitat {
+            if population.population[0].fitness < self.simulation_result.fittest[0].fitness {
+                new_fittest_found = true;
+                self.simulation_result.best_iteration = iteration;
+                self.simulation_result.best_population_id = population.id;
+                self.simulation_result.fittest.insert(
+                    0,
+                    population.population[0]
+                        .clone(),
+                );
+                // See https://github.com/willi-kappler/darwin-rs/issues/12
+                self.simulation_result.fittest.truncate(
+                    self.num_of_global_fittest,
+                );
+                population.fitness_counter += 1;
+                let event = NewFittestEvent {
+                    population_id: population.id,
+                    population_label: population.label.clone(),
+                    iteration,
+                    fitness: self.direction.apply(&population.population[0].fitness),
+                    fitness_counter: population.fitness_counter,
+                    fittest: &self.simulation_result.fittest[0],
+                };
+                for observer in &self.observers {
+                    observer.new_fittest(&event);
+                }
+                let event = SimEvent::NewFittest {
+                    fitness: event.fitness,
+                    population_id: population.id,
+                    iteration,
+                };
+                publish(&mut self.subscribers, event);
+                // Call methond `new_fittest_found` of the newly found fittest individual.
+                // The default implementation for this method does nothing.
+                population.guarded(0, "new_fittest_found", |individual, _| {
+                    individual.new_fittest_found()
+                });
+            }
+        }
+
+        if new_fittest_found {
+            self.publish_best();
+        }
+
+        // Now copy the most fittest individual back to each population
+        // if the user has specified it and the share_every count is reached
+        self.share_counter += 1;
+        if self.share_fittest && new_fittest_found && (self.share_counter >= self.share_every) {
+            let phase_start = Instant::now();
+            let population_id = self.simulation_result.best_population_id;
+            let probability = self.share_probability;
+            let source = self
+                .habitat
+                .iter()
+                .position(|population| population.id == population_id)
+                .filter(|&source| self.habitat[source].rng.gen_bool(probability));
+            if let Some(source) = source {
+                // The population that found it and its neighbours in the topology take it in.
+                let mut receivers = self.topology.neighbours(source, self.habitat.len());
+                receivers.push(source);
+                let top = self.share_top.min(self.simulation_result.fittest.len());
+                let shared = &self.simulation_result.fittest[..top];
+                for index in receivers {
+                    self.habitat[index].receive_shared(shared, self.interbreed_elites);
+                }
+                self.share_counter = 0;
+                self.notify_phase(Phase::Migration, None, iteration, phase_start);
+                publish(&mut self.subscribers, SimEvent::Migration { population_id, iteration });
+            }
+        }
+        if self.migration.is_due(iteration) {
+            self.migrate(iteration);
+        }
+
+        self.simulation_result.update_improvement_factor();
+        self.record_history(iteration);
+
+        let multi_objective = self.habitat
+            .iter()
+            .filter(|population| matches!(population.ranking, Ranking::Pareto | Ranking::Nsga2))
+            .flat_map(|population| population.population.iter());
+        for wrapper in multi_objective {
+            self.simulation_result.pareto_front.insert(wrapper);
+        }
+
+        #[cfg(feature = "serialize")]
+        self.write_periodic_checkpoint(iteration);
+
+        stats
+    }
+
+    /// Sends the emigrants of each population that has not retired to its neighbours in the
+    /// topology that have not retired, see `SimulationBuilder::migration_interval`.
+    fn migrate(&mut self, iteration: u32) {
+        let phase_start = Instant::now();
+        let migration = self.migration;
+        let interbreed_elites = self.interbreed_elites;
+
+        let emigrants: Vec<(usize, Vec<IndividualWrapper<T>>)> = self
+            .habitat
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, population)| population.retired.is_none())
+            .map(|(index, population)| {
+                (index, migration.select(&population.population, &mut population.rng))
+            })
+            .collect();
+
+        // The other simulations get the emigrants of all populations, their emigrants go to all
+        // populations.
+        let immigrants = self.remote_islands.as_ref().map(|exchange| {
+            let all: Vec<IndividualWrapper<T>> =
+                emigrants.iter().flat_map(|(_, migrants)| migrants.iter().cloned()).collect();
+            exchange.exchange(&all)
+        });
+
+        for (source, migrants) in emigrants {
+            for index in self.topology.neighbours(source, self.habitat.len()) {
+                let population = &mut self.habitat[index];
+                if population.retired.is_none() {
+                    population.receive_migrants(&migrants, interbreed_elites);
+                }
+            }
+            let population_id = self.habitat[source].id;
+            publish(&mut self.subscribers, SimEvent::Migration { population_id, iteration });
+        }
+        if let Some(immigrants) = immigrants.filter(|immigrants| !immigrants.is_empty()) {
+            for population in &mut self.habitat {
+                if population.retired.is_none() {
+                    population.receive_migrants(&immigrants, interbreed_elites);
+                }
+            }
+        }
+        self.notify_phase(Phase::Migration, None, iteration, phase_start);
+    }
+
+    /// Writes the checkpoint of `checkpoint_every` if it is due after the given iteration.
+    /// A failure is logged, the simulation goes on.
+    #[cfg(feature = "serialize")]
+    fn write_periodic_checkpoint(&self, iteration: u32) {
+        if let Some(ref periodic) = self.checkpoint_every {
+            if iteration % periodic.every == 0 {
+                if let Err(error) = (periodic.write)(self, &periodic.path) {
+                    warn!("checkpoint after iteration {} failed: {}", iteration, error);
+                }
+            }
+        }
+    }
+}
+
+/// Checkpoints, only available with the feature `serialize`, see module checkpoint.
+#[cfg(feature = "serialize")]
+impl<T: Individual + Send + Sync + Clone + Debug> Simulation<T> {
+    /// Writes the state of the simulation (after `run` or between two runs) to the given file.
+    /// Continue it later with `resume`.
+    pub fn checkpoint<P: AsRef<Path>>(&self, path: P) -> checkpoint::Result<()>
+    where
+        T: Serialize,
+        T::Fitness: Serialize,
+    {
+        checkpoint::write_simulation(self, path.as_ref())
+    }
+
+    /// Restores the state written by `checkpoint` into this simulation, which must have been
+    /// built with the same populations. The stop criteria and the other settings may differ.
+    /// The next `run` continues after the iteration of the checkpoint, see `resumed`.
+    pub fn resume<P: AsRef<Path>>(&mut self, path: P) -> checkpoint::Result<()>
+    where
+        T: DeserializeOwned,
+        T::Fitness: DeserializeOwned,
+    {
+        Checkpoint::read(path)?.restore(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::mem;
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::AtomicBool;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use rayon::ThreadPoolBuilder;
+    use simulation::{
+        Arrival, CancellationToken, EndCondition, Island, Pause, Retirement, RunStatus, SimError,
+        SimEvent, SimulationType, StopCondition,
+    };
+    use migration::{Emigrants, MigrantExchange, Migration, Topology};
+    use simulation_builder::{MemoryPolicy, SimulationBuilder};
+    use population_builder::PopulationBuilder;
+    use schedule::Schedule;
+    use select::{MinimizeSelector, RouletteSelector, SelectorError};
+    use individual::{Crossover, Individual, IndividualWrapper};
+    use test::{wrap, Test};
+    use archive::{HallOfFame, MapElites};
+    use population::{PanicPolicy, Ranking, RetireReason};
+    use rand::Rng;
+    use rng::{self, SimRng};
+    use local_search::LocalSearch;
+    use observer::{CrossoverEvent, NewFittestEvent, Observer, ResetEvent};
+    use statistics::IterationStats;
+    use fitness::Fitness;
+    #[cfg(feature = "serialize")]
+    use checkpoint::CheckpointError;
+    #[cfg(feature = "serialize")]
+    use persist::Format;
+    use simulation::Simulation;
+    #[cfg(feature = "serialize")]
+    use std::env;
+
+    #[derive(Debug, Clone)]
+    struct Crossing {
+        f: f64,
+    }
+
+    impl Individual for Crossing {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {}
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            self.f
+        }
+
+        fn reset(&mut self) {}
+    }
+
+    impl Crossover for Crossing {
+        fn crossover(&mut self, other: &mut Crossing, _rng: &mut SimRng) -> Crossing {
+            Crossing { f: (self.f + other.f) / 2.0 }
+        }
+    }
+
+    #[test]
+    fn speciation_selects_parents() {
+        let initial: Vec<Crossing> = (0..10).map(|i| Crossing { f: f64::from(i) }).collect();
+        let builder = PopulationBuilder::<Crossing>::new()
+            .initial_population(&initial)
+            .reset_limit_end(0)
+            .speciation(1.0, 4);
+        // Speciation needs crossover.
+        assert!(builder.finalize().is_err());
+        let population = PopulationBuilder::<Crossing>::new()
+            .initial_population(&initial)
+            .crossover()
+            .reset_limit_end(0)
+            .speciation(1.0, 4)
+            .finalize()
+            .unwrap();
+        let mut simulation = SimulationBuilder::<Crossing>::new()
+            .iterations(10)
+            .add_population(population)
+            .finalize()
+            .unwrap();
+
+        // The selector is not used, so it may not even fit the population.
+        assert_eq!(simulation.run(&MinimizeSelector::new(100)), RunStatus::IterationLimit);
+        // All individuals have the distance 0.0, so they form one species: the mutated and
+        // the original individuals.
+        let species = &simulation.habitat[0].species;
+        assert_eq!(species.len(), 1);
+        assert_eq!(species[0].size, 20);
+        assert_eq!(species[0].offspring, 4);
+        assert_eq!(simulation.simulation_result.counters.crossovers, 40);
+    }
+
+    /// Schaffer's problem: minimize x^2 and (x - 2)^2, the Pareto front is 0 <= x <= 2.
+    #[derive(Debug, Clone)]
+    struct Schaffer {
+        x: f64,
+        rng: SimRng,
+    }
+
+    impl Individual for Schaffer {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {
+            self.x += self.rng.gen_range(-0.5..0.5);
+        }
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            self.x * self.x
+        }
+
+        fn reset(&mut self) {}
+
+        fn objectives(&self) -> Vec<f64> {
+            vec![self.x * self.x, (self.x - 2.0) * (self.x - 2.0)]
+        }
+    }
+
+    impl Crossover for Schaffer {
+        fn crossover(&mut self, other: &mut Schaffer, _rng: &mut SimRng) -> Schaffer {
+            Schaffer { x: (self.x + other.x) / 2.0, rng: rng::from_seed(self.rng.gen()) }
+        }
+    }
+
+    #[test]
+    fn nsga2_approximates_pareto_front() {
+        let population = PopulationBuilder::<Schaffer>::new()
+            .init_seed(3)
+            .generate_initial_population(20, |rng| Schaffer {
+                x: rng.gen_range(4.0..6.0),
+                rng: rng::from_seed(rng.gen()),
+            })
+            .crossover()
+            .ranking(Ranking::Nsga2)
+            .evolution_seed(3)
+            .reset_limit_end(0)
+            .finalize()
+            .unwrap();
+        let mut simulation = SimulationBuilder::<Schaffer>::new()
+            .iterations(50)
+            .add_population(population)
+            .finalize()
+            .unwrap();
+
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::IterationLimit);
+        let front = &simulation.simulation_result.pareto_front;
+        assert!(front.len() > 5);
+        assert!(front.iter().all(|wrapper| (wrapper.individual.x - 1.0).abs() < 1.01));
+        // Spread out over the front, not only near its ends.
+        assert!(front.iter().any(|wrapper| (wrapper.individual.x - 1.0).abs() < 0.5));
+    }
+
+    #[test]
+    fn branches_are_independent() {
+        let population = PopulationBuilder::<Test>::new()
+            .generate_initial_population(10, |_| Test { f: 1.0 })
+            .evolution_seed(1)
+            .finalize()
+            .unwrap();
+        let simulation = SimulationBuilder::<Test>::new()
+            .iterations(10)
+            .add_population(population)
+            .finalize()
+            .unwrap();
+        let sigma = simulation.parameters.register("sigma", Schedule::Constant(1.0));
+
+        let mut branch1 = simulation.branch(1);
+        let branch2 = simulation.branch(2);
+        assert_ne!(branch1.habitat[0].rng, branch2.habitat[0].rng);
+        assert_eq!(branch1.habitat[0].rng, simulation.branch(1).habitat[0].rng);
+
+        branch1.parameters.get("sigma").unwrap().set(2.0);
+        branch1.run(&MinimizeSelector::new(2));
+        assert_eq!(sigma.get(), 1.0);
+        assert_eq!(branch2.parameters.get("sigma").unwrap().get(), 1.0);
+        assert_eq!(branch1.simulation_result.iteration_counter, 10);
+    }
+
+    #[test]
+    fn unreachable_target_stops() {
+        let population = PopulationBuilder::<Test>::new()
+            .generate_initial_population(10, |_| Test { f: 1.0 })
+            .finalize()
+            .unwrap();
+        let mut simulation = SimulationBuilder::<Test>::new()
+            .fitness(0.0)
+            .max_iterations(50)
+            .add_population(population)
+            .finalize()
+            .unwrap();
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::IterationLimit);
+        assert_eq!(simulation.simulation_result.iteration_counter, 50);
+
+        simulation.max_iterations = 1000;
+        simulation.stagnation_limit = 5;
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::Stagnated);
+        assert_eq!(simulation.simulation_result.iteration_counter, 5);
+
+        simulation.type_of_simulation = SimulationType::EndFitness(1.0);
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::TargetReached);
+    }
+
+    #[test]
+    fn migrants_interbreed_with_elites() {
+        let population1 = PopulationBuilder::<Crossing>::new()
+            .initial_population(&[Crossing { f: 1.0 }, Crossing { f: 2.0 }, Crossing { f: 3.0 }])
+            .crossover()
+            .finalize()
+            .unwrap();
+        let population2 = PopulationBuilder::<Crossing>::new()
+            .initial_population(&[Crossing { f: 5.0 }, Crossing { f: 7.0 }, Crossing { f: 9.0 }])
+            .crossover()
+            .set_id(2)
+            .finalize()
+            .unwrap();
+        let mut simulation = SimulationBuilder::<Crossing>::new()
+            .iterations(10)
+            .add_population(population1)
+            .add_population(population2)
+            .share_fittest()
+            .share_every(1)
+            .interbreed_migrants(2)
+            .finalize()
+            .unwrap();
+        for population in &mut simulation.habitat {
+            population.calculate_fitness();
+        }
+        simulation.simulation_result.fittest = vec![simulation.habitat[1].population[0].clone()];
+
+        // Population 1 has a new fittest individual, which migrates to population 2.
+        simulation.update_results(1, Instant::now());
+        assert_eq!(simulation.habitat[1].population[0].individual.f, 5.0);
+        let hybrids: Vec<f64> = simulation.habitat[1].hybrids.iter().map(|h| h.f).collect();
+        assert_eq!(hybrids, vec![3.0, 4.0]);
+    }
+
+    #[test]
+    fn populations_exchange_emigrants() {
+        let population1 = PopulationBuilder::<Test>::new()
+            .generate_initial_population(4, |_| Test { f: 1.0 })
+            .finalize()
+            .unwrap();
+        let population2 = PopulationBuilder::<Test>::new()
+            .generate_initial_population(4, |_| Test { f: 5.0 })
+            .set_id(2)
+            .finalize()
+            .unwrap();
+        let mut simulation = SimulationBuilder::<Test>::new()
+            .iterations(10)
+            .add_population(population1)
+            .add_population(population2)
+            .migration_interval(2)
+            .migrants_per_event(3)
+            .emigrant_selection(Emigrants::Tournament(2))
+            .finalize()
+            .unwrap();
+        assert_eq!(
+            simulation.migration,
+            Migration { interval: 2, migrants: 3, emigrants: Emigrants::Tournament(2) }
+        );
+        let events = simulation.subscribe();
+        for population in &mut simulation.habitat {
+            population.calculate_fitness();
+        }
+        simulation.simulation_result.fittest = vec![simulation.habitat[0].population[0].clone()];
+
+        let fitness = |simulation: &Simulation<Test>, index: usize| -> Vec<f64> {
+            simulation.habitat[index].population.iter().map(|wrapper| wrapper.fitness).collect()
+        };
+        simulation.update_results(1, Instant::now());
+        assert_eq!(fitness(&simulation, 1), vec![5.0; 4]);
+
+        // The emigrants replace the least fit individuals.
+        simulation.update_results(2, Instant::now());
+        assert_eq!(fitness(&simulation, 0), vec![1.0, 5.0, 5.0, 5.0]);
+        assert_eq!(fitness(&simulation, 1), vec![1.0, 1.0, 1.0, 5.0]);
+        let sources: Vec<u32> = events
+            .try_iter()
+            .filter_map(|event| match event {
+                SimEvent::Migration { population_id, iteration: 2 } => Some(population_id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(sources, vec![1, 2]);
+
+        assert!(SimulationBuilder::<Test>::new()
+            .iterations(10)
+            .migrants_per_event(0)
+            .finalize()
+            .is_err());
+    }
+
+    #[test]
+    fn ring_topology_passes_emigrants_on() {
+        let mut builder = SimulationBuilder::<Test>::new()
+            .iterations(10)
+            .migration_interval(1)
+            .topology(Topology::Ring)
+            .share_fittest()
+            .share_every(1);
+        for id in 1..4 {
+            let population = PopulationBuilder::<Test>::new()
+                .generate_initial_population(3, |_| Test { f: f64::from(id) })
+                .set_id(id)
+                .finalize()
+                .unwrap();
+            builder = builder.add_population(population);
+        }
+        let mut simulation = builder.finalize().unwrap();
+        for population in &mut simulation.habitat {
+            population.calculate_fitness();
+        }
+        let mut fittest = simulation.habitat[2].population[0].clone();
+        fittest.fitness = 9.0;
+        simulation.simulation_result.fittest = vec![fittest];
+
+        // Population 1 has the new fittest individual, it is shared with population 2 only
+        // ([1, 2, 2]). Then each population sends its fittest individual to the next one.
+        simulation.update_results(1, Instant::now());
+        let fitness: Vec<Vec<f64>> = simulation
+            .habitat
+            .iter()
+            .map(|population| population.population.iter().map(|w| w.fitness).collect())
+            .collect();
+        assert_eq!(
+            fitness,
+            vec![vec![1.0, 1.0, 3.0], vec![1.0, 1.0, 2.0], vec![1.0, 3.0, 3.0]]
+        );
+
+        assert!(SimulationBuilder::<Test>::new()
+            .iterations(10)
+            .topology(Topology::Custom(vec![vec![1]]))
+            .finalize()
+            .is_err());
+    }
+
+    /// Records the fitness of the emigrants and always sends back the same immigrant.
+    #[derive(Debug, Default)]
+    struct Mailbox {
+        sent: Mutex<Vec<f64>>,
+    }
+
+    impl MigrantExchange<Test> for Mailbox {
+        fn exchange(&self, emigrants: &[IndividualWrapper<Test>]) -> Vec<IndividualWrapper<Test>> {
+            self.sent.lock().unwrap().extend(emigrants.iter().map(|wrapper| wrapper.fitness));
+            wrap(&[Test { f: 0.5 }])
+        }
+    }
+
+    #[test]
+    fn remote_islands_exchange_emigrants() {
+        let mailbox = Arc::new(Mailbox::default());
+        let mut builder = SimulationBuilder::<Test>::new()
+            .iterations(10)
+            .migration_interval(2)
+            .remote_islands(mailbox.clone());
+        for id in 1..3 {
+            let population = PopulationBuilder::<Test>::new()
+                .generate_initial_population(3, |_| Test { f: f64::from(id) })
+                .set_id(id)
+                .finalize()
+                .unwrap();
+            builder = builder.add_population(population);
+        }
+        let mut simulation = builder.finalize().unwrap();
+        for population in &mut simulation.habitat {
+            population.calculate_fitness();
+        }
+        simulation.simulation_result.fittest = wrap(&[Test { f: 0.0 }]);
+
+        simulation.update_results(1, Instant::now());
+        assert!(mailbox.sent.lock().unwrap().is_empty());
+
+        // First the populations exchange their fittest individuals, then the immigrant from
+        // outside replaces the least fit individual of each one.
+        simulation.update_results(2, Instant::now());
+        assert_eq!(*mailbox.sent.lock().unwrap(), vec![1.0, 2.0]);
+        let fitness: Vec<Vec<f64>> = simulation
+            .habitat
+            .iter()
+            .map(|population| population.population.iter().map(|w| w.fitness).collect())
+            .collect();
+        assert_eq!(fitness, vec![vec![0.5, 1.0, 1.0], vec![0.5, 1.0, 2.0]]);
+
+        // The exchange needs the migration.
+        let result = SimulationBuilder::<Test>::new()
+            .iterations(10)
+            .remote_islands(mailbox)
+            .add_population(simulation.habitat.remove(0))
+            .finalize();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn populations_of_different_sizes_evolve_together() {
+        let mut builder = SimulationBuilder::<Walker>::new()
+            .iterations(20)
+            .seed(7)
+            .share_fittest()
+            .share_top(2)
+            .migration_interval(3)
+            .migrants_per_event(2)
+            .emigrant_selection(Emigrants::Random)
+            .keep_stats_history();
+        for &(id, size) in &[(1, 3), (2, 7), (3, 12)] {
+            let population = PopulationBuilder::<Walker>::new()
+                .init_seed(u64::from(id))
+                .generate_initial_population(size, |rng| Walker { x: rng.gen_range(100..200) })
+                .set_id(id)
+                .finalize()
+                .unwrap();
+            builder = builder.add_population(population);
+        }
+        let mut simulation = builder.finalize().unwrap();
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::IterationLimit);
+
+        for population in &simulation.habitat {
+            assert_eq!(population.population.len(), population.num_of_individuals as usize);
+        }
+        let result = &simulation.simulation_result;
+        assert!(result.fittest[0].individual.x < 100);
+        // The initial statistics and the ones of 20 iterations for each population.
+        assert_eq!(result.stats_history.len(), 3 * 21);
+    }
+
+    #[test]
+    fn sharing_spares_explorers() {
+        let build = |probability: f64| {
+            let mut builder = SimulationBuilder::<Test>::new()
+                .iterations(10)
+                .share_fittest()
+                .share_every(1)
+                .share_top(2)
+                .share_probability(probability);
+            for id in 1..4 {
+                let population = PopulationBuilder::<Test>::new()
+                    .generate_initial_population(3, |_| Test { f: f64::from(id) })
+                    .set_id(id);
+                let population = if id == 3 { population.explorer() } else { population };
+                builder = builder.add_population(population.finalize().unwrap());
+            }
+            let mut simulation = builder.finalize().unwrap();
+            for population in &mut simulation.habitat {
+                population.calculate_fitness();
+            }
+            simulation.simulation_result.fittest = wrap(&[Test { f: 9.0 }, Test { f: 10.0 }]);
+            simulation
+        };
+        let fitness = |simulation: &Simulation<Test>| -> Vec<Vec<f64>> {
+            simulation
+                .habitat
+                .iter()
+                .map(|population| population.population.iter().map(|w| w.fitness).collect())
+                .collect()
+        };
+
+        // Population 1 finds the new fittest individual, the top two (1.0 and 9.0) replace the
+        // least fit individuals of the populations that are no explorers.
+        let mut simulation = build(1.0);
+        simulation.update_results(1, Instant::now());
+        assert_eq!(
+            fitness(&simulation),
+            vec![vec![1.0, 1.0, 9.0], vec![1.0, 2.0, 9.0], vec![3.0, 3.0, 3.0]]
+        );
+        assert_eq!(simulation.share_counter, 0);
+
+        let mut simulation = build(1e-12);
+        simulation.update_results(1, Instant::now());
+        assert_eq!(
+            fitness(&simulation),
+            vec![vec![1.0, 1.0, 1.0], vec![2.0, 2.0, 2.0], vec![3.0, 3.0, 3.0]]
+        );
+        assert_eq!(simulation.share_counter, 1);
+
+        for probability in &[0.0, 1.5, f64::NAN] {
+            let builder = SimulationBuilder::<Test>::new().iterations(10);
+            assert!(builder.share_probability(*probability).finalize().is_err());
+        }
+        assert!(SimulationBuilder::<Test>::new().iterations(10).share_top(0).finalize().is_err());
+    }
+
+    #[derive(Debug, Clone)]
+    struct Grumpy {
+        f: f64,
+    }
+
+    impl Individual for Grumpy {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {}
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            self.f
+        }
+
+        fn reset(&mut self) {}
+
+        fn new_fittest_found(&mut self) {
+            panic!("not impressed");
+        }
+    }
+
+    impl Crossover for Grumpy {
+        fn crossover(&mut self, _other: &mut Grumpy, _rng: &mut SimRng) -> Grumpy {
+            panic!("no partner");
+        }
+    }
+
+    #[test]
+    fn migration_panics_are_caught() {
+        let initial = [Grumpy { f: 1.0 }, Grumpy { f: 2.0 }, Grumpy { f: 3.0 }];
+        let population1 = PopulationBuilder::<Grumpy>::new()
+            .initial_population(&initial)
+            .crossover()
+            .finalize()
+            .unwrap();
+        let population2 = PopulationBuilder::<Grumpy>::new()
+            .initial_population(&initial)
+            .crossover()
+            .set_id(2)
+            .finalize()
+            .unwrap();
+        let mut simulation = SimulationBuilder::<Grumpy>::new()
+            .iterations(10)
+            .add_population(population1)
+            .add_population(population2)
+            .share_fittest()
+            .share_every(1)
+            .interbreed_migrants(1)
+            .finalize()
+            .unwrap();
+        for population in &mut simulation.habitat {
+            population.calculate_fitness();
+        }
+        let mut fittest = simulation.habitat[1].population[2].clone();
+        fittest.fitness = 5.0;
+        simulation.simulation_result.fittest = vec![fittest];
+
+        // Population 1 reports the new fittest individual, it migrates to population 2.
+        simulation.update_results(1, Instant::now());
+        let report = simulation.habitat[0].panicked.take().unwrap();
+        assert_eq!(report.operation, "new_fittest_found");
+        assert_eq!(report.message, "not impressed");
+        let report = simulation.habitat[1].panicked.take().unwrap();
+        assert_eq!(report.operation, "crossover");
+        assert_eq!(report.individual.unwrap(), "Grumpy { f: 1.0 }");
+        assert!(simulation.habitat[1].hybrids.is_empty());
+        assert_eq!(simulation.habitat[1].counters.panics, 1);
+    }
+
+    #[test]
+    fn result_records_provenance() {
+        let population = |id, f| {
+            PopulationBuilder::<Test>::new()
+                .set_id(id)
+                .generate_initial_population(5, |_| Test { f })
+                .finalize()
+                .unwrap()
+        };
+        let mut simulation = SimulationBuilder::<Test>::new()
+            .iterations(10)
+            .seed(7)
+            .code_revision("abc123")
+            .add_population(population(3, 5.0))
+            .add_population(population(4, 1.0))
+            .finalize()
+            .unwrap();
+        simulation.run(&MinimizeSelector::new(2));
+
+        let result = &simulation.simulation_result;
+        assert_eq!(result.seed, Some(7));
+        assert_eq!(result.provenance.code_revision, Some("abc123".to_string()));
+        // The initial fittest individual is taken from the first population, the second one
+        // reports the better one after the first iteration.
+        assert_eq!((result.best_iteration, result.best_population_id), (1, 4));
+
+        let lines: Vec<&str> = result.configuration.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("EndIteration(10)") && lines[0].contains("seed: Some(7)"));
+        assert!(lines[2].starts_with("population 4: individuals: 5,"));
+    }
+
+    #[test]
+    fn selection_error_stops_simulation() {
+        let population = PopulationBuilder::<Crossing>::new()
+            .initial_population(&[Crossing { f: 3.0 }, Crossing { f: 2.0 }, Crossing { f: 1.0 }])
+            .crossover()
+            .set_id(7)
+            .label("tiny")
+            .finalize()
+            .unwrap();
+        let mut simulation = SimulationBuilder::<Crossing>::new()
+            .iterations(20)
+            .add_population(population)
+            .finalize()
+            .unwrap();
+        // 2 * 4 parents are more than the population has.
+        let error = SimError::SelectionFailed(
+            7,
+            "tiny".to_string(),
+            SelectorError::PopulationTooSmall { required: 9, actual: 6 },
+        );
+        assert_eq!(simulation.run(&MinimizeSelector::new(4)), RunStatus::Error(error.clone()));
+        assert!(error.to_string().starts_with("selection failed in population 7 (tiny): "));
+        assert_eq!(simulation.simulation_result.iteration_counter, 1);
+    }
+
+    #[test]
+    fn stop_conditions_checked_every_n() {
+        let population = PopulationBuilder::<Test>::new()
+            .generate_initial_population(10, |_| Test { f: 1.0 })
+            .finalize()
+            .unwrap();
+        let mut simulation = SimulationBuilder::<Test>::new()
+            .fitness(1.0)
+            .check_every(StopCondition::Target, 7)
+            .add_population(population)
+            .finalize()
+            .unwrap();
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::TargetReached);
+        assert_eq!(simulation.simulation_result.iteration_counter, 7);
+
+        simulation.type_of_simulation = SimulationType::EndFitness(0.0);
+        simulation.stagnation_limit = 5;
+        simulation.check_every.set(StopCondition::Stagnation, 4);
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::Stagnated);
+        assert_eq!(simulation.simulation_result.iteration_counter, 8);
+
+        simulation.max_iterations = 6;
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::IterationLimit);
+        assert_eq!(simulation.simulation_result.iteration_counter, 6);
+    }
+
+    #[derive(Debug, Clone)]
+    struct Grower {
+        x: f64,
+    }
+
+    impl Individual for Grower {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {
+            self.x += 1.0;
+        }
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            self.x
+        }
+
+        fn reset(&mut self) {}
+    }
+
+    #[test]
+    fn maximize_flips_all_comparisons() {
+        let build = |maximize: bool| {
+            let population = PopulationBuilder::<Grower>::new()
+                .generate_initial_population(4, |_| Grower { x: 1.0 })
+                .reset_limit_end(0)
+                .finalize()
+                .unwrap();
+            let mut builder = SimulationBuilder::<Grower>::new()
+                .fitness(5.0)
+                .max_iterations(20)
+                .share_fittest()
+                .add_population(population);
+            if maximize {
+                builder = builder.maximize();
+            }
+            builder.finalize().unwrap()
+        };
+
+        // Minimizing, the mutations never help and 5.0 is reached at once.
+        let mut simulation = build(false);
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::TargetReached);
+        assert_eq!(simulation.simulation_result.iteration_counter, 1);
+
+        let mut simulation = build(true);
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::TargetReached);
+        let result = &simulation.simulation_result;
+        assert_eq!(result.iteration_counter, 4);
+        assert_eq!(result.fitness(&result.fittest[0]), 5.0);
+        assert_eq!(result.improvement_factor, 0.2);
+    }
+
+    #[derive(Debug, Clone, Default)]
+    struct Goal {
+        target: f64,
+        step: f64,
+    }
+
+    #[derive(Debug, Clone)]
+    struct Seeker {
+        x: f64,
+    }
+
+    impl Individual for Seeker {
+        type Fitness = f64;
+        type Context = Goal;
+
+        fn mutate(&mut self, context: &Goal, _rng: &mut SimRng) {
+            self.x += context.step;
+        }
+
+        fn calculate_fitness(&mut self, context: &Goal) -> f64 {
+            (context.target - self.x).abs()
+        }
+
+        fn reset(&mut self) {}
+    }
+
+    #[test]
+    fn context_is_shared_by_all_populations() {
+        let population = |id| {
+            PopulationBuilder::<Seeker>::new()
+                .set_id(id)
+                .generate_initial_population(4, |_| Seeker { x: 0.0 })
+                .reset_limit_end(0)
+                .finalize()
+                .unwrap()
+        };
+        let mut simulation = SimulationBuilder::<Seeker>::new()
+            .fitness(0.0)
+            .max_iterations(20)
+            .context(Goal { target: 4.0, step: 1.0 })
+            .add_population(population(1))
+            .add_population(population(2))
+            .finalize()
+            .unwrap();
+
+        assert!(simulation
+            .habitat
+            .iter()
+            .all(|population| Arc::ptr_eq(&population.context, &simulation.context)));
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::TargetReached);
+        assert_eq!(simulation.simulation_result.iteration_counter, 4);
+        assert_eq!(simulation.simulation_result.original_fitness, 4.0);
+    }
+
+    #[test]
+    fn memory_estimate() {
+        let population = PopulationBuilder::<Test>::new()
+            .generate_initial_population(10, |_| Test { f: 1.0 })
+            .finalize()
+            .unwrap();
+        let mut simulation = SimulationBuilder::<Test>::new()
+            .iterations(10)
+            .add_population(population)
+            .num_of_global_fittest(5)
+            .finalize()
+            .unwrap();
+        // Test (8 bytes) + fitness, num_of_mutations, id, age, dirty, case_errors and
+        // objectives.
+        let size = mem::size_of::<IndividualWrapper<Test>>();
+        assert_eq!(size, 80);
+        assert_eq!(simulation.estimated_memory(), size * (3 * 10 + 5));
+
+        simulation.habitat[0].crossover = Some(|a, _, _| *a);
+        assert_eq!(simulation.estimated_memory(), size * (4 * 10 + 5));
+    }
+
+    #[test]
+    fn memory_limit() {
+        let builder = SimulationBuilder::<Test>::new()
+            .iterations(10)
+            .add_population(
+                PopulationBuilder::<Test>::new()
+                    .generate_initial_population(10, |_| Test { f: 1.0 })
+                    .finalize()
+                    .unwrap(),
+            )
+            .num_of_global_fittest(100);
+        let size = mem::size_of::<IndividualWrapper<Test>>();
+
+        assert!(builder.clone().memory_limit(size * 130, MemoryPolicy::Refuse).finalize().is_ok());
+        assert!(builder.clone().memory_limit(size * 129, MemoryPolicy::Refuse).finalize().is_err());
+
+        let shrunk = builder
+            .clone()
+            .memory_limit(size * 50, MemoryPolicy::ShrinkArchive)
+            .finalize()
+            .unwrap();
+        assert_eq!(shrunk.num_of_global_fittest, 20);
+        assert!(builder.memory_limit(size * 30, MemoryPolicy::ShrinkArchive).finalize().is_err());
+    }
+
+    #[test]
+    fn growing_archive_trips_memory_limit() {
+        let population = PopulationBuilder::<Walker>::new()
+            .generate_initial_population(4, |_| Walker { x: 1000 })
+            .reset_limit_end(0)
+            .finalize()
+            .unwrap();
+        let size = mem::size_of::<IndividualWrapper<Walker>>();
+        // Each walker moves to a new cell in every iteration, so the archive keeps growing.
+        let mut simulation = SimulationBuilder::<Walker>::new()
+            .iterations(100)
+            .add_population(population)
+            .num_of_global_fittest(1)
+            .archive(MapElites::new(|walker: &Walker| vec![walker.x as usize]))
+            .memory_limit(size * 30, MemoryPolicy::Refuse)
+            .finalize()
+            .unwrap();
+
+        match simulation.run(&MinimizeSelector::new(2)) {
+            RunStatus::Error(SimError::MemoryLimitExceeded(estimate, limit)) => {
+                assert!(estimate > limit);
+                assert_eq!(limit, size * 30);
+            }
+            status => panic!("unexpected status {:?}", status),
+        }
+        assert!(simulation.archive.as_ref().unwrap().len() > 4);
+        assert!(simulation.simulation_result.iteration_counter < 100);
+    }
+
+    #[derive(Debug, Clone)]
+    struct Walker {
+        x: i64,
+    }
+
+    impl Individual for Walker {
+        type Fitness = f64;
+        type Context = ();
+
+        // Each mutation moves one step towards zero.
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {
+            self.x -= self.x.signum();
+        }
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            self.x.abs() as f64
+        }
+
+        fn reset(&mut self) {}
+    }
+
+    #[test]
+    fn islands_run_asynchronously() {
+        let population1 = PopulationBuilder::<Walker>::new()
+            .generate_initial_population(5, |_| Walker { x: 10 })
+            .finalize()
+            .unwrap();
+        let population2 = PopulationBuilder::<Walker>::new()
+            .generate_initial_population(5, |_| Walker { x: 1000 })
+            .set_id(2)
+            .finalize()
+            .unwrap();
+        let recorder = Arc::new(Recorder::default());
+        let mut simulation = SimulationBuilder::<Walker>::new()
+            .factor(0.0)
+            .add_population(population1)
+            .add_population(population2)
+            .share_fittest()
+            .share_every(1)
+            .asynchronous_islands()
+            // Both islands share the single evaluation thread.
+            .threads(1)
+            .observer(recorder.clone())
+            .finalize()
+            .unwrap();
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::TargetReached);
+
+        // Which population gets there first depends on the threads.
+        let result = &simulation.simulation_result;
+        assert_eq!(result.fittest[0].individual.x, 0);
+        assert!(result.iteration_counter > 0);
+        let events = recorder.events.lock().unwrap();
+        assert!(events.iter().all(|event| event.starts_with("fittest")));
+        assert!(events.last().unwrap().ends_with(" 0"));
+
+        // A population takes in the shared individuals before its next iteration and shares its
+        // own improvements.
+        let mut population = PopulationBuilder::<Walker>::new()
+            .generate_initial_population(5, |_| Walker { x: 1000 })
+            .finalize()
+            .unwrap();
+        population.calculate_fitness();
+        let (migrant_sender, migrants) = mpsc::channel();
+        let (neighbour, shared) = mpsc::channel();
+        let (report_sender, reports) = mpsc::channel();
+        migrant_sender.send(Arrival::Shared(wrap(&[Walker { x: 3 }]))).unwrap();
+        let island = Island {
+            index: 0,
+            iterations: (1, 2),
+            share_every: Some(1),
+            share_top: 1,
+            share_probability: 1.0,
+            migration: Migration::default(),
+            interbreed_elites: 0,
+            migrants,
+            neighbours: vec![neighbour],
+            reports: report_sender,
+            stop: &AtomicBool::new(false),
+            pool: &ThreadPoolBuilder::new().num_threads(1).build().unwrap(),
+        };
+        island.run(&mut population, &MinimizeSelector::new(2));
+
+        assert_eq!(population.population[0].individual.x, 1);
+        let shared: Vec<i64> = shared
+            .try_iter()
+            .map(|arrival| match arrival {
+                Arrival::Shared(shared) => shared[0].individual.x,
+                Arrival::Emigrants(_) => panic!("no migration"),
+            })
+            .collect();
+        assert_eq!(shared, vec![2, 1]);
+        let iterations: Vec<u32> = reports.try_iter().map(|report| report.iteration).collect();
+        assert_eq!(iterations, vec![1, 2]);
+    }
+
+    /// Records the events of the observer hooks.
+    #[derive(Debug, Default)]
+    struct Recorder {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl<T: Individual> Observer<T> for Recorder {
+        fn iteration_start(&self, iteration: u32) {
+            self.events.lock().unwrap().push(format!("start {}", iteration));
+        }
+
+        fn new_fittest(&self, event: &NewFittestEvent<T>) {
+            let fitness = event.fitness.to_f64();
+            self.events.lock().unwrap().push(format!("fittest {} {}", event.iteration, fitness));
+        }
+
+        fn reset(&self, event: &ResetEvent) {
+            let limit = event.reset_limit;
+            self.events.lock().unwrap().push(format!("reset {} {}", event.iteration, limit));
+        }
+
+        fn crossover(&self, event: &CrossoverEvent) {
+            let children = event.children;
+            self.events.lock().unwrap().push(format!("crossover {} {}", event.iteration, children));
+        }
+    }
+
+    #[test]
+    fn observer_hooks() {
+        let recorder = Arc::new(Recorder::default());
+        let population = PopulationBuilder::<Walker>::new()
+            .generate_initial_population(4, |_| Walker { x: 20 })
+            .reset_limit_end(0)
+            .finalize()
+            .unwrap();
+        let mut simulation = SimulationBuilder::<Walker>::new()
+            .iterations(10)
+            .observer(recorder.clone())
+            .add_population(population)
+            .finalize()
+            .unwrap();
+        simulation.run(&MinimizeSelector::new(2));
+        let events = mem::take(&mut *recorder.events.lock().unwrap());
+        assert_eq!(events[..4], ["start 1", "fittest 1 19", "start 2", "fittest 2 18"]);
+        assert_eq!(events.len(), 20);
+
+        let initial: Vec<Crossing> = (0..10).map(|i| Crossing { f: f64::from(i) }).collect();
+        let population = PopulationBuilder::<Crossing>::new()
+            .initial_population(&initial)
+            .crossover()
+            .reset_limit_start(3)
+            .reset_limit_end(100)
+            .reset_limit_increment(1)
+            .finalize()
+            .unwrap();
+        let mut simulation = SimulationBuilder::<Crossing>::new()
+            .iterations(10)
+            .observer(recorder.clone())
+            .add_population(population)
+            .finalize()
+            .unwrap();
+        simulation.run(&MinimizeSelector::new(2));
+        let events = recorder.events.lock().unwrap();
+        let resets: Vec<&String> = events.iter().filter(|e| e.starts_with("reset")).collect();
+        assert_eq!(resets, ["reset 4 4", "reset 9 5"]);
+        assert_eq!(events.iter().filter(|e| e.starts_with("crossover")).count(), 10);
+        assert!(!events.iter().any(|e| e.starts_with("fittest")));
+    }
+
+    #[test]
+    fn subscribers_receive_events() {
+        let populations = (1..3)
+            .map(|id| {
+                PopulationBuilder::<Walker>::new()
+                    .set_id(id)
+                    .generate_initial_population(4, |_| Walker { x: 20 })
+                    .reset_limit_end(0)
+                    .finalize()
+                    .unwrap()
+            })
+            .collect();
+        let mut simulation = SimulationBuilder::<Walker>::new()
+            .iterations(10)
+            .share_fittest()
+            .share_every(1)
+            .add_multiple_populations(populations)
+            .finalize()
+            .unwrap();
+        let receiver = simulation.subscribe();
+        let monitor = thread::spawn(move || receiver.iter().collect::<Vec<_>>());
+        simulation.run(&MinimizeSelector::new(2));
+        // The monitor gets all events once the receiver is dropped with the simulation.
+        drop(simulation);
+        let events = monitor.join().unwrap();
+
+        assert_eq!(
+            events[0],
+            SimEvent::NewFittest { fitness: 19.0, population_id: 1, iteration: 1 }
+        );
+        assert_eq!(events[1], SimEvent::Migration { population_id: 1, iteration: 1 });
+        assert_eq!(
+            events.last(),
+            Some(&SimEvent::Finished { status: RunStatus::IterationLimit, iteration: 10 })
+        );
+        let migrations = events.iter().filter(|e| matches!(e, SimEvent::Migration { .. }));
+        assert_eq!(migrations.count(), 10);
+    }
+
+    /// Collects the names of the new spans.
+    #[cfg(feature = "tracing")]
+    #[derive(Default)]
+    struct SpanNames {
+        names: Mutex<Vec<&'static str>>,
+    }
+
+    #[cfg(feature = "tracing")]
+    impl tracing::Subscriber for SpanNames {
+        fn enabled(&self, _metadata: &tracing::Metadata) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &tracing::span::Attributes) -> tracing::span::Id {
+            let mut names = self.names.lock().unwrap();
+            names.push(span.metadata().name());
+            tracing::span::Id::from_u64(names.len() as u64)
+        }
+
+        fn record(&self, _span: &tracing::span::Id, _values: &tracing::span::Record) {}
+
+        fn record_follows_from(&self, _span: &tracing::span::Id, _follows: &tracing::span::Id) {}
+
+        fn event(&self, _event: &tracing::Event) {}
+
+        fn enter(&self, _span: &tracing::span::Id) {}
+
+        fn exit(&self, _span: &tracing::span::Id) {}
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn run_is_traced() {
+        let mut simulation = endless().iterations(10).finalize().unwrap();
+        let subscriber = Arc::new(SpanNames::default());
+        tracing::subscriber::with_default(subscriber.clone(), || {
+            simulation.run(&MinimizeSelector::new(2));
+        });
+        // The spans of the populations are created on the worker threads.
+        let names = subscriber.names.lock().unwrap();
+        assert_eq!(names[0], "run");
+        assert_eq!(names.iter().filter(|&&name| name == "iteration").count(), 10);
+    }
+
+    #[test]
+    fn histories_are_kept() {
+        let population = PopulationBuilder::<Walker>::new()
+            .set_id(3)
+            .generate_initial_population(4, |_| Walker { x: 20 })
+            .reset_limit_end(0)
+            .finalize()
+            .unwrap();
+        let mut simulation = SimulationBuilder::<Walker>::new()
+            .iterations(10)
+            .keep_stats_history()
+            .record_history(0, true)
+            .add_population(population)
+            .finalize()
+            .unwrap();
+        simulation.run(&MinimizeSelector::new(2));
+
+        let history = simulation.simulation_result.history.as_ref().unwrap();
+        let expected: Vec<(u32, f64)> = (0..11).map(|i| (i, f64::from(20 - i))).collect();
+        assert_eq!(history.curve(), expected);
+        assert_eq!(history.entries[10].snapshot.as_ref().map(|walker| walker.x), Some(10));
+
+        let history = &simulation.simulation_result.stats_history;
+        assert_eq!(history.len(), 11);
+        let (iteration, id, initial) = history[0];
+        assert_eq!((iteration, id, initial.best, initial.std_dev), (0, 3, 20.0, 0.0));
+        let (iteration, _, last) = history[10];
+        assert_eq!((iteration, last.best), (10, 10.0));
+        assert!(last.best <= last.median && last.median <= last.worst);
+    }
+
+    #[test]
+    fn paused_run_does_not_publish_finished() {
+        let mut simulation = endless().iterations(10).finalize().unwrap();
+        let pause = simulation.pause_handle();
+        simulation.observers.push(Arc::new(Pauser { pause: pause.clone(), after: 3 }));
+        let receiver = simulation.subscribe();
+
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::Paused);
+        let finished = |event: &SimEvent<f64>| matches!(event, SimEvent::Finished { .. });
+        assert!(!receiver.try_iter().any(|event| finished(&event)));
+
+        pause.unpause();
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::IterationLimit);
+        let events: Vec<_> = receiver.try_iter().filter(finished).collect();
+        assert_eq!(
+            events,
+            [SimEvent::Finished { status: RunStatus::IterationLimit, iteration: 10 }]
+        );
+    }
+
+    #[test]
+    fn calibration_picks_best_mutation_rate() {
+        let population = PopulationBuilder::<Walker>::new()
+            .generate_initial_population(5, |_| Walker { x: 1000 })
+            .reset_limit_end(0)
+            .finalize()
+            .unwrap();
+        let mut simulation = SimulationBuilder::<Walker>::new()
+            .iterations(10)
+            .add_population(population)
+            .calibrate_mutations(&[1, 8, 3], 2)
+            .finalize()
+            .unwrap();
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::IterationLimit);
+
+        let population = &simulation.habitat[0];
+        assert!(population.population.iter().all(|wrapper| wrapper.num_of_mutations == 8));
+        // 2 calibration iterations and 10 iterations with 8 steps each.
+        assert_eq!(population.population[0].individual.x, 1000 - 12 * 8);
+        // The initial evaluation, 3 candidates * 2 iterations and 10 iterations.
+        assert_eq!(simulation.simulation_result.counters.evaluations, 5 + 3 * 2 * 5 + 10 * 5);
+        // Each of the 3 * 2 calibration iterations and 10 iterations improved the fittest one.
+        assert_eq!(simulation.simulation_result.counters.improvements, 16);
+        assert_eq!(simulation.simulation_result.improvement_rates, vec![(1, 16.0 * 1000.0 / 85.0)]);
+
+        assert!(SimulationBuilder::<Walker>::new()
+            .iterations(10)
+            .calibrate_mutations(&[], 2)
+            .finalize()
+            .is_err());
+    }
+
+    #[test]
+    fn archives_collect_survivors() {
+        let population = PopulationBuilder::<Walker>::new()
+            .generate_initial_population(4, |_| Walker { x: 100 })
+            .reset_limit_end(0)
+            .archive(HallOfFame::new(3))
+            .finalize()
+            .unwrap();
+        let mut simulation = SimulationBuilder::<Walker>::new()
+            .iterations(10)
+            .add_population(population)
+            .archive(MapElites::new(|walker: &Walker| vec![(walker.x % 2) as usize]))
+            .finalize()
+            .unwrap();
+        simulation.run(&MinimizeSelector::new(2));
+
+        // All walkers take one step per iteration.
+        let archive = simulation.habitat[0].archive.as_ref().unwrap();
+        assert_eq!(archive.best().unwrap().individual.x, 90);
+        assert_eq!(archive.len(), 3);
+        let archive = simulation.archive.as_ref().unwrap();
+        assert_eq!(archive.len(), 2);
+        assert_eq!(archive.best().unwrap().individual.x, 90);
+    }
+
+    #[derive(Debug, Clone)]
+    struct Fragile {
+        f: f64,
+        broken: bool,
+    }
+
+    impl Individual for Fragile {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {
+            if self.broken {
+                panic!("broken genome");
+            }
+        }
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            self.f
+        }
+
+        fn reset(&mut self) {}
+    }
+
+    impl Crossover for Fragile {
+        fn crossover(&mut self, _other: &mut Fragile, _rng: &mut SimRng) -> Fragile {
+            Fragile { f: self.f, broken: false }
+        }
+    }
+
+    #[test]
+    fn panics_are_caught() {
+        let run = |policy| {
+            let initial: Vec<Fragile> =
+                (0..5).map(|i| Fragile { f: f64::from(i), broken: i == 2 }).collect();
+            let population = PopulationBuilder::<Fragile>::new()
+                .initial_population(&initial)
+                .panic_policy(policy)
+                .label("fragile")
+                .reset_limit_end(0)
+                .finalize()
+                .unwrap();
+            let mut simulation = SimulationBuilder::<Fragile>::new()
+                .iterations(10)
+                .add_population(population)
+                .finalize()
+                .unwrap();
+            let status = simulation.run(&MinimizeSelector::new(2));
+            (status, simulation.simulation_result)
+        };
+
+        let (status, result) = run(PanicPolicy::Abort);
+        assert_eq!(result.iteration_counter, 1);
+        match status {
+            RunStatus::Error(SimError::Panicked(report)) => {
+                assert_eq!(report.population_id, 1);
+                assert_eq!(report.population_label, "fragile");
+                assert_eq!(report.operation, "mutate");
+                assert_eq!(report.message, "broken genome");
+                assert_eq!(
+                    report.to_string(),
+                    "mutate panicked in population 1 (fragile): broken genome, \
+                     individual: Fragile { f: 2.0, broken: true }"
+                );
+                assert_eq!(report.individual.unwrap(), "Fragile { f: 2.0, broken: true }");
+            }
+            status => panic!("unexpected status: {:?}", status),
+        }
+
+        // The unmutated copy of the broken individual survives the first iteration and panics
+        // again, then the copies of the fitter individuals push it out.
+        let (status, result) = run(PanicPolicy::WorstFitness);
+        assert_eq!(status, RunStatus::IterationLimit);
+        assert_eq!(result.iteration_counter, 10);
+        assert_eq!(result.counters.panics, 2);
+        assert_eq!(result.fittest[0].fitness, 0.0);
+    }
+
+    #[test]
+    fn panics_with_roulette_selection() {
+        let initial: Vec<Fragile> =
+            (0..5).map(|i| Fragile { f: f64::from(i), broken: i == 2 }).collect();
+        let population = PopulationBuilder::<Fragile>::new()
+            .initial_population(&initial)
+            .panic_policy(PanicPolicy::WorstFitness)
+            .crossover()
+            .reset_limit_end(0)
+            .finalize()
+            .unwrap();
+        let mut simulation = SimulationBuilder::<Fragile>::new()
+            .iterations(10)
+            .add_population(population)
+            .finalize()
+            .unwrap();
+
+        // The broken individual has the fitness infinity during the selection, the roulette
+        // wheel must skip it instead of failing.
+        assert_eq!(simulation.run(&RouletteSelector::new(4)), RunStatus::IterationLimit);
+        assert!(simulation.simulation_result.counters.panics > 0);
+        assert_eq!(simulation.simulation_result.fittest[0].fitness, 0.0);
+    }
+
+    /// Moves the individual with fitness 3.0 to 0.0 and gives up on the one with 2.0.
+    struct Polish;
+
+    impl LocalSearch<Test> for Polish {
+        fn improve(
+            &self,
+            individual: &mut Test,
+            _: &(),
+            _: &mut SimRng,
+            fitness: f64,
+            _budget: u32,
+        ) -> f64 {
+            if fitness == 2.0 {
+                individual.f = -1.0;
+                return f64::NAN;
+            }
+            if fitness == 3.0 {
+                individual.f = 0.0;
+            }
+            individual.f
+        }
+    }
+
+    #[test]
+    fn polish_improves_fittest() {
+        let population = PopulationBuilder::<Test>::new()
+            .generate_initial_population(10, |_| Test { f: 1.0 })
+            .finalize()
+            .unwrap();
+        let mut simulation = SimulationBuilder::<Test>::new()
+            .iterations(10)
+            .add_population(population)
+            .finalize()
+            .unwrap();
+        let mut fittest = PopulationBuilder::<Test>::new()
+            .initial_population(&[Test { f: 1.0 }, Test { f: 2.0 }, Test { f: 3.0 }])
+            .finalize()
+            .unwrap();
+        fittest.calculate_fitness();
+        let result = &mut simulation.simulation_result;
+        result.original_fitness = 4.0;
+        result.fittest = fittest.population;
+
+        let report = result.polish(&Polish, &(), &mut rng::from_seed(1), 10);
+        assert_eq!(report, vec![(1.0, 1.0), (2.0, 2.0), (3.0, 0.0)]);
+        let fitness: Vec<f64> = result.fittest.iter().map(|wrapper| wrapper.fitness).collect();
+        assert_eq!(fitness, vec![0.0, 1.0, 2.0]);
+        // The individual of the NaN result is unchanged.
+        assert_eq!(result.fittest[2].individual.f, 2.0);
+        assert_eq!(result.improvement_factor, 0.0);
+    }
+
+    /// Cancels the token after the given iteration.
+    #[derive(Debug)]
+    struct Canceller {
+        token: CancellationToken,
+        after: u32,
+    }
+
+    impl Observer<Test> for Canceller {
+        fn iteration_finished(&self, stats: &IterationStats) {
+            if stats.iteration == self.after {
+                self.token.cancel();
+            }
+        }
+    }
+
+    fn endless() -> SimulationBuilder<Test> {
+        let population = PopulationBuilder::<Test>::new()
+            .generate_initial_population(10, |_| Test { f: 1.0 })
+            .finalize()
+            .unwrap();
+        // The target can never be reached.
+        SimulationBuilder::<Test>::new().fitness(0.0).add_population(population)
+    }
+
+    #[test]
+    fn cancelled_simulation_stops() {
+        let token = CancellationToken::new();
+        let mut simulation = endless().cancellation_token(&token).finalize().unwrap();
+        simulation.observers.push(Arc::new(Canceller { token, after: 3 }));
+
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::Cancelled);
+        assert_eq!(simulation.simulation_result.iteration_counter, 3);
+        assert_eq!(simulation.simulation_result.fittest[0].fitness, 1.0);
+
+        // The flag stays set, so the next run stops after its first iteration.
+        simulation.type_of_simulation = SimulationType::EndIteration(100);
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::Cancelled);
+        assert_eq!(simulation.simulation_result.iteration_counter, 1);
+    }
+
+    #[test]
+    fn token_cancels_from_other_thread() {
+        let token = CancellationToken::new();
+        let mut simulation = endless().cancellation_token(&token).finalize().unwrap();
+        let canceller = {
+            let token = token.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(20));
+                token.cancel();
+            })
+        };
+
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::Cancelled);
+        canceller.join().unwrap();
+        assert!(simulation.cancelled.is_cancelled());
+        assert!(simulation.simulation_result.iteration_counter > 0);
+        assert_eq!(simulation.simulation_result.fittest[0].fitness, 1.0);
+    }
+
+    /// Pauses the simulation after the given iteration.
+    #[derive(Debug)]
+    struct Pauser {
+        pause: Pause,
+        after: u32,
+    }
+
+    impl Observer<Test> for Pauser {
+        fn iteration_finished(&self, stats: &IterationStats) {
+            if stats.iteration == self.after {
+                self.pause.pause();
+            }
+        }
+    }
+
+    #[test]
+    fn paused_simulation_continues() {
+        let mut simulation = endless().iterations(10).finalize().unwrap();
+        let pause = simulation.pause_handle();
+        simulation.observers.push(Arc::new(Pauser { pause: pause.clone(), after: 3 }));
+
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::Paused);
+        assert_eq!(simulation.simulation_result.iteration_counter, 3);
+        assert!(simulation.resumed);
+
+        // The populations can be modified while the simulation is paused.
+        simulation.habitat[0].population[9].individual.f = 0.5;
+        simulation.habitat[0].population[9].dirty = true;
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::Paused);
+        assert_eq!(simulation.simulation_result.iteration_counter, 3);
+
+        pause.unpause();
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::IterationLimit);
+        assert_eq!(simulation.simulation_result.iteration_counter, 10);
+        assert_eq!(simulation.simulation_result.fittest[0].fitness, 0.5);
+        assert!(!simulation.resumed);
+    }
+
+    #[test]
+    fn best_snapshot_follows_the_run() {
+        let mut simulation = endless().iterations(10).finalize().unwrap();
+        assert!(simulation.best_snapshot().is_none());
+        let best = simulation.best.clone();
+        let pause = simulation.pause_handle();
+        simulation.observers.push(Arc::new(Pauser { pause: pause.clone(), after: 3 }));
+
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::Paused);
+        assert_eq!(best.get().map(|wrapper| wrapper.fitness), Some(1.0));
+
+        simulation.habitat[0].population[9].individual.f = 0.5;
+        simulation.habitat[0].population[9].dirty = true;
+        pause.unpause();
+        let reader = thread::spawn(move || {
+            // Wait until the new fittest individual shows up.
+            while best.get().map(|wrapper| wrapper.fitness) != Some(0.5) {
+                thread::yield_now();
+            }
+        });
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::IterationLimit);
+        reader.join().unwrap();
+
+        // Branches do not share the snapshot.
+        let branch = simulation.branch(1);
+        simulation.best.replace(None);
+        assert_eq!(branch.best_snapshot().map(|wrapper| wrapper.fitness), Some(0.5));
+    }
+
+    #[test]
+    fn combined_end_conditions() {
+        let run = |condition: EndCondition| {
+            let mut simulation = endless().end_condition(condition).finalize().unwrap();
+            let status = simulation.run(&MinimizeSelector::new(2));
+            (status, simulation.simulation_result.iteration_counter)
+        };
+
+        // The fitness of the endless simulation never improves.
+        let unreachable = EndCondition::Fitness(0.0);
+        assert_eq!(
+            run(EndCondition::Any(vec![unreachable.clone(), EndCondition::Iterations(15)])),
+            (RunStatus::IterationLimit, 15)
+        );
+        assert_eq!(
+            run(EndCondition::Any(vec![unreachable.clone(), EndCondition::Stagnation(5)])),
+            (RunStatus::Stagnated, 5)
+        );
+        assert_eq!(
+            run(EndCondition::All(vec![
+                EndCondition::TimeLimit(Duration::from_millis(0)),
+                EndCondition::Iterations(12),
+            ])),
+            (RunStatus::TimeLimit, 12)
+        );
+        assert_eq!(
+            run(EndCondition::All(vec![EndCondition::Factor(1.0), EndCondition::Iterations(3)])),
+            (RunStatus::TargetReached, 3)
+        );
+
+        let invalid = EndCondition::Any(vec![unreachable, EndCondition::All(Vec::new())]);
+        assert!(endless().end_condition(invalid).finalize().is_err());
+    }
+
+    #[test]
+    fn custom_end_condition() {
+        let mut simulation = endless().end_when(|stats| stats.iteration >= 7).finalize().unwrap();
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::ConditionMet);
+        assert_eq!(simulation.simulation_result.iteration_counter, 7);
+
+        // The predicate is checked as often as the target.
+        let mut simulation = endless()
+            .end_when(|stats| stats.iteration >= 7)
+            .check_every(StopCondition::Target, 2)
+            .finalize()
+            .unwrap();
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::ConditionMet);
+        assert_eq!(simulation.simulation_result.iteration_counter, 8);
+    }
+
+    #[test]
+    fn populations_retire() {
+        let converging = PopulationBuilder::<Test>::new()
+            .set_id(1)
+            .generate_initial_population(10, |_| Test { f: 1.0 })
+            .retire_after(5)
+            .finalize()
+            .unwrap();
+        let resetting = PopulationBuilder::<Test>::new()
+            .set_id(2)
+            .generate_initial_population(10, |_| Test { f: 1.0 })
+            .reset_limit_start(3)
+            .reset_limit_end(100)
+            .reset_limit_increment(1)
+            .max_resets(1)
+            .finalize()
+            .unwrap();
+        let mut simulation = SimulationBuilder::<Test>::new()
+            .iterations(100)
+            .add_population(converging)
+            .add_population(resetting)
+            .finalize()
+            .unwrap();
+
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::Retired);
+        let result = &simulation.simulation_result;
+        assert_eq!(
+            result.retirements,
+            vec![
+                Retirement { population_id: 1, iteration: 5, reason: RetireReason::Converged },
+                Retirement {
+                    population_id: 2,
+                    iteration: 9,
+                    reason: RetireReason::ResetsExhausted,
+                },
+            ]
+        );
+        assert_eq!(result.iteration_counter, 9);
+        // The retired population has not evolved any more.
+        assert_eq!(simulation.habitat[0].stagnation, 5);
+    }
+
+    #[test]
+    fn iterations_are_timed() {
+        let mut simulation = endless().iterations(10).finalize().unwrap();
+        simulation.run(&MinimizeSelector::new(2));
+
+        let result = &simulation.simulation_result;
+        assert_eq!(result.iteration_times.len(), 10);
+        let iterations: Duration = result.iteration_times.iter().sum();
+        assert!(result.elapsed >= iterations);
+        assert_eq!(result.average_iteration_time(), Some(iterations / 10));
+        assert!(result.evaluations_per_second() > 0.0);
+
+        // A continued run appends its iterations.
+        simulation.resumed = true;
+        simulation.type_of_simulation = SimulationType::EndIteration(15);
+        simulation.run(&MinimizeSelector::new(2));
+        assert_eq!(simulation.simulation_result.iteration_times.len(), 15);
+    }
+
+    #[test]
+    fn time_limit_stops_simulation() {
+        let mut simulation = endless().finalize().unwrap();
+        simulation.time_limit = Some(Duration::from_millis(20));
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::TimeLimit);
+        assert!(simulation.total_time_in_ms >= 20.0);
+
+        simulation.type_of_simulation = SimulationType::EndIteration(1_000_000);
+        simulation.time_limit = Some(Duration::from_millis(0));
+        assert_eq!(simulation.run(&MinimizeSelector::new(2)), RunStatus::TimeLimit);
+        assert_eq!(simulation.simulation_result.iteration_counter, 1);
+    }
+
+    /// Takes a random step with the generator of its population.
+    #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+    struct Rambler {
+        x: f64,
+    }
+
+    impl Individual for Rambler {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), rng: &mut SimRng) {
+            self.x += rng.gen_range(-1.0..1.0);
+        }
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            self.x.abs()
+        }
+
+        fn reset(&mut self) {}
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn resumed_run_matches_uninterrupted_run() {
+        let initial = [Rambler { x: 9.0 }, Rambler { x: -5.0 }, Rambler { x: 1.0 }];
+        let builder = |iterations, populations: u32| {
+            let populations = (1..populations + 1)
+                .map(|id| {
+                    PopulationBuilder::<Rambler>::new()
+                        .set_id(id)
+                        .initial_population(&initial)
+                        .finalize()
+                        .unwrap()
+                })
+                .collect();
+            SimulationBuilder::<Rambler>::new()
+                .iterations(iterations)
+                .seed(3)
+                .add_multiple_populations(populations)
+        };
+        let outcome = |simulation: &Simulation<Rambler>| {
+            let xs: Vec<f64> =
+                simulation.habitat[0].population.iter().map(|w| w.individual.x).collect();
+            (xs, simulation.simulation_result.fittest[0].fitness)
+        };
+        let selector = MinimizeSelector::new(2);
+        let periodic = env::temp_dir().join("darwin-rs-periodic.checkpoint");
+        let explicit = env::temp_dir().join("darwin-rs-explicit.checkpoint");
+
+        let mut uninterrupted = builder(30, 1).finalize().unwrap();
+        uninterrupted.run(&selector);
+
+        // Stops after 15 iterations, the last periodic checkpoint is the one of iteration 10.
+        let mut interrupted = builder(15, 1).checkpoint_every(10, &periodic).finalize().unwrap();
+        interrupted.run(&selector);
+        interrupted.checkpoint(&explicit).unwrap();
+
+        for path in &[periodic, explicit] {
+            let mut resumed = builder(30, 1).finalize().unwrap();
+            resumed.resume(path).unwrap();
+            assert!(resumed.resumed);
+            assert_eq!(resumed.run(&selector), RunStatus::IterationLimit);
+            assert_eq!(resumed.simulation_result.iteration_counter, 30);
+            assert_eq!(outcome(&resumed), outcome(&uninterrupted));
+            let counters = resumed.simulation_result.counters;
+            assert_eq!(counters, uninterrupted.simulation_result.counters);
+        }
+
+        let mut other = builder(30, 2).finalize().unwrap();
+        match other.resume(env::temp_dir().join("darwin-rs-explicit.checkpoint")) {
+            Err(CheckpointError::PopulationMismatch) => {}
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[cfg(feature = "serialize")]
+    #[test]
+    fn saved_fittest_starts_next_run() {
+        let population = PopulationBuilder::<Rambler>::new()
+            .init_seed(7)
+            .generate_initial_population(6, |rng| Rambler { x: rng.gen_range(-9.0..9.0) })
+            .finalize()
+            .unwrap();
+        let mut simulation = SimulationBuilder::<Rambler>::new()
+            .iterations(10)
+            .seed(7)
+            .num_of_global_fittest(3)
+            .add_population(population)
+            .finalize()
+            .unwrap();
+        simulation.run(&MinimizeSelector::new(2));
+        let fittest: Vec<f64> =
+            simulation.simulation_result.fittest.iter().map(|w| w.individual.x).collect();
+        assert_eq!(fittest.len(), 3);
+
+        for (name, format) in &[("fittest.json", Format::Json), ("fittest.bin", Format::Bincode)] {
+            let path = env::temp_dir().join(format!("darwin-rs-{}", name));
+            simulation.simulation_result.save_fittest(&path, *format).unwrap();
+
+            let population = PopulationBuilder::<Rambler>::new()
+                .initial_population_from_file(&path)
+                .unwrap()
+                .finalize()
+                .unwrap();
+            let loaded: Vec<f64> = population.population.iter().map(|w| w.individual.x).collect();
+            assert_eq!(loaded, fittest);
+        }
+    }
+
+    #[test]
+    fn steps_continue_where_they_stopped() {
+        let initial = [Rambler { x: 9.0 }, Rambler { x: -5.0 }, Rambler { x: 1.0 }];
+        let builder = || {
+            let population = PopulationBuilder::<Rambler>::new()
+                .initial_population(&initial)
+                .finalize()
+                .unwrap();
+            SimulationBuilder::<Rambler>::new()
+                .iterations(20)
+                .seed(5)
+                .add_population(population)
+                .finalize()
+                .unwrap()
+        };
+        let selector = MinimizeSelector::new(2);
+        let outcome = |simulation: &Simulation<Rambler>| {
+            let xs: Vec<f64> =
+                simulation.habitat[0].population.iter().map(|w| w.individual.x).collect();
+            (xs, simulation.simulation_result.fittest[0].fitness)
+        };
+
+        let mut uninterrupted = builder();
+        let summaries: Vec<_> = uninterrupted.steps(&selector).collect();
+        assert_eq!(summaries.len(), 20);
+        assert!(summaries[..19].iter().all(|summary| summary.status.is_none()));
+        assert_eq!(summaries[19].iteration, 20);
+        assert_eq!(summaries[19].status, Some(RunStatus::IterationLimit));
+        assert_eq!(summaries[19].fitness, uninterrupted.simulation_result.fittest[0].fitness);
+        assert!(!uninterrupted.resumed);
 
+        let mut stepped = builder();
+        let iterations: Vec<u32> =
+            stepped.steps(&selector).take(5).map(|summary| summary.iteration).collect();
+        assert_eq!(iterations, vec![1, 2, 3, 4, 5]);
+        assert!(stepped.resumed);
+        assert_eq!(stepped.step(&selector).map(|summary| summary.iteration), Some(6));
+        assert_eq!(stepped.run(&selector), RunStatus::IterationLimit);
+        assert_eq!(stepped.simulation_result.iteration_counter, 20);
+        assert_eq!(outcome(&stepped), outcome(&uninterrupted));
     }
 }
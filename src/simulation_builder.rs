@@ -13,11 +13,36 @@
 //!
 //!
 
-use std;
+use std::env;
 use std::fmt::Debug;
-use simulation::{Simulation, SimulationType, SimulationResult};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(feature = "serialize")]
+use std::path::PathBuf;
+#[cfg(feature = "serialize")]
+use serde::Serialize;
+#[cfg(feature = "serialize")]
+use checkpoint::{self, Periodic};
+#[cfg(feature = "config")]
+use config::{Config, End};
+use simulation::{
+    BestSnapshot, CancellationToken, CheckIntervals, EndCondition, EndWhen, Pause, Simulation,
+    SimulationType, SimulationResult, StopCondition,
+};
+use archive::Archive;
+use fitness::Fitness;
+use pareto::ParetoFront;
 use individual::Individual;
+use migration::{Emigrants, MigrantExchange, Migration, Topology};
 use population::Population;
+use population_builder;
+use observer::{LoggingObserver, Observer};
+use statistics::{Counters, IterationStats};
+use schedule::ParameterRegistry;
+use provenance::Provenance;
+use rng;
+use select::Direction;
 
 /// This is a helper struct in order to build (configure) a valid simulation.
 /// See builder pattern: https://en.wikipedia.org/wiki/Builder_pattern
@@ -27,11 +52,81 @@ use population::Population;
 pub struct SimulationBuilder<T: Individual + Send + Sync + Debug + Clone> {
     /// The actual simulation.
     simulation: Simulation<T>,
+    /// The memory limit in bytes and what to do if it is exceeded, see `memory_limit`.
+    memory_limit: Option<(usize, MemoryPolicy)>,
+    /// The data shared by all individuals, see `context`.
+    context: Option<Arc<T::Context>>,
+    /// Register the default `LoggingObserver`, see `logging`.
+    logging: bool,
+}
+
+/// What `SimulationBuilder::finalize` does if the estimated memory of the simulation exceeds
+/// the memory limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MemoryPolicy {
+    /// Return the error `MemoryLimitExceeded` (default).
+    #[default]
+    Refuse,
+    /// Reduce the number of global fittest individuals (`num_of_global_fittest`, at least one
+    /// is always kept) until the estimate fits. If this is not enough, the error
+    /// `MemoryLimitExceeded` is returned.
+    ShrinkArchive,
 }
 
 error_chain! {
+    links {
+        Population(::population_builder::Error, ::population_builder::ErrorKind);
+    }
+
     errors {
         EndIterationTooLow
+        EndConditionInvalid
+        CheckIntervalZero
+        CalibrationInvalid
+        TimeLimitInvalid
+        HistoryLimitTooLow
+        MigrationInvalid
+        TopologyInvalid
+        RemoteIslandsInvalid
+        SharingInvalid
+        MemoryLimitExceeded(estimate: usize, limit: usize) {
+            description("estimated memory exceeds the memory limit")
+            display("estimated memory of {} bytes exceeds the memory limit of {} bytes",
+                    estimate, limit)
+        }
+        InvalidEnvironmentVariable(name: String, value: String) {
+            description("invalid value in environment variable")
+            display("invalid value in environment variable {}: '{}'", name, value)
+        }
+        TooManyMigrants(migrants: usize, id: u32, replaceable: usize) {
+            description("more migrants than replaceable individuals in a population")
+            display("{} migrants would replace all {} replaceable individuals of population {}, \
+                     see migrants_per_event and share_top", migrants, replaceable, id)
+        }
+        TooManyPopulationConfigs(configs: usize, populations: usize) {
+            description("more population settings than populations in the configuration")
+            display("the configuration has settings for {} populations, the simulation has {}",
+                    configs, populations)
+        }
+    }
+}
+
+/// Reads and parses the environment variable `name`. Returns `Ok(None)` if it is not set.
+fn env_var<V: FromStr>(name: &str) -> Result<Option<V>> {
+    match env::var(name) {
+        Ok(value) => value.trim().parse().map(Some).map_err(|_| {
+            ErrorKind::InvalidEnvironmentVariable(name.to_string(), value.clone()).into()
+        }),
+        Err(env::VarError::NotPresent) => Ok(None),
+        Err(env::VarError::NotUnicode(value)) => Err(
+            ErrorKind::InvalidEnvironmentVariable(name.to_string(), value.to_string_lossy().into_owned()).into()
+        ),
+    }
+}
+
+impl<T: Individual + Send + Sync + Clone + Debug> Default for SimulationBuilder<T> {
+    fn default() -> SimulationBuilder<T> {
+        SimulationBuilder::new()
     }
 }
 
@@ -47,18 +142,65 @@ impl<T: Individual + Send + Sync + Clone + Debug> SimulationBuilder<T> {
                 habitat: Vec::new(),
                 total_time_in_ms: 0.0,
                 simulation_result: SimulationResult {
-                    improvement_factor: std::f64::MAX,
-                    original_fitness: std::f64::MAX,
+                    improvement_factor: f64::MAX,
+                    original_fitness: T::Fitness::worst(),
                     fittest: Vec::new(),
                     iteration_counter: 0,
+                    counters: Counters::default(),
+                    iteration_times: Vec::new(),
+                    elapsed: Duration::default(),
+                    improvement_rates: Vec::new(),
+                    stats_history: Vec::new(),
+                    history: None,
+                    pareto_front: ParetoFront::new(),
+                    direction: Direction::Minimize,
+                    seed: None,
+                    configuration: String::new(),
+                    provenance: Provenance::collect(None, 2),
+                    best_iteration: 0,
+                    best_population_id: 0,
+                    retirements: Vec::new(),
                 },
                 share_fittest: false,
+                share_top: 1,
+                share_probability: 1.0,
+                migration: Migration::default(),
+                topology: Topology::FullyConnected,
+                remote_islands: None,
+                asynchronous_islands: false,
                 num_of_global_fittest: 10,
                 output_every: 10,
-                output_every_counter: 0,
                 share_every: 10,
                 share_counter: 0,
+                observers: Vec::new(),
+                subscribers: Vec::new(),
+                parameters: ParameterRegistry::new(),
+                provenance: Provenance::collect(None, 2),
+                max_iterations: 1_000_000,
+                stagnation_limit: 0,
+                interbreed_elites: 0,
+                check_every: CheckIntervals::default(),
+                mutation_calibration: None,
+                archive: None,
+                track_diversity: false,
+                keep_stats_history: false,
+                record_history: None,
+                time_limit: None,
+                end_when: None,
+                cancelled: CancellationToken::new(),
+                pause: Pause::default(),
+                best: BestSnapshot::new(),
+                memory_limit: None,
+                direction: Direction::Minimize,
+                context: Arc::new(T::Context::default()),
+                seed: None,
+                resumed: false,
+                #[cfg(feature = "serialize")]
+                checkpoint_every: None,
             },
+            memory_limit: None,
+            context: None,
+            logging: true,
         }
     }
 
@@ -83,7 +225,153 @@ impl<T: Individual + Send + Sync + Clone + Debug> SimulationBuilder<T> {
         self
     }
 
-    /// Sets the number of threads in order to speed up the simulation.
+    /// Set the combined stop criteria for the simulation (see `EndCondition`) and thus sets the
+    /// simulation type to `EndCondition`. Replaces `iterations`, `factor` and `fitness`.
+    pub fn end_condition(mut self, condition: EndCondition) -> SimulationBuilder<T> {
+        self.simulation.type_of_simulation = SimulationType::EndCondition(condition);
+        self
+    }
+
+    /// Maximize the fitness instead of minimizing it (default): higher values of
+    /// `Individual::calculate_fitness` are better, also for the target of `fitness` and for
+    /// `Individual::quick_bound`, which must then return an upper bound. All comparisons
+    /// (sorting, sharing the fittest individual, stop criteria) are flipped consistently:
+    /// internally the fitness is negated, so `IndividualWrapper::fitness`, the statistics and
+    /// the archives hold the negated values and the selectors keep `Direction::Minimize`. Use
+    /// `SimulationResult::fitness` to get the fitness of the result as calculated.
+    pub fn maximize(mut self) -> SimulationBuilder<T> {
+        self.simulation.direction = Direction::Maximize;
+        self
+    }
+
+    /// Set the data shared by all individuals (see `Individual::Context`). It is stored only
+    /// once and shared by all populations and threads, this replaces the contexts set by
+    /// `PopulationBuilder::context`.
+    pub fn context(mut self, context: T::Context) -> SimulationBuilder<T> {
+        self.context = Some(Arc::new(context));
+        self
+    }
+
+    /// Set the global seed of the simulation. `finalize` replaces the random number generator
+    /// of population n (in the order they were added) with the stream n of this seed (see
+    /// `rng::for_population`), overriding `PopulationBuilder::evolution_seed`. Two runs with
+    /// the same seed, populations and number of threads produce identical results, as long as
+    /// the individuals only use the random number generator given to them.
+    pub fn seed(mut self, seed: u64) -> SimulationBuilder<T> {
+        self.simulation.seed = Some(seed);
+        self
+    }
+
+    /// Write a checkpoint of the simulation to the given file every `iterations` iterations
+    /// while it runs (at least 1), so a crashed run can be continued with
+    /// `Simulation::resume`. The file is overwritten each time. Only available with the
+    /// feature `serialize`.
+    #[cfg(feature = "serialize")]
+    pub fn checkpoint_every<P: Into<PathBuf>>(
+        mut self,
+        iterations: u32,
+        path: P,
+    ) -> SimulationBuilder<T>
+    where
+        T: Serialize,
+        T::Fitness: Serialize,
+    {
+        self.simulation.checkpoint_every = Some(Periodic {
+            every: iterations.max(1),
+            path: path.into(),
+            write: checkpoint::write_simulation,
+        });
+        self
+    }
+
+    /// Set the maximum number of iterations for the simulation types `EndFitness` and
+    /// `EndFactor` (default: 1_000_000). If the target is not reached by then, `run` gives up
+    /// and returns `RunStatus::IterationLimit`.
+    pub fn max_iterations(mut self, max_iterations: u32) -> SimulationBuilder<T> {
+        self.simulation.max_iterations = max_iterations;
+        self
+    }
+
+    /// Stop the simulation types `EndFitness` and `EndFactor` with
+    /// `RunStatus::Stagnated` if the fittest individual has not improved for
+    /// `stagnation_limit` iterations. Default: 0 (disabled).
+    pub fn stagnation_limit(mut self, stagnation_limit: u32) -> SimulationBuilder<T> {
+        self.simulation.stagnation_limit = stagnation_limit;
+        self
+    }
+
+    /// Evaluate the given stop condition only every `every` iterations (default: 1, every
+    /// iteration). Use this for stop conditions that are expensive compared to an iteration.
+    pub fn check_every(mut self, condition: StopCondition, every: u32) -> SimulationBuilder<T> {
+        self.simulation.check_every.set(condition, every);
+        self
+    }
+
+    /// Refuse to build a simulation whose estimated peak memory (see
+    /// `Simulation::estimated_memory`) exceeds `limit` bytes, or shrink it according to
+    /// `policy`. This catches oversized configurations before a long run, instead of running
+    /// out of memory in the middle of it. The estimate is checked again after each iteration,
+    /// since archives can grow: the run stops with `SimError::MemoryLimitExceeded`. For a
+    /// useful estimate implement `Individual::size_hint`.
+    pub fn memory_limit(mut self, limit: usize, policy: MemoryPolicy) -> SimulationBuilder<T> {
+        self.memory_limit = Some((limit, policy));
+        self
+    }
+
+    /// Calibrate the number of mutations before the simulation starts: each population tries
+    /// each of the `candidates` (for example `&[1, 2, 5, 10]`) for `iterations` iterations
+    /// and keeps the one with the best improvement of the fittest individual per fitness
+    /// evaluation. This replaces the manual tuning of the mutation rate
+    /// (`PopulationBuilder::mutation_rate` and friends) for new problems.
+    /// See `Population::calibrate_mutations` for details.
+    pub fn calibrate_mutations(
+        mut self,
+        candidates: &[u32],
+        iterations: u32,
+    ) -> SimulationBuilder<T> {
+        self.simulation.mutation_calibration = Some((candidates.to_vec(), iterations));
+        self
+    }
+
+    /// Set an archive for the whole simulation, for example a `HallOfFame` or `MapElites` (see
+    /// module archive). It is offered the survivors of all populations after each iteration.
+    /// Access it later with `Simulation::archive`.
+    pub fn archive<A: Archive<T> + 'static>(mut self, archive: A) -> SimulationBuilder<T> {
+        self.simulation.archive = Some(Box::new(archive));
+        self
+    }
+
+    /// Calculate the diversity of all populations after each iteration (fitness variance, mean
+    /// distance and entropy of the genotypes, see module diversity) and hand it over to the
+    /// observers in `IterationStats::diversity` (default: off). This helps to diagnose
+    /// premature convergence. The mean distance needs n * (n - 1) / 2 calls of
+    /// `Individual::distance` per population and iteration.
+    pub fn track_diversity(mut self) -> SimulationBuilder<T> {
+        self.simulation.track_diversity = true;
+        self
+    }
+
+    /// Keep the fitness statistics (best, worst, mean, median and standard deviation) of each
+    /// population after each iteration in `SimulationResult::stats_history` (default: off), so
+    /// they can be analyzed after the run. They are handed over to the observers in
+    /// `IterationStats::fitness` in any case.
+    pub fn keep_stats_history(mut self) -> SimulationBuilder<T> {
+        self.simulation.keep_stats_history = true;
+        self
+    }
+
+    /// Record the fitness of the fittest individual after each iteration in
+    /// `SimulationResult::history` (default: off), so the convergence curve can be plotted
+    /// after the run. With `snapshots` a copy of the fittest individual is kept too. If the
+    /// history has `max_entries` entries, it is downsampled (see module history), 0 means no
+    /// limit. Otherwise `max_entries` must be at least 2.
+    pub fn record_history(mut self, max_entries: usize, snapshots: bool) -> SimulationBuilder<T> {
+        self.simulation.record_history = Some((max_entries, snapshots));
+        self
+    }
+
+    /// Sets the number of threads in order to speed up the simulation: the size of the rayon
+    /// thread pool that runs the populations and evaluates their individuals.
     pub fn threads(mut self, threads: usize) -> SimulationBuilder<T> {
         self.simulation.num_of_threads = threads;
         self
@@ -106,6 +394,36 @@ impl<T: Individual + Send + Sync + Clone + Debug> SimulationBuilder<T> {
         self
     }
 
+    /// Stop the simulation after the iteration in which this wall-clock time has elapsed since
+    /// the start of `run`, whatever the simulation type: it ends with the first criterion that
+    /// is met, the time limit or the iterations, fitness or improvement factor. `run` then
+    /// returns `RunStatus::TimeLimit`. Default: no time limit
+    pub fn time_limit(mut self, time_limit: Duration) -> SimulationBuilder<T> {
+        self.simulation.time_limit = Some(time_limit);
+        self
+    }
+
+    /// Stop the simulation after the iteration for whose statistics the given predicate returns
+    /// true, whatever the simulation type, for example if the diversity has collapsed or an
+    /// external budget is used up. `run` then returns `RunStatus::ConditionMet`. The predicate
+    /// is called as often as the target (see `check_every` with `StopCondition::Target`).
+    pub fn end_when<F>(mut self, predicate: F) -> SimulationBuilder<T>
+    where
+        F: Fn(&IterationStats) -> bool + Send + Sync + 'static,
+    {
+        self.simulation.end_when = Some(EndWhen(Arc::new(predicate)));
+        self
+    }
+
+    /// Stop the simulation gracefully after the iteration in which the given token has been
+    /// cancelled, for example from another thread or a Ctrl-C handler. `run` then returns
+    /// `RunStatus::Cancelled` and the result holds the fittest individuals so far. The same token
+    /// can be given to several simulations. Default: a new token, see `Simulation::cancelled`.
+    pub fn cancellation_token(mut self, token: &CancellationToken) -> SimulationBuilder<T> {
+        self.simulation.cancelled = token.clone();
+        self
+    }
+
     /// If this option is enabled (default: off), then the fittest individual of all populations
     /// is shared between all populations.
     pub fn share_fittest(mut self) -> SimulationBuilder<T> {
@@ -113,6 +431,97 @@ impl<T: Individual + Send + Sync + Clone + Debug> SimulationBuilder<T> {
         self
     }
 
+    /// Let the populations exchange individuals every `interval` iterations (default: never):
+    /// each population that has not retired sends copies of its emigrants (see
+    /// `migrants_per_event` and `emigrant_selection`) to all other populations, where they
+    /// replace the least fit individuals (or with `interbreed_migrants` are crossed with the
+    /// elites). This is independent of `share_fittest`, see the module `migration`.
+    pub fn migration_interval(mut self, interval: u32) -> SimulationBuilder<T> {
+        self.simulation.migration.interval = interval;
+        self
+    }
+
+    /// The number of emigrants of each population in each migration, see
+    /// `migration_interval`. Must be > 0. Default: 1
+    pub fn migrants_per_event(mut self, migrants: usize) -> SimulationBuilder<T> {
+        self.simulation.migration.migrants = migrants;
+        self
+    }
+
+    /// How the emigrants of each population are chosen, see `migration_interval`. A tournament
+    /// needs at least one contestant. Default: `Emigrants::Best`
+    pub fn emigrant_selection(mut self, emigrants: Emigrants) -> SimulationBuilder<T> {
+        self.simulation.migration.emigrants = emigrants;
+        self
+    }
+
+    /// Which populations send their emigrants (see `migration_interval`) and the shared fittest
+    /// individual (see `share_fittest`, it goes to the population that found it and its
+    /// neighbours) to which other populations, for example `Topology::Ring`. Sparse topologies
+    /// spread good individuals more slowly and keep the populations diverse for longer.
+    /// Default: `Topology::FullyConnected`
+    pub fn topology(mut self, topology: Topology) -> SimulationBuilder<T> {
+        self.simulation.topology = topology;
+        self
+    }
+
+    /// Let the populations also exchange emigrants with populations outside of this simulation,
+    /// for example with simulations on other machines (see `distributed::RemoteIslands`,
+    /// feature `distributed`). At each migration (see `migration_interval`, which must be set)
+    /// the emigrants of all populations are sent away and the immigrants that have arrived in
+    /// the meantime go to all populations, the simulation never waits for them. Not with
+    /// `asynchronous_islands`.
+    pub fn remote_islands(mut self, exchange: Arc<dyn MigrantExchange<T>>) -> SimulationBuilder<T> {
+        self.simulation.remote_islands = Some(exchange);
+        self
+    }
+
+    /// Run each population on its own thread without waiting for the other populations after
+    /// each iteration (default: off). Fast populations are no longer held back by slow ones,
+    /// which helps when the fitness evaluation takes very different times. With `share_fittest`
+    /// each population sends its new fittest individuals to its neighbours (see `topology`),
+    /// which take them in at the start of their next iteration (`share_every` counts the
+    /// iterations of the sending population), the emigrants of `migration_interval` likewise.
+    /// The iteration of the simulation is the one of the fastest population. Each population
+    /// gets a thread of its own, the individuals of all populations are evaluated on the
+    /// `threads` of the simulation. Of the observer methods called in each iteration only
+    /// `Observer::new_fittest` is called; `end_when`, `memory_limit`,
+    /// `Simulation::pause_handle`, `Simulation::steps` and the archive, history and statistics
+    /// do not apply.
+    pub fn asynchronous_islands(mut self) -> SimulationBuilder<T> {
+        self.simulation.asynchronous_islands = true;
+        self
+    }
+
+    /// If sharing is enabled (see `share_fittest`), share the `top` global fittest individuals
+    /// instead of only the fittest one. They replace the least fit individuals of each
+    /// population (or with `interbreed_migrants` are crossed with the elites). Must be > 0.
+    /// Default: 1
+    pub fn share_top(mut self, top: usize) -> SimulationBuilder<T> {
+        self.simulation.share_top = top;
+        self
+    }
+
+    /// If sharing is enabled (see `share_fittest`), only share a new fittest individual with
+    /// this probability (0.0 < probability <= 1.0), so the populations do not all converge to
+    /// the same individual. A skipped sharing is tried again with the next new fittest
+    /// individual. Populations can also refuse shared individuals altogether, see
+    /// `PopulationBuilder::explorer`. Default: 1.0
+    pub fn share_probability(mut self, probability: f64) -> SimulationBuilder<T> {
+        self.simulation.share_probability = probability;
+        self
+    }
+
+    /// If sharing is enabled (see `share_fittest`), the shared fittest individual is delivered
+    /// as a crossover partner instead of replacing the fittest individual of each population:
+    /// it is crossed with the `elites` fittest individuals of each population and the children
+    /// compete with the residents in the next iteration. This mixes the populations more gently.
+    /// Only has an effect on populations that use crossover (`PopulationBuilder::crossover`).
+    pub fn interbreed_migrants(mut self, elites: u32) -> SimulationBuilder<T> {
+        self.simulation.interbreed_elites = elites;
+        self
+    }
+
     /// How many global fittest should be kept ? (The size of the "high score list")
     pub fn num_of_global_fittest(mut self, num_of_global_fittest: usize) -> SimulationBuilder<T> {
         self.simulation.num_of_global_fittest = num_of_global_fittest;
@@ -120,12 +529,20 @@ impl<T: Individual + Send + Sync + Clone + Debug> SimulationBuilder<T> {
     }
 
     /// Do not output every time a new individual is found, only every nth time.
-    /// n == output_every
+    /// n == output_every. Only used by the default logging observer, see `logging`.
     pub fn output_every(mut self, output_every: u32) -> SimulationBuilder<T> {
         self.simulation.output_every = output_every;
         self
     }
 
+    /// Write the progress of the simulation to the log with a `LoggingObserver` that is
+    /// registered before all other observers (default: true). Turn it off if your own
+    /// observers take care of the output.
+    pub fn logging(mut self, logging: bool) -> SimulationBuilder<T> {
+        self.logging = logging;
+        self
+    }
+
     /// If share fittest is enabled and the number share_every of iteration has passed then
     /// the fittest individual is shared between all populations
     pub fn share_every(mut self, share_every: u32) -> SimulationBuilder<T> {
@@ -133,14 +550,486 @@ impl<T: Individual + Send + Sync + Clone + Debug> SimulationBuilder<T> {
         self
     }
 
+    /// Register an observer that follows the simulation. Keep a clone of the `Arc` if you
+    /// want to access the observer after the simulation has finished.
+    pub fn observer<O: Observer<T> + 'static>(mut self, observer: Arc<O>) -> SimulationBuilder<T> {
+        self.simulation.observers.push(observer);
+        self
+    }
+
+    /// Set the registry of scheduled operator parameters (for example a mutation step size that
+    /// shrinks over time). The simulation updates all registered parameters at the beginning of
+    /// each iteration.
+    pub fn parameters(mut self, parameters: ParameterRegistry) -> SimulationBuilder<T> {
+        self.simulation.parameters = parameters;
+        self
+    }
+
+    /// Set the revision of your code (for example the git commit hash). It is recorded in the
+    /// provenance information of the simulation, so results can be traced back to the code
+    /// that produced them.
+    pub fn code_revision(mut self, revision: &str) -> SimulationBuilder<T> {
+        self.simulation.provenance.code_revision = Some(revision.to_string());
+        self
+    }
+
+    /// Overlays some of the parameters with values from environment variables, so batch jobs
+    /// can vary runs without recompilation. Call this after all populations have been added.
+    /// The following variables are read (if they are set), `PREFIX` is replaced by `prefix`:
+    ///
+    /// - `PREFIX_THREADS`: the number of threads, see `threads`
+    /// - `PREFIX_ITERATIONS`: the number of iterations, see `iterations`
+    /// - `PREFIX_SEED`: the global seed, see `seed`
+    /// - `PREFIX_POPULATION_SIZE`: the number of individuals of each population. Individuals
+    ///   are removed from the end or cloned from the start of the population to match this size.
+    ///
+    /// Returns an error if one of the variables can not be parsed or if a value is rejected by
+    /// the same checks as `finalize` (and `PopulationBuilder::finalize`).
+    pub fn from_env(mut self, prefix: &str) -> Result<SimulationBuilder<T>> {
+        if let Some(threads) = env_var(&format!("{}_THREADS", prefix))? {
+            self = self.threads(threads);
+        }
+
+        if let Some(iterations) = env_var(&format!("{}_ITERATIONS", prefix))? {
+            self = self.iterations(iterations);
+        }
+
+        if let Some(seed) = env_var(&format!("{}_SEED", prefix))? {
+            self = self.seed(seed);
+        }
+
+        if let Some(size) = env_var::<u32>(&format!("{}_POPULATION_SIZE", prefix))? {
+            for population in &mut self.simulation.habitat {
+                population.resize(size);
+                population_builder::check(population)?;
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Overlays the parameters with the values that are present in the given configuration
+    /// (see the module `config`), so they can be tweaked without recompilation. Call this after
+    /// all populations have been added, the nth population entry of the configuration applies to
+    /// the nth population.
+    ///
+    /// Returns an error if the configuration has more population entries than the simulation
+    /// has populations or if a value is rejected by the same checks as `finalize` (and
+    /// `PopulationBuilder::finalize`).
+    #[cfg(feature = "config")]
+    pub fn from_config(mut self, config: &Config) -> Result<SimulationBuilder<T>> {
+        if let Some(threads) = config.threads {
+            self = self.threads(threads);
+        }
+        if let Some(seed) = config.seed {
+            self = self.seed(seed);
+        }
+        match config.end {
+            Some(End::Iterations(iterations)) => self = self.iterations(iterations),
+            Some(End::Factor(factor)) => self = self.factor(factor),
+            Some(End::Fitness(fitness)) => self = self.fitness(fitness),
+            None => {}
+        }
+        if let Some(max_iterations) = config.max_iterations {
+            self = self.max_iterations(max_iterations);
+        }
+        if let Some(stagnation_limit) = config.stagnation_limit {
+            self = self.stagnation_limit(stagnation_limit);
+        }
+        if let Some(time_limit) = config.time_limit {
+            if !(time_limit >= 0.0 && time_limit.is_finite()) {
+                return Err(ErrorKind::TimeLimitInvalid.into());
+            }
+            self = self.time_limit(Duration::from_secs_f64(time_limit));
+        }
+        if let Some(share_fittest) = config.share_fittest {
+            self.simulation.share_fittest = share_fittest;
+        }
+        if let Some(share_top) = config.share_top {
+            self = self.share_top(share_top);
+        }
+        if let Some(share_probability) = config.share_probability {
+            self = self.share_probability(share_probability);
+        }
+        if let Some(asynchronous_islands) = config.asynchronous_islands {
+            self.simulation.asynchronous_islands = asynchronous_islands;
+        }
+        if let Some(share_every) = config.share_every {
+            self = self.share_every(share_every);
+        }
+        if let Some(num_of_global_fittest) = config.num_of_global_fittest {
+            self = self.num_of_global_fittest(num_of_global_fittest);
+        }
+        if let Some(output_every) = config.output_every {
+            self = self.output_every(output_every);
+        }
+
+        let populations = self.simulation.habitat.len();
+        if config.populations.len() > populations {
+            let configs = config.populations.len();
+            return Err(ErrorKind::TooManyPopulationConfigs(configs, populations).into());
+        }
+        let habitat = &mut self.simulation.habitat;
+        for (settings, population) in config.populations.iter().zip(habitat) {
+            settings.apply(population);
+            population_builder::check(population)?;
+        }
+
+        Ok(self)
+    }
+
+    /// Checks that the individuals of a migration (see `migrants_per_event`) or a sharing (see
+    /// `share_top`) do not replace all individuals but the elite of any population, which can
+    /// happen in the smaller ones if the populations have different sizes.
+    fn check_migrants(&self) -> Result<()> {
+        let simulation = &self.simulation;
+        let mut migrants = 0;
+        if simulation.migration.interval > 0 {
+            migrants = simulation.migration.migrants;
+        }
+        if simulation.share_fittest && simulation.share_top > 1 {
+            migrants = migrants.max(simulation.share_top);
+        }
+
+        for population in &simulation.habitat {
+            // Migrants that are crossed with the elites do not replace anyone.
+            if population.crossover.is_some() && simulation.interbreed_elites > 0 {
+                continue;
+            }
+            let replaceable =
+                population.num_of_individuals.saturating_sub(population.elitism) as usize;
+            if migrants >= replaceable {
+                return Err(ErrorKind::TooManyMigrants(migrants, population.id, replaceable).into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Compares the estimated memory of the simulation with the limit and applies the policy.
+    fn check_memory(&mut self, limit: usize, policy: MemoryPolicy) -> Result<()> {
+        let mut estimate = self.simulation.estimated_memory();
+
+        if policy == MemoryPolicy::ShrinkArchive && estimate > limit {
+            let fittest = self.simulation.num_of_global_fittest;
+            self.simulation.num_of_global_fittest = 0;
+            let base = self.simulation.estimated_memory();
+            let archived = ((estimate - base) / fittest.max(1)).max(1);
+
+            self.simulation.num_of_global_fittest =
+                fittest.min(limit.saturating_sub(base) / archived).max(1);
+            estimate = self.simulation.estimated_memory();
+            warn!(
+                "memory limit: num_of_global_fittest reduced from {} to {}",
+                fittest,
+                self.simulation.num_of_global_fittest
+            );
+        }
+
+        if estimate > limit {
+            Err(ErrorKind::MemoryLimitExceeded(estimate, limit).into())
+        } else {
+            Ok(())
+        }
+    }
+
     /// This checks the configuration of the simulation and returns an error or Ok if no errors
     /// where found.
-    pub fn finalize(self) -> Result<Simulation<T>> {
+    pub fn finalize(mut self) -> Result<Simulation<T>> {
         match self.simulation {
-            Simulation { type_of_simulation: SimulationType::EndIteration(0...9), .. } => {
+            Simulation { type_of_simulation: SimulationType::EndIteration(0..=9), .. } => {
                 Err(ErrorKind::EndIterationTooLow.into())
             }
-            _ => Ok(self.simulation),
+            Simulation { type_of_simulation: SimulationType::EndCondition(ref condition), .. }
+                if !condition.is_valid() => {
+                Err(ErrorKind::EndConditionInvalid.into())
+            }
+            Simulation { check_every: CheckIntervals { target: 0, .. }, .. } |
+            Simulation { check_every: CheckIntervals { stagnation: 0, .. }, .. } => {
+                Err(ErrorKind::CheckIntervalZero.into())
+            }
+            Simulation { mutation_calibration: Some((ref candidates, iterations)), .. }
+                if candidates.is_empty() || iterations == 0 => {
+                Err(ErrorKind::CalibrationInvalid.into())
+            }
+            Simulation { record_history: Some((1, _)), .. } => {
+                Err(ErrorKind::HistoryLimitTooLow.into())
+            }
+            Simulation { migration, .. } if !migration.is_valid() => {
+                Err(ErrorKind::MigrationInvalid.into())
+            }
+            Simulation { ref topology, ref habitat, .. } if !topology.is_valid(habitat.len()) => {
+                Err(ErrorKind::TopologyInvalid.into())
+            }
+            Simulation { remote_islands: Some(_), migration, asynchronous_islands, .. }
+                if migration.interval == 0 || asynchronous_islands => {
+                Err(ErrorKind::RemoteIslandsInvalid.into())
+            }
+            Simulation { share_top, share_probability, .. }
+                if share_top == 0 || !(share_probability > 0.0 && share_probability <= 1.0) => {
+                Err(ErrorKind::SharingInvalid.into())
+            }
+            _ => {
+                self.check_migrants()?;
+                if let Some((limit, policy)) = self.memory_limit {
+                    self.check_memory(limit, policy)?;
+                    self.simulation.memory_limit = Some(limit);
+                }
+                let context = self.context.take();
+                for (population, n) in self.simulation.habitat.iter_mut().zip(0..) {
+                    population.direction = self.simulation.direction;
+                    if let Some(ref context) = context {
+                        population.context = context.clone();
+                    }
+                    if let Some(seed) = self.simulation.seed {
+                        population.rng = rng::for_population(seed, n);
+                    }
+                }
+                if let Some(context) = context {
+                    self.simulation.context = context;
+                }
+                if self.logging {
+                    let logger = LoggingObserver::new(self.simulation.output_every);
+                    self.simulation.observers.insert(0, Arc::new(logger));
+                }
+                self.simulation.provenance = Provenance::collect(
+                    self.simulation.provenance.code_revision.take(),
+                    self.simulation.num_of_threads,
+                );
+                Ok(self.simulation)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+
+    use rand::Rng;
+
+    use super::{ErrorKind, SimulationBuilder};
+    use individual::Individual;
+    use population_builder::{self, PopulationBuilder};
+    use rng::SimRng;
+    use select::MinimizeSelector;
+    use simulation::SimulationType;
+    use test::Test;
+
+    fn builder() -> SimulationBuilder<Test> {
+        let initial: Vec<Test> = (0..10).map(|i| Test { f: f64::from(i) }).collect();
+        let population = PopulationBuilder::<Test>::new()
+            .initial_population(&initial)
+            .elitism(2)
+            .reset_limit_end(0)
+            .finalize()
+            .unwrap();
+        SimulationBuilder::<Test>::new().iterations(100).add_population(population)
+    }
+
+    #[test]
+    fn from_env_overlays_parameters() {
+        env::set_var("DARWIN_ENV_VALID_THREADS", "3");
+        env::set_var("DARWIN_ENV_VALID_ITERATIONS", "50");
+        env::set_var("DARWIN_ENV_VALID_POPULATION_SIZE", "4");
+
+        let simulation = builder().from_env("DARWIN_ENV_VALID").unwrap().finalize().unwrap();
+        assert_eq!(simulation.num_of_threads, 3);
+        match simulation.type_of_simulation {
+            SimulationType::EndIteration(iterations) => assert_eq!(iterations, 50),
+            ref other => panic!("unexpected simulation type: {:?}", other),
         }
+        assert_eq!(simulation.habitat[0].num_of_individuals, 4);
+        assert_eq!(simulation.habitat[0].population.len(), 4);
+    }
+
+    #[test]
+    fn from_env_rejects_unparsable_values() {
+        env::set_var("DARWIN_ENV_PARSE_THREADS", "many");
+
+        match builder().from_env("DARWIN_ENV_PARSE").map(|_| ()).unwrap_err().0 {
+            ErrorKind::InvalidEnvironmentVariable(name, value) => {
+                assert_eq!(name, "DARWIN_ENV_PARSE_THREADS");
+                assert_eq!(value, "many");
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+    }
+
+    #[test]
+    fn from_env_checks_ranges() {
+        env::set_var("DARWIN_ENV_EMPTY_POPULATION_SIZE", "0");
+        match builder().from_env("DARWIN_ENV_EMPTY").map(|_| ()).unwrap_err().0 {
+            ErrorKind::Population(population_builder::ErrorKind::IndividualsTooLow) => {}
+            other => panic!("unexpected error: {}", other),
+        }
+
+        // The population keeps two elites, so it needs at least three individuals.
+        env::set_var("DARWIN_ENV_SMALL_POPULATION_SIZE", "3");
+        assert!(builder().from_env("DARWIN_ENV_SMALL").is_ok());
+        env::set_var("DARWIN_ENV_SMALL_POPULATION_SIZE", "2");
+        assert!(builder().from_env("DARWIN_ENV_SMALL").is_err());
+
+        env::set_var("DARWIN_ENV_SHORT_ITERATIONS", "5");
+        let simulation = builder().from_env("DARWIN_ENV_SHORT").unwrap().finalize();
+        match simulation.map(|_| ()).unwrap_err().0 {
+            ErrorKind::EndIterationTooLow => {}
+            other => panic!("unexpected error: {}", other),
+        }
+    }
+
+    #[test]
+    fn history_limit_too_low() {
+        match builder().record_history(1, false).finalize().map(|_| ()).unwrap_err().0 {
+            ErrorKind::HistoryLimitTooLow => {}
+            other => panic!("unexpected error: {}", other),
+        }
+        assert!(builder().record_history(2, false).finalize().is_ok());
+    }
+
+    #[test]
+    fn migrants_must_fit_the_smallest_population() {
+        let small = PopulationBuilder::<Test>::new()
+            .generate_initial_population(4, |_| Test { f: 1.0 })
+            .set_id(2)
+            .finalize()
+            .unwrap();
+        let builder = || builder().add_population(small.clone()).migration_interval(5);
+
+        assert!(builder().migrants_per_event(3).finalize().is_ok());
+        let error = builder().migrants_per_event(4).finalize().map(|_| ()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "4 migrants would replace all 4 replaceable individuals of population 2, see \
+             migrants_per_event and share_top"
+        );
+        // The larger population keeps two elites.
+        match builder().migrants_per_event(8).finalize().map(|_| ()).unwrap_err().0 {
+            ErrorKind::TooManyMigrants(8, 1, 8) => {}
+            other => panic!("unexpected error: {}", other),
+        }
+        let sharing = || builder().migration_interval(0).share_fittest();
+        assert!(sharing().share_top(4).finalize().is_err());
+        assert!(sharing().share_top(3).finalize().is_ok());
+    }
+
+    #[cfg(feature = "config")]
+    #[test]
+    fn from_config_overlays_parameters() {
+        use config::Config;
+
+        let config = Config::from_toml(
+            r#"
+            threads = 3
+            end = { iterations = 50 }
+
+            [[population]]
+            mutation = { rates = [1, 3] }
+            reset_limit_start = 20
+            label = "configured"
+            individuals = 6
+            "#,
+        )
+        .unwrap();
+        let simulation = builder().from_config(&config).unwrap().finalize().unwrap();
+        assert_eq!(simulation.num_of_threads, 3);
+        match simulation.type_of_simulation {
+            SimulationType::EndIteration(iterations) => assert_eq!(iterations, 50),
+            ref other => panic!("unexpected simulation type: {:?}", other),
+        }
+        let population = &simulation.habitat[0];
+        assert_eq!(population.label, "configured");
+        assert_eq!(population.reset_limit, 20);
+        let rates: Vec<u32> = population.population.iter().map(|w| w.num_of_mutations).collect();
+        assert_eq!(rates, vec![1, 3, 1, 3, 1, 3]);
+        assert_eq!(population.num_of_individuals, 6);
+
+        let config = Config::from_toml("time_limit = -1.0").unwrap();
+        match builder().from_config(&config).map(|_| ()).unwrap_err().0 {
+            ErrorKind::TimeLimitInvalid => {}
+            other => panic!("unexpected error: {}", other),
+        }
+        let config = Config::from_toml("[[population]]\nelitism = 10").unwrap();
+        assert!(builder().from_config(&config).is_err());
+        let config = Config::from_toml("[[population]]\n[[population]]").unwrap();
+        match builder().from_config(&config).map(|_| ()).unwrap_err().0 {
+            ErrorKind::TooManyPopulationConfigs(2, 1) => {}
+            other => panic!("unexpected error: {}", other),
+        }
+    }
+
+    #[test]
+    fn time_limit_ends_any_simulation() {
+        use std::time::Duration;
+        use simulation::RunStatus;
+
+        let selector = MinimizeSelector::new(2);
+        let mut simulation = builder()
+            .iterations(1_000_000)
+            .time_limit(Duration::from_millis(20))
+            .finalize()
+            .unwrap();
+        assert_eq!(simulation.run(&selector), RunStatus::TimeLimit);
+        assert!(simulation.simulation_result.iteration_counter < 1_000_000);
+
+        let mut simulation = builder().time_limit(Duration::from_secs(3600)).finalize().unwrap();
+        assert_eq!(simulation.run(&selector), RunStatus::IterationLimit);
+        assert_eq!(simulation.simulation_result.iteration_counter, 100);
+    }
+
+    /// Takes a random step with the generator of its population.
+    #[derive(Debug, Clone)]
+    struct Drift {
+        x: f64,
+    }
+
+    impl Individual for Drift {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), rng: &mut SimRng) {
+            self.x += rng.gen_range(-1.0..1.0);
+        }
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            self.x.abs()
+        }
+
+        fn reset(&mut self) {}
+    }
+
+    #[test]
+    fn same_seed_same_run() {
+        let initial = [Drift { x: 10.0 }, Drift { x: -5.0 }, Drift { x: 1.0 }];
+        let run = |seed, evolution_seed| {
+            let populations = (0..3)
+                .map(|id| {
+                    PopulationBuilder::<Drift>::new()
+                        .set_id(id)
+                        .initial_population(&initial)
+                        .evolution_seed(evolution_seed)
+                        .finalize()
+                        .unwrap()
+                })
+                .collect();
+            let mut simulation = SimulationBuilder::<Drift>::new()
+                .iterations(20)
+                .threads(3)
+                .seed(seed)
+                .add_multiple_populations(populations)
+                .finalize()
+                .unwrap();
+            assert_eq!(simulation.seed, Some(seed));
+            assert_ne!(simulation.habitat[0].rng, simulation.habitat[1].rng);
+            simulation.run(&MinimizeSelector::new(2));
+            simulation
+                .habitat
+                .iter()
+                .flat_map(|population| population.population.iter().map(|w| w.individual.x))
+                .collect::<Vec<f64>>()
+        };
+
+        // The global seed overrides the evolution seeds of the populations.
+        assert_eq!(run(1, 5), run(1, 6));
+        assert_ne!(run(1, 5), run(2, 5));
     }
 }
@@ -0,0 +1,300 @@
+//! This module defines the speciation of a population (like in NEAT): the individuals are
+//! clustered into species by their genotype distance, the species share their fitness and
+//! each species gets a number of offspring according to its shared fitness. It also defines
+//! the clearing procedure, another niching method.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+//! The genotype distance is measured with `Individual::distance`. Since a lower fitness is
+//! better and the fitness can be negative, the fitness is shared by rank: the individual with
+//! rank r (0 for the fittest one) scores 1 / (1 + r) and this score is divided by the size of
+//! its species. Large species of mediocre individuals thus get less offspring than small
+//! species with a fit individual, which protects new innovations.
+
+use std::cmp::Ordering;
+
+use rand::Rng;
+
+use fitness::Fitness;
+use individual::{Individual, IndividualWrapper};
+use rng::SimRng;
+
+/// The `Speciation` type. The configuration of the speciation of a population, see
+/// `PopulationBuilder::speciation`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Speciation {
+    /// An individual belongs to the first species whose representative (its fittest member) is
+    /// closer than this distance, otherwise it founds a new species.
+    pub threshold: f64,
+    /// The number of pairs of parents selected in each iteration.
+    pub pairs: usize,
+}
+
+/// The `Species` type. The statistics of one species in one iteration.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Species {
+    /// The number of individuals in the species.
+    pub size: usize,
+    /// The fitness of the fittest member (see `Fitness::to_f64`), the representative of the
+    /// species.
+    pub best_fitness: f64,
+    /// The sum of the shared fitness scores of the members, see module documentation.
+    pub shared_fitness: f64,
+    /// The number of pairs of parents selected from this species.
+    pub offspring: usize,
+}
+
+/// Clusters the given individuals into species, fittest individuals first: each individual
+/// joins the first species whose representative is closer than `threshold`, otherwise it
+/// founds a new species. Returns the indices of the members of each species, the
+/// representative first.
+pub fn cluster<T>(population: &[IndividualWrapper<T>], threshold: f64) -> Vec<Vec<usize>>
+where
+    T: Individual,
+{
+    let mut species: Vec<Vec<usize>> = Vec::new();
+
+    for index in ranking(population) {
+        let individual = &population[index].individual;
+        let found = species
+            .iter_mut()
+            .find(|members| individual.distance(&population[members[0]].individual) < threshold);
+        match found {
+            Some(members) => members.push(index),
+            None => species.push(vec![index]),
+        }
+    }
+
+    species
+}
+
+/// Divides `total` among the given (non negative) shares by the largest remainder method.
+/// If all shares are zero, `total` is divided evenly.
+pub fn allocate(shares: &[f64], total: usize) -> Vec<usize> {
+    let sum: f64 = shares.iter().sum();
+    if shares.is_empty() {
+        return Vec::new();
+    }
+    let quotas: Vec<f64> = if sum > 0.0 {
+        shares.iter().map(|share| share / sum * total as f64).collect()
+    } else {
+        vec![total as f64 / shares.len() as f64; shares.len()]
+    };
+
+    let mut counts: Vec<usize> = quotas.iter().map(|quota| quota.floor() as usize).collect();
+    let mut by_remainder: Vec<usize> = (0..quotas.len()).collect();
+    by_remainder.sort_by(|&a, &b| {
+        let remainder = |index: usize| quotas[index] - quotas[index].floor();
+        remainder(b).partial_cmp(&remainder(a)).unwrap_or(Ordering::Equal)
+    });
+    let missing = total - counts.iter().sum::<usize>();
+    for &index in by_remainder.iter().take(missing) {
+        counts[index] += 1;
+    }
+
+    counts
+}
+
+/// Clusters the population into species, allocates the pairs of parents to the species by
+/// their shared fitness and selects the parents of each pair by a binary tournament within
+/// the species. Returns the parents and the statistics of the species.
+pub fn select_parents<T>(
+    population: &[IndividualWrapper<T>],
+    speciation: Speciation,
+    rng: &mut SimRng,
+) -> (Vec<(T, T)>, Vec<Species>)
+where
+    T: Individual + Clone,
+{
+    let mut rank = vec![0; population.len()];
+    for (position, index) in ranking(population).into_iter().enumerate() {
+        rank[index] = position;
+    }
+
+    let clusters = cluster(population, speciation.threshold);
+    let shares: Vec<f64> = clusters
+        .iter()
+        .map(|members| {
+            let scores: f64 = members.iter().map(|&index| 1.0 / (1.0 + rank[index] as f64)).sum();
+            scores / members.len() as f64
+        })
+        .collect();
+    let offspring = allocate(&shares, speciation.pairs);
+
+    let mut parents = Vec::with_capacity(speciation.pairs);
+    let mut species = Vec::with_capacity(clusters.len());
+    for ((members, shared_fitness), offspring) in clusters.iter().zip(shares).zip(offspring) {
+        for _ in 0..offspring {
+            let first = tournament(population, members, rng);
+            let second = tournament(population, members, rng);
+            parents.push((
+                population[first].individual.clone(),
+                population[second].individual.clone(),
+            ));
+        }
+        species.push(Species {
+            size: members.len(),
+            best_fitness: population[members[0]].fitness.to_f64(),
+            shared_fitness,
+            offspring,
+        });
+    }
+
+    (parents, species)
+}
+
+/// The clearing procedure: within each niche (all individuals closer than `radius` to the
+/// fittest individual of the niche) only the `capacity` fittest individuals keep their
+/// fitness, the others get the worst fitness (see `Fitness::worst`). Returns the indices and
+/// the original fitness values of the cleared individuals, see `restore`.
+pub fn clear<T: Individual>(
+    population: &mut [IndividualWrapper<T>],
+    radius: f64,
+    capacity: usize,
+) -> Vec<(usize, T::Fitness)> {
+    let ranking = ranking(population);
+    let mut cleared = vec![false; population.len()];
+    let mut result = Vec::new();
+
+    for (position, &winner) in ranking.iter().enumerate() {
+        if cleared[winner] {
+            continue;
+        }
+        let mut winners = 1;
+        for &other in &ranking[position + 1..] {
+            if cleared[other] ||
+                population[winner].individual.distance(&population[other].individual) >= radius
+            {
+                continue;
+            }
+            if winners < capacity {
+                winners += 1;
+            } else {
+                cleared[other] = true;
+                result.push((other, population[other].fitness.clone()));
+            }
+        }
+    }
+
+    for &(index, _) in &result {
+        population[index].fitness = T::Fitness::worst();
+    }
+    result
+}
+
+/// Gives the individuals cleared by `clear` their original fitness back.
+pub fn restore<T: Individual>(
+    population: &mut [IndividualWrapper<T>],
+    cleared: &[(usize, T::Fitness)],
+) {
+    for &(index, ref fitness) in cleared {
+        population[index].fitness = fitness.clone();
+    }
+}
+
+/// Returns the indices of the individuals sorted by fitness, the fittest first and NaN last.
+fn ranking<T: Individual>(population: &[IndividualWrapper<T>]) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..population.len()).collect();
+    indices.sort_by(|&a, &b| population[a].fitness.compare(&population[b].fitness));
+    indices
+}
+
+/// Returns the fitter of two randomly chosen members.
+fn tournament<T: Individual>(
+    population: &[IndividualWrapper<T>],
+    members: &[usize],
+    rng: &mut SimRng,
+) -> usize {
+    let first = members[rng.gen_range(0..members.len())];
+    let second = members[rng.gen_range(0..members.len())];
+    if population[second].fitness < population[first].fitness { second } else { first }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{allocate, clear, cluster, restore, select_parents, Speciation};
+    use individual::Individual;
+    use rng::{self, SimRng};
+    use test::wrap;
+
+    #[derive(Debug, Clone)]
+    struct Point {
+        x: f64,
+    }
+
+    impl Individual for Point {
+        type Fitness = f64;
+        type Context = ();
+
+        fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {}
+
+        fn calculate_fitness(&mut self, _context: &()) -> f64 {
+            self.x.abs()
+        }
+
+        fn reset(&mut self) {}
+
+        fn distance(&self, other: &Point) -> f64 {
+            (self.x - other.x).abs()
+        }
+    }
+
+    #[test]
+    fn largest_remainder() {
+        assert_eq!(allocate(&[1.0, 1.0, 1.0], 10), vec![4, 3, 3]);
+        assert_eq!(allocate(&[3.0, 1.0], 10), vec![8, 2]);
+        assert_eq!(allocate(&[0.0, 0.0], 3), vec![2, 1]);
+        assert!(allocate(&[], 3).is_empty());
+    }
+
+    #[test]
+    fn species_share_fitness() {
+        // Two clusters: around 0 (four individuals) and around 100 (one individual).
+        let points: Vec<Point> =
+            [0.0, 1.0, -1.0, 2.0, 100.0].iter().map(|&x| Point { x }).collect();
+        let population = wrap(&points);
+        assert_eq!(cluster(&population, 5.0), vec![vec![0, 1, 2, 3], vec![4]]);
+
+        let speciation = Speciation { threshold: 5.0, pairs: 10 };
+        let (parents, species) = select_parents(&population, speciation, &mut rng::from_seed(1));
+        assert_eq!(parents.len(), 10);
+        assert_eq!(species.len(), 2);
+        assert_eq!(species[0].size, 4);
+        assert_eq!(species[1].size, 1);
+        assert_eq!(species[1].best_fitness, 100.0);
+        // The lone individual has the rank 4 and thus the shared fitness 1 / 5, the other
+        // species (1 + 1 / 2 + 1 / 3 + 1 / 4) / 4.
+        assert_eq!(species[1].shared_fitness, 0.2);
+        assert_eq!(species[0].offspring, 7);
+        assert_eq!(species[1].offspring, 3);
+        assert!(parents.iter().all(|(a, b)| (a.x - b.x).abs() < 5.0));
+    }
+
+    #[test]
+    fn clearing_keeps_the_winners() {
+        let points: Vec<Point> =
+            [0.5, 0.0, 1.0, 10.0, 10.5, 11.0].iter().map(|&x| Point { x }).collect();
+        let mut population = wrap(&points);
+
+        let cleared = clear(&mut population, 2.0, 2);
+        // Niche around 0.0: 0.0 and 0.5 win, niche around 10.0: 10.0 and 10.5 win.
+        assert_eq!(cleared, vec![(2, 1.0), (5, 11.0)]);
+        let fitness: Vec<f64> = population.iter().map(|wrapper| wrapper.fitness).collect();
+        assert_eq!(fitness, vec![0.5, 0.0, f64::INFINITY, 10.0, 10.5, f64::INFINITY]);
+
+        restore(&mut population, &cleared);
+        assert_eq!(population[2].fitness, 1.0);
+        assert_eq!(population[5].fitness, 11.0);
+    }
+}
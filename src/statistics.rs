@@ -0,0 +1,287 @@
+//! This module defines the statistics that are collected while the simulation is running.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use diversity::Diversity;
+use fitness::Fitness;
+use individual::{Individual, IndividualWrapper};
+use select::Direction;
+use species::Species;
+
+/// The `Counters` type. Each population counts its own work in its own instance, so the
+/// worker threads never have to share (and lock) anything. After each iteration the counters
+/// of all populations are merged by the simulation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Counters {
+    /// The number of calls to `calculate_fitness`.
+    pub evaluations: u64,
+    /// The number of offspring whose full evaluation was skipped, because their
+    /// `quick_bound` was already worse than the worst survivor.
+    pub skipped_evaluations: u64,
+    /// The number of calls to `mutate`.
+    pub mutations: u64,
+    /// The number of calls to `crossover`.
+    pub crossovers: u64,
+    /// The number of times all individuals of a population have been reset.
+    pub resets: u64,
+    /// The number of iterations in which the fittest individual of a population has improved.
+    pub improvements: u64,
+    /// The number of panics in the user code that were caught, see `PanicPolicy`.
+    pub panics: u64,
+    /// The number of duplicates replaced by mutated variants, see
+    /// `PopulationBuilder::deduplicate`.
+    pub duplicates: u64,
+    /// The number of fitness values taken from the fitness cache instead of calling
+    /// `calculate_fitness`, see `PopulationBuilder::fitness_cache`.
+    pub cache_hits: u64,
+}
+
+impl Counters {
+    /// Adds the counts of `other` to this counters.
+    pub fn merge(&mut self, other: &Counters) {
+        self.evaluations += other.evaluations;
+        self.skipped_evaluations += other.skipped_evaluations;
+        self.mutations += other.mutations;
+        self.crossovers += other.crossovers;
+        self.resets += other.resets;
+        self.improvements += other.improvements;
+        self.panics += other.panics;
+        self.duplicates += other.duplicates;
+        self.cache_hits += other.cache_hits;
+    }
+}
+
+/// The `ImprovementRate` type. The number of improvements of the fittest individual per 1000
+/// fitness evaluations of one population, over a rolling window of the last iterations.
+/// An external scheduler can use it to decide which populations deserve more (or less)
+/// computing power.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImprovementRate {
+    /// The number of iterations in the window.
+    window: usize,
+    /// The improvements and evaluations of each iteration in the window.
+    history: VecDeque<(u64, u64)>,
+    /// The sum of the improvements in the window.
+    improvements: u64,
+    /// The sum of the evaluations in the window.
+    evaluations: u64,
+}
+
+impl Default for ImprovementRate {
+    /// A window of 100 iterations.
+    fn default() -> ImprovementRate {
+        ImprovementRate::new(100)
+    }
+}
+
+impl ImprovementRate {
+    /// Creates an empty rolling window over the given number of iterations (at least one).
+    pub fn new(window: usize) -> ImprovementRate {
+        ImprovementRate {
+            window: window.max(1),
+            history: VecDeque::with_capacity(window.max(1)),
+            improvements: 0,
+            evaluations: 0,
+        }
+    }
+
+    /// Adds the counters of one iteration and drops the oldest iteration if the window is full.
+    pub fn push(&mut self, counters: &Counters) {
+        if self.history.len() == self.window {
+            if let Some((improvements, evaluations)) = self.history.pop_front() {
+                self.improvements -= improvements;
+                self.evaluations -= evaluations;
+            }
+        }
+
+        self.history.push_back((counters.improvements, counters.evaluations));
+        self.improvements += counters.improvements;
+        self.evaluations += counters.evaluations;
+    }
+
+    /// Returns the improvements per 1000 evaluations in the window, 0.0 if nothing has been
+    /// evaluated yet.
+    pub fn per_1000_evaluations(&self) -> f64 {
+        if self.evaluations == 0 {
+            0.0
+        } else {
+            self.improvements as f64 * 1000.0 / self.evaluations as f64
+        }
+    }
+}
+
+/// The `FitnessStats` type. The distribution of the fitness values of one population (see
+/// `Fitness::to_f64`), as calculated by `Individual::calculate_fitness`, also in maximize mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct FitnessStats {
+    /// The fitness of the fittest individual.
+    pub best: f64,
+    /// The fitness of the least fit individual.
+    pub worst: f64,
+    /// The mean fitness.
+    pub mean: f64,
+    /// The median fitness.
+    pub median: f64,
+    /// The standard deviation of the fitness values.
+    pub std_dev: f64,
+}
+
+impl FitnessStats {
+    /// Calculates the statistics of the given population, whose fitness values are stored for
+    /// the given direction (see `Direction::apply`). All values are 0.0 if it is empty.
+    pub fn of<T: Individual>(
+        population: &[IndividualWrapper<T>],
+        direction: Direction,
+    ) -> FitnessStats {
+        if population.is_empty() {
+            return FitnessStats::default();
+        }
+
+        // Lower values are better, whatever the direction.
+        let mut values: Vec<f64> =
+            population.iter().map(|wrapper| wrapper.fitness.to_f64()).collect();
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+        let n = values.len();
+        let mean = values.iter().sum::<f64>() / n as f64;
+        let variance =
+            values.iter().map(|value| (value - mean) * (value - mean)).sum::<f64>() / n as f64;
+        let median = if n % 2 == 0 {
+            (values[n / 2 - 1] + values[n / 2]) / 2.0
+        } else {
+            values[n / 2]
+        };
+
+        FitnessStats {
+            best: direction.apply(&values[0]),
+            worst: direction.apply(&values[n - 1]),
+            mean: direction.apply(&mean),
+            median: direction.apply(&median),
+            std_dev: variance.sqrt(),
+        }
+    }
+}
+
+/// The `IterationStats` type. Contains the statistics of one iteration, it is handed over
+/// to the observers after each iteration.
+#[derive(Debug, Clone, Default)]
+pub struct IterationStats {
+    /// The number of the iteration, starting with 1.
+    pub iteration: u32,
+    /// How long the iteration took, for iteration 0 the initialization of the run.
+    pub duration: Duration,
+    /// The counters of this iteration, merged over all populations.
+    pub total: Counters,
+    /// The counters of this iteration for each population: (population id, label, counters).
+    pub per_population: Vec<(u32, String, Counters)>,
+    /// The improvements per 1000 evaluations of each population over its rolling window:
+    /// (population id, rate), see `ImprovementRate`.
+    pub improvement_rates: Vec<(u32, f64)>,
+    /// The fitness statistics of each population at the end of the iteration: (population id,
+    /// statistics), see `FitnessStats`.
+    pub fitness: Vec<(u32, FitnessStats)>,
+    /// The diversity of each population at the end of the iteration: (population id, metrics).
+    /// Only filled in if enabled with `SimulationBuilder::track_diversity`.
+    pub diversity: Vec<(u32, Diversity)>,
+    /// The species of each population with speciation in this iteration: (population id,
+    /// species), see `PopulationBuilder::speciation`.
+    pub species: Vec<(u32, Vec<Species>)>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Counters, FitnessStats, ImprovementRate};
+    use select::Direction;
+    use test::{wrap, Test};
+
+    #[test]
+    fn merge_adds_all_counters() {
+        let mut counters = Counters {
+            evaluations: 1,
+            skipped_evaluations: 5,
+            mutations: 2,
+            crossovers: 3,
+            resets: 4,
+            improvements: 6,
+            panics: 7,
+            duplicates: 8,
+            cache_hits: 9,
+        };
+        counters.merge(&Counters {
+            evaluations: 10,
+            skipped_evaluations: 50,
+            mutations: 20,
+            crossovers: 30,
+            resets: 40,
+            improvements: 60,
+            panics: 70,
+            duplicates: 80,
+            cache_hits: 90,
+        });
+
+        assert_eq!(counters, Counters {
+            evaluations: 11,
+            skipped_evaluations: 55,
+            mutations: 22,
+            crossovers: 33,
+            resets: 44,
+            improvements: 66,
+            panics: 77,
+            duplicates: 88,
+            cache_hits: 99,
+        });
+    }
+
+    #[test]
+    fn fitness_stats_of_population() {
+        let fitness = [4.0, 1.0, 2.0, 1.0];
+        let population = wrap(&fitness.iter().map(|&f| Test { f }).collect::<Vec<_>>());
+        let stats = FitnessStats::of(&population, Direction::Minimize);
+        assert_eq!(
+            stats,
+            FitnessStats { best: 1.0, worst: 4.0, mean: 2.0, median: 1.5, std_dev: 1.5f64.sqrt() }
+        );
+
+        // In maximize mode the fitness values are stored negated.
+        let population = wrap(&[Test { f: -4.0 }, Test { f: -1.0 }, Test { f: -2.0 }]);
+        let stats = FitnessStats::of(&population, Direction::Maximize);
+        assert_eq!((stats.best, stats.worst, stats.median), (4.0, 1.0, 2.0));
+        assert_eq!(FitnessStats::of::<Test>(&[], Direction::Minimize), FitnessStats::default());
+    }
+
+    #[test]
+    fn improvement_rate_rolls() {
+        let mut rate = ImprovementRate::new(2);
+        assert_eq!(rate.per_1000_evaluations(), 0.0);
+
+        let iteration = |improvements, evaluations| Counters {
+            improvements,
+            evaluations,
+            ..Counters::default()
+        };
+        rate.push(&iteration(1, 500));
+        assert_eq!(rate.per_1000_evaluations(), 2.0);
+        rate.push(&iteration(0, 1500));
+        assert_eq!(rate.per_1000_evaluations(), 0.5);
+        // The first iteration drops out of the window.
+        rate.push(&iteration(0, 500));
+        assert_eq!(rate.per_1000_evaluations(), 0.0);
+    }
+}
@@ -0,0 +1,57 @@
+//! This module defines a minimal individual that is used by the unit tests of the library.
+//!
+//! darwin-rs: evolutionary algorithms with Rust
+//!
+//! Written by Willi Kappler, Version 0.4 (2017.06.26)
+//!
+//! Repository: https://github.com/willi-kappler/darwin-rs
+//!
+//! License: MIT
+//!
+//! This library allows you to write evolutionary algorithms (EA) in Rust.
+//! Examples provided: TSP, Sudoku, Queens Problem, OCR
+//!
+//!
+
+use individual::{Individual, IndividualWrapper};
+use rng::SimRng;
+
+/// A test individual, its fitness is just the stored value `f`.
+#[derive(Debug, Clone, Copy)]
+pub struct Test {
+    /// The fitness value this individual reports.
+    pub f: f64,
+}
+
+impl Individual for Test {
+    type Fitness = f64;
+    type Context = ();
+
+    fn mutate(&mut self, _context: &(), _rng: &mut SimRng) {}
+
+    fn calculate_fitness(&mut self, _context: &()) -> f64 {
+        self.f
+    }
+
+    fn reset(&mut self) {}
+}
+
+/// Wraps the given individuals and calculates their fitness, like a population does.
+pub fn wrap<I: Individual + Clone>(individuals: &[I]) -> Vec<IndividualWrapper<I>> {
+    individuals
+        .iter()
+        .map(|individual| {
+            let mut individual = individual.clone();
+            IndividualWrapper {
+                fitness: individual.calculate_fitness(&I::Context::default()),
+                case_errors: individual.case_errors(),
+                objectives: Vec::new(),
+                individual,
+                num_of_mutations: 1,
+                id: 1,
+                age: 0,
+                dirty: false,
+            }
+        })
+        .collect()
+}